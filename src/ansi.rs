@@ -0,0 +1,169 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+
+/// Split text carrying ANSI escape codes into styled spans (--keep-ansi).
+/// SGR sequences (`\x1b[...m`) set the style of the text after them; other
+/// CSI sequences and stray escapes are dropped.
+pub fn ansi_to_spans(text: &str) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut style = Style::default();
+    let mut run = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            run.push(c);
+            continue;
+        }
+        if chars.peek() != Some(&'[') {
+            continue;
+        }
+        chars.next();
+        // Parameter and intermediate bytes up to the final byte (@ to ~)
+        let mut params = String::new();
+        let mut terminator = None;
+        for c in chars.by_ref() {
+            if ('@'..='~').contains(&c) {
+                terminator = Some(c);
+                break;
+            }
+            params.push(c);
+        }
+        if terminator != Some('m') {
+            continue;
+        }
+        if !run.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut run), style));
+        }
+        style = apply_sgr(style, &params);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, style));
+    }
+    spans
+}
+
+/// Apply the `;`-separated SGR parameters of one escape sequence to `style`.
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let codes: Vec<u16> = params
+        .split([';', ':'])
+        .map(|p| p.parse().unwrap_or(0))
+        .collect();
+    let mut codes = codes.iter().copied();
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(basic_color(code - 30, false)),
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            // Back to the default colour, i.e. whatever the text is drawn over
+            39 => Style { fg: None, ..style },
+            40..=47 => style.bg(basic_color(code - 40, false)),
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => Style { bg: None, ..style },
+            90..=97 => style.fg(basic_color(code - 90, true)),
+            100..=107 => style.bg(basic_color(code - 100, true)),
+            _ => style,
+        };
+    }
+    style
+}
+
+/// The 8 standard colours and their bright variants.
+fn basic_color(index: u16, bright: bool) -> Color {
+    match (index, bright) {
+        (0, false) => Color::Black,
+        (1, false) => Color::Red,
+        (2, false) => Color::Green,
+        (3, false) => Color::Yellow,
+        (4, false) => Color::Blue,
+        (5, false) => Color::Magenta,
+        (6, false) => Color::Cyan,
+        (7, false) => Color::Gray,
+        (0, true) => Color::DarkGray,
+        (1, true) => Color::LightRed,
+        (2, true) => Color::LightGreen,
+        (3, true) => Color::LightYellow,
+        (4, true) => Color::LightBlue,
+        (5, true) => Color::LightMagenta,
+        (6, true) => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+/// `5;n` (256-colour palette) or `2;r;g;b` after a 38/48 code.
+fn extended_color(codes: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    match codes.next()? {
+        5 => Some(Color::Indexed(codes.next()? as u8)),
+        2 => {
+            let (r, g, b) = (codes.next()?, codes.next()?, codes.next()?);
+            Some(Color::Rgb(r as u8, g as u8, b as u8))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sgr_codes_map_to_styles() {
+        let spans = ansi_to_spans("plain \x1b[1;31mbold red\x1b[0m \x1b[3;4;94mlink\x1b[22m");
+        let parts: Vec<(&str, Style)> = spans
+            .iter()
+            .map(|s| (s.content.as_ref(), s.style))
+            .collect();
+        assert_eq!(
+            parts,
+            [
+                ("plain ", Style::default()),
+                (
+                    "bold red",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                ),
+                (" ", Style::default()),
+                (
+                    "link",
+                    Style::default()
+                        .fg(Color::LightBlue)
+                        .add_modifier(Modifier::ITALIC | Modifier::UNDERLINED)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn extended_colours_and_other_sequences() {
+        let spans = ansi_to_spans("\x1b[38;5;208mo\x1b[48;2;10;20;30mb\x1b[2K\x1b[42;39mg");
+        assert_eq!(spans[0].style.fg, Some(Color::Indexed(208)));
+        assert_eq!(spans[1].style.bg, Some(Color::Rgb(10, 20, 30)));
+        // Erase-line is dropped without touching the style
+        assert_eq!(spans[1].content, "b");
+        assert_eq!(spans[2].style.fg, None);
+        assert_eq!(spans[2].style.bg, Some(Color::Green));
+
+        let text: String = ansi_to_spans("a\x1bb\x1b[1")
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(text, "ab");
+    }
+}
@@ -1,23 +1,79 @@
-use std::collections::VecDeque;
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
 use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Local};
 use ratatui::style::Color;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
-use crate::source::FileHistory;
+use crate::config::Config;
+use crate::filter::{parse_filter, FilterMatcher, FilterTerm};
+use crate::fuzzy::fuzzy_score;
+use crate::history::{PromptHistories, PromptHistory};
+use crate::json_tree::JsonTreeState;
+use crate::keymap::KeyMap;
+use crate::parser::{strip_ansi, LogParser};
+use crate::session::Session;
+use crate::source::{ConnStatus, FlagStore, History, ReplayControl, SpillFile};
+use crate::stats::{message_template, shape_filter, MessageStats, TOP_MESSAGES};
+use crate::theme::Theme;
 
 pub const MAX_LOG_LINES: usize = 10_000;
 pub const HISTORY_CHUNK: usize = 500;
-const EPS_WINDOW_SECS: usize = 60;
+pub const DEFAULT_EPS_WINDOW_SECS: usize = 60;
+/// Accepted sparkline windows (--eps-window, eps_window_secs), in seconds.
+pub const EPS_WINDOW_RANGE: std::ops::RangeInclusive<usize> = 10..=600;
+/// Windows the `+` / `-` keys step through, within `EPS_WINDOW_RANGE`.
+const EPS_WINDOW_STEPS: [usize; 6] = [10, 30, 60, 120, 300, 600];
+/// Most lines `]` shows around each filter match.
+pub const MAX_CONTEXT_LINES: usize = 20;
+pub const STATUS_TIMEOUT_SECS: u64 = 3;
+pub const DEFAULT_TIMESTAMP_MAX_WIDTH: usize = 32;
 
-pub const HIGHLIGHT_COLORS: [Color; 4] = [
+/// `request_id=abc`, `"traceId": "abc"`, `X-Request-ID: abc` and the like;
+/// the value is what `I` tracks.
+static CORRELATION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)\b((?:x-)?(?:request|trace|correlation)[_-]?id)["']?\s*[:=]\s*["']?([\w.:-]+)"#,
+    )
+    .unwrap()
+});
+
+/// An ID being tracked with `I`, shown as a banner while its filter is on.
+#[derive(Debug, Clone)]
+pub struct Correlation {
+    /// The filter text it set; editing the filter ends the tracking
+    pub filter: String,
+    /// `request_id=abc123`, or just the ID when no key was found
+    pub label: String,
+}
+
+/// Entries sharing a message template (G key), shown as the first of them.
+#[derive(Debug, Clone)]
+pub struct GroupedEntry {
+    pub template: String,
+    /// Buffer indices of the group, oldest (the one shown) first
+    pub indices: Vec<usize>,
+}
+
+/// Templates remembered by message before the cache starts over.
+const TEMPLATE_CACHE_CAP: usize = 2 * MAX_LOG_LINES;
+
+pub const HIGHLIGHT_COLORS: [Color; 8] = [
     Color::Magenta,
     Color::Cyan,
     Color::LightYellow,
     Color::LightRed,
+    Color::LightGreen,
+    Color::LightBlue,
+    Color::Yellow,
+    Color::LightMagenta,
 ];
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum LogLevel {
     Trace,
     Debug,
@@ -28,14 +84,144 @@ pub enum LogLevel {
     Unknown,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
     pub raw: String,
     pub level: LogLevel,
     pub timestamp: Option<String>,
+    /// `timestamp` parsed into local time, when the format is recognized
+    pub ts_parsed: Option<DateTime<Local>>,
     pub message: Option<String>,
     pub metadata: Option<String>,
     pub extra_lines: Vec<String>,
+    /// Originating stream for merged sources (e.g. compose service `api-1`)
+    pub source: Option<String>,
+    /// HTTP status of an access-log line (nginx/Apache, Envoy), for the status facet
+    #[serde(default)]
+    pub status: Option<u16>,
+    /// Synthetic marker inserted by logpulse (d key, resume, clear), not a log line
+    pub divider: bool,
+}
+
+impl LogLevel {
+    /// Minimum-level floors in order, as the 1-5 keys set them
+    const FLOORS: [LogLevel; 5] = [
+        LogLevel::Trace,
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Warn,
+        LogLevel::Error,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Fatal => "FATAL",
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Trace => "TRACE",
+            LogLevel::Unknown => "",
+        }
+    }
+
+    /// A level by name as written in `level:warn+` (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "trace" => Some(LogLevel::Trace),
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warn" | "warning" => Some(LogLevel::Warn),
+            "error" | "err" => Some(LogLevel::Error),
+            "fatal" | "critical" => Some(LogLevel::Fatal),
+            _ => None,
+        }
+    }
+
+    /// At or above `floor` in severity. Lines without a level never are.
+    pub fn at_least(self, floor: LogLevel) -> bool {
+        self != LogLevel::Unknown && self as u8 >= floor as u8
+    }
+}
+
+impl LogEntry {
+    /// The entry as a JSON object for J exports and `--print --json`;
+    /// fields that weren't parsed are `null`.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "timestamp": self.timestamp,
+            "level": (self.level != LogLevel::Unknown).then(|| self.level.name().to_ascii_lowercase()),
+            "message": self.message,
+            "raw": self.raw,
+            "extra_lines": self.extra_lines,
+        })
+    }
+
+    /// The populated parsed fields as a JSON object (Ctrl+Y). A JSON line gets
+    /// them merged in, overriding its own keys, instead of being wrapped.
+    pub fn to_merged_json(&self) -> serde_json::Value {
+        let object = match serde_json::from_str(self.raw.trim()) {
+            Ok(serde_json::Value::Object(object)) => Some(object),
+            _ => None,
+        };
+        // JSON parsers keep the whole line as metadata for the detail view
+        let metadata = self.metadata.as_deref().filter(|meta| {
+            meta.trim() != self.raw.trim()
+                && !object.as_ref().is_some_and(|object| {
+                    serde_json::from_str::<serde_json::Map<_, _>>(meta).is_ok_and(|m| &m == object)
+                })
+        });
+        let mut fields = serde_json::Map::new();
+        let mut put = |key: &str, value: Option<&str>| {
+            if let Some(value) = value {
+                fields.insert(key.to_string(), value.into());
+            }
+        };
+        put("timestamp", self.timestamp.as_deref());
+        put(
+            "level",
+            (self.level != LogLevel::Unknown)
+                .then(|| self.level.name().to_ascii_lowercase())
+                .as_deref(),
+        );
+        put("message", self.message.as_deref());
+        put("metadata", metadata);
+        put("source", self.source.as_deref());
+        if let Some(status) = self.status {
+            fields.insert("status".to_string(), status.into());
+        }
+        if !self.extra_lines.is_empty() {
+            fields.insert("extra_lines".to_string(), self.extra_lines.clone().into());
+        }
+        match object {
+            Some(mut object) => {
+                object.extend(fields);
+                object.into()
+            }
+            None => {
+                fields.insert("raw".to_string(), self.raw.clone().into());
+                fields.into()
+            }
+        }
+    }
+
+    /// A divider marker stamped with the current time, e.g. `14:03:22 resumed`.
+    pub fn divider(label: &str) -> Self {
+        let now = Local::now();
+        let time = now.format("%H:%M:%S").to_string();
+        LogEntry {
+            raw: String::new(),
+            level: LogLevel::Unknown,
+            message: Some(format!("{} {}", time, label).trim_end().to_string()),
+            timestamp: Some(time),
+            ts_parsed: Some(now),
+            metadata: None,
+            extra_lines: Vec::new(),
+            source: None,
+            status: None,
+            divider: true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -45,13 +231,75 @@ pub enum InputMode {
     Search,
     Highlight,
     SavePrompt,
+    /// Filename entered, choosing between all visible and bookmarked-only entries
+    SaveScope,
+    /// Y over a large view: confirm copying this many lines
+    ConfirmCopy(usize),
+    /// Filename for a structured export of the visible entries (J, C keys)
+    ExportPrompt(ExportFormat),
     TimeJump,
+    /// Regex whose capture is the numeric sort key (o key)
+    SortPrompt,
+    /// Fuzzy query typed live (Ctrl+F)
+    FuzzySearch,
+    /// Request / trace ID to track across entries (I key)
+    CorrelationSearch,
+    /// Regex to find inside the open detail view (`/` there)
+    DetailSearch,
+}
+
+/// Structured formats for exporting the visible entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Html => "HTML",
+        }
+    }
+}
+
+/// What the filter and search regexes are tested against (M key toggles).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchScope {
+    /// The whole raw line
+    Raw,
+    /// The parsed message, or the raw line when the parser found none
+    Message,
+}
+
+/// How entry timestamps are shown in the feed (t key cycles).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeDisplay {
+    Absolute,
+    Relative,
+    None,
+}
+
+impl TimeDisplay {
+    pub fn next(self) -> Self {
+        match self {
+            TimeDisplay::None => TimeDisplay::Absolute,
+            TimeDisplay::Absolute => TimeDisplay::Relative,
+            TimeDisplay::Relative => TimeDisplay::None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ViewMode {
     Feed,
     Detail,
+    Help,
+    Stats,
+    FormatPicker,
 }
 
 pub struct App {
@@ -59,33 +307,169 @@ pub struct App {
     pub scroll_offset: usize,
     pub selected_index: usize,
     pub frozen: bool,
+    // Entries that arrived while frozen, shown in the header
+    pub paused_new: u64,
     pub error_only: bool,
+    // HTTP status facet (r key) — 5 shows only 5xx access-log lines, and so on;
+    // lines without a status pass
+    pub status_facet: Option<u16>,
+    // Minimum level (1-5 keys, L cycles) — entries below the floor are hidden;
+    // a `level:warn+` filter token sets `filter_min_level` on top of it
+    pub min_level: Option<LogLevel>,
+    pub filter_min_level: Option<LogLevel>,
     pub input_mode: InputMode,
     pub view_mode: ViewMode,
     pub filter_text: String,
-    pub filter_regex: Option<Regex>,
+    // Filter terms compiled from `filter_text`, and the first invalid one
+    pub filter: FilterMatcher,
+    pub filter_error: Option<String>,
+    /// `svc:NAME` tokens from the filter — matches entries whose source starts with NAME
+    pub filter_sources: Vec<String>,
+    // Submitted filters, searches and highlights (Up/Down in their prompts)
+    pub prompt_history: PromptHistories,
     pub filename: String,
+    /// Name of the parser in use (saved with sessions)
+    pub format_name: String,
     pub error_count: u64,
     pub total_count: u64,
     pub eps_history: VecDeque<u64>,
+    // Sparkline window in seconds, the length of every EPS history (+/- keys)
+    pub eps_window_secs: usize,
     pub current_eps: u64,
     eps_counter: u64,
     eps_last_tick: Instant,
+    // Stats view (i key) — per-level totals and EPS history, indexed by `level as usize`
+    pub level_counts: [u64; 7],
+    pub level_eps_counters: [VecDeque<u64>; 7],
+    level_eps_tick: [u64; 7],
+    // Top messages in the stats view, counted by shape as lines arrive;
+    // ↑/↓ pick a row and Enter filters on it
+    pub message_stats: MessageStats,
+    pub stats_selected: usize,
+    // Help overlay (F1 / H) — first row shown, and the furthest it can
+    // scroll given the rows and size of the last draw
+    pub help_scroll: usize,
+    pub help_max_scroll: Cell<usize>,
+    // Recent-errors popup (p), drawn over whatever view is open; any key
+    // closes it without touching the scroll position or filters
+    pub error_peek: bool,
+    // Plain detail view: first line shown, and the `/` search over its
+    // text, whose matching lines n/N jump between
+    pub detail_scroll: usize,
+    pub detail_search: Option<Regex>,
+    // Format picker, opened at startup when detection is unsure: one label
+    // per candidate, the highlighted row and, once chosen, its index
+    pub format_choices: Vec<String>,
+    pub format_selected: usize,
+    pub format_picked: Option<usize>,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
     pub should_quit: bool,
-    pub history: Option<FileHistory>,
+    pub history: Option<History>,
     pub needs_history_load: bool,
     pub horizontal_scroll: usize,
+    // Horizontal fit (A key) — longest row of the feed window and the width it
+    // had, recorded on each draw; W refits every frame while following the tail
+    pub max_visible_line_len: Cell<usize>,
+    pub feed_width: Cell<usize>,
+    pub auto_fit: bool,
+    // Entries that fit in the feed on the last draw — the PgUp/PgDn step;
+    // fewer than its rows when entries wrap or are expanded
+    pub viewport_height: Cell<usize>,
+    // Indices of the visible entries, rebuilt by `visible_logs` when
+    // `cache_dirty` is set, so the several lookups of a frame share one scan
+    visible_cache: RefCell<Vec<usize>>,
+    cache_dirty: Cell<bool>,
+    pub time_display: TimeDisplay,
+    // Absolute time column: widest timestamp in the buffer, capped at the max
+    pub timestamp_width: usize,
+    pub timestamp_max_width: usize,
     // Multiline grouping
     pub has_structured_logs: bool,
+    // Dedup (D key) — collapse consecutive identical lines; first index → repeat count
+    pub deduplicate: bool,
+    pub dedup_counts: HashMap<usize, usize>,
+    // Set with `cache_dirty`: the counts are stale until the next recount
+    dedup_dirty: Cell<bool>,
+    // Grouping (G key) — entries with the same message template collapse into
+    // the first; its index → the group, and the indices it hides
+    pub group_similar: bool,
+    pub groups: HashMap<usize, GroupedEntry>,
+    group_hidden: HashSet<usize>,
+    template_cache: HashMap<String, String>,
+    // Table layout (Tab key) — fixed Timestamp/Level/Message/Metadata columns
+    pub table_view: bool,
+    // Line wrapping (w key) — long rows continue on `↳` rows instead of scrolling
+    pub wrap_lines: bool,
+    // Raw lines (R key) — the feed shows `raw` instead of level tag + message
+    pub show_raw: bool,
+    // Level sparklines (v key) — the header grows a row of per-level activity
+    pub spark_detail: bool,
+    // Detail view of a JSON entry as a collapsible tree
+    pub detail_tree: Option<JsonTreeState>,
+    // Filter / search target (M key)
+    pub match_scope: MatchScope,
+    // Feed key bindings, remappable via [keybindings]
+    pub keymap: KeyMap,
+    // Colors (--theme / [theme])
+    pub theme: Theme,
     // Search (? key)
     pub search_text: String,
     pub search_regex: Option<Regex>,
+    // Numeric sort (o key) — visible entries ordered by a captured number, descending
+    pub sort_regex: Option<Regex>,
+    // Correlation tracking (I key) — the filter it set and the banner label
+    pub correlation: Option<Correlation>,
+    // Grep context (] / [ keys) — entries shown before and after each filter match
+    pub context_lines: usize,
+    // Fuzzy search (Ctrl+F) — hides weak matches, best matches first
+    pub fuzzy_search_query: String,
     // Highlight (* key)
     pub highlights: Vec<(Regex, Color)>,
+    pub highlight_colors: Vec<Color>,
+    // Bookmarks (b key) — indices into `logs`, kept in sync as the buffer shifts
+    pub bookmarks: BTreeSet<usize>,
+    // Flags (f key) — like bookmarks, but persisted for local files via `flag_store`
+    pub flagged: BTreeSet<usize>,
+    pub flagged_only: bool,
+    pub flag_store: Option<FlagStore>,
+    // Deploy diff — error signatures snapshotted with z; Z shows only errors not in it
+    pub error_signature_snapshot: HashSet<String>,
+    pub diff_mode: bool,
+    // Inline expansion (x key) — entries whose continuation lines show in the feed
+    pub expanded: BTreeSet<usize>,
     // Shared input buffer for Search/Highlight/SavePrompt
     pub input_buffer: String,
+    // Cursor in the text the current prompt edits, a byte offset; anything
+    // past the end (as set when a prompt opens) means the end
+    pub input_cursor: usize,
+    // Mouse: time and visible index of the last left click, for double-click
+    pub last_click: Option<(Instant, usize)>,
     // Temporary status message ("Copied!", "Saved 42 entries to file.log")
     pub status_message: Option<(String, Instant)>,
+    pub status_timeout: Duration,
+    // Ring buffer capacity (--buffer / max_lines)
+    pub max_lines: usize,
+    // --spill: evicted entries go to disk and `history` pages them back in;
+    // the first `spill_paged` entries of `logs` were read back from it
+    pub spill: Option<SpillFile>,
+    pub spill_paged: usize,
+    // Approximate memory per entry, sampled once a second while spilling
+    pub bytes_per_entry: usize,
+    // A project .logpulse.toml was merged into the config
+    pub project_config: bool,
+    // Connection state per host (multi-host ssh only)
+    pub hosts: Vec<Arc<AtomicBool>>,
+    // Connection state of a docker / ssh / k8s / http / CloudWatch source
+    pub conn: ConnStatus,
+    // `logpulse replay` pacing — Space pauses it, < / > change the speed
+    pub replay: Option<ReplayControl>,
+    // --keep-ansi: entry.raw keeps colour codes and the feed renders them
+    pub keep_ansi: bool,
+    // --since: entries with an older timestamp are dropped on arrival
+    pub since: Option<DateTime<Local>>,
+    // The last timestamped entry was dropped by `since`, so its continuation lines are too
+    skipping_old: bool,
 }
 
 impl App {
@@ -95,43 +479,240 @@ impl App {
             scroll_offset: 0,
             selected_index: 0,
             frozen: false,
+            paused_new: 0,
             error_only: false,
+            min_level: None,
+            status_facet: None,
+            filter_min_level: None,
             input_mode: InputMode::Normal,
             view_mode: ViewMode::Feed,
             filter_text: String::new(),
-            filter_regex: None,
+            filter: FilterMatcher::default(),
+            filter_error: None,
+            filter_sources: Vec::new(),
+            prompt_history: PromptHistories::default(),
             filename,
+            format_name: String::new(),
             error_count: 0,
             total_count: 0,
-            eps_history: VecDeque::from(vec![0; EPS_WINDOW_SECS]),
+            eps_history: VecDeque::from(vec![0; DEFAULT_EPS_WINDOW_SECS]),
+            eps_window_secs: DEFAULT_EPS_WINDOW_SECS,
             current_eps: 0,
             eps_counter: 0,
             eps_last_tick: Instant::now(),
+            level_counts: [0; 7],
+            level_eps_counters: std::array::from_fn(|_| {
+                VecDeque::from(vec![0; DEFAULT_EPS_WINDOW_SECS])
+            }),
+            level_eps_tick: [0; 7],
+            message_stats: MessageStats::default(),
+            stats_selected: 0,
+            first_timestamp: None,
+            last_timestamp: None,
+            help_scroll: 0,
+            help_max_scroll: Cell::new(0),
+            error_peek: false,
+            detail_scroll: 0,
+            detail_search: None,
+            format_choices: Vec::new(),
+            format_selected: 0,
+            format_picked: None,
             should_quit: false,
             history: None,
             needs_history_load: false,
             horizontal_scroll: 0,
+            max_visible_line_len: Cell::new(0),
+            feed_width: Cell::new(0),
+            viewport_height: Cell::new(0),
+            visible_cache: RefCell::new(Vec::new()),
+            cache_dirty: Cell::new(true),
+            auto_fit: false,
+            time_display: TimeDisplay::None,
+            timestamp_width: 0,
+            timestamp_max_width: DEFAULT_TIMESTAMP_MAX_WIDTH,
             has_structured_logs: false,
+            deduplicate: false,
+            dedup_counts: HashMap::new(),
+            dedup_dirty: Cell::new(true),
+            group_similar: false,
+            groups: HashMap::new(),
+            group_hidden: HashSet::new(),
+            template_cache: HashMap::new(),
+            table_view: false,
+            wrap_lines: false,
+            show_raw: false,
+            spark_detail: false,
+            detail_tree: None,
+            match_scope: MatchScope::Raw,
+            keymap: KeyMap::default(),
+            theme: Theme::default(),
             search_text: String::new(),
             search_regex: None,
+            sort_regex: None,
+            correlation: None,
+            context_lines: 0,
+            fuzzy_search_query: String::new(),
             highlights: Vec::new(),
+            highlight_colors: HIGHLIGHT_COLORS.to_vec(),
+            bookmarks: BTreeSet::new(),
+            flagged: BTreeSet::new(),
+            flagged_only: false,
+            error_signature_snapshot: HashSet::new(),
+            diff_mode: false,
+            flag_store: None,
+            expanded: BTreeSet::new(),
             input_buffer: String::new(),
+            input_cursor: usize::MAX,
+            last_click: None,
             status_message: None,
+            status_timeout: Duration::from_secs(STATUS_TIMEOUT_SECS),
+            max_lines: MAX_LOG_LINES,
+            spill: None,
+            spill_paged: 0,
+            bytes_per_entry: 0,
+            project_config: false,
+            hosts: Vec::new(),
+            conn: ConnStatus::default(),
+            replay: None,
+            keep_ansi: false,
+            since: None,
+            skipping_old: false,
+        }
+    }
+
+    /// Apply user configuration (buffer size, EPS window, colors, theme,
+    /// starting filter, timeouts).
+    pub fn apply_config(&mut self, config: &Config) {
+        self.max_lines = config.max_lines.max(1);
+        self.set_eps_window(config.eps_window_secs);
+        if let Ok(theme) = config.theme() {
+            self.theme = theme;
+        }
+        // The theme's palette unless `highlight_colors` was changed
+        self.highlight_colors = self.theme.palette.to_vec();
+        if config.highlight_colors != Config::default().highlight_colors {
+            if let Ok(colors) = config.highlight_colors() {
+                self.highlight_colors = colors;
+            }
+        }
+        if let Ok(auto) = config.auto_highlights() {
+            for (pattern, color) in auto {
+                self.add_highlight(pattern, Some(color));
+            }
+        }
+        if !config.filter.is_empty() {
+            self.filter_text = config.filter.clone();
+            self.update_filter_regex();
+        }
+        self.status_timeout = Duration::from_secs(config.status_timeout_secs);
+        self.project_config = config.project_path.is_some();
+        self.timestamp_max_width = config.timestamp_max_width.max(1);
+    }
+
+    /// Filters and view restrictions currently narrowing the feed.
+    pub fn active_filters(&self) -> Vec<String> {
+        let mut active = Vec::new();
+        if !self.filter_text.is_empty() {
+            active.push(format!("filter /{}", self.filter_text));
         }
+        if self.error_only {
+            active.push("errors only".to_string());
+        }
+        if let Some(level) = self.effective_min_level() {
+            active.push(format!("level ≥ {}", level.name()));
+        }
+        if let Some(class) = self.status_facet {
+            active.push(format!("status {}xx", class));
+        }
+        if self.flagged_only {
+            active.push("flagged only".to_string());
+        }
+        if self.diff_mode {
+            active.push("new errors since snapshot".to_string());
+        }
+        if self.deduplicate {
+            active.push("dedup".to_string());
+        }
+        if let Some(ref re) = self.sort_regex {
+            active.push(format!("sorted by {}", re.as_str()));
+        }
+        active
+    }
+
+    /// Hosts currently connected in a multi-host ssh session.
+    pub fn connected_hosts(&self) -> usize {
+        self.hosts
+            .iter()
+            .filter(|h| h.load(Ordering::Relaxed))
+            .count()
+    }
+
+    /// Replace the buffer with a saved session, frozen since nothing new arrives.
+    pub fn load_session(&mut self, session: Session) {
+        self.filename = format!("{} (session {})", session.filename, session.saved_at);
+        self.format_name = session.format;
+        self.logs = session.logs.into();
+        self.invalidate_visible();
+        let real = self.logs.iter().filter(|e| !e.divider);
+        self.total_count = real.clone().count() as u64;
+        self.error_count = real
+            .filter(|e| matches!(e.level, LogLevel::Error | LogLevel::Fatal))
+            .count() as u64;
+        self.bookmarks = session
+            .bookmarks
+            .into_iter()
+            .filter(|&i| i < self.logs.len())
+            .collect();
+        self.max_lines = self.max_lines.max(self.logs.len());
+        self.timestamp_width = 0;
+        for i in 0..self.logs.len() {
+            self.widen_timestamp_column(i);
+        }
+        self.frozen = true;
     }
 
+    /// Tests add hand-built entries without a parser: an Unknown-level line
+    /// continues the one before.
+    #[cfg(test)]
     pub fn add_log(&mut self, entry: LogEntry) {
+        let continues = entry.level == LogLevel::Unknown;
+        self.add_entry(entry, continues);
+    }
+
+    /// Add a line `parser` just parsed; the parser says whether it begins an
+    /// entry or continues the previous one.
+    pub fn add_parsed_log(&mut self, entry: LogEntry, parser: &dyn LogParser) {
+        let continues = !parser.starts_new_entry(&strip_ansi(&entry.raw));
+        self.add_entry(entry, continues);
+    }
+
+    fn add_entry(&mut self, entry: LogEntry, continues: bool) {
+        if let Some(cutoff) = self.since {
+            match entry.ts_parsed {
+                Some(ts) => self.skipping_old = ts < cutoff,
+                // Continuation lines follow their parent entry
+                None if continues => {}
+                None => self.skipping_old = false,
+            }
+            if self.skipping_old {
+                return;
+            }
+        }
+
         if !matches!(entry.level, LogLevel::Unknown) {
             self.has_structured_logs = true;
         }
 
         // Multiline grouping: in structured logs (Laravel, JSON, Go, etc.),
-        // any Unknown-level line after a known-level entry is a continuation
-        // (stack trace, JSON body, PHP [stacktrace], etc.)
-        if self.has_structured_logs && entry.level == LogLevel::Unknown {
+        // a continuation line after a known-level entry joins it (stack
+        // trace, JSON body, PHP [stacktrace], etc.)
+        // Only lines from the same source are joined when streams are merged.
+        if self.has_structured_logs && continues {
             if let Some(last) = self.logs.back_mut() {
-                if last.level != LogLevel::Unknown {
+                if last.level != LogLevel::Unknown && last.source == entry.source {
                     last.extra_lines.push(entry.raw);
+                    // It may now match the filter through the new line
+                    self.invalidate_visible();
                     return;
                 }
             }
@@ -142,14 +723,154 @@ impl App {
         }
         self.total_count += 1;
         self.eps_counter += 1;
+        self.level_counts[entry.level as usize] += 1;
+        self.level_eps_tick[entry.level as usize] += 1;
+        self.message_stats
+            .record(entry.message.as_deref().unwrap_or(&entry.raw));
+        if self.frozen {
+            self.paused_new += 1;
+        }
+        if let Some(ref ts) = entry.timestamp {
+            if self.first_timestamp.is_none() {
+                self.first_timestamp = Some(ts.clone());
+            }
+            self.last_timestamp = Some(ts.clone());
+        }
+
+        self.push_entry(entry);
+    }
+
+    /// Indices of the last `n` Error/Fatal entries in the buffer, oldest
+    /// first, ignoring filters.
+    pub fn recent_errors(&self, n: usize) -> Vec<usize> {
+        let mut found: Vec<usize> = self
+            .logs
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, entry)| matches!(entry.level, LogLevel::Error | LogLevel::Fatal))
+            .map(|(idx, _)| idx)
+            .take(n)
+            .collect();
+        found.reverse();
+        found
+    }
 
-        if self.logs.len() >= MAX_LOG_LINES {
-            self.logs.pop_front();
+    /// Space: pause the view, or resume following the tail. Lines keep
+    /// arriving into the buffer either way.
+    pub fn toggle_pause(&mut self) {
+        self.frozen = !self.frozen;
+        // A replay stops emitting too, so it resumes where it was paused
+        if let Some(ref replay) = self.replay {
+            replay.set_paused(self.frozen);
+        }
+        if !self.frozen {
+            self.paused_new = 0;
+            self.insert_divider("resumed");
+        }
+    }
+
+    /// Insert a divider marker (not counted in stats, skipped by search).
+    pub fn insert_divider(&mut self, label: &str) {
+        self.push_entry(LogEntry::divider(label));
+    }
+
+    /// Append to the ring buffer, evicting the oldest entry when full.
+    /// While frozen the selection follows its entry as the front is evicted,
+    /// so the paused view stays put (by position when sorted).
+    fn push_entry(&mut self, entry: LogEntry) {
+        self.invalidate_visible();
+        if self.logs.len() >= self.max_lines + self.spill_paged {
+            // Entries paged back in from the spill stay; the oldest live one goes
+            let idx = self.spill_paged;
+            if self.frozen && self.matches_filter(idx, &self.logs[idx]) {
+                let position = (0..idx)
+                    .filter(|&i| self.matches_filter(i, &self.logs[i]))
+                    .count();
+                if position < self.selected_index {
+                    self.selected_index -= 1;
+                }
+            }
+            if let Some(evicted) = self.logs.remove(idx) {
+                self.spill_entry(&evicted);
+            }
             if self.scroll_offset > 0 {
                 self.scroll_offset = self.scroll_offset.saturating_sub(1);
             }
+            shift_marks_down(&mut self.bookmarks, idx);
+            shift_marks_down(&mut self.flagged, idx);
+            shift_marks_down(&mut self.expanded, idx);
         }
+        let is_flagged = self
+            .flag_store
+            .as_ref()
+            .is_some_and(|f| f.contains(&entry.raw));
         self.logs.push_back(entry);
+        if is_flagged {
+            self.flagged.insert(self.logs.len() - 1);
+        }
+        self.widen_timestamp_column(self.logs.len() - 1);
+    }
+
+    /// Write an evicted entry to the spill file, if there is one.
+    fn spill_entry(&mut self, entry: &LogEntry) {
+        let Some(ref mut spill) = self.spill else {
+            return;
+        };
+        if entry.divider {
+            return;
+        }
+        if let Err(e) = spill.append(&entry.raw, &entry.extra_lines) {
+            self.spill = None;
+            self.set_status(format!("Spill file write failed, spilling stopped: {}", e));
+            return;
+        }
+        // Nothing paged in: scrolling up continues right below the new end
+        if self.spill_paged == 0 {
+            if let Some(history) = self.history.as_mut().and_then(History::spill_mut) {
+                history.set_offset(spill.bytes());
+            }
+        }
+    }
+
+    /// Start spilling evicted entries to `spill`. Scrolling up reads them
+    /// back first, then the source's own scrollback.
+    pub fn enable_spill(&mut self, spill: SpillFile) {
+        let older = self.history.take().map(Box::new);
+        self.history = Some(History::Spilled(spill.history(), older));
+        self.spill = Some(spill);
+    }
+
+    /// Drop entries paged back in from the spill (they are still on disk),
+    /// so memory goes back to the buffer size.
+    fn release_spilled(&mut self) {
+        if self.spill_paged == 0 {
+            return;
+        }
+        let count = self.spill_paged;
+        self.logs.drain(..count);
+        self.invalidate_visible();
+        self.spill_paged = 0;
+        for marks in [&mut self.bookmarks, &mut self.flagged, &mut self.expanded] {
+            *marks = marks
+                .iter()
+                .filter(|&&i| i >= count)
+                .map(|&i| i - count)
+                .collect();
+        }
+        self.scroll_offset = 0;
+        self.clamp_selection();
+        if let (Some(spill), Some(history)) = (&self.spill, &mut self.history) {
+            history.restart_spilled(spill.bytes());
+        }
+    }
+
+    /// Grow the time column to fit the timestamp of `logs[idx]`.
+    fn widen_timestamp_column(&mut self, idx: usize) {
+        if let Some(ref ts) = self.logs[idx].timestamp {
+            let width = ts.chars().count().min(self.timestamp_max_width);
+            self.timestamp_width = self.timestamp_width.max(width);
+        }
     }
 
     pub fn tick_eps(&mut self) {
@@ -159,165 +880,809 @@ impl App {
             self.eps_history.pop_front();
             self.eps_history.push_back(self.eps_counter);
             self.eps_counter = 0;
+            for (history, count) in self
+                .level_eps_counters
+                .iter_mut()
+                .zip(self.level_eps_tick.iter_mut())
+            {
+                history.pop_front();
+                history.push_back(std::mem::take(count));
+            }
             self.eps_last_tick = now;
+            if self.spill.is_some() && !self.logs.is_empty() {
+                let total: usize = self.logs.iter().map(entry_size).sum();
+                self.bytes_per_entry = total / self.logs.len();
+            }
         }
     }
 
-    pub fn update_filter_regex(&mut self) {
-        self.filter_regex = if self.filter_text.is_empty() {
-            None
+    /// Resize the sparkline and per-level EPS histories to `secs`, dropping
+    /// the oldest seconds or padding with empty ones.
+    pub fn set_eps_window(&mut self, secs: usize) {
+        let secs = secs.max(1);
+        self.eps_window_secs = secs;
+        for history in std::iter::once(&mut self.eps_history).chain(&mut self.level_eps_counters) {
+            while history.len() > secs {
+                history.pop_front();
+            }
+            while history.len() < secs {
+                history.push_front(0);
+            }
+        }
+    }
+
+    /// +/-: step to the next wider or narrower sparkline window.
+    pub fn zoom_eps_window(&mut self, wider: bool) {
+        let next = if wider {
+            EPS_WINDOW_STEPS.iter().find(|&&s| s > self.eps_window_secs)
         } else {
-            Regex::new(&format!("(?i){}", &self.filter_text))
-                .or_else(|_| Regex::new(&format!("(?i){}", regex::escape(&self.filter_text))))
-                .ok()
+            EPS_WINDOW_STEPS
+                .iter()
+                .rev()
+                .find(|&&s| s < self.eps_window_secs)
         };
+        if let Some(&secs) = next {
+            self.set_eps_window(secs);
+        }
     }
 
-    fn matches_filter(&self, entry: &LogEntry) -> bool {
-        if self.error_only && !matches!(entry.level, LogLevel::Error | LogLevel::Fatal) {
-            return false;
-        }
-        if let Some(ref re) = self.filter_regex {
-            if !re.is_match(&entry.raw) && !entry.extra_lines.iter().any(|l| re.is_match(l)) {
-                return false;
-            }
-        }
-        true
+    /// Lowest, mean and highest events per second over the sparkline window.
+    pub fn eps_summary(&self) -> (u64, f64, u64) {
+        let min = self.eps_history.iter().copied().min().unwrap_or(0);
+        let max = self.eps_history.iter().copied().max().unwrap_or(0);
+        let sum: u64 = self.eps_history.iter().sum();
+        (min, sum as f64 / self.eps_history.len().max(1) as f64, max)
     }
 
-    pub fn visible_logs(&self) -> Vec<(usize, &LogEntry)> {
-        self.logs
-            .iter()
-            .enumerate()
-            .filter(|(_, entry)| self.matches_filter(entry))
-            .collect()
+    /// Errors and fatals as a share of every line received.
+    pub fn error_rate_percent(&self) -> f64 {
+        self.error_count as f64 * 100.0 / self.total_count.max(1) as f64
     }
 
-    pub fn visible_count(&self) -> usize {
-        self.logs
+    /// The same share over the sparkline window only.
+    pub fn recent_error_rate_percent(&self) -> f64 {
+        let errors: u64 = [LogLevel::Error, LogLevel::Fatal]
             .iter()
-            .filter(|entry| self.matches_filter(entry))
-            .count()
+            .flat_map(|&level| &self.level_eps_counters[level as usize])
+            .sum();
+        let total: u64 = self.eps_history.iter().sum();
+        errors as f64 * 100.0 / total.max(1) as f64
     }
 
-    pub fn clamp_selection(&mut self) {
-        let count = self.visible_count();
-        if count == 0 {
-            self.selected_index = 0;
-        } else if self.selected_index >= count {
-            self.selected_index = count - 1;
-        }
+    /// Oldest and newest parsed timestamps among the buffered entries.
+    pub fn buffer_time_span(&self) -> Option<(DateTime<Local>, DateTime<Local>)> {
+        let first = self.logs.iter().find_map(|e| e.ts_parsed)?;
+        let last = self.logs.iter().rev().find_map(|e| e.ts_parsed)?;
+        Some((first, last))
     }
 
-    pub fn clear_logs(&mut self) {
-        self.logs.clear();
-        self.scroll_offset = 0;
-        self.selected_index = 0;
+    /// Enter in the stats view: filter the feed on the selected top message's
+    /// shape and return to it. Returns the shape.
+    pub fn filter_top_message(&mut self) -> Option<String> {
+        let shape = self
+            .message_stats
+            .top(TOP_MESSAGES)
+            .get(self.stats_selected)?
+            .0
+            .to_string();
+        self.filter_text = shape_filter(&shape);
+        self.prompt_history.filter.push(&self.filter_text);
+        self.update_filter_regex();
+        self.clamp_selection();
+        self.view_mode = ViewMode::Feed;
+        Some(shape)
     }
 
-    pub fn scroll_down(&mut self) {
-        let count = self.visible_count();
-        if count > 0 && self.selected_index < count - 1 {
-            self.selected_index += 1;
+    /// The stricter of the key-set floor and a `level:...+` filter token.
+    pub fn effective_min_level(&self) -> Option<LogLevel> {
+        match (self.min_level, self.filter_min_level) {
+            (Some(a), Some(b)) => Some(if a as u8 >= b as u8 { a } else { b }),
+            (a, b) => a.or(b),
         }
     }
 
-    pub fn scroll_up(&mut self) {
-        if self.selected_index > 0 {
-            self.selected_index -= 1;
-        } else if self.history.as_ref().is_some_and(|h| h.has_more()) {
-            self.needs_history_load = true;
+    /// Set or clear the minimum level. The selection stays on its entry when
+    /// that still passes (else moves to the next one that does), and keeps
+    /// following the tail if it was.
+    pub fn set_min_level(&mut self, level: Option<LogLevel>) {
+        let visible = self.visible_logs();
+        let following = !self.frozen && self.selected_index + 1 >= visible.len();
+        let selected = visible.get(self.selected_index).map(|(idx, _)| *idx);
+        self.min_level = level;
+        self.invalidate_visible();
+        let visible = self.visible_logs();
+        let position = selected.and_then(|sel| {
+            visible
+                .iter()
+                .position(|(idx, _)| *idx == sel)
+                .or_else(|| visible.iter().position(|(idx, _)| *idx > sel))
+        });
+        match position {
+            Some(pos) if !following => self.selected_index = pos,
+            _ => self.selected_index = visible.len().saturating_sub(1),
         }
     }
 
-    pub fn page_down(&mut self, page_size: usize) {
-        let count = self.visible_count();
-        if count > 0 {
-            self.selected_index = (self.selected_index + page_size).min(count - 1);
+    /// r: all, then 5xx, 4xx, 3xx, 2xx and back to all.
+    pub fn cycle_status_facet(&mut self) {
+        self.status_facet = match self.status_facet {
+            None => Some(5),
+            Some(class) if class > 2 => Some(class - 1),
+            Some(_) => None,
+        };
+        self.clamp_selection();
+    }
+
+    /// L: no floor, then each of [`LogLevel::FLOORS`] in turn.
+    pub fn cycle_min_level(&mut self) {
+        let next = match self.min_level {
+            None => Some(LogLevel::FLOORS[0]),
+            Some(level) => LogLevel::FLOORS
+                .iter()
+                .position(|&l| l == level)
+                .and_then(|i| LogLevel::FLOORS.get(i + 1).copied()),
+        };
+        self.set_min_level(next);
+    }
+
+    /// Compile `filter_text`: regex terms (all must match), `!term` excludes,
+    /// `"quoted phrases"`, `svc:NAME` and `level:NAME+`. An invalid term is
+    /// left out and reported in `filter_error`.
+    pub fn update_filter_regex(&mut self) {
+        self.invalidate_visible();
+        self.filter = FilterMatcher::default();
+        self.filter_error = None;
+        self.filter_sources.clear();
+        self.filter_min_level = None;
+        for token in parse_filter(&self.filter_text) {
+            let (list, compiled) = match token.term {
+                FilterTerm::Include(re) => (&mut self.filter.include, re),
+                FilterTerm::Exclude(re) => (&mut self.filter.exclude, re),
+                FilterTerm::Source(name) => {
+                    self.filter_sources.push(name);
+                    continue;
+                }
+                FilterTerm::MinLevel(level) => {
+                    self.filter_min_level = Some(level);
+                    continue;
+                }
+            };
+            match compiled {
+                Ok(re) => list.push(re),
+                Err(e) => {
+                    self.filter_error.get_or_insert(e);
+                }
+            }
         }
     }
 
-    pub fn page_up(&mut self, page_size: usize) {
-        self.selected_index = self.selected_index.saturating_sub(page_size);
-        if self.selected_index == 0 && self.history.as_ref().is_some_and(|h| h.has_more()) {
-            self.needs_history_load = true;
+    // --- Filter history ---
+
+    // --- Prompt editing ---
+
+    /// History of the open prompt, if it keeps one.
+    fn prompt_history_mut(&mut self) -> Option<&mut PromptHistory> {
+        match self.input_mode {
+            InputMode::Filter => Some(&mut self.prompt_history.filter),
+            InputMode::Search => Some(&mut self.prompt_history.search),
+            InputMode::Highlight => Some(&mut self.prompt_history.highlight),
+            _ => None,
         }
     }
 
-    pub fn jump_to_start(&mut self) {
-        self.selected_index = 0;
-        if self.history.as_ref().is_some_and(|h| h.has_more()) {
-            self.needs_history_load = true;
+    /// Up arrow in a prompt: recall the previous submitted value.
+    pub fn prompt_history_prev(&mut self) {
+        if let Some(text) = self.prompt_history_mut().and_then(PromptHistory::older) {
+            self.recall_input(text);
         }
     }
 
-    pub fn jump_to_end(&mut self) {
-        let count = self.visible_count();
-        if count > 0 {
-            self.selected_index = count - 1;
+    /// Down arrow in a prompt: recall the next value, or clear past the newest.
+    pub fn prompt_history_next(&mut self) {
+        if let Some(text) = self.prompt_history_mut().and_then(PromptHistory::newer) {
+            self.recall_input(text);
         }
     }
 
-    pub fn scroll_right(&mut self) {
-        self.horizontal_scroll += 20;
+    /// Stop stepping through the prompt's history (it was edited or reopened).
+    pub fn reset_prompt_history(&mut self) {
+        if let Some(history) = self.prompt_history_mut() {
+            history.reset();
+        }
     }
 
-    pub fn scroll_left(&mut self) {
-        self.horizontal_scroll = self.horizontal_scroll.saturating_sub(20);
+    fn recall_input(&mut self, text: String) {
+        *self.input_text_mut() = text;
+        self.input_cursor = usize::MAX;
+        if self.input_mode == InputMode::Filter {
+            self.update_filter_regex();
+        }
     }
 
-    pub fn prepend_logs(&mut self, entries: Vec<LogEntry>) {
-        let count = entries.len();
-        if count == 0 {
-            return;
+    /// The text the current prompt edits.
+    pub fn input_text(&self) -> &str {
+        match self.input_mode {
+            InputMode::Filter => &self.filter_text,
+            InputMode::FuzzySearch => &self.fuzzy_search_query,
+            _ => &self.input_buffer,
         }
-        for entry in entries.into_iter().rev() {
-            self.logs.push_front(entry);
-            if self.logs.len() > MAX_LOG_LINES {
-                self.logs.pop_back();
-            }
+    }
+
+    fn input_text_mut(&mut self) -> &mut String {
+        match self.input_mode {
+            InputMode::Filter => &mut self.filter_text,
+            InputMode::FuzzySearch => &mut self.fuzzy_search_query,
+            _ => &mut self.input_buffer,
         }
-        self.selected_index = count;
     }
 
-    // --- Search ---
+    /// The cursor clamped into the prompt's text.
+    pub fn input_cursor_pos(&self) -> usize {
+        self.input_cursor.min(self.input_text().len())
+    }
 
-    pub fn update_search_regex(&mut self) {
-        self.search_regex = if self.search_text.is_empty() {
-            None
-        } else {
-            Regex::new(&format!("(?i){}", &self.search_text))
-                .or_else(|_| Regex::new(&format!("(?i){}", regex::escape(&self.search_text))))
-                .ok()
+    /// Insert at the cursor (typing or a paste) and move past the new text.
+    pub fn input_insert(&mut self, text: &str) {
+        let at = self.input_cursor_pos();
+        self.input_text_mut().insert_str(at, text);
+        self.input_cursor = at + text.len();
+    }
+
+    /// Backspace: delete the character before the cursor.
+    pub fn input_backspace(&mut self) -> bool {
+        let at = self.input_cursor_pos();
+        let Some(c) = self.input_text()[..at].chars().next_back() else {
+            return false;
         };
+        let start = at - c.len_utf8();
+        self.input_text_mut().remove(start);
+        self.input_cursor = start;
+        true
     }
 
-    pub fn search_next(&mut self) {
-        if let Some(ref re) = self.search_regex {
-            let visible = self.visible_logs();
-            if visible.is_empty() {
-                return;
-            }
-            let start = self.selected_index + 1;
-            for i in 0..visible.len() {
-                let idx = (start + i) % visible.len();
-                if let Some((_, entry)) = visible.get(idx) {
-                    if re.is_match(&entry.raw) || entry.extra_lines.iter().any(|l| re.is_match(l)) {
-                        self.selected_index = idx;
-                        return;
-                    }
-                }
-            }
+    /// Delete: remove the character under the cursor.
+    pub fn input_delete(&mut self) -> bool {
+        let at = self.input_cursor_pos();
+        if at == self.input_text().len() {
+            return false;
         }
+        self.input_text_mut().remove(at);
+        self.input_cursor = at;
+        true
     }
 
-    pub fn search_prev(&mut self) {
-        if let Some(ref re) = self.search_regex {
-            let visible = self.visible_logs();
-            if visible.is_empty() {
-                return;
-            }
-            let start = if self.selected_index == 0 {
+    pub fn input_left(&mut self) {
+        let at = self.input_cursor_pos();
+        let step = self.input_text()[..at]
+            .chars()
+            .next_back()
+            .map_or(0, char::len_utf8);
+        self.input_cursor = at - step;
+    }
+
+    pub fn input_right(&mut self) {
+        let at = self.input_cursor_pos();
+        let step = self.input_text()[at..]
+            .chars()
+            .next()
+            .map_or(0, char::len_utf8);
+        self.input_cursor = at + step;
+    }
+
+    /// Filter / search test: the line (per `match_scope`) or a continuation line.
+    fn regex_matches(&self, re: &Regex, entry: &LogEntry) -> bool {
+        let text = match self.match_scope {
+            MatchScope::Raw => &entry.raw,
+            MatchScope::Message => entry.message.as_deref().unwrap_or(&entry.raw),
+        };
+        re.is_match(text) || entry.extra_lines.iter().any(|l| re.is_match(l))
+    }
+
+    fn matches_filter(&self, idx: usize, entry: &LogEntry) -> bool {
+        // Dividers stay under a text filter, so a narrowed feed still shows
+        // where "new" begins; level, error and flag views show only entries
+        if entry.divider {
+            return !self.flagged_only
+                && !self.error_only
+                && !self.diff_mode
+                && self.effective_min_level().is_none();
+        }
+        if self.flagged_only && !self.flagged.contains(&idx) {
+            return false;
+        }
+        if self.error_only && !matches!(entry.level, LogLevel::Error | LogLevel::Fatal) {
+            return false;
+        }
+        if let Some(floor) = self.effective_min_level() {
+            if !entry.level.at_least(floor) {
+                return false;
+            }
+        }
+        if let (Some(class), Some(status)) = (self.status_facet, entry.status) {
+            if status / 100 != class {
+                return false;
+            }
+        }
+        if self.diff_mode && !self.is_new_error(entry) {
+            return false;
+        }
+        if !self.filter_sources.is_empty() {
+            let source = entry.source.as_deref().unwrap_or("");
+            if !self
+                .filter_sources
+                .iter()
+                .any(|s| source.starts_with(s.as_str()))
+            {
+                return false;
+            }
+        }
+        if !self
+            .filter
+            .include
+            .iter()
+            .all(|re| self.regex_matches(re, entry))
+        {
+            return false;
+        }
+        if self
+            .filter
+            .exclude
+            .iter()
+            .any(|re| self.regex_matches(re, entry))
+        {
+            return false;
+        }
+        if !self.fuzzy_search_query.is_empty()
+            && fuzzy_score(&self.fuzzy_search_query, &entry.raw).is_none()
+        {
+            return false;
+        }
+        true
+    }
+
+    /// Whether an entry outside the buffer passes the filters (--print).
+    pub fn passes_filters(&self, entry: &LogEntry) -> bool {
+        self.matches_filter(usize::MAX, entry)
+    }
+
+    /// Entries passing the current filters, without dedup collapsing
+    /// (what export writes, so repeats aren't lost).
+    pub fn filtered_logs(&self) -> Vec<(usize, &LogEntry)> {
+        self.logs
+            .iter()
+            .enumerate()
+            .filter(|(idx, entry)| self.matches_filter(*idx, entry))
+            .collect()
+    }
+
+    /// Entries shown in the feed, filtered, collapsed, sorted. The indices are
+    /// cached until [`App::invalidate_visible`].
+    pub fn visible_logs(&self) -> Vec<(usize, &LogEntry)> {
+        self.visible_indices()
+            .iter()
+            .map(|&idx| (idx, &self.logs[idx]))
+            .collect()
+    }
+
+    /// Buffer indices of the visible entries, in display order. Borrowed
+    /// from the cache, so reading it doesn't allocate.
+    pub fn visible_indices(&self) -> Ref<'_, [usize]> {
+        if self.cache_dirty.replace(false) {
+            let mut cache = self.visible_cache.borrow_mut();
+            cache.clear();
+            cache.extend(self.scan_visible().into_iter().map(|(idx, _)| idx));
+        }
+        Ref::map(self.visible_cache.borrow(), Vec::as_slice)
+    }
+
+    /// The entry at `position` in the visible list, with its buffer index.
+    pub fn visible_entry(&self, position: usize) -> Option<(usize, &LogEntry)> {
+        let idx = *self.visible_indices().get(position)?;
+        Some((idx, &self.logs[idx]))
+    }
+
+    pub fn selected_entry(&self) -> Option<(usize, &LogEntry)> {
+        self.visible_entry(self.selected_index)
+    }
+
+    fn scan_visible(&self) -> Vec<(usize, &LogEntry)> {
+        let shown = self.shown_entries();
+        let mut visible: Vec<(usize, &LogEntry)> = Vec::new();
+        for (idx, entry) in self.logs.iter().enumerate() {
+            if !shown[idx] || self.group_hidden.contains(&idx) {
+                continue;
+            }
+            if self.deduplicate
+                && visible
+                    .last()
+                    .is_some_and(|(_, prev)| is_repeat(prev, entry))
+            {
+                continue;
+            }
+            visible.push((idx, entry));
+        }
+        if let Some(ref re) = self.sort_regex {
+            sort_by_field(&mut visible, re);
+        }
+        if !self.fuzzy_search_query.is_empty() {
+            visible.sort_by_cached_key(|(_, entry)| {
+                std::cmp::Reverse(fuzzy_score(&self.fuzzy_search_query, &entry.raw))
+            });
+        }
+        visible
+    }
+
+    /// Buffer entries passing the filters, widened by `context_lines`
+    /// entries on either side of each match (like `grep -C`).
+    fn shown_entries(&self) -> Vec<bool> {
+        let matched: Vec<bool> = self
+            .logs
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| self.matches_filter(idx, entry))
+            .collect();
+        if self.context_lines == 0 {
+            return matched;
+        }
+        let mut shown = matched.clone();
+        for (idx, entry) in self.logs.iter().enumerate() {
+            if matched[idx] && !entry.divider {
+                let start = idx.saturating_sub(self.context_lines);
+                let end = (idx + self.context_lines).min(shown.len() - 1);
+                shown[start..=end].fill(true);
+            }
+        }
+        shown
+    }
+
+    /// Shown only as context around a filter match (drawn dimmed).
+    pub fn is_context_line(&self, idx: usize, entry: &LogEntry) -> bool {
+        self.context_lines > 0 && !self.matches_filter(idx, entry)
+    }
+
+    /// `]` / `[`: show more or fewer entries around each filter match.
+    pub fn step_context_lines(&mut self, more: bool) {
+        self.context_lines = if more {
+            (self.context_lines + 1).min(MAX_CONTEXT_LINES)
+        } else {
+            self.context_lines.saturating_sub(1)
+        };
+        self.clamp_selection();
+    }
+
+    pub fn visible_count(&self) -> usize {
+        self.visible_indices().len()
+    }
+
+    /// Recount collapsed repeats for dedup mode (first visible index → total occurrences).
+    /// Runs every tick, so it only rescans after [`App::invalidate_visible`].
+    pub fn update_dedup_counts(&mut self) {
+        if !self.dedup_dirty.replace(false) {
+            return;
+        }
+        self.dedup_counts.clear();
+        if !self.deduplicate {
+            return;
+        }
+        let mut counts = HashMap::new();
+        let mut first: Option<(usize, &LogEntry)> = None;
+        for (idx, entry) in self.logs.iter().enumerate() {
+            if !self.matches_filter(idx, entry) {
+                continue;
+            }
+            match first {
+                Some((first_idx, prev)) if is_repeat(prev, entry) => {
+                    *counts.entry(first_idx).or_insert(1) += 1;
+                }
+                _ => first = Some((idx, entry)),
+            }
+        }
+        self.dedup_counts = counts;
+    }
+
+    /// Regroup the filtered entries by message template for `group_similar`.
+    /// Templates are cached by message, as this runs on every tick.
+    pub fn update_groups(&mut self) {
+        self.groups.clear();
+        self.group_hidden.clear();
+        if !self.group_similar {
+            return;
+        }
+        if self.template_cache.len() > TEMPLATE_CACHE_CAP {
+            self.template_cache.clear();
+        }
+        let members: Vec<usize> = self
+            .logs
+            .iter()
+            .enumerate()
+            .filter(|(idx, entry)| !entry.divider && self.matches_filter(*idx, entry))
+            .map(|(idx, _)| idx)
+            .collect();
+        let message = |idx: usize| {
+            let entry = &self.logs[idx];
+            entry.message.as_deref().unwrap_or(&entry.raw)
+        };
+        for &idx in &members {
+            if !self.template_cache.contains_key(message(idx)) {
+                let template = message_template(message(idx));
+                self.template_cache
+                    .insert(message(idx).to_string(), template);
+            }
+        }
+        let mut by_template: HashMap<&str, GroupedEntry> = HashMap::new();
+        for idx in members {
+            let template = self.template_cache[message(idx)].as_str();
+            by_template
+                .entry(template)
+                .or_insert_with(|| GroupedEntry {
+                    template: template.to_string(),
+                    indices: Vec::new(),
+                })
+                .indices
+                .push(idx);
+        }
+        let groups: Vec<GroupedEntry> = by_template
+            .into_values()
+            .filter(|group| group.indices.len() > 1)
+            .collect();
+        for group in groups {
+            self.group_hidden.extend(&group.indices[1..]);
+            self.groups.insert(group.indices[0], group);
+        }
+        self.invalidate_visible();
+    }
+
+    /// Re-filter on the next `visible_logs` call. Needed after changing
+    /// anything the filters read; each key event starts with it.
+    pub fn invalidate_visible(&self) {
+        self.cache_dirty.set(true);
+        self.dedup_dirty.set(true);
+    }
+
+    /// Called after the filters change, so it re-filters first.
+    pub fn clamp_selection(&mut self) {
+        self.invalidate_visible();
+        let count = self.visible_count();
+        if count == 0 {
+            self.selected_index = 0;
+        } else if self.selected_index >= count {
+            self.selected_index = count - 1;
+        }
+    }
+
+    pub fn clear_logs(&mut self) {
+        self.release_spilled();
+        self.logs.clear();
+        self.invalidate_visible();
+        self.timestamp_width = 0;
+        self.bookmarks.clear();
+        self.flagged.clear();
+        self.expanded.clear();
+        self.scroll_offset = 0;
+        self.selected_index = 0;
+    }
+
+    /// Open the detail view for the selected entry (a tree for JSON).
+    pub fn open_detail(&mut self) {
+        self.detail_tree = self
+            .selected_entry()
+            .and_then(|(_, entry)| JsonTreeState::from_entry(entry));
+        self.detail_scroll = 0;
+        self.detail_search = None;
+        self.view_mode = ViewMode::Detail;
+    }
+
+    pub fn close_detail(&mut self) {
+        self.detail_tree = None;
+        self.detail_search = None;
+        self.view_mode = ViewMode::Feed;
+    }
+
+    /// Search the detail view for `pattern` (a regex, or literal text when it
+    /// doesn't compile), case-insensitively; empty clears the search.
+    pub fn set_detail_search(&mut self, pattern: &str) {
+        self.detail_search = if pattern.is_empty() {
+            None
+        } else {
+            Regex::new(&format!("(?i){}", pattern))
+                .or_else(|_| Regex::new(&format!("(?i){}", regex::escape(pattern))))
+                .ok()
+        };
+    }
+
+    /// Scroll the detail view to the next (or previous) of `lines` the
+    /// detail search matches, wrapping around. `from_current` lets the line
+    /// already at the top count. False when no line matches.
+    pub fn detail_search_jump(
+        &mut self,
+        lines: &[String],
+        forward: bool,
+        from_current: bool,
+    ) -> bool {
+        let Some(ref re) = self.detail_search else {
+            return false;
+        };
+        let matches: Vec<usize> = (0..lines.len())
+            .filter(|&i| re.is_match(&lines[i]))
+            .collect();
+        let at = self.detail_scroll;
+        let next = if forward {
+            matches
+                .iter()
+                .find(|&&i| i > at || (from_current && i == at))
+                .or(matches.first())
+        } else {
+            matches.iter().rev().find(|&&i| i < at).or(matches.last())
+        };
+        match next {
+            Some(&i) => {
+                self.detail_scroll = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn scroll_down(&mut self) {
+        let count = self.visible_count();
+        if count > 0 && self.selected_index < count - 1 {
+            self.selected_index += 1;
+        }
+    }
+
+    /// Move the help overlay by `rows`, within what the last draw could show.
+    pub fn scroll_help(&mut self, rows: isize) {
+        self.help_scroll = self
+            .help_scroll
+            .saturating_add_signed(rows)
+            .min(self.help_max_scroll.get());
+    }
+
+    pub fn scroll_up(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else if self.history.as_ref().is_some_and(|h| h.has_more()) {
+            self.needs_history_load = true;
+        }
+    }
+
+    pub fn page_down(&mut self, page_size: usize) {
+        let count = self.visible_count();
+        if count > 0 {
+            self.selected_index = (self.selected_index + page_size).min(count - 1);
+        }
+    }
+
+    pub fn page_up(&mut self, page_size: usize) {
+        self.selected_index = self.selected_index.saturating_sub(page_size);
+        if self.selected_index == 0 && self.history.as_ref().is_some_and(|h| h.has_more()) {
+            self.needs_history_load = true;
+        }
+    }
+
+    pub fn jump_to_start(&mut self) {
+        self.selected_index = 0;
+        if self.history.as_ref().is_some_and(|h| h.has_more()) {
+            self.needs_history_load = true;
+        }
+    }
+
+    pub fn jump_to_end(&mut self) {
+        self.release_spilled();
+        let count = self.visible_count();
+        if count > 0 {
+            self.selected_index = count - 1;
+        }
+    }
+
+    /// Scroll right `columns`, no further than it takes to bring the end of
+    /// the longest visible line (level prefix included) to the right edge.
+    pub fn scroll_right(&mut self, columns: usize) {
+        self.horizontal_scroll =
+            (self.horizontal_scroll + columns).min(self.max_horizontal_scroll());
+    }
+
+    pub fn scroll_left(&mut self, columns: usize) {
+        self.horizontal_scroll = self.horizontal_scroll.saturating_sub(columns);
+    }
+
+    fn max_horizontal_scroll(&self) -> usize {
+        self.max_visible_line_len
+            .get()
+            .saturating_sub(self.feed_width.get())
+    }
+
+    /// After a draw: pull the scroll back in when the lines on screen got
+    /// shorter (a new filter, scrolling to shorter entries).
+    pub fn clamp_horizontal_scroll(&mut self) {
+        self.horizontal_scroll = self.horizontal_scroll.min(self.max_horizontal_scroll());
+    }
+
+    /// Scroll just far enough right that the longest visible line ends at
+    /// the right edge (back to 0 when everything fits).
+    pub fn fit_horizontal_scroll(&mut self) {
+        self.horizontal_scroll = self.max_horizontal_scroll();
+    }
+
+    /// Live view on the newest entry (not paused, selection at the end).
+    pub fn is_following(&self) -> bool {
+        !self.frozen && self.selected_index + 1 >= self.visible_count()
+    }
+
+    pub fn prepend_logs(&mut self, entries: Vec<LogEntry>) {
+        let count = entries.len();
+        if count == 0 {
+            return;
+        }
+        self.invalidate_visible();
+        for entry in entries.into_iter().rev() {
+            self.logs.push_front(entry);
+            // Spilled entries are paged in on top of the buffer, not in place of it
+            if self.spill.is_none() && self.logs.len() > self.max_lines {
+                self.logs.pop_back();
+            }
+        }
+        if self.spill.is_some() {
+            self.spill_paged += count;
+        }
+        let len = self.logs.len();
+        for i in 0..count.min(len) {
+            self.widen_timestamp_column(i);
+        }
+        shift_marks_up(&mut self.bookmarks, count, len);
+        shift_marks_up(&mut self.flagged, count, len);
+        shift_marks_up(&mut self.expanded, count, len);
+        if let Some(ref store) = self.flag_store {
+            for i in 0..count.min(len) {
+                if store.contains(&self.logs[i].raw) {
+                    self.flagged.insert(i);
+                }
+            }
+        }
+        self.selected_index = count;
+    }
+
+    // --- Search ---
+
+    pub fn update_search_regex(&mut self) {
+        self.search_regex = if self.search_text.is_empty() {
+            None
+        } else {
+            Regex::new(&format!("(?i){}", &self.search_text))
+                .or_else(|_| Regex::new(&format!("(?i){}", regex::escape(&self.search_text))))
+                .ok()
+        };
+    }
+
+    pub fn search_next(&mut self) {
+        if let Some(ref re) = self.search_regex {
+            let visible = self.visible_logs();
+            if visible.is_empty() {
+                return;
+            }
+            let start = self.selected_index + 1;
+            for i in 0..visible.len() {
+                let idx = (start + i) % visible.len();
+                if let Some((_, entry)) = visible.get(idx) {
+                    if entry.divider {
+                        continue;
+                    }
+                    if self.regex_matches(re, entry) {
+                        self.selected_index = idx;
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn search_prev(&mut self) {
+        if let Some(ref re) = self.search_regex {
+            let visible = self.visible_logs();
+            if visible.is_empty() {
+                return;
+            }
+            let start = if self.selected_index == 0 {
                 visible.len() - 1
             } else {
                 self.selected_index - 1
@@ -325,7 +1690,10 @@ impl App {
             for i in 0..visible.len() {
                 let idx = (start + visible.len() - i) % visible.len();
                 if let Some((_, entry)) = visible.get(idx) {
-                    if re.is_match(&entry.raw) || entry.extra_lines.iter().any(|l| re.is_match(l)) {
+                    if entry.divider {
+                        continue;
+                    }
+                    if self.regex_matches(re, entry) {
                         self.selected_index = idx;
                         return;
                     }
@@ -334,14 +1702,92 @@ impl App {
         }
     }
 
+    // --- Numeric sort ---
+
+    /// Sort the feed by the number captured by `pattern` (the `value` group,
+    /// else the first group, else the whole match). An empty pattern turns sorting off.
+    pub fn set_sort(&mut self, pattern: &str) -> Result<(), String> {
+        self.invalidate_visible();
+        if pattern.is_empty() {
+            self.sort_regex = None;
+            return Ok(());
+        }
+        let re = Regex::new(pattern).map_err(|e| e.to_string())?;
+        self.sort_regex = Some(re);
+        // Largest first: start at the top and stop following the tail
+        self.selected_index = 0;
+        self.frozen = true;
+        Ok(())
+    }
+
+    /// Clear the fuzzy search, back to the chronological feed.
+    pub fn clear_fuzzy_search(&mut self) {
+        self.fuzzy_search_query.clear();
+        self.clamp_selection();
+    }
+
+    // --- Correlation ---
+
+    /// The request / trace ID in the selected entry, to prefill the `I` prompt.
+    pub fn selected_correlation_id(&self) -> Option<String> {
+        let (_, entry) = self.selected_entry()?;
+        let caps = CORRELATION_RE.captures(&entry.raw)?;
+        Some(caps[2].to_string())
+    }
+
+    /// Show only entries containing `id` (literally), with `n` / `N`
+    /// stepping through them and the ID highlighted. An empty `id` stops
+    /// tracking.
+    pub fn set_correlation_filter(&mut self, id: &str) {
+        let id = id.trim().replace('"', "");
+        if id.is_empty() {
+            if self.tracking_label().is_some() {
+                self.filter_text.clear();
+                self.search_text.clear();
+                self.update_filter_regex();
+                self.update_search_regex();
+            }
+            self.correlation = None;
+            return;
+        }
+        self.filter_text = format!("\"{}\"", id);
+        self.update_filter_regex();
+        self.search_text = regex::escape(&id);
+        self.update_search_regex();
+        let label = self
+            .visible_logs()
+            .iter()
+            .find_map(|(_, entry)| {
+                CORRELATION_RE
+                    .captures_iter(&entry.raw)
+                    .find(|caps| caps[2].eq_ignore_ascii_case(&id))
+                    .map(|caps| format!("{}={}", &caps[1], id))
+            })
+            .unwrap_or_else(|| id.clone());
+        self.correlation = Some(Correlation {
+            filter: self.filter_text.clone(),
+            label,
+        });
+    }
+
+    /// The tracked ID's label while its filter is still the active one.
+    pub fn tracking_label(&self) -> Option<&str> {
+        self.correlation
+            .as_ref()
+            .filter(|c| c.filter == self.filter_text)
+            .map(|c| c.label.as_str())
+    }
+
     // --- Highlights ---
 
-    pub fn add_highlight(&mut self, pattern: &str) {
+    /// Highlight `pattern` in `color`, or the next color of the `*` palette.
+    pub fn add_highlight(&mut self, pattern: &str, color: Option<Color>) {
         if pattern.is_empty() {
             self.highlights.clear();
             return;
         }
-        let color = HIGHLIGHT_COLORS[self.highlights.len() % HIGHLIGHT_COLORS.len()];
+        let color = color
+            .unwrap_or(self.highlight_colors[self.highlights.len() % self.highlight_colors.len()]);
         if let Ok(re) = Regex::new(&format!("(?i){}", pattern))
             .or_else(|_| Regex::new(&format!("(?i){}", regex::escape(pattern))))
         {
@@ -349,11 +1795,103 @@ impl App {
         }
     }
 
+    // --- Bookmarks ---
+
+    /// Toggle a bookmark on the selected entry. Returns the new state, or None if nothing is selected.
+    pub fn toggle_bookmark(&mut self) -> Option<bool> {
+        let idx = self.selected_entry().map(|(i, _)| i)?;
+        if self.bookmarks.remove(&idx) {
+            Some(false)
+        } else {
+            self.bookmarks.insert(idx);
+            Some(true)
+        }
+    }
+
+    /// Show or hide the selected entry's continuation lines inline.
+    /// Returns the new state, or None if it has none.
+    pub fn toggle_expanded(&mut self) -> Option<bool> {
+        let idx = self
+            .selected_entry()
+            .filter(|(_, entry)| !entry.extra_lines.is_empty())
+            .map(|(i, _)| i)?;
+        if self.expanded.remove(&idx) {
+            Some(false)
+        } else {
+            self.expanded.insert(idx);
+            Some(true)
+        }
+    }
+
+    /// Jump to the next visible bookmarked entry, wrapping around.
+    pub fn next_bookmark(&mut self) {
+        if self.bookmarks.is_empty() {
+            return;
+        }
+        let visible = self.visible_indices();
+        let start = self.selected_index + 1;
+        let found = (0..visible.len())
+            .map(|i| (start + i) % visible.len())
+            .find(|&pos| self.bookmarks.contains(&visible[pos]));
+        drop(visible);
+        if let Some(pos) = found {
+            self.selected_index = pos;
+        }
+    }
+
+    // --- Flags ---
+
+    /// Remember the signatures of every error in the buffer (z key).
+    /// Returns how many distinct ones there are.
+    pub fn snapshot_error_signatures(&mut self) -> usize {
+        self.error_signature_snapshot = self
+            .logs
+            .iter()
+            .filter(|e| !e.divider && matches!(e.level, LogLevel::Error | LogLevel::Fatal))
+            .map(error_signature)
+            .collect();
+        self.invalidate_visible();
+        self.error_signature_snapshot.len()
+    }
+
+    /// An error whose signature wasn't in the snapshot.
+    fn is_new_error(&self, entry: &LogEntry) -> bool {
+        matches!(entry.level, LogLevel::Error | LogLevel::Fatal)
+            && !self
+                .error_signature_snapshot
+                .contains(&error_signature(entry))
+    }
+
+    /// Errors in the buffer that are new since the snapshot, for `[DIFF: N new]`.
+    pub fn new_error_count(&self) -> usize {
+        self.logs.iter().filter(|e| self.is_new_error(e)).count()
+    }
+
+    /// Toggle the flag on the selected entry and persist it for local files.
+    /// Returns the new state, or None if nothing is selected.
+    pub fn toggle_flag(&mut self) -> Option<bool> {
+        let idx = self.selected_entry().map(|(i, _)| i)?;
+        let flagged = !self.flagged.remove(&idx);
+        if flagged {
+            self.flagged.insert(idx);
+        }
+        self.invalidate_visible();
+        if let Some(ref mut store) = self.flag_store {
+            if let Err(e) = store.set(&self.logs[idx].raw, flagged) {
+                self.set_status(format!("Saving flags failed: {}", e));
+            }
+        }
+        Some(flagged)
+    }
+
     // --- Time jump ---
 
     pub fn jump_to_time(&mut self, time_str: &str) {
         let visible = self.visible_logs();
         for (idx, (_, entry)) in visible.iter().enumerate() {
+            if entry.divider {
+                continue;
+            }
             // Check parsed timestamp first
             if let Some(ref ts) = entry.timestamp {
                 if ts.contains(time_str) {
@@ -379,9 +1917,1084 @@ impl App {
 
     pub fn clear_expired_status(&mut self) {
         if let Some((_, when)) = &self.status_message {
-            if when.elapsed() > Duration::from_secs(3) {
+            if when.elapsed() > self.status_timeout {
                 self.status_message = None;
             }
         }
     }
 }
+
+/// Whether `entry` repeats `prev` for dedup purposes (dividers never collapse).
+fn is_repeat(prev: &LogEntry, entry: &LogEntry) -> bool {
+    !entry.divider && !prev.divider && prev.raw == entry.raw && prev.source == entry.source
+}
+
+/// Number captured by `re` in the entry, e.g. `312` from `latency=312ms`.
+fn sort_value(entry: &LogEntry, re: &Regex) -> Option<f64> {
+    let caps = re.captures(&entry.raw)?;
+    let text = caps
+        .name("value")
+        .or_else(|| caps.get(1))
+        .or_else(|| caps.get(0))?
+        .as_str();
+    let end = text
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')))
+        .unwrap_or(text.len());
+    text[..end].parse().ok()
+}
+
+/// Order entries by their captured number, largest first; entries without
+/// one keep their chronological order at the bottom.
+fn sort_by_field(visible: &mut Vec<(usize, &LogEntry)>, re: &Regex) {
+    let mut keyed: Vec<_> = visible
+        .drain(..)
+        .map(|(idx, entry)| (sort_value(entry, re), idx, entry))
+        .collect();
+    keyed.sort_by(|a, b| match (a.0, b.0) {
+        (Some(x), Some(y)) => y.total_cmp(&x),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    visible.extend(keyed.into_iter().map(|(_, idx, entry)| (idx, entry)));
+}
+
+/// Shift marked buffer indices after the entry at `removed` was evicted.
+fn shift_marks_down(marks: &mut BTreeSet<usize>, removed: usize) {
+    if !marks.is_empty() {
+        *marks = marks
+            .iter()
+            .filter(|&&i| i != removed)
+            .map(|&i| if i > removed { i - 1 } else { i })
+            .collect();
+    }
+}
+
+/// Shift marked buffer indices after `count` entries were prepended.
+fn shift_marks_up(marks: &mut BTreeSet<usize>, count: usize, len: usize) {
+    if !marks.is_empty() {
+        *marks = marks
+            .iter()
+            .map(|&i| i + count)
+            .filter(|&i| i < len)
+            .collect();
+    }
+}
+
+/// Rough in-memory size of an entry, for the `--spill` header readout.
+fn entry_size(entry: &LogEntry) -> usize {
+    let text = |s: &Option<String>| s.as_ref().map_or(0, String::capacity);
+    std::mem::size_of::<LogEntry>()
+        + entry.raw.capacity()
+        + text(&entry.timestamp)
+        + text(&entry.message)
+        + text(&entry.metadata)
+        + text(&entry.source)
+        + entry
+            .extra_lines
+            .iter()
+            .map(|l| std::mem::size_of::<String>() + l.capacity())
+            .sum::<usize>()
+}
+
+/// What makes two errors "the same" for deploy diffs: the message with runs
+/// of digits collapsed, so ids, ports and durations don't count as new.
+fn error_signature(entry: &LogEntry) -> String {
+    let message = entry.message.as_deref().unwrap_or(&entry.raw);
+    let mut signature = String::with_capacity(message.len());
+    for c in message.trim().chars() {
+        if c.is_ascii_digit() {
+            if !signature.ends_with('#') {
+                signature.push('#');
+            }
+        } else {
+            signature.push(c);
+        }
+    }
+    signature
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{CriParser, JsonParser, MysqlParser, PlainParser};
+
+    /// Takes every hand-built entry as a record of its own.
+    struct Prebuilt;
+
+    impl LogParser for Prebuilt {
+        fn name(&self) -> &str {
+            "Prebuilt"
+        }
+
+        fn can_parse(&self, _line: &str) -> bool {
+            true
+        }
+
+        fn parse(&self, line: &str) -> LogEntry {
+            PlainParser.parse(line)
+        }
+    }
+
+    #[test]
+    fn since_drops_old_entries_and_their_continuations() {
+        let mut app = App::new("test".to_string());
+        let now = Local::now();
+        app.since = Some(now - chrono::TimeDelta::minutes(5));
+
+        let continuation = |raw: &str| LogEntry {
+            level: LogLevel::Unknown,
+            ..entry(raw)
+        };
+
+        let mut old = entry("old error");
+        old.ts_parsed = Some(now - chrono::TimeDelta::minutes(10));
+        app.add_log(old);
+        app.add_log(continuation("  at old stack frame"));
+        let mut fresh = entry("new error");
+        fresh.ts_parsed = Some(now);
+        app.add_log(fresh);
+        app.add_log(continuation("  at new stack frame"));
+
+        assert_eq!(app.logs.len(), 1);
+        assert_eq!(app.logs[0].raw, "new error");
+        assert_eq!(app.logs[0].extra_lines, vec!["  at new stack frame"]);
+    }
+
+    #[test]
+    fn sort_orders_by_captured_number_descending() {
+        let mut app = App::new("test".to_string());
+        for raw in [
+            "GET /a latency=12ms",
+            "no latency here",
+            "GET /b latency=312ms",
+            "GET /c latency=4.5ms",
+        ] {
+            app.add_log(entry(raw));
+        }
+        app.set_sort(r"latency=(\d+(?:\.\d+)?)").unwrap();
+        let order: Vec<usize> = app.visible_logs().iter().map(|(i, _)| *i).collect();
+        assert_eq!(order, [2, 0, 3, 1]);
+        assert_eq!(app.logs[0].raw, "GET /a latency=12ms");
+        assert!(app.frozen);
+
+        assert!(app.set_sort("(").is_err());
+        app.set_sort("").unwrap();
+        let order: Vec<usize> = app.visible_logs().iter().map(|(i, _)| *i).collect();
+        assert_eq!(order, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn fuzzy_search_hides_weak_matches_and_ranks_best_first() {
+        let mut app = App::new("test".to_string());
+        for raw in [
+            "db: timeout after 5s",
+            "nothing relevant",
+            "db timeout",
+            "d.b.t.i.m.e.o.u.t scattered",
+        ] {
+            app.add_log(entry(raw));
+        }
+        app.fuzzy_search_query = "dbtimeout".to_string();
+        let order: Vec<usize> = app.visible_logs().iter().map(|(i, _)| *i).collect();
+        assert_eq!(order, [2, 0]);
+        assert_eq!(app.visible_count(), 2);
+
+        app.clear_fuzzy_search();
+        assert_eq!(app.visible_count(), 4);
+    }
+
+    #[test]
+    fn message_scope_ignores_noisy_raw_fields() {
+        let mut app = App::new("test".to_string());
+        app.add_log(LogEntry {
+            message: Some("GET /api 200".to_string()),
+            ..entry(r#"{"ip":"10.0.0.5","msg":"GET /api 200"}"#)
+        });
+        app.add_log(entry("plain 10.0.0.5 line"));
+        app.filter_text = r"10\.0\.0\.5".to_string();
+        app.update_filter_regex();
+        assert_eq!(app.visible_count(), 2);
+
+        app.match_scope = MatchScope::Message;
+        app.invalidate_visible();
+        assert_eq!(app.visible_count(), 1);
+        assert_eq!(app.visible_logs()[0].1.raw, "plain 10.0.0.5 line");
+
+        app.filter_text.clear();
+        app.update_filter_regex();
+        app.search_text = "api".to_string();
+        app.update_search_regex();
+        app.selected_index = 1;
+        app.search_next();
+        assert_eq!(app.selected_index, 0);
+    }
+
+    #[test]
+    fn timestamp_column_fits_longest_timestamp() {
+        let mut app = App::new("test".to_string());
+        app.timestamp_max_width = 24;
+        let stamped = |ts: &str| LogEntry {
+            timestamp: Some(ts.to_string()),
+            ..entry("msg")
+        };
+        app.add_log(stamped("10:30:01"));
+        assert_eq!(app.timestamp_width, 8);
+        app.prepend_logs(vec![stamped("2024-01-15 10:30:01")]);
+        assert_eq!(app.timestamp_width, 19);
+        app.add_log(stamped("2024-01-15T10:30:01.123456789Z"));
+        assert_eq!(app.timestamp_width, 24);
+        app.clear_logs();
+        assert_eq!(app.timestamp_width, 0);
+    }
+
+    #[test]
+    fn highlight_palette_follows_the_theme_unless_configured() {
+        let mut app = App::new("test".to_string());
+        let mut config = Config {
+            theme: crate::config::ThemeConfig::Named("light".to_string()),
+            ..Config::default()
+        };
+        app.apply_config(&config);
+        assert_eq!(app.highlight_colors, config.theme().unwrap().palette);
+
+        config.highlight_colors[0] = "red".to_string();
+        app.apply_config(&config);
+        assert_eq!(app.highlight_colors[..2], [Color::Red, Color::Cyan]);
+    }
+
+    #[test]
+    fn error_rate_over_all_lines_and_the_window() {
+        let mut app = App::new("test".to_string());
+        assert_eq!(app.error_rate_percent(), 0.0);
+        for i in 0..40 {
+            let level = if i < 2 {
+                LogLevel::Error
+            } else {
+                LogLevel::Info
+            };
+            app.add_log(LogEntry {
+                level,
+                ..entry("line")
+            });
+        }
+        assert_eq!(app.error_rate_percent(), 5.0);
+
+        // Only the lines counted in the window: 1 fatal and 3 info in the last second
+        *app.eps_history.back_mut().unwrap() = 4;
+        *app.level_eps_counters[LogLevel::Fatal as usize]
+            .back_mut()
+            .unwrap() = 1;
+        assert_eq!(app.recent_error_rate_percent(), 25.0);
+    }
+
+    #[test]
+    fn correlation_tracks_one_request_id() {
+        let mut app = App::new("test".to_string());
+        for raw in [
+            r#"{"msg":"start","request_id":"abc-123"}"#,
+            "GET /health 200",
+            "db query request_id=abc-123 took 40ms",
+            r#"{"msg":"other","request_id":"abc-1234x"}"#,
+        ] {
+            app.add_log(entry(raw));
+        }
+        app.selected_index = 0;
+        assert_eq!(app.selected_correlation_id().as_deref(), Some("abc-123"));
+
+        app.set_correlation_filter(" abc-123 ");
+        assert_eq!(app.filter_text, "\"abc-123\"");
+        assert_eq!(app.tracking_label(), Some("request_id=abc-123"));
+        // Literal match: the longer ID containing it still matches as a substring
+        assert_eq!(app.visible_count(), 3);
+        app.search_next();
+        assert_eq!(app.selected_index, 1);
+
+        // Editing the filter ends the tracking banner; empty ID clears it all
+        app.filter_text.push_str(" db");
+        assert_eq!(app.tracking_label(), None);
+        app.set_correlation_filter("abc-123");
+        app.set_correlation_filter("");
+        assert!(app.filter_text.is_empty() && app.search_regex.is_none());
+        assert_eq!(app.visible_count(), 4);
+    }
+
+    #[test]
+    fn context_lines_surround_filter_matches() {
+        let mut app = App::new("test".to_string());
+        for raw in ["a", "b", "c boom", "d", "e", "f", "g boom"] {
+            app.add_log(entry(raw));
+        }
+        app.filter_text = "boom".to_string();
+        app.update_filter_regex();
+        assert_eq!(app.visible_count(), 2);
+
+        app.step_context_lines(true);
+        assert_eq!(&*app.visible_indices(), &[1, 2, 3, 5, 6]);
+        assert!(app.is_context_line(1, &app.logs[1]));
+        assert!(!app.is_context_line(2, &app.logs[2]));
+
+        // Overlapping windows merge; the end of the buffer clips them
+        app.step_context_lines(true);
+        app.step_context_lines(true);
+        assert_eq!(app.visible_count(), 7);
+
+        for _ in 0..5 {
+            app.step_context_lines(false);
+        }
+        assert_eq!(app.context_lines, 0);
+        assert_eq!(app.visible_count(), 2);
+    }
+
+    #[test]
+    fn similar_messages_collapse_into_their_first() {
+        let mut app = App::new("test".to_string());
+        for raw in [
+            "ERROR connection to 10.0.0.1 refused",
+            "INFO cache warmed",
+            "ERROR connection to 10.0.0.2 refused",
+            "ERROR connection to 10.0.0.3:5432 refused",
+        ] {
+            app.add_log(entry(raw));
+        }
+        app.group_similar = true;
+        app.update_groups();
+        assert_eq!(&*app.visible_indices(), &[0, 1]);
+        let group = &app.groups[&0];
+        assert_eq!(group.indices, [0, 2, 3]);
+        assert!(group.template.contains("connection to <IP> refused"));
+
+        // Only filtered entries are grouped
+        app.filter_text = "!10.0.0.1".to_string();
+        app.update_filter_regex();
+        app.update_groups();
+        assert_eq!(app.groups[&2].indices, [2, 3]);
+
+        app.group_similar = false;
+        app.update_groups();
+        assert!(app.groups.is_empty());
+        assert_eq!(app.visible_count(), 3);
+    }
+
+    #[test]
+    fn recent_errors_lists_the_last_errors_oldest_first() {
+        let mut app = App::new("test".to_string());
+        for i in 0..15 {
+            let level = if i % 2 == 0 {
+                LogLevel::Error
+            } else {
+                LogLevel::Info
+            };
+            app.add_log(LogEntry {
+                level,
+                ..entry(&format!("line {}", i))
+            });
+        }
+        app.add_log(LogEntry {
+            level: LogLevel::Fatal,
+            ..entry("out of memory")
+        });
+        assert_eq!(app.recent_errors(3), [12, 14, 15]);
+        assert_eq!(app.recent_errors(100).len(), 9);
+    }
+
+    #[test]
+    fn parsed_lines_group_by_the_parsers_rules() {
+        let mut app = App::new("test".to_string());
+        let json = JsonParser::default();
+        for line in [
+            r#"{"level":"error","msg":"boom"}"#,
+            "  at handler (app.js:10)",
+            // No level, but a whole object: its own entry
+            r#"{"msg":"heartbeat"}"#,
+            "2024-01-15 10:30:02 worker restarted",
+        ] {
+            app.add_parsed_log(json.parse(line), &json);
+        }
+        let raws: Vec<&str> = app.logs.iter().map(|e| e.raw.as_str()).collect();
+        assert_eq!(raws.len(), 3);
+        assert_eq!(app.logs[0].extra_lines, ["  at handler (app.js:10)"]);
+        assert_eq!(raws[1], r#"{"msg":"heartbeat"}"#);
+        assert!(raws[2].starts_with("2024-01-15"));
+
+        // Plain text: a stack frame still follows the line with a level
+        let mut app = App::new("test".to_string());
+        for line in ["ERROR boom", "    at Foo.bar(Foo.java:1)", "INFO ok"] {
+            app.add_parsed_log(PlainParser.parse(line), &PlainParser);
+        }
+        assert_eq!(app.logs.len(), 2);
+        assert_eq!(app.logs[0].extra_lines.len(), 1);
+    }
+
+    #[test]
+    fn horizontal_scroll_stops_at_the_longest_line() {
+        let mut app = App::new("test".to_string());
+        // As the last draw recorded them
+        app.max_visible_line_len.set(130);
+        app.feed_width.set(100);
+        app.scroll_right(20);
+        app.scroll_right(20);
+        assert_eq!(app.horizontal_scroll, 30);
+        app.scroll_left(1);
+        assert_eq!(app.horizontal_scroll, 29);
+        app.scroll_left(20);
+        app.scroll_left(20);
+        assert_eq!(app.horizontal_scroll, 0);
+
+        // A filter leaves shorter lines on screen
+        app.horizontal_scroll = 30;
+        app.max_visible_line_len.set(110);
+        app.clamp_horizontal_scroll();
+        assert_eq!(app.horizontal_scroll, 10);
+    }
+
+    #[test]
+    fn slow_query_block_is_one_entry() {
+        let mut app = App::new("slow.log".to_string());
+        let mysql = MysqlParser::default();
+        for line in [
+            "# Time: 2024-01-15T10:30:01.123456Z",
+            "# User@Host: root[root] @ localhost []  Id:     8",
+            "# Query_time: 2.000123  Lock_time: 0.000000 Rows_sent: 1  Rows_examined: 0",
+            "SET timestamp=1705314601;",
+            "SELECT * FROM errors;",
+            "# User@Host: app[app] @ 10.0.0.5 []  Id:     9",
+            "# Query_time: 3.100000  Lock_time: 0.000000 Rows_sent: 0  Rows_examined: 9",
+            "SELECT 1;",
+        ] {
+            app.add_parsed_log(mysql.parse(line), &mysql);
+        }
+        assert_eq!(app.logs.len(), 2);
+        assert_eq!(app.logs[0].extra_lines.len(), 4);
+        assert_eq!(
+            app.logs[0].extra_lines[0],
+            "# User@Host: root[root] @ localhost []  Id:     8"
+        );
+        assert_eq!(
+            app.logs[1].raw,
+            "# User@Host: app[app] @ 10.0.0.5 []  Id:     9"
+        );
+        assert_eq!(app.logs[1].extra_lines.len(), 2);
+    }
+
+    #[test]
+    fn cri_fragments_join_their_record() {
+        let mut app = App::new("pod.log".to_string());
+        let cri = CriParser::default();
+        for line in [
+            "2024-01-15T10:30:01Z stdout P 2024-01-15 10:30:01 ERROR long ",
+            "2024-01-15T10:30:01Z stdout F 2024-01-15 10:30:01 tail of it",
+            "2024-01-15T10:30:02Z stdout F next record",
+        ] {
+            app.add_parsed_log(cri.parse(line), &cri);
+        }
+        assert_eq!(app.logs.len(), 2);
+        assert_eq!(
+            app.logs[0].extra_lines,
+            vec!["2024-01-15 10:30:01 tail of it"]
+        );
+    }
+
+    #[test]
+    fn visible_entries_are_cached_until_something_changes() {
+        let mut app = App::new("test".to_string());
+        app.add_log(entry("GET /health"));
+        app.add_log(LogEntry {
+            level: LogLevel::Error,
+            ..entry("boom")
+        });
+        assert_eq!(app.visible_count(), 2);
+
+        // Direct field changes wait for an invalidation (each key event does one)
+        app.error_only = true;
+        assert_eq!(app.visible_count(), 2);
+        app.invalidate_visible();
+        assert_eq!(app.visible_count(), 1);
+
+        // Buffer and filter changes invalidate by themselves
+        app.add_log(LogEntry {
+            level: LogLevel::Fatal,
+            ..entry("down")
+        });
+        assert_eq!(app.visible_count(), 2);
+        app.filter_text = "down".to_string();
+        app.update_filter_regex();
+        assert_eq!(app.visible_logs()[0].1.raw, "down");
+        app.prepend_logs(vec![LogEntry {
+            level: LogLevel::Error,
+            ..entry("down earlier")
+        }]);
+        assert_eq!(app.visible_count(), 2);
+        app.clear_logs();
+        assert_eq!(app.visible_count(), 0);
+    }
+
+    #[test]
+    fn eps_window_zooms_through_steps_and_keeps_recent_seconds() {
+        let mut app = App::new("test".to_string());
+        app.eps_history = (1..=60).collect();
+        app.zoom_eps_window(false);
+        assert_eq!(app.eps_window_secs, 30);
+        assert_eq!(app.eps_history.len(), 30);
+        assert_eq!(app.eps_history.front(), Some(&31));
+        assert!(app.level_eps_counters.iter().all(|h| h.len() == 30));
+
+        app.zoom_eps_window(true);
+        assert_eq!(app.eps_history.len(), 60);
+        assert_eq!(app.eps_history.front(), Some(&0));
+        assert_eq!(app.eps_history.back(), Some(&60));
+
+        // Off-step windows (from the config) move to the neighbouring step
+        app.set_eps_window(45);
+        app.zoom_eps_window(true);
+        assert_eq!(app.eps_window_secs, 60);
+        app.set_eps_window(600);
+        app.zoom_eps_window(true);
+        assert_eq!(app.eps_window_secs, 600);
+    }
+
+    #[test]
+    fn prompt_history_follows_the_open_prompt() {
+        let mut app = App::new("test".to_string());
+        for pattern in ["timeout", "error", "svc:api"] {
+            app.prompt_history.filter.push(pattern);
+        }
+        app.prompt_history.search.push("retry");
+
+        app.input_mode = InputMode::Filter;
+        app.prompt_history_prev();
+        assert_eq!(app.filter_text, "svc:api");
+        app.prompt_history_prev();
+        app.prompt_history_prev();
+        assert_eq!(app.filter_text, "timeout");
+        assert_eq!(app.filter.include.len(), 1);
+        app.prompt_history_next();
+        assert_eq!(app.filter_text, "error");
+        app.prompt_history_next();
+        app.prompt_history_next();
+        assert_eq!(app.filter_text, "");
+        assert!(app.filter.include.is_empty());
+
+        app.input_mode = InputMode::Search;
+        app.prompt_history_prev();
+        assert_eq!(app.input_buffer, "retry");
+        assert_eq!(app.input_cursor_pos(), 5);
+
+        // Prompts without a history ignore the arrows
+        app.input_mode = InputMode::FuzzySearch;
+        app.prompt_history_prev();
+        assert_eq!(app.fuzzy_search_query, "");
+    }
+
+    #[test]
+    fn stats_track_levels_and_timestamp_range() {
+        let mut app = App::new("test".to_string());
+        let at = |level: LogLevel, ts: &str| LogEntry {
+            level,
+            timestamp: Some(ts.to_string()),
+            ..entry("msg")
+        };
+        app.add_log(at(LogLevel::Info, "10:30:01"));
+        app.add_log(at(LogLevel::Error, "10:30:02"));
+        app.add_log(at(LogLevel::Error, "10:30:03"));
+        app.insert_divider("resumed");
+
+        assert_eq!(app.level_counts[LogLevel::Info as usize], 1);
+        assert_eq!(app.level_counts[LogLevel::Error as usize], 2);
+        assert_eq!(app.level_counts.iter().sum::<u64>(), app.total_count);
+        assert_eq!(app.first_timestamp.as_deref(), Some("10:30:01"));
+        assert_eq!(app.last_timestamp.as_deref(), Some("10:30:03"));
+
+        app.eps_last_tick = Instant::now() - Duration::from_secs(2);
+        app.tick_eps();
+        assert_eq!(
+            app.level_eps_counters[LogLevel::Error as usize].back(),
+            Some(&2)
+        );
+        assert_eq!(app.level_eps_tick, [0; 7]);
+    }
+
+    #[test]
+    fn top_message_row_filters_the_feed() {
+        let mut app = App::new("test".to_string());
+        for raw in [
+            "job 1 done",
+            "disk 91% full",
+            "job 22 done",
+            "job 3 done",
+            "disk 95% full",
+        ] {
+            app.add_log(entry(raw));
+        }
+        app.view_mode = ViewMode::Stats;
+        app.stats_selected = 1;
+        assert_eq!(app.filter_top_message().as_deref(), Some("disk <n>% full"));
+        assert_eq!(app.view_mode, ViewMode::Feed);
+        let shown: Vec<&str> = app
+            .visible_logs()
+            .iter()
+            .map(|(_, e)| e.raw.as_str())
+            .collect();
+        assert_eq!(shown, ["disk 91% full", "disk 95% full"]);
+        assert_eq!(app.eps_summary(), (0, 0.0, 0));
+    }
+
+    #[test]
+    fn prompt_editing_at_the_cursor() {
+        let mut app = App::new("test".to_string());
+        app.input_mode = InputMode::Filter;
+        app.input_insert("timeout");
+        app.input_left();
+        app.input_left();
+        app.input_left();
+        app.input_insert("é");
+        assert_eq!(app.filter_text, "timeéout");
+        assert!(app.input_backspace());
+        assert!(app.input_delete());
+        assert_eq!(app.filter_text, "timeut");
+        app.input_cursor = 0;
+        assert!(!app.input_backspace());
+        app.input_insert("(?i)");
+        assert_eq!(app.filter_text, "(?i)timeut");
+        app.input_cursor = usize::MAX;
+        assert!(!app.input_delete());
+
+        // Other prompts edit the shared buffer
+        app.input_mode = InputMode::Search;
+        app.input_insert("abc");
+        app.input_left();
+        app.input_right();
+        app.input_right();
+        assert_eq!(app.input_cursor_pos(), 3);
+        assert_eq!(app.input_buffer, "abc");
+    }
+
+    #[test]
+    fn merged_json_enriches_json_lines_and_wraps_others() {
+        let json = LogEntry {
+            raw: r#"{"lvl":"W","msg":"slow","level":"W"}"#.to_string(),
+            level: LogLevel::Warn,
+            message: Some("slow".to_string()),
+            ..entry("")
+        };
+        assert_eq!(
+            json.to_merged_json(),
+            serde_json::json!({"lvl": "W", "msg": "slow", "level": "warn", "message": "slow"})
+        );
+
+        // The line JsonParser keeps as metadata isn't repeated inside itself
+        let line = r#"{"time":"2024-01-15T10:30:01Z","level":"error","msg":"boom","user":"bob"}"#;
+        let parsed = JsonParser::default().parse(line).to_merged_json();
+        assert_eq!(parsed["user"], "bob");
+        assert_eq!(parsed["message"], "boom");
+        assert!(parsed.get("metadata").is_none());
+
+        let plain = LogEntry {
+            extra_lines: vec!["  at x".to_string()],
+            status: Some(502),
+            ..entry("GET / 502")
+        };
+        assert_eq!(
+            plain.to_merged_json(),
+            serde_json::json!({
+                "level": "info",
+                "status": 502,
+                "extra_lines": ["  at x"],
+                "raw": "GET / 502",
+            })
+        );
+    }
+
+    fn entry(raw: &str) -> LogEntry {
+        LogEntry {
+            raw: raw.to_string(),
+            level: LogLevel::Info,
+            timestamp: None,
+            ts_parsed: None,
+            message: None,
+            metadata: None,
+            extra_lines: Vec::new(),
+            source: None,
+            status: None,
+            divider: false,
+        }
+    }
+
+    #[test]
+    fn bookmarks_follow_buffer_eviction() {
+        let mut app = App::new("test".to_string());
+        for i in 0..MAX_LOG_LINES {
+            app.add_log(entry(&format!("line {}", i)));
+        }
+        app.bookmarks.extend([0, 5]);
+        app.add_log(entry("overflow"));
+        assert_eq!(app.bookmarks.iter().copied().collect::<Vec<_>>(), [4]);
+        assert_eq!(app.logs[4].raw, "line 5");
+    }
+
+    #[test]
+    fn expand_only_entries_with_continuations() {
+        let mut app = App::new("test".to_string());
+        app.add_log(entry("plain"));
+        let mut trace = entry("panic");
+        trace.extra_lines = vec!["  at main.rs:1".to_string()];
+        app.add_log(trace);
+
+        app.selected_index = 0;
+        assert_eq!(app.toggle_expanded(), None);
+        app.selected_index = 1;
+        assert_eq!(app.toggle_expanded(), Some(true));
+        assert!(app.expanded.contains(&1));
+        assert_eq!(app.toggle_expanded(), Some(false));
+        assert!(app.expanded.is_empty());
+    }
+
+    #[test]
+    fn next_bookmark_wraps_around() {
+        let mut app = App::new("test".to_string());
+        for i in 0..10 {
+            app.add_log(entry(&format!("line {}", i)));
+        }
+        app.selected_index = 2;
+        app.toggle_bookmark();
+        app.selected_index = 7;
+        app.toggle_bookmark();
+
+        app.selected_index = 3;
+        app.next_bookmark();
+        assert_eq!(app.selected_index, 7);
+        app.next_bookmark();
+        assert_eq!(app.selected_index, 2);
+    }
+
+    #[test]
+    fn dividers_are_uncounted_and_skipped_by_search() {
+        let mut app = App::new("test".to_string());
+        app.add_log(entry("first match"));
+        app.insert_divider("resumed");
+        app.add_log(entry("second match"));
+        assert_eq!(app.total_count, 2);
+
+        app.filter_text = "second".to_string();
+        app.update_filter_regex();
+        assert_eq!(
+            app.visible_count(),
+            2,
+            "divider stays visible under a filter"
+        );
+
+        app.min_level = Some(LogLevel::Error);
+        app.invalidate_visible();
+        assert_eq!(app.visible_count(), 0, "the level floor hides it");
+        app.min_level = None;
+        app.error_only = true;
+        app.invalidate_visible();
+        assert_eq!(app.visible_count(), 0, "error-only hides it");
+        app.error_only = false;
+        app.flagged_only = true;
+        app.invalidate_visible();
+        assert_eq!(app.visible_count(), 0, "the flagged view hides it");
+        app.flagged_only = false;
+
+        app.filter_text.clear();
+        app.update_filter_regex();
+        app.search_text = "match|resumed".to_string();
+        app.update_search_regex();
+        app.selected_index = 0;
+        app.search_next();
+        assert_eq!(app.selected_index, 2);
+    }
+
+    #[test]
+    fn dedup_recounts_only_after_a_change() {
+        let mut app = App::new("test".to_string());
+        app.deduplicate = true;
+        for _ in 0..2 {
+            app.add_parsed_log(entry("heartbeat OK"), &Prebuilt);
+        }
+        app.update_dedup_counts();
+        assert_eq!(app.dedup_counts.get(&0), Some(&2));
+
+        // An idle tick keeps the counts it has without rescanning
+        app.dedup_counts.insert(0, 99);
+        app.update_dedup_counts();
+        assert_eq!(app.dedup_counts.get(&0), Some(&99));
+
+        app.add_parsed_log(entry("heartbeat OK"), &Prebuilt);
+        app.update_dedup_counts();
+        assert_eq!(app.dedup_counts.get(&0), Some(&3));
+
+        app.deduplicate = false;
+        app.invalidate_visible();
+        app.update_dedup_counts();
+        assert!(app.dedup_counts.is_empty());
+    }
+
+    #[test]
+    fn dedup_collapses_consecutive_repeats() {
+        let mut app = App::new("test".to_string());
+        for raw in [
+            "heartbeat OK",
+            "heartbeat OK",
+            "heartbeat OK",
+            "request",
+            "heartbeat OK",
+        ] {
+            app.add_log(entry(raw));
+        }
+        app.deduplicate = true;
+        app.update_dedup_counts();
+
+        let visible: Vec<usize> = app.visible_logs().iter().map(|(i, _)| *i).collect();
+        assert_eq!(visible, [0, 3, 4]);
+        assert_eq!(app.visible_count(), 3);
+        assert_eq!(app.dedup_counts.get(&0), Some(&3));
+        assert_eq!(app.dedup_counts.get(&4), None);
+        // Export sees every line
+        assert_eq!(app.filtered_logs().len(), 5);
+    }
+
+    #[test]
+    fn paused_view_keeps_ingesting_and_stays_on_its_entry() {
+        let mut app = App::new("test".to_string());
+        app.max_lines = 5;
+        for i in 0..5 {
+            app.add_log(entry(&format!("line {}", i)));
+        }
+        app.selected_index = 2;
+        app.toggle_pause();
+        for i in 5..8 {
+            app.add_log(entry(&format!("line {}", i)));
+        }
+        assert_eq!(app.paused_new, 3);
+        assert_eq!(app.logs.len(), 5);
+        // "line 2" was evicted; the selection stays at the front
+        assert_eq!(app.selected_index, 0);
+        assert_eq!(app.visible_logs()[app.selected_index].1.raw, "line 3");
+
+        app.toggle_pause();
+        assert!(!app.frozen);
+        assert_eq!(app.paused_new, 0);
+        assert!(app.logs.back().unwrap().divider);
+    }
+
+    #[test]
+    fn spilled_entries_page_back_in_above_the_buffer() {
+        let path = std::env::temp_dir().join(format!("logpulse-spill-{}.log", std::process::id()));
+        let mut app = App::new("test".to_string());
+        app.max_lines = 3;
+        app.enable_spill(SpillFile::create(path.to_str().unwrap()).unwrap());
+        for i in 0..6 {
+            app.add_log(entry(&format!("line {}", i)));
+        }
+        assert_eq!(app.spill.as_ref().unwrap().lines(), 3);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "line 0\nline 1\nline 2\n"
+        );
+
+        let Some(History::Spilled(ref mut history, None)) = app.history else {
+            panic!("spill history");
+        };
+        let older = history.load_older(2);
+        assert_eq!(older, ["line 1", "line 2"]);
+        app.prepend_logs(older.iter().map(|l| entry(l)).collect());
+        assert_eq!(app.logs.len(), 5);
+        assert_eq!(app.spill_paged, 2);
+
+        // New lines evict the oldest live entry, not the ones paged back in
+        app.add_log(entry("line 6"));
+        let raws: Vec<&str> = app.logs.iter().map(|e| e.raw.as_str()).collect();
+        assert_eq!(raws, ["line 1", "line 2", "line 4", "line 5", "line 6"]);
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .ends_with("line 3\n"));
+
+        app.jump_to_end();
+        assert_eq!(app.logs.len(), 3);
+        assert_eq!(app.spill_paged, 0);
+        let Some(History::Spilled(ref mut history, None)) = app.history else {
+            panic!("spill history");
+        };
+        assert_eq!(history.load_older(1), ["line 3"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn spill_scrollback_continues_into_the_source_file() {
+        let dir = std::env::temp_dir();
+        let source = dir.join(format!("logpulse-spill-source-{}.log", std::process::id()));
+        let spill = dir.join(format!("logpulse-spill-chain-{}.log", std::process::id()));
+        std::fs::write(&source, "old 0\nold 1\n").unwrap();
+        let mut app = App::new("test".to_string());
+        app.max_lines = 2;
+        app.history = Some(History::Local(crate::source::FileHistory::new(
+            source.clone(),
+            12,
+        )));
+        app.enable_spill(SpillFile::create(spill.to_str().unwrap()).unwrap());
+        for i in 0..3 {
+            app.add_parsed_log(entry(&format!("line {}", i)), &Prebuilt);
+        }
+
+        let history = app.history.as_mut().unwrap();
+        assert_eq!(history.load_older(5).unwrap(), ["line 0"]);
+        assert!(history.has_more());
+        let older = history.load_older(5).unwrap();
+        assert_eq!(older, ["old 0", "old 1"]);
+        app.prepend_logs(older.iter().map(|l| entry(l)).collect());
+        assert!(!app.history.as_ref().unwrap().has_more());
+
+        // Dropping what was paged in starts both over
+        app.jump_to_end();
+        let history = app.history.as_mut().unwrap();
+        assert_eq!(history.load_older(5).unwrap(), ["line 0"]);
+        assert_eq!(history.load_older(5).unwrap(), ["old 0", "old 1"]);
+        std::fs::remove_file(&source).unwrap();
+        std::fs::remove_file(&spill).unwrap();
+    }
+
+    #[test]
+    fn min_level_floor_keeps_selected_entry() {
+        let mut app = App::new("test".to_string());
+        for (raw, level) in [
+            ("-", LogLevel::Unknown),
+            ("a", LogLevel::Debug),
+            ("b", LogLevel::Warn),
+            ("c", LogLevel::Info),
+            ("d", LogLevel::Error),
+            ("e", LogLevel::Trace),
+        ] {
+            app.add_log(LogEntry {
+                level,
+                ..entry(raw)
+            });
+        }
+        let raws = |app: &App| -> String {
+            app.visible_logs()
+                .iter()
+                .map(|(_, e)| e.raw.as_str())
+                .collect()
+        };
+
+        app.frozen = true;
+        app.selected_index = 4;
+        app.set_min_level(Some(LogLevel::Warn));
+        assert_eq!(raws(&app), "bd");
+        assert_eq!(app.selected_index, 1);
+        app.set_min_level(None);
+        assert_eq!(app.selected_index, 4);
+        // "c" is hidden: the selection moves on to the next entry shown
+        app.selected_index = 3;
+        app.set_min_level(Some(LogLevel::Warn));
+        assert_eq!(app.selected_index, 1);
+
+        // Following the tail keeps following; lines without a level are hidden
+        app.frozen = false;
+        app.selected_index = 0;
+        app.set_min_level(None);
+        app.selected_index = 5;
+        app.cycle_min_level();
+        assert_eq!(app.min_level, Some(LogLevel::Trace));
+        assert_eq!(raws(&app), "abcde");
+        assert_eq!(app.selected_index, 4);
+
+        // A filter token composes with the key floor and the regex
+        app.filter_text = "level:info+ [a-c]".to_string();
+        app.update_filter_regex();
+        assert_eq!(app.effective_min_level(), Some(LogLevel::Info));
+        assert_eq!(raws(&app), "bc");
+        assert!(app.active_filters().contains(&"level ≥ INFO".to_string()));
+    }
+
+    #[test]
+    fn filter_terms_include_exclude_and_report_errors() {
+        let mut app = App::new("test".to_string());
+        for raw in [
+            "GET /api/users 200",
+            "GET /health 200",
+            "POST /api/users 500",
+            "db down: retrying",
+        ] {
+            app.add_log(entry(raw));
+        }
+        let raws = |app: &App| -> Vec<String> {
+            app.visible_logs()
+                .iter()
+                .map(|(_, e)| e.raw.clone())
+                .collect()
+        };
+
+        // Every include must match and no exclude may
+        app.filter_text = "get !health".to_string();
+        app.update_filter_regex();
+        assert_eq!(raws(&app), ["GET /api/users 200"]);
+        app.filter_text = r#"!"/api/users" "db down""#.to_string();
+        app.update_filter_regex();
+        assert_eq!(raws(&app), ["db down: retrying"]);
+
+        // An invalid term is reported and left out instead of hiding everything
+        app.filter_text = "users (500".to_string();
+        app.update_filter_regex();
+        assert_eq!(app.filter_error.as_deref(), Some("(500: unclosed group"));
+        assert_eq!(raws(&app).len(), 2);
+        app.filter_text = "users".to_string();
+        app.update_filter_regex();
+        assert!(app.filter_error.is_none());
+    }
+
+    #[test]
+    fn status_facet_cycles_classes_and_passes_other_lines() {
+        let mut app = App::new("test".to_string());
+        for (raw, status) in [
+            ("a", Some(200)),
+            ("b", Some(503)),
+            ("c", None),
+            ("d", Some(404)),
+        ] {
+            app.add_log(LogEntry {
+                status,
+                ..entry(raw)
+            });
+        }
+        let raws = |app: &App| -> String {
+            app.visible_logs()
+                .iter()
+                .map(|(_, e)| e.raw.as_str())
+                .collect()
+        };
+        app.cycle_status_facet();
+        assert_eq!(app.status_facet, Some(5));
+        assert_eq!(raws(&app), "bc");
+        app.cycle_status_facet();
+        assert_eq!(raws(&app), "cd");
+        for _ in 0..3 {
+            app.cycle_status_facet();
+        }
+        assert_eq!(app.status_facet, None);
+        assert_eq!(raws(&app), "abcd");
+    }
+
+    #[test]
+    fn diff_mode_shows_only_errors_missing_from_snapshot() {
+        let mut app = App::new("test".to_string());
+        let error = |raw: &str| LogEntry {
+            level: LogLevel::Error,
+            ..entry(raw)
+        };
+        app.add_log(error("timeout after 3012ms"));
+        app.add_log(entry("deploy started"));
+        assert_eq!(app.snapshot_error_signatures(), 1);
+
+        app.add_log(error("timeout after 2950ms"));
+        app.add_log(error("column user_id missing"));
+        app.diff_mode = true;
+        let visible: Vec<&str> = app
+            .visible_logs()
+            .iter()
+            .map(|(_, e)| e.raw.as_str())
+            .collect();
+        assert_eq!(visible, ["column user_id missing"]);
+        assert_eq!(app.new_error_count(), 1);
+    }
+}
@@ -0,0 +1,888 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{
+    LogLevel, DEFAULT_EPS_WINDOW_SECS, DEFAULT_TIMESTAMP_MAX_WIDTH, EPS_WINDOW_RANGE,
+    HIGHLIGHT_COLORS, MAX_LOG_LINES, STATUS_TIMEOUT_SECS,
+};
+use crate::parser::CustomRegexParser;
+use crate::source::{SshOpts, TAIL_LINES};
+use crate::theme::{Theme, THEME_NAMES};
+
+/// User configuration, read from `$XDG_CONFIG_HOME/logpulse/config.toml`
+/// (or `~/.config/logpulse/config.toml`, or `--config`), with a project
+/// `.logpulse.toml` merged on top. Missing keys keep their defaults, unknown
+/// ones are warned about; CLI flags override whatever is set here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Entries kept in the in-memory ring buffer
+    pub max_lines: usize,
+    /// Lines of backlog loaded on startup (files, docker, kubectl, ssh)
+    pub tail_lines: usize,
+    /// Parser used when --format isn't given: auto, json, laravel, ...
+    pub default_format: String,
+    /// Regex with named groups used instead of `default_format` when
+    /// --format isn't given, like --parser-regex
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parser_regex: Option<String>,
+    /// Filter the feed starts with (`/` syntax), e.g. `!healthcheck level:warn+`;
+    /// --grep replaces it in --print mode
+    pub filter: String,
+    /// Seconds of history in the activity sparkline
+    pub eps_window_secs: usize,
+    /// Colors cycled by the `*` highlight key (names like `magenta` or `#ff8800`)
+    pub highlight_colors: [String; 8],
+    pub status_timeout_secs: u64,
+    /// Widest the timestamp column (t key) grows before timestamps are cut off
+    pub timestamp_max_width: usize,
+    /// Container reconnect backoff: first delay, doubled up to the max
+    pub reconnect_initial_delay_ms: u64,
+    pub reconnect_max_delay_ms: u64,
+    /// Named patterns always highlighted in a fixed color, e.g.
+    /// `[auto_highlight.ip]` with `pattern = '...'` and `color = "cyan"`
+    pub auto_highlight: BTreeMap<String, AutoHighlight>,
+    /// Feed keys by action name, e.g. `scroll_up = ["ctrl+p", "up"]`;
+    /// an action listed here loses its default keys
+    pub keybindings: BTreeMap<String, KeyBinding>,
+    /// Built-in theme name (`theme = "nord"`), or a `[theme]` table of
+    /// colors such as `error_color = "#ff5555"` over an optional `base` theme
+    pub theme: ThemeConfig,
+    /// Extra words marking a level, by level name: `[levels]` with
+    /// `error = ["FAIL", "E"]`. Matched as whole words, before the built-in ones
+    pub levels: BTreeMap<String, Vec<String>>,
+    /// ssh defaults by host, e.g. `[ssh."web1.corp.com"]` with `user`, `port`,
+    /// `identity` and `jump`; `[ssh."*"]` applies to hosts without an entry
+    pub ssh: BTreeMap<String, SshHost>,
+    /// User config file in use (the default path or --config)
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// Project `.logpulse.toml` that was merged in, if any
+    #[serde(skip)]
+    pub project_path: Option<PathBuf>,
+    /// Problems that don't stop loading, such as unknown keys
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+/// Top-level keys of [`Config`], to warn about misspelled ones.
+const CONFIG_KEYS: &[&str] = &[
+    "max_lines",
+    "tail_lines",
+    "default_format",
+    "parser_regex",
+    "filter",
+    "eps_window_secs",
+    "highlight_colors",
+    "status_timeout_secs",
+    "timestamp_max_width",
+    "reconnect_initial_delay_ms",
+    "reconnect_max_delay_ms",
+    "auto_highlight",
+    "keybindings",
+    "theme",
+    "levels",
+    "ssh",
+];
+
+/// Settings given on the command line, which win over the config files.
+#[derive(Debug, Default)]
+pub struct Overrides {
+    pub max_lines: Option<usize>,
+    pub tail_lines: Option<usize>,
+    pub default_format: Option<String>,
+    pub eps_window_secs: Option<usize>,
+    pub theme: Option<String>,
+}
+
+/// One `[ssh."<host>"]` entry; the matching command line options win.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SshHost {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Private key file, passed as `ssh -i`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    /// Jump host, passed as `ssh -J`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jump: Option<String>,
+}
+
+impl SshHost {
+    /// Fill in what the command line left unset; the user only applies to
+    /// a target without one.
+    pub fn apply(&self, opts: &mut SshOpts) {
+        if let Some(ref user) = self.user {
+            if !opts.target.contains('@') {
+                opts.target = format!("{}@{}", user, opts.target);
+            }
+        }
+        opts.port = opts.port.or(self.port);
+        if opts.key.is_none() {
+            opts.key.clone_from(&self.identity);
+        }
+        if opts.jump.is_none() {
+            opts.jump.clone_from(&self.jump);
+        }
+    }
+}
+
+/// One `[auto_highlight.<name>]` entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoHighlight {
+    pub pattern: String,
+    pub color: String,
+}
+
+/// The `theme` key: a built-in theme name or a table of color overrides.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ThemeConfig {
+    Named(String),
+    Custom {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base: Option<String>,
+        #[serde(flatten)]
+        colors: BTreeMap<String, String>,
+    },
+}
+
+/// One `[keybindings]` value: a key spec or a list of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBinding {
+    pub fn specs(&self) -> &[String] {
+        match self {
+            KeyBinding::One(spec) => std::slice::from_ref(spec),
+            KeyBinding::Many(specs) => specs,
+        }
+    }
+}
+
+/// A key with its modifiers, written `ctrl+k`, `alt+j`, `f5`, `pagedown`,
+/// `space` or a single character (`K` is shift+k).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeySpec {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    /// The spec a key press matches. Shift is part of the character itself
+    /// (`D`, `?`), so it's dropped for character keys.
+    pub fn from_event(key: &KeyEvent) -> Self {
+        let mut modifiers = key.modifiers;
+        if matches!(key.code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self {
+            code: key.code,
+            modifiers,
+        }
+    }
+}
+
+impl FromStr for KeySpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid key '{}'", spec);
+        // `+` itself is a key: `+`, `ctrl++`
+        let (mods, key) = match spec.strip_suffix('+') {
+            Some("") => ("", "+"),
+            Some(rest) if rest.ends_with('+') => (&rest[..rest.len() - 1], "+"),
+            _ => spec.rsplit_once('+').unwrap_or(("", spec)),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(invalid()),
+                },
+            },
+        };
+
+        // Shifted characters and shift+tab are their own key codes
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            code => code,
+        };
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_lines: MAX_LOG_LINES,
+            tail_lines: TAIL_LINES,
+            default_format: "auto".to_string(),
+            parser_regex: None,
+            filter: String::new(),
+            eps_window_secs: DEFAULT_EPS_WINDOW_SECS,
+            highlight_colors: HIGHLIGHT_COLORS.map(|c| c.to_string()),
+            status_timeout_secs: STATUS_TIMEOUT_SECS,
+            timestamp_max_width: DEFAULT_TIMESTAMP_MAX_WIDTH,
+            reconnect_initial_delay_ms: 1000,
+            reconnect_max_delay_ms: 10_000,
+            auto_highlight: BTreeMap::new(),
+            keybindings: BTreeMap::new(),
+            theme: ThemeConfig::Named("default".to_string()),
+            levels: BTreeMap::new(),
+            ssh: BTreeMap::new(),
+            path: None,
+            project_path: None,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the user config file (`explicit`, from --config, must exist) and
+    /// the nearest project `.logpulse.toml`, falling back to defaults when
+    /// there is neither.
+    pub fn load(explicit: Option<&Path>) -> Result<Self, String> {
+        let mut merged = toml::Table::new();
+        let path = match explicit {
+            Some(path) => Some(path.to_path_buf()),
+            None => config_path(),
+        };
+        if let Some(ref path) = path {
+            if explicit.is_some() || path.exists() {
+                merge_tables(&mut merged, read_table(path)?);
+            }
+        }
+        let project_path = std::env::current_dir()
+            .ok()
+            .and_then(|dir| find_project_config(&dir));
+        let mut warnings = Vec::new();
+        if let Some(ref path) = project_path {
+            let mut project = read_table(path)?;
+            warnings.extend(drop_user_only_keys(path, &mut project));
+            merge_tables(&mut merged, project);
+        }
+
+        let mut config = Self::from_table(merged)?;
+        config.path = path;
+        config.project_path = project_path;
+        config.warnings.extend(warnings);
+        Ok(config)
+    }
+
+    /// Apply command line settings over the ones from the files.
+    pub fn apply_overrides(&mut self, overrides: Overrides) {
+        if let Some(n) = overrides.max_lines {
+            self.max_lines = n;
+        }
+        if let Some(n) = overrides.tail_lines {
+            self.tail_lines = n;
+        }
+        if let Some(format) = overrides.default_format {
+            self.default_format = format;
+        }
+        if let Some(secs) = overrides.eps_window_secs {
+            self.eps_window_secs = secs;
+        }
+        if let Some(name) = overrides.theme {
+            self.theme = ThemeConfig::Named(name);
+        }
+    }
+
+    #[cfg(test)]
+    fn from_toml(data: &str) -> Result<Self, String> {
+        let table: toml::Table = toml::from_str(data).map_err(|e| e.to_string())?;
+        Self::from_table(table)
+    }
+
+    fn from_table(mut table: toml::Table) -> Result<Self, String> {
+        let mut unknown: Vec<String> = table
+            .keys()
+            .filter(|key| !CONFIG_KEYS.contains(&key.as_str()))
+            .map(|key| format!("config: unknown key '{}' ignored", key))
+            .collect();
+        unknown.extend(unknown_subtable_keys(&mut table));
+        let mut config: Self = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())?;
+        config.highlight_colors()?;
+        config.auto_highlights()?;
+        config.theme()?;
+        check_eps_window(config.eps_window_secs).map_err(|e| format!("eps_window_secs: {}", e))?;
+        if let Some(ref pattern) = config.parser_regex {
+            CustomRegexParser::new(pattern).map_err(|e| format!("parser_regex: {}", e))?;
+        }
+        for (host, entry) in &config.ssh {
+            for (key, value) in [("user", &entry.user), ("jump", &entry.jump)] {
+                if value.as_deref().is_some_and(|v| v.starts_with('-')) {
+                    return Err(format!("ssh.\"{}\".{}: can't start with '-'", host, key));
+                }
+            }
+        }
+        config.warnings = unknown;
+        for name in config.levels.keys() {
+            if LogLevel::from_name(name).is_none() {
+                config.warnings.push(format!(
+                    "config: unknown level '{}' in [levels] ignored",
+                    name
+                ));
+            }
+        }
+        Ok(config)
+    }
+
+    /// `[levels]` words with their level, skipping unknown level names.
+    pub fn level_keywords(&self) -> Vec<(String, LogLevel)> {
+        self.levels
+            .iter()
+            .filter_map(|(name, words)| Some((LogLevel::from_name(name)?, words)))
+            .flat_map(|(level, words)| words.iter().map(move |w| (w.clone(), level)))
+            .collect()
+    }
+
+    /// ssh defaults for `target` (`host` or `user@host`): the host's own
+    /// entry, else `[ssh."*"]`.
+    pub fn ssh_defaults(&self, target: &str) -> Option<&SshHost> {
+        let host = target.rsplit('@').next().unwrap_or(target);
+        self.ssh.get(host).or_else(|| self.ssh.get("*"))
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn highlight_colors(&self) -> Result<Vec<Color>, String> {
+        self.highlight_colors
+            .iter()
+            .map(|name| {
+                Color::from_str(name).map_err(|_| format!("invalid highlight color '{}'", name))
+            })
+            .collect()
+    }
+
+    /// Auto-highlight patterns with their parsed colors, in name order.
+    pub fn auto_highlights(&self) -> Result<Vec<(&str, Color)>, String> {
+        self.auto_highlight
+            .iter()
+            .map(|(name, auto)| {
+                regex::Regex::new(&auto.pattern)
+                    .map_err(|e| format!("auto_highlight.{}: {}", name, e))?;
+                let color = Color::from_str(&auto.color).map_err(|_| {
+                    format!("auto_highlight.{}: invalid color '{}'", name, auto.color)
+                })?;
+                Ok((auto.pattern.as_str(), color))
+            })
+            .collect()
+    }
+
+    /// The configured theme with any `[theme]` color overrides applied.
+    pub fn theme(&self) -> Result<Theme, String> {
+        let (base, colors) = match &self.theme {
+            ThemeConfig::Named(name) => (name.as_str(), None),
+            ThemeConfig::Custom { base, colors } => {
+                (base.as_deref().unwrap_or("default"), Some(colors))
+            }
+        };
+        let mut theme = Theme::builtin(base).ok_or_else(|| {
+            format!(
+                "unknown theme '{}' (expected one of: {})",
+                base,
+                THEME_NAMES.join(", ")
+            )
+        })?;
+        for (field, value) in colors.into_iter().flatten() {
+            let slot = theme
+                .color_mut(field)
+                .ok_or_else(|| format!("theme: unknown color '{}'", field))?;
+            *slot = Color::from_str(value)
+                .map_err(|_| format!("theme.{}: invalid color '{}'", field, value))?;
+        }
+        Ok(theme)
+    }
+}
+
+/// Remove what a project config may not set. A checkout can carry a
+/// `.logpulse.toml`; it doesn't get to say how ssh connects.
+fn drop_user_only_keys(path: &Path, project: &mut toml::Table) -> Vec<String> {
+    project
+        .remove("ssh")
+        .map(|_| {
+            format!(
+                "{}: [ssh] is only read from the user config; ignored",
+                path.display()
+            )
+        })
+        .into_iter()
+        .collect()
+}
+
+/// Keys of the `[ssh."<host>"]` and `[auto_highlight.<name>]` entries.
+const SSH_HOST_KEYS: &[&str] = &["user", "port", "identity", "jump"];
+const AUTO_HIGHLIGHT_KEYS: &[&str] = &["pattern", "color"];
+
+/// Drop misspelled keys inside `[theme]`, `[ssh."<host>"]` and
+/// `[auto_highlight.<name>]`, returning a warning for each.
+fn unknown_subtable_keys(table: &mut toml::Table) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut prune = |section: &str, entry: &mut toml::Table, known: &dyn Fn(&str) -> bool| {
+        entry.retain(|key, _| {
+            let keep = known(key);
+            if !keep {
+                warnings.push(format!(
+                    "config: unknown key '{}' in [{}] ignored",
+                    key, section
+                ));
+            }
+            keep
+        });
+    };
+    if let Some(toml::Value::Table(theme)) = table.get_mut("theme") {
+        prune("theme", theme, &|key| {
+            key == "base" || Theme::default().color_mut(key).is_some()
+        });
+    }
+    for (name, keys) in [
+        ("ssh", SSH_HOST_KEYS),
+        ("auto_highlight", AUTO_HIGHLIGHT_KEYS),
+    ] {
+        if let Some(toml::Value::Table(entries)) = table.get_mut(name) {
+            for (entry_name, entry) in entries.iter_mut() {
+                if let toml::Value::Table(entry) = entry {
+                    let section = format!("{}.{}", name, entry_name);
+                    prune(&section, entry, &|key| keys.contains(&key));
+                }
+            }
+        }
+    }
+    warnings
+}
+
+/// Reject sparkline windows outside `EPS_WINDOW_RANGE`.
+pub fn check_eps_window(secs: usize) -> Result<usize, String> {
+    if EPS_WINDOW_RANGE.contains(&secs) {
+        Ok(secs)
+    } else {
+        Err(format!(
+            "{} is out of range ({}-{} seconds)",
+            secs,
+            EPS_WINDOW_RANGE.start(),
+            EPS_WINDOW_RANGE.end()
+        ))
+    }
+}
+
+/// `$XDG_CONFIG_HOME/logpulse/config.toml`, else `~/.config/logpulse/config.toml`.
+pub fn config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("logpulse").join("config.toml"))
+}
+
+/// Remember the sparkline window chosen with +/- in the user config file.
+pub fn save_eps_window(config: &Config, secs: usize) -> Result<(), String> {
+    let path = config
+        .path
+        .as_deref()
+        .ok_or("no config directory (HOME is not set)")?;
+    set_config_value(path, "eps_window_secs", secs as i64)
+}
+
+/// Set a top-level integer key in a config file, keeping its other keys,
+/// comments and layout; the file is created if missing.
+fn set_config_value(path: &Path, key: &str, value: i64) -> Result<(), String> {
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let mut doc: toml_edit::DocumentMut = data
+        .parse()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    doc[key] = toml_edit::value(value);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    std::fs::write(path, doc.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Walk up from `start` to `/` looking for a `.logpulse.toml`.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(".logpulse.toml"))
+        .find(|path| path.is_file())
+}
+
+fn read_table(path: &Path) -> Result<toml::Table, String> {
+    let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    toml::from_str(&data).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Merge `overlay` into `base`: nested tables are merged key by key,
+/// any other value in `overlay` replaces the one in `base`.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(nested)) => {
+                merge_tables(existing, nested);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_config_keeps_defaults() {
+        let config = Config::from_toml("max_lines = 50000\ndefault_format = \"json\"\n").unwrap();
+        assert_eq!(config.max_lines, 50_000);
+        assert_eq!(config.default_format, "json");
+        assert_eq!(config.tail_lines, TAIL_LINES);
+        assert_eq!(config.highlight_colors().unwrap()[0], HIGHLIGHT_COLORS[0]);
+    }
+
+    #[test]
+    fn printed_config_round_trips() {
+        let printed = Config::default().to_toml();
+        let parsed = Config::from_toml(&printed).unwrap();
+        assert_eq!(parsed.to_toml(), printed);
+    }
+
+    #[test]
+    fn project_config_overrides_user_keys() {
+        let mut merged: toml::Table =
+            toml::from_str("max_lines = 50000\ndefault_format = \"json\"\n").unwrap();
+        merge_tables(
+            &mut merged,
+            toml::from_str("default_format = \"laravel\"").unwrap(),
+        );
+        let config = Config::from_table(merged).unwrap();
+        assert_eq!(config.max_lines, 50_000);
+        assert_eq!(config.default_format, "laravel");
+    }
+
+    #[test]
+    fn project_config_found_in_parent_dir() {
+        let root = std::env::temp_dir().join(format!("logpulse-cfg-{}", std::process::id()));
+        let nested = root.join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join(".logpulse.toml"), "tail_lines = 10\n").unwrap();
+
+        assert_eq!(
+            find_project_config(&nested),
+            Some(root.join(".logpulse.toml"))
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn invalid_color_is_rejected() {
+        let data = "highlight_colors = [\"red\", \"nope\", \"blue\", \"green\", \"cyan\", \"gray\", \"white\", \"black\"]";
+        assert!(Config::from_toml(data).is_err());
+    }
+
+    #[test]
+    fn auto_highlights_parse_and_validate() {
+        let config = Config::from_toml(
+            "[auto_highlight.status]\npattern = '\\b5\\d\\d\\b'\ncolor = \"red\"\n\n\
+             [auto_highlight.ip]\npattern = '\\d+\\.\\d+\\.\\d+\\.\\d+'\ncolor = \"#00ffff\"\n",
+        )
+        .unwrap();
+        let auto = config.auto_highlights().unwrap();
+        assert_eq!(auto[0], (r"\d+\.\d+\.\d+\.\d+", Color::Rgb(0, 255, 255)));
+        assert_eq!(auto[1], (r"\b5\d\d\b", Color::Red));
+
+        assert!(Config::from_toml("[auto_highlight.bad]\npattern = '('\ncolor = \"red\"").is_err());
+        assert!(
+            Config::from_toml("[auto_highlight.bad]\npattern = 'x'\ncolor = \"nope\"").is_err()
+        );
+    }
+
+    #[test]
+    fn key_specs_parse_modifiers_and_names() {
+        let spec = |s: &str| s.parse::<KeySpec>().map(|k| (k.code, k.modifiers));
+        assert_eq!(
+            spec("ctrl+k"),
+            Ok((KeyCode::Char('k'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(spec("Alt+J"), Ok((KeyCode::Char('J'), KeyModifiers::ALT)));
+        assert_eq!(
+            spec("shift+d"),
+            Ok((KeyCode::Char('D'), KeyModifiers::NONE))
+        );
+        assert_eq!(spec("f5"), Ok((KeyCode::F(5), KeyModifiers::NONE)));
+        assert_eq!(spec("space"), Ok((KeyCode::Char(' '), KeyModifiers::NONE)));
+        assert_eq!(
+            spec("ctrl++"),
+            Ok((KeyCode::Char('+'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            spec("shift+tab"),
+            Ok((KeyCode::BackTab, KeyModifiers::NONE))
+        );
+        assert!(spec("hyper+k").is_err());
+        assert!(spec("f13").is_err());
+        assert!(spec("k+").is_err());
+
+        // A shifted key press matches the plain uppercase spec
+        let press = KeyEvent::new(KeyCode::Char('D'), KeyModifiers::SHIFT);
+        assert_eq!(KeySpec::from_event(&press), "D".parse().unwrap());
+    }
+
+    #[test]
+    fn keybindings_accept_one_key_or_a_list() {
+        let config = Config::from_toml(
+            "[keybindings]\nquit = \"ctrl+q\"\nscroll_up = [\"k\", \"ctrl+p\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.keybindings["quit"].specs(), ["ctrl+q"]);
+        assert_eq!(config.keybindings["scroll_up"].specs(), ["k", "ctrl+p"]);
+    }
+
+    #[test]
+    fn theme_by_name_or_custom_table() {
+        let config = Config::from_toml("theme = \"gruvbox\"").unwrap();
+        assert_eq!(config.theme().unwrap(), Theme::builtin("gruvbox").unwrap());
+
+        let config = Config::from_toml(
+            "[theme]\nbase = \"nord\"\nerror_color = \"#ff0000\"\nselected_bg = \"blue\"",
+        )
+        .unwrap();
+        let theme = config.theme().unwrap();
+        assert_eq!(theme.error_color, Color::Rgb(255, 0, 0));
+        assert_eq!(theme.selected_bg, Color::Blue);
+        assert_eq!(theme.info_color, Theme::builtin("nord").unwrap().info_color);
+        // Round-trips through print-config
+        assert_eq!(
+            Config::from_toml(&config.to_toml())
+                .unwrap()
+                .theme()
+                .unwrap(),
+            theme
+        );
+
+        assert!(Config::from_toml("theme = \"dracula\"").is_err());
+        let typo =
+            Config::from_toml("[theme]\nerror_colour = \"red\"\nwarn_color = \"cyan\"").unwrap();
+        assert_eq!(
+            typo.warnings,
+            vec!["config: unknown key 'error_colour' in [theme] ignored"]
+        );
+        assert_eq!(typo.theme().unwrap().warn_color, Color::Cyan);
+        assert!(Config::from_toml("[theme]\nerror_color = \"reddish\"").is_err());
+    }
+
+    #[test]
+    fn command_line_wins_over_file_over_defaults() {
+        let mut config =
+            Config::from_toml("max_lines = 50000\ntail_lines = 200\ntheme = \"nord\"\n").unwrap();
+        config.apply_overrides(Overrides {
+            max_lines: Some(100),
+            default_format: Some("json".to_string()),
+            ..Overrides::default()
+        });
+        assert_eq!(config.max_lines, 100);
+        assert_eq!(config.default_format, "json");
+        assert_eq!(config.tail_lines, 200);
+        assert!(matches!(config.theme, ThemeConfig::Named(ref name) if name == "nord"));
+        assert_eq!(config.eps_window_secs, DEFAULT_EPS_WINDOW_SECS);
+        assert_eq!(config.timestamp_max_width, DEFAULT_TIMESTAMP_MAX_WIDTH);
+    }
+
+    #[test]
+    fn unknown_keys_and_levels_warn() {
+        let config = Config::from_toml(
+            "max_line = 5\ntail_lines = 20\n\n[levels]\nerror = [\"FAIL\", \"E\"]\nnotice = [\"N\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.tail_lines, 20);
+        assert_eq!(
+            config.warnings,
+            [
+                "config: unknown key 'max_line' ignored",
+                "config: unknown level 'notice' in [levels] ignored"
+            ]
+        );
+        assert_eq!(
+            config.level_keywords(),
+            [
+                ("FAIL".to_string(), LogLevel::Error),
+                ("E".to_string(), LogLevel::Error)
+            ]
+        );
+        assert!(Config::from_toml(&Config::default().to_toml())
+            .unwrap()
+            .warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn ssh_host_defaults_fill_unset_options() {
+        let config = Config::from_toml(
+            "[ssh.\"web1.corp.com\"]\nuser = \"deploy\"\nport = 2222\nidentity = \"~/.ssh/prod\"\n\n\
+             [ssh.\"*\"]\njump = \"bastion\"\n",
+        )
+        .unwrap();
+        let opts = |target: &str, port: Option<u16>| SshOpts {
+            target: target.to_string(),
+            port,
+            key: None,
+            jump: None,
+            no_reconnect: false,
+            connected: Default::default(),
+        };
+
+        let mut web1 = opts("web1.corp.com", Some(22));
+        config.ssh_defaults(&web1.target).unwrap().apply(&mut web1);
+        assert_eq!(web1.target, "deploy@web1.corp.com");
+        assert_eq!(web1.port, Some(22));
+        assert_eq!(web1.key.as_deref(), Some("~/.ssh/prod"));
+        assert_eq!(web1.jump, None);
+
+        let mut other = opts("root@db1", None);
+        config
+            .ssh_defaults(&other.target)
+            .unwrap()
+            .apply(&mut other);
+        assert_eq!(other.target, "root@db1");
+        assert_eq!(other.jump.as_deref(), Some("bastion"));
+        assert!(Config::default().ssh_defaults("web1").is_none());
+    }
+
+    #[test]
+    fn preset_filter_and_parser_regex() {
+        let config = Config::from_toml(
+            "filter = \"!healthcheck level:warn+\"\nparser_regex = '^(?P<level>\\w+) (?P<message>.*)$'\n",
+        )
+        .unwrap();
+        assert_eq!(config.filter, "!healthcheck level:warn+");
+        assert!(config.parser_regex.is_some());
+        assert!(config.warnings.is_empty());
+
+        let mut app = crate::app::App::new("app.log".to_string());
+        app.apply_config(&config);
+        assert_eq!(app.filter_text, "!healthcheck level:warn+");
+        assert_eq!(app.filter_min_level, Some(LogLevel::Warn));
+
+        assert!(Config::from_toml("parser_regex = '('").is_err());
+        assert!(Config::from_toml("parser_regex = '(\\w+)'").is_err());
+    }
+
+    #[test]
+    fn ssh_defaults_are_user_config_only() {
+        let mut project: toml::Table = toml::from_str(
+            "tail_lines = 10
+[ssh.\"*\"]
+user = \"deploy\"
+",
+        )
+        .unwrap();
+        let warnings = drop_user_only_keys(Path::new(".logpulse.toml"), &mut project);
+        assert_eq!(warnings.len(), 1);
+        assert!(!project.contains_key("ssh"));
+        assert!(project.contains_key("tail_lines"));
+
+        assert!(Config::from_toml("[ssh.\"*\"]\nuser = \"-oProxyCommand=touch x #\"").is_err());
+        assert!(Config::from_toml("[ssh.\"*\"]\njump = \"-oProxyJump=x\"").is_err());
+        let typo = Config::from_toml("[ssh.\"*\"]\nusr = \"deploy\"").unwrap();
+        assert_eq!(
+            typo.warnings,
+            vec!["config: unknown key 'usr' in [ssh.*] ignored"]
+        );
+    }
+
+    #[test]
+    fn explicit_config_path_must_exist() {
+        let path =
+            std::env::temp_dir().join(format!("logpulse-missing-{}.toml", std::process::id()));
+        assert!(Config::load(Some(&path)).is_err());
+    }
+
+    #[test]
+    fn eps_window_outside_range_is_rejected() {
+        assert_eq!(
+            Config::from_toml("eps_window_secs = 300")
+                .unwrap()
+                .eps_window_secs,
+            300
+        );
+        assert!(Config::from_toml("eps_window_secs = 5").is_err());
+        assert!(Config::from_toml("eps_window_secs = 601").is_err());
+    }
+
+    #[test]
+    fn config_value_is_written_in_place() {
+        let dir = std::env::temp_dir().join(format!("logpulse-config-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        set_config_value(&path, "eps_window_secs", 120).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "eps_window_secs = 120\n"
+        );
+
+        std::fs::write(
+            &path,
+            "# mine\nmax_lines = 5000\neps_window_secs = 60 # default\n\n[theme]\nbase = \"nord\"\n",
+        )
+        .unwrap();
+        set_config_value(&path, "eps_window_secs", 300).unwrap();
+        let data = std::fs::read_to_string(&path).unwrap();
+        assert!(data.starts_with("# mine\nmax_lines = 5000\neps_window_secs = 300"));
+        assert!(data.ends_with("[theme]\nbase = \"nord\"\n"));
+        assert_eq!(Config::from_toml(&data).unwrap().eps_window_secs, 300);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
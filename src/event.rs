@@ -1,242 +1,973 @@
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
-use std::time::Duration;
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::layout::Rect;
+use std::time::{Duration, Instant};
 
-use crate::app::{App, InputMode, ViewMode};
+use crate::app::{App, ExportFormat, InputMode, LogLevel, MatchScope, TimeDisplay, ViewMode};
+use crate::keymap::Action;
+use crate::session::{default_session_path, Session};
+use crate::stats::TOP_MESSAGES;
+use crate::theme::css_color;
+use crate::ui::{detail_copy_text, detail_lines, feed_entry_at, footer_hint_at, in_footer};
+
+/// Two clicks on the same entry within this open its detail view.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+/// Rows PgUp / PgDn move the help overlay.
+const HELP_PAGE: isize = 10;
+/// Entries moved per scroll wheel notch.
+const WHEEL_LINES: usize = 3;
+/// Columns per h/l (←/→) press; Shift+←/→ moves one
+const HSCROLL_STEP: usize = 20;
+
+/// Help rows for keys that belong to a prompt or view rather than a
+/// rebindable action; the rest of the help comes from `keymap::HELP`.
+pub const CONTEXT_KEYS: &[(&str, &str)] = &[
+    (
+        "←/→ Home/End",
+        "In any prompt: move the cursor, Del deletes",
+    ),
+    (
+        "/ / Tab",
+        "Detail: filter JSON fields / field table or tree",
+    ),
+    ("/ n/N", "Detail: search the text / next, previous match"),
+    ("Ctrl+C", "Force quit"),
+];
 
 pub fn handle_events(app: &mut App) -> std::io::Result<bool> {
     if event::poll(Duration::from_millis(50))? {
-        if let Event::Key(key) = event::read()? {
-            // Ctrl+C always quits
-            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
-                app.should_quit = true;
-                return Ok(true);
+        let mode = app.input_mode;
+        let quit = match event::read()? {
+            Event::Key(key) => handle_key(app, key),
+            Event::Mouse(mouse) => {
+                handle_mouse(app, mouse);
+                false
+            }
+            Event::Paste(text) => {
+                paste_input(app, &text);
+                false
             }
+            _ => false,
+        };
+        // Handlers flip filter fields directly; the next frame re-filters once
+        app.invalidate_visible();
+        // A prompt that just opened starts with the cursor at the end and
+        // Up recalling its newest history entry
+        if app.input_mode != mode {
+            app.input_cursor = usize::MAX;
+            app.reset_prompt_history();
+        }
+        if quit {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Returns true when the app should quit.
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    // Ctrl+C always quits
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        app.should_quit = true;
+        return true;
+    }
+    if app.error_peek {
+        app.error_peek = false;
+        return true;
+    }
 
-            match app.view_mode {
-                ViewMode::Detail => match key.code {
-                    KeyCode::Esc | KeyCode::Char('q') => {
-                        app.view_mode = ViewMode::Feed;
+    match app.view_mode {
+        // The help overlay scrolls like the feed; Esc, q or its own key close it
+        ViewMode::Help => match key.code {
+            KeyCode::Up | KeyCode::Char('k') => app.scroll_help(-1),
+            KeyCode::Down | KeyCode::Char('j') => app.scroll_help(1),
+            KeyCode::PageUp => app.scroll_help(-HELP_PAGE),
+            KeyCode::PageDown | KeyCode::Char(' ') => app.scroll_help(HELP_PAGE),
+            KeyCode::Home => app.help_scroll = 0,
+            KeyCode::End => app.help_scroll = app.help_max_scroll.get(),
+            KeyCode::Esc | KeyCode::Char('q') => app.view_mode = ViewMode::Feed,
+            _ => {
+                if app.keymap.action(&key) == Some(Action::Help) {
+                    app.view_mode = ViewMode::Feed;
+                }
+            }
+        },
+        // ↑/↓ pick a format, Enter uses it, Esc keeps the best guess
+        ViewMode::FormatPicker => match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.format_selected = app.format_selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let last = app.format_choices.len().saturating_sub(1);
+                app.format_selected = (app.format_selected + 1).min(last);
+            }
+            KeyCode::Enter => {
+                app.format_picked = Some(app.format_selected);
+                app.view_mode = ViewMode::Feed;
+            }
+            KeyCode::Esc => {
+                app.format_picked = Some(0);
+                app.view_mode = ViewMode::Feed;
+            }
+            _ => {}
+        },
+        // Any key closes the stats view
+        // ↑/↓ pick a top message, Enter filters on it
+        ViewMode::Stats => match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.stats_selected = app.stats_selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let rows = app.message_stats.top(TOP_MESSAGES).len();
+                app.stats_selected = (app.stats_selected + 1).min(rows.saturating_sub(1));
+            }
+            KeyCode::Enter => match app.filter_top_message() {
+                Some(shape) => app.set_status(format!("Filtering on: {}", shape)),
+                None => app.view_mode = ViewMode::Feed,
+            },
+            _ => {
+                app.view_mode = ViewMode::Feed;
+            }
+        },
+        // Typing a search for the plain detail view
+        ViewMode::Detail if app.input_mode == InputMode::DetailSearch => match key.code {
+            KeyCode::Esc => {
+                app.input_mode = InputMode::Normal;
+                app.input_buffer.clear();
+            }
+            KeyCode::Enter => {
+                app.input_mode = InputMode::Normal;
+                let pattern = std::mem::take(&mut app.input_buffer);
+                app.set_detail_search(&pattern);
+                let lines = selected_detail_lines(app);
+                if app.detail_search.is_some() && !app.detail_search_jump(&lines, true, true) {
+                    app.set_status(format!("No match for '{}' in detail", pattern));
+                }
+            }
+            _ => edit_input(app, key.code),
+        },
+        // Typing a field filter in the detail table
+        ViewMode::Detail if app.detail_tree.as_ref().is_some_and(|t| t.filtering) => {
+            if let Some(ref mut tree) = app.detail_tree {
+                match key.code {
+                    KeyCode::Esc => {
+                        tree.filtering = false;
+                        tree.set_field_filter(String::new());
+                    }
+                    KeyCode::Enter => tree.filtering = false,
+                    KeyCode::Backspace => {
+                        let mut filter = tree.field_filter.clone();
+                        filter.pop();
+                        tree.set_field_filter(filter);
+                    }
+                    KeyCode::Char(c) => {
+                        let filter = format!("{}{}", tree.field_filter, c);
+                        tree.set_field_filter(filter);
                     }
                     _ => {}
-                },
-                ViewMode::Feed => match app.input_mode {
-                    InputMode::Filter => match key.code {
-                        KeyCode::Esc => {
-                            app.input_mode = InputMode::Normal;
-                            app.filter_text.clear();
-                            app.update_filter_regex();
-                            app.clamp_selection();
-                        }
-                        KeyCode::Enter => {
-                            app.input_mode = InputMode::Normal;
-                            app.update_filter_regex();
-                            app.clamp_selection();
-                        }
-                        KeyCode::Backspace => {
-                            app.filter_text.pop();
-                            app.update_filter_regex();
-                        }
-                        KeyCode::Char(c) => {
-                            app.filter_text.push(c);
-                            app.update_filter_regex();
-                        }
-                        _ => {}
-                    },
-                    InputMode::Search => match key.code {
-                        KeyCode::Esc => {
-                            app.input_mode = InputMode::Normal;
-                            app.input_buffer.clear();
-                            app.search_text.clear();
-                            app.search_regex = None;
-                        }
-                        KeyCode::Enter => {
-                            app.input_mode = InputMode::Normal;
-                            app.search_text = app.input_buffer.clone();
-                            app.input_buffer.clear();
-                            app.update_search_regex();
-                            app.search_next();
-                        }
-                        KeyCode::Backspace => {
-                            app.input_buffer.pop();
-                        }
-                        KeyCode::Char(c) => {
-                            app.input_buffer.push(c);
-                        }
-                        _ => {}
-                    },
-                    InputMode::Highlight => match key.code {
-                        KeyCode::Esc => {
-                            app.input_mode = InputMode::Normal;
-                            app.input_buffer.clear();
-                        }
-                        KeyCode::Enter => {
-                            app.input_mode = InputMode::Normal;
-                            let pattern = app.input_buffer.clone();
-                            app.input_buffer.clear();
-                            if pattern.is_empty() {
-                                app.highlights.clear();
-                                app.set_status("Highlights cleared".to_string());
-                            } else {
-                                app.add_highlight(&pattern);
-                                app.set_status(format!(
-                                    "Highlight added ({} active)",
-                                    app.highlights.len()
-                                ));
-                            }
-                        }
-                        KeyCode::Backspace => {
-                            app.input_buffer.pop();
-                        }
-                        KeyCode::Char(c) => {
-                            app.input_buffer.push(c);
-                        }
-                        _ => {}
-                    },
-                    InputMode::SavePrompt => match key.code {
-                        KeyCode::Esc => {
-                            app.input_mode = InputMode::Normal;
-                            app.input_buffer.clear();
-                        }
-                        KeyCode::Enter => {
-                            app.input_mode = InputMode::Normal;
-                            let filename = app.input_buffer.clone();
-                            app.input_buffer.clear();
-                            if !filename.is_empty() {
-                                match export_visible_logs(app, &filename) {
-                                    Ok(count) => app.set_status(format!(
-                                        "Saved {} entries to {}",
-                                        count, filename
-                                    )),
-                                    Err(e) => app.set_status(format!("Save failed: {}", e)),
+                }
+            }
+        }
+        ViewMode::Detail => match key.code {
+            // The first Esc drops a search, the next closes
+            KeyCode::Esc if app.detail_search.is_some() => app.detail_search = None,
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.close_detail();
+            }
+            // Copy what the modal shows (pretty JSON for tree entries)
+            KeyCode::Char('y') => {
+                let text = app
+                    .selected_entry()
+                    .map(|(_, entry)| detail_copy_text(entry, app.detail_tree.as_ref()));
+                if let Some(text) = text {
+                    let what = if app.detail_tree.is_some() {
+                        "detail as JSON"
+                    } else {
+                        "detail"
+                    };
+                    match copy_to_clipboard(&text) {
+                        Ok(()) => app.set_status(format!("Copied {}", what)),
+                        Err(e) => app.set_status(format!("Copy failed: {}", e)),
+                    }
+                }
+            }
+            // Copy just the stack trace / continuation lines
+            KeyCode::Char('C') => {
+                let extra = app
+                    .selected_entry()
+                    .map(|(_, entry)| entry.extra_lines.join("\n"))
+                    .unwrap_or_default();
+                if extra.is_empty() {
+                    app.set_status("No continuation lines to copy".to_string());
+                } else {
+                    match copy_to_clipboard(&extra) {
+                        Ok(()) => app.set_status("Copied stack trace".to_string()),
+                        Err(e) => app.set_status(format!("Copy failed: {}", e)),
+                    }
+                }
+            }
+            _ => {
+                if let Some(ref mut tree) = app.detail_tree {
+                    match key.code {
+                        KeyCode::Up | KeyCode::Char('k') => tree.move_up(),
+                        KeyCode::Down | KeyCode::Char('j') => tree.move_down(),
+                        KeyCode::Enter => tree.toggle(),
+                        KeyCode::Tab => tree.toggle_table(),
+                        KeyCode::Char('/') => tree.start_filter(),
+                        KeyCode::Char(' ') => {
+                            if let Some(text) = tree.selected_text() {
+                                match copy_to_clipboard(&text) {
+                                    Ok(()) => app.set_status("Copied value".to_string()),
+                                    Err(e) => app.set_status(format!("Copy failed: {}", e)),
                                 }
                             }
                         }
-                        KeyCode::Backspace => {
-                            app.input_buffer.pop();
-                        }
-                        KeyCode::Char(c) => {
-                            app.input_buffer.push(c);
-                        }
-                        _ => {}
-                    },
-                    InputMode::TimeJump => match key.code {
-                        KeyCode::Esc => {
-                            app.input_mode = InputMode::Normal;
-                            app.input_buffer.clear();
-                        }
-                        KeyCode::Enter => {
-                            app.input_mode = InputMode::Normal;
-                            let time_str = app.input_buffer.clone();
-                            app.input_buffer.clear();
-                            if !time_str.is_empty() {
-                                app.jump_to_time(&time_str);
-                                app.set_status(format!("Jumped to {}", time_str));
-                            }
-                        }
-                        KeyCode::Backspace => {
-                            app.input_buffer.pop();
-                        }
-                        KeyCode::Char(c) => {
-                            app.input_buffer.push(c);
-                        }
                         _ => {}
-                    },
-                    InputMode::Normal => match key.code {
-                        KeyCode::Char('q') => {
-                            app.should_quit = true;
-                            return Ok(true);
-                        }
-                        KeyCode::Char(' ') => {
-                            app.frozen = !app.frozen;
-                        }
-                        KeyCode::Char('/') => {
-                            app.input_mode = InputMode::Filter;
-                            app.filter_text.clear();
-                        }
-                        KeyCode::Char('e') => {
-                            app.error_only = !app.error_only;
-                            app.clamp_selection();
-                        }
-                        KeyCode::Enter => {
-                            if app.visible_count() > 0 {
-                                app.view_mode = ViewMode::Detail;
-                            }
-                        }
-                        KeyCode::Char('c') => {
-                            app.clear_logs();
-                        }
+                    }
+                } else {
+                    let lines = selected_detail_lines(app);
+                    let last = lines.len().saturating_sub(1);
+                    match key.code {
                         KeyCode::Up | KeyCode::Char('k') => {
-                            app.scroll_up();
+                            app.detail_scroll = app.detail_scroll.min(last).saturating_sub(1);
                         }
                         KeyCode::Down | KeyCode::Char('j') => {
-                            app.scroll_down();
-                        }
-                        KeyCode::Right | KeyCode::Char('l') => {
-                            app.scroll_right();
-                        }
-                        KeyCode::Left | KeyCode::Char('h') => {
-                            app.scroll_left();
-                        }
-                        KeyCode::PageDown => {
-                            app.page_down(50);
-                        }
-                        KeyCode::PageUp => {
-                            app.page_up(50);
+                            app.detail_scroll = (app.detail_scroll + 1).min(last);
                         }
-                        KeyCode::Home => {
-                            app.jump_to_start();
-                        }
-                        KeyCode::End => {
-                            app.jump_to_end();
-                        }
-                        // Search
-                        KeyCode::Char('?') => {
-                            app.input_mode = InputMode::Search;
+                        KeyCode::Home => app.detail_scroll = 0,
+                        KeyCode::Char('/') => {
+                            app.input_mode = InputMode::DetailSearch;
                             app.input_buffer.clear();
                         }
                         KeyCode::Char('n') => {
-                            app.search_next();
+                            app.detail_search_jump(&lines, true, false);
                         }
                         KeyCode::Char('N') => {
-                            app.search_prev();
-                        }
-                        // Copy to clipboard
-                        KeyCode::Char('y') => {
-                            let visible = app.visible_logs();
-                            if let Some((_, entry)) = visible.get(app.selected_index) {
-                                let mut text = entry.raw.clone();
-                                for extra in &entry.extra_lines {
-                                    text.push('\n');
-                                    text.push_str(extra);
-                                }
-                                match copy_to_clipboard(&text) {
-                                    Ok(()) => app.set_status("Copied to clipboard".to_string()),
-                                    Err(e) => app.set_status(format!("Copy failed: {}", e)),
-                                }
-                            }
-                        }
-                        // Highlight
-                        KeyCode::Char('*') => {
-                            app.input_mode = InputMode::Highlight;
-                            app.input_buffer.clear();
-                        }
-                        // Export / Save
-                        KeyCode::Char('s') => {
-                            app.input_mode = InputMode::SavePrompt;
-                            app.input_buffer.clear();
-                        }
-                        // Time jump
-                        KeyCode::Char('g') => {
-                            app.input_mode = InputMode::TimeJump;
-                            app.input_buffer.clear();
+                            app.detail_search_jump(&lines, false, false);
                         }
                         _ => {}
-                    },
+                    }
+                }
+            }
+        },
+        ViewMode::Feed => match app.input_mode {
+            InputMode::Filter => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                    app.filter_text.clear();
+                    app.update_filter_regex();
+                    app.clamp_selection();
+                }
+                KeyCode::Enter => {
+                    app.input_mode = InputMode::Normal;
+                    app.prompt_history.filter.push(&app.filter_text);
+                    app.update_filter_regex();
+                    app.clamp_selection();
+                }
+                KeyCode::Up => app.prompt_history_prev(),
+                KeyCode::Down => app.prompt_history_next(),
+                _ => edit_input(app, key.code),
+            },
+            InputMode::Search => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                    app.input_buffer.clear();
+                    app.search_text.clear();
+                    app.search_regex = None;
+                }
+                KeyCode::Enter => {
+                    app.input_mode = InputMode::Normal;
+                    app.search_text = app.input_buffer.clone();
+                    app.input_buffer.clear();
+                    app.prompt_history.search.push(&app.search_text);
+                    app.update_search_regex();
+                    app.search_next();
+                }
+                KeyCode::Up => app.prompt_history_prev(),
+                KeyCode::Down => app.prompt_history_next(),
+                _ => edit_input(app, key.code),
+            },
+            InputMode::Highlight => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                    app.input_buffer.clear();
+                }
+                KeyCode::Enter => {
+                    app.input_mode = InputMode::Normal;
+                    let pattern = app.input_buffer.clone();
+                    app.input_buffer.clear();
+                    app.prompt_history.highlight.push(&pattern);
+                    if pattern.is_empty() {
+                        app.highlights.clear();
+                        app.set_status("Highlights cleared".to_string());
+                    } else {
+                        app.add_highlight(&pattern, None);
+                        app.set_status(format!(
+                            "Highlight added ({} active)",
+                            app.highlights.len()
+                        ));
+                    }
+                }
+                KeyCode::Up => app.prompt_history_prev(),
+                KeyCode::Down => app.prompt_history_next(),
+                _ => edit_input(app, key.code),
+            },
+            InputMode::FuzzySearch => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                    app.clear_fuzzy_search();
+                }
+                KeyCode::Enter => {
+                    // Best match sorts first; stay on it as new lines arrive
+                    app.input_mode = InputMode::Normal;
+                    app.selected_index = 0;
+                    if !app.fuzzy_search_query.is_empty() {
+                        app.frozen = true;
+                        app.set_status(format!(
+                            "{} fuzzy matches (Esc to clear)",
+                            app.visible_count()
+                        ));
+                    }
+                }
+                _ => edit_input(app, key.code),
+            },
+            InputMode::SortPrompt => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                    app.input_buffer.clear();
+                }
+                KeyCode::Enter => {
+                    app.input_mode = InputMode::Normal;
+                    let pattern = std::mem::take(&mut app.input_buffer);
+                    match app.set_sort(&pattern) {
+                        Ok(()) if pattern.is_empty() => {}
+                        Ok(()) => app.set_status(format!(
+                            "Sorted by {} (largest first, o to undo)",
+                            pattern
+                        )),
+                        Err(e) => app.set_status(format!("Invalid sort regex: {}", e)),
+                    }
+                }
+                _ => edit_input(app, key.code),
+            },
+            InputMode::SavePrompt => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                    app.input_buffer.clear();
+                }
+                KeyCode::Enter => {
+                    if app.input_buffer.is_empty() {
+                        app.input_mode = InputMode::Normal;
+                    } else if app.bookmarks.is_empty() {
+                        app.input_mode = InputMode::Normal;
+                        save_visible_logs(app, false);
+                    } else {
+                        // Keep the filename in the buffer and ask what to save
+                        app.input_mode = InputMode::SaveScope;
+                    }
+                }
+                _ => edit_input(app, key.code),
+            },
+            InputMode::SaveScope => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                    app.input_buffer.clear();
+                }
+                KeyCode::Enter | KeyCode::Char('a') => {
+                    app.input_mode = InputMode::Normal;
+                    save_visible_logs(app, false);
+                }
+                KeyCode::Char('b') => {
+                    app.input_mode = InputMode::Normal;
+                    save_visible_logs(app, true);
+                }
+                _ => {}
+            },
+            InputMode::ConfirmCopy(_) => {
+                app.input_mode = InputMode::Normal;
+                if key.code == KeyCode::Char('y') {
+                    copy_visible(app);
+                }
+            }
+            InputMode::ExportPrompt(format) => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                    app.input_buffer.clear();
+                }
+                KeyCode::Enter => {
+                    app.input_mode = InputMode::Normal;
+                    let filename = std::mem::take(&mut app.input_buffer);
+                    if !filename.is_empty() {
+                        export_structured(app, format, &filename);
+                    }
+                }
+                _ => edit_input(app, key.code),
+            },
+            InputMode::TimeJump => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                    app.input_buffer.clear();
+                }
+                KeyCode::Enter => {
+                    app.input_mode = InputMode::Normal;
+                    let time_str = app.input_buffer.clone();
+                    app.input_buffer.clear();
+                    if !time_str.is_empty() {
+                        app.jump_to_time(&time_str);
+                        app.set_status(format!("Jumped to {}", time_str));
+                    }
+                }
+                _ => edit_input(app, key.code),
+            },
+            InputMode::CorrelationSearch => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                    app.input_buffer.clear();
+                }
+                KeyCode::Enter => {
+                    app.input_mode = InputMode::Normal;
+                    let id = std::mem::take(&mut app.input_buffer);
+                    app.set_correlation_filter(&id);
+                    app.scroll_offset = 0;
+                    app.selected_index = 0;
+                }
+                _ => edit_input(app, key.code),
+            },
+            // Only typed over the detail view; nothing to do once it's gone
+            InputMode::DetailSearch => app.input_mode = InputMode::Normal,
+            InputMode::Normal => {
+                if let Some(action) = app.keymap.action(&key) {
+                    return run_action(app, action);
+                }
+            }
+        },
+    }
+    false
+}
+
+/// The plain detail view's lines for the selected entry.
+fn selected_detail_lines(app: &App) -> Vec<String> {
+    app.selected_entry()
+        .map(|(idx, entry)| detail_lines(app, idx, entry))
+        .unwrap_or_default()
+}
+
+/// Cursor movement and editing shared by every prompt.
+fn edit_input(app: &mut App, code: KeyCode) {
+    let changed = match code {
+        KeyCode::Left => {
+            app.input_left();
+            false
+        }
+        KeyCode::Right => {
+            app.input_right();
+            false
+        }
+        KeyCode::Home => {
+            app.input_cursor = 0;
+            false
+        }
+        KeyCode::End => {
+            app.input_cursor = usize::MAX;
+            false
+        }
+        KeyCode::Backspace => app.input_backspace(),
+        KeyCode::Delete => app.input_delete(),
+        KeyCode::Char(c) => {
+            app.input_insert(c.encode_utf8(&mut [0; 4]));
+            true
+        }
+        _ => false,
+    };
+    if changed {
+        input_changed(app);
+    }
+}
+
+/// Bracketed paste: the whole text lands at the cursor in one go, line
+/// breaks flattened to spaces. Ignored outside the text prompts.
+fn paste_input(app: &mut App, text: &str) {
+    let prompt = app.view_mode == ViewMode::Feed
+        && !matches!(
+            app.input_mode,
+            InputMode::Normal | InputMode::SaveScope | InputMode::ConfirmCopy(_)
+        );
+    if !prompt {
+        return;
+    }
+    let text = text
+        .trim_end_matches(['\r', '\n'])
+        .replace(['\r', '\n'], " ");
+    app.input_insert(&text);
+    input_changed(app);
+}
+
+/// Prompts that act as you type: the filter and fuzzy search.
+fn input_changed(app: &mut App) {
+    app.reset_prompt_history();
+    match app.input_mode {
+        InputMode::Filter => app.update_filter_regex(),
+        InputMode::FuzzySearch => app.selected_index = 0,
+        _ => {}
+    }
+}
+
+/// Runs a feed action; true when the app should quit.
+fn run_action(app: &mut App, action: Action) -> bool {
+    match action {
+        Action::Quit => {
+            app.should_quit = true;
+            return true;
+        }
+        Action::Help => {
+            app.view_mode = ViewMode::Help;
+            app.help_scroll = 0;
+        }
+        Action::Stats => {
+            app.view_mode = ViewMode::Stats;
+            app.stats_selected = 0;
+        }
+        Action::Pause => {
+            app.toggle_pause();
+        }
+        Action::Divider => {
+            app.insert_divider("");
+        }
+        Action::TableView => {
+            app.table_view = !app.table_view;
+        }
+        Action::WrapLines => {
+            app.wrap_lines = !app.wrap_lines;
+        }
+        Action::RawLines => {
+            app.show_raw = !app.show_raw;
+        }
+        Action::SparkDetail => {
+            app.spark_detail = !app.spark_detail;
+        }
+        Action::EpsWindowWider | Action::EpsWindowNarrower => {
+            app.zoom_eps_window(action == Action::EpsWindowWider);
+            app.set_status(format!("Activity window: {}s", app.eps_window_secs));
+        }
+        Action::ContextMore | Action::ContextLess => {
+            app.step_context_lines(action == Action::ContextMore);
+            app.set_status(format!("Context: {} lines", app.context_lines));
+        }
+        Action::ReplayFaster | Action::ReplaySlower => match app.replay {
+            Some(ref replay) => {
+                replay.step_speed(action == Action::ReplayFaster);
+                app.set_status(format!("Replay speed: {:.1}x", replay.speed()));
+            }
+            None => app.set_status("Not a replay (logpulse replay <file>)".to_string()),
+        },
+        Action::Dedup => {
+            app.deduplicate = !app.deduplicate;
+            app.invalidate_visible();
+            app.update_dedup_counts();
+            app.clamp_selection();
+        }
+        Action::GroupSimilar => {
+            app.group_similar = !app.group_similar;
+            app.update_groups();
+            app.clamp_selection();
+        }
+        Action::ErrorPeek => app.error_peek = true,
+        Action::Filter => {
+            app.input_mode = InputMode::Filter;
+            app.filter_text.clear();
+        }
+        Action::ErrorOnly => {
+            app.error_only = !app.error_only;
+            app.clamp_selection();
+        }
+        Action::MinLevel(level) => {
+            let floor = (app.min_level != Some(level)).then_some(level);
+            app.set_min_level(floor);
+            report_min_level(app);
+        }
+        Action::CycleMinLevel => {
+            app.cycle_min_level();
+            report_min_level(app);
+        }
+        Action::StatusFacet => {
+            app.cycle_status_facet();
+            app.set_status(match app.status_facet {
+                Some(class) => format!("Showing {}xx responses (other lines too)", class),
+                None => "Showing all responses".to_string(),
+            });
+        }
+        Action::MatchScope => {
+            app.match_scope = match app.match_scope {
+                MatchScope::Raw => MatchScope::Message,
+                MatchScope::Message => MatchScope::Raw,
+            };
+            app.clamp_selection();
+            app.set_status(match app.match_scope {
+                MatchScope::Raw => "Filter / search match the raw line".to_string(),
+                MatchScope::Message => "Filter / search match the parsed message only".to_string(),
+            });
+        }
+        Action::Detail if app.visible_count() > 0 => {
+            app.open_detail();
+        }
+        Action::Clear => {
+            app.clear_logs();
+            app.insert_divider("cleared");
+        }
+        Action::ScrollUp => {
+            app.scroll_up();
+        }
+        Action::ScrollDown => {
+            app.scroll_down();
+        }
+        Action::ScrollRight => app.scroll_right(HSCROLL_STEP),
+        Action::ScrollLeft => app.scroll_left(HSCROLL_STEP),
+        Action::ScrollRightFine => app.scroll_right(1),
+        Action::ScrollLeftFine => app.scroll_left(1),
+        Action::ScrollHome => app.horizontal_scroll = 0,
+        Action::FitWidth => {
+            app.fit_horizontal_scroll();
+        }
+        Action::AutoFit => {
+            app.auto_fit = !app.auto_fit;
+            app.set_status(format!(
+                "Auto-fit while following: {}",
+                if app.auto_fit { "on" } else { "off" }
+            ));
+        }
+        Action::PageDown => {
+            app.page_down(app.viewport_height.get().max(1));
+        }
+        Action::PageUp => {
+            app.page_up(app.viewport_height.get().max(1));
+        }
+        Action::JumpStart => {
+            app.jump_to_start();
+        }
+        Action::JumpEnd => {
+            app.jump_to_end();
+        }
+        // Search
+        Action::Search => {
+            app.input_mode = InputMode::Search;
+            app.input_buffer.clear();
+        }
+        Action::SearchNext => {
+            app.search_next();
+        }
+        Action::SearchPrev => {
+            app.search_prev();
+        }
+        // Copy to clipboard
+        Action::Copy => {
+            if let Some((_, entry)) = app.selected_entry() {
+                let mut text = entry.raw.clone();
+                for extra in &entry.extra_lines {
+                    text.push('\n');
+                    text.push_str(extra);
+                }
+                match copy_to_clipboard(&text) {
+                    Ok(()) => app.set_status("Copied to clipboard".to_string()),
+                    Err(e) => app.set_status(format!("Copy failed: {}", e)),
+                }
+            }
+        }
+        Action::CopyJson => {
+            let text = app
+                .selected_entry()
+                .map(|(_, entry)| entry.to_merged_json())
+                .and_then(|value| serde_json::to_string_pretty(&value).ok());
+            if let Some(text) = text {
+                match copy_to_clipboard(&text) {
+                    Ok(()) => app.set_status("Copied as JSON".to_string()),
+                    Err(e) => app.set_status(format!("Copy failed: {}", e)),
+                }
+            }
+        }
+        Action::CopyAll => {
+            let (_, lines) = visible_text(app);
+            if lines > COPY_CONFIRM_LINES {
+                app.input_mode = InputMode::ConfirmCopy(lines);
+            } else {
+                copy_visible(app);
+            }
+        }
+        Action::TimeDisplay => {
+            app.time_display = app.time_display.next();
+        }
+        Action::AgeDisplay => {
+            app.time_display = if app.time_display == TimeDisplay::Relative {
+                TimeDisplay::None
+            } else {
+                TimeDisplay::Relative
+            };
+        }
+        Action::Expand => match app.toggle_expanded() {
+            Some(_) => {}
+            None => app.set_status("No continuation lines to expand".to_string()),
+        },
+        // Bookmarks
+        Action::Bookmark => match app.toggle_bookmark() {
+            Some(true) => app.set_status(format!("Bookmarked ({} total)", app.bookmarks.len())),
+            Some(false) => app.set_status("Bookmark removed".to_string()),
+            None => {}
+        },
+        Action::NextBookmark => {
+            app.next_bookmark();
+        }
+        // Flags
+        Action::FuzzySearch => {
+            app.input_mode = InputMode::FuzzySearch;
+            app.fuzzy_search_query.clear();
+            app.selected_index = 0;
+        }
+        Action::ClearFuzzy if !app.fuzzy_search_query.is_empty() => {
+            app.clear_fuzzy_search();
+            app.set_status("Fuzzy search cleared".to_string());
+        }
+        Action::Flag => match app.toggle_flag() {
+            Some(true) => app.set_status(format!("Flagged ({} total)", app.flagged.len())),
+            Some(false) => app.set_status("Flag removed".to_string()),
+            None => {}
+        },
+        Action::FlaggedOnly => {
+            app.flagged_only = !app.flagged_only;
+            app.clamp_selection();
+        }
+        Action::SnapshotErrors => {
+            let count = app.snapshot_error_signatures();
+            app.set_status(format!(
+                "Snapshot of {} distinct errors; Z shows only new ones",
+                count
+            ));
+        }
+        Action::DiffMode if app.diff_mode => {
+            app.diff_mode = false;
+            app.clamp_selection();
+        }
+        Action::DiffMode => {
+            app.diff_mode = true;
+            app.clamp_selection();
+            if app.error_signature_snapshot.is_empty() {
+                app.set_status("No error snapshot yet (z): every error counts as new".to_string());
+            }
+        }
+        Action::ExportFlagged => {
+            let filename = match app.flag_store {
+                Some(ref store) => {
+                    format!("{}.flagged.log", store.log_path().display())
+                }
+                None => "logpulse.flagged.log".to_string(),
+            };
+            match export_flagged_logs(app, &filename) {
+                Ok(count) => app.set_status(format!(
+                    "Exported {} flagged entries to {}",
+                    count, filename
+                )),
+                Err(e) => app.set_status(format!("Export failed: {}", e)),
+            }
+        }
+        // Highlight
+        Action::Highlight => {
+            app.input_mode = InputMode::Highlight;
+            app.input_buffer.clear();
+        }
+        // Session snapshot
+        Action::SaveSession => {
+            let path = default_session_path();
+            match Session::from_app(app).save(std::path::Path::new(&path)) {
+                Ok(()) => app.set_status(format!(
+                    "Saved session ({} entries) to {}",
+                    app.logs.len(),
+                    path
+                )),
+                Err(e) => app.set_status(format!("Session save failed: {}", e)),
+            }
+        }
+        // Export / Save
+        Action::Save => {
+            app.input_mode = InputMode::SavePrompt;
+            app.input_buffer.clear();
+        }
+        Action::SaveJson => {
+            app.input_mode = InputMode::ExportPrompt(ExportFormat::Json);
+            app.input_buffer.clear();
+        }
+        Action::SaveHtml => {
+            app.input_mode = InputMode::ExportPrompt(ExportFormat::Html);
+            app.input_buffer.clear();
+        }
+        Action::SaveCsv => {
+            app.input_mode = InputMode::ExportPrompt(ExportFormat::Csv);
+            app.input_buffer.clear();
+        }
+        // Numeric sort
+        Action::Sort if app.sort_regex.is_some() => {
+            let _ = app.set_sort("");
+            app.set_status("Chronological order".to_string());
+        }
+        Action::Sort => {
+            app.input_mode = InputMode::SortPrompt;
+            app.input_buffer.clear();
+        }
+        // Time jump
+        Action::TimeJump => {
+            app.input_mode = InputMode::TimeJump;
+            app.input_buffer.clear();
+        }
+        // Correlation tracking, prefilled from the selected entry
+        Action::Correlation => {
+            app.input_mode = InputMode::CorrelationSearch;
+            app.input_buffer = app.selected_correlation_id().unwrap_or_default();
+        }
+        _ => {}
+    }
+    false
+}
+
+fn report_min_level(app: &mut App) {
+    app.set_status(match app.min_level {
+        Some(level) => format!("Showing {} and above", level.name()),
+        None => "Showing all levels".to_string(),
+    });
+}
+
+fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    let screen = match crossterm::terminal::size() {
+        Ok((width, height)) => Rect::new(0, 0, width, height),
+        Err(_) => return,
+    };
+    if app.error_peek {
+        if let MouseEventKind::Down(_) = mouse.kind {
+            app.error_peek = false;
+        }
+        return;
+    }
+    match app.view_mode {
+        ViewMode::Help => match mouse.kind {
+            MouseEventKind::ScrollUp => app.scroll_help(-3),
+            MouseEventKind::ScrollDown => app.scroll_help(3),
+            MouseEventKind::Down(_) => app.view_mode = ViewMode::Feed,
+            _ => {}
+        },
+        ViewMode::Stats => {
+            if let MouseEventKind::Down(_) = mouse.kind {
+                app.view_mode = ViewMode::Feed;
+            }
+        }
+        ViewMode::FormatPicker => {}
+        ViewMode::Detail => {
+            if let Some(ref mut tree) = app.detail_tree {
+                match mouse.kind {
+                    MouseEventKind::ScrollUp => tree.move_up(),
+                    MouseEventKind::ScrollDown => tree.move_down(),
+                    _ => {}
+                }
+            } else {
+                let last = selected_detail_lines(app).len().saturating_sub(1);
+                match mouse.kind {
+                    MouseEventKind::ScrollUp => {
+                        app.detail_scroll = app.detail_scroll.min(last).saturating_sub(WHEEL_LINES);
+                    }
+                    MouseEventKind::ScrollDown => {
+                        app.detail_scroll = (app.detail_scroll + WHEEL_LINES).min(last);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        ViewMode::Feed if app.input_mode == InputMode::Normal => match mouse.kind {
+            MouseEventKind::ScrollUp => {
+                for _ in 0..WHEEL_LINES {
+                    app.scroll_up();
+                }
+            }
+            MouseEventKind::ScrollDown => {
+                for _ in 0..WHEEL_LINES {
+                    app.scroll_down();
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) if in_footer(app, screen, mouse.row) => {
+                // A hint that opens an input mode acts like its key; elsewhere, filter
+                let action = match footer_hint_at(app, screen, mouse.column) {
+                    Some("?") => Action::Search,
+                    Some("*") => Action::Highlight,
+                    Some("s") => Action::Save,
+                    Some("J") => Action::SaveJson,
+                    Some("C") => Action::SaveCsv,
+                    Some("g") => Action::TimeJump,
+                    _ => Action::Filter,
+                };
+                run_action(app, action);
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let Some(idx) = feed_entry_at(app, screen, mouse.row) else {
+                    return;
+                };
+                let now = Instant::now();
+                let double = app
+                    .last_click
+                    .is_some_and(|(at, last)| last == idx && now - at < DOUBLE_CLICK);
+                app.selected_index = idx;
+                if double {
+                    app.last_click = None;
+                    app.open_detail();
+                } else {
+                    app.last_click = Some((now, idx));
+                }
+            }
+            _ => {}
+        },
+        ViewMode::Feed => {}
+    }
+}
+
+/// Y asks first above this many lines.
+const COPY_CONFIRM_LINES: usize = 10_000;
+
+/// The filtered entries as raw lines (continuations included) and their count.
+fn visible_text(app: &App) -> (String, usize) {
+    let mut text = String::new();
+    let mut lines = 0;
+    for (_, entry) in app.filtered_logs() {
+        if entry.divider {
+            continue;
+        }
+        for line in std::iter::once(&entry.raw).chain(&entry.extra_lines) {
+            text.push_str(line);
+            text.push('\n');
+            lines += 1;
+        }
+    }
+    (text, lines)
+}
+
+/// Y: copy every filtered entry. Without a clipboard tool the text goes to
+/// a temp file instead, so it isn't lost.
+fn copy_visible(app: &mut App) {
+    let (text, lines) = visible_text(app);
+    match copy_to_clipboard(&text) {
+        Ok(()) => app.set_status(format!("Copied {} lines to clipboard", lines)),
+        Err(e) => {
+            let written = crate::source::create_private_temp("logpulse-copy", "txt").and_then(
+                |(path, mut file)| {
+                    std::io::Write::write_all(&mut file, text.as_bytes()).map(|()| path)
                 },
+            );
+            match written {
+                Ok(path) => app.set_status(format!(
+                    "Clipboard unavailable ({}); wrote {} lines to {}",
+                    e,
+                    lines,
+                    path.display()
+                )),
+                Err(write_err) => {
+                    app.set_status(format!("Copy failed: {}; temp file: {}", e, write_err))
+                }
             }
         }
     }
-    Ok(false)
 }
 
 fn copy_to_clipboard(text: &str) -> Result<(), String> {
@@ -284,19 +1015,39 @@ fn copy_to_clipboard(text: &str) -> Result<(), String> {
             .unwrap()
             .write_all(text.as_bytes())
             .map_err(|e| e.to_string())?;
-        child.wait().map_err(|e| e.to_string())?;
+        // xclip without a display starts fine and then fails
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err(format!("clipboard command failed ({})", status));
+        }
         Ok(())
     }
 }
 
-fn export_visible_logs(app: &App, filename: &str) -> Result<usize, String> {
+/// Save visible entries to the filename held in `input_buffer`, reporting via status.
+fn save_visible_logs(app: &mut App, only_bookmarked: bool) {
+    let filename = std::mem::take(&mut app.input_buffer);
+    match export_visible_logs(app, &filename, only_bookmarked) {
+        Ok(count) => app.set_status(format!("Saved {} entries to {}", count, filename)),
+        Err(e) => app.set_status(format!("Save failed: {}", e)),
+    }
+}
+
+fn export_visible_logs(app: &App, filename: &str, only_bookmarked: bool) -> Result<usize, String> {
     use std::io::Write;
 
-    let visible = app.visible_logs();
+    // Un-collapsed: dedup mode only changes the view
+    let visible = app.filtered_logs();
     let mut file = std::fs::File::create(filename).map_err(|e| e.to_string())?;
 
     let mut count = 0;
-    for (_, entry) in &visible {
+    for (idx, entry) in &visible {
+        if entry.divider {
+            continue;
+        }
+        if only_bookmarked && !app.bookmarks.contains(idx) {
+            continue;
+        }
         writeln!(file, "{}", entry.raw).map_err(|e| e.to_string())?;
         for extra in &entry.extra_lines {
             writeln!(file, "{}", extra).map_err(|e| e.to_string())?;
@@ -306,3 +1057,574 @@ fn export_visible_logs(app: &App, filename: &str) -> Result<usize, String> {
 
     Ok(count)
 }
+
+/// Export the visible entries for the J / C keys, reporting via status.
+fn export_structured(app: &mut App, format: ExportFormat, filename: &str) {
+    let result = match format {
+        ExportFormat::Json => export_visible_logs_json(app, filename).map(|count| (count, None)),
+        ExportFormat::Html => export_visible_logs_html(app, filename).map(|count| (count, None)),
+        ExportFormat::Csv => {
+            export_visible_logs_csv(app, filename).map(|(count, columns)| (count, Some(columns)))
+        }
+    };
+    match result {
+        Ok((count, columns)) => {
+            let columns = columns
+                .map(|n| format!(", {} columns", n))
+                .unwrap_or_default();
+            app.set_status(format!(
+                "Exported {} entries as {} to {}{}",
+                count,
+                format.name(),
+                filename,
+                columns
+            ))
+        }
+        Err(e) => app.set_status(format!("{} export failed: {}", format.name(), e)),
+    }
+}
+
+/// Write the visible entries to `filename` as a JSON array of objects, for `jq`.
+fn export_visible_logs_json(app: &App, filename: &str) -> Result<usize, String> {
+    let entries: Vec<serde_json::Value> = app
+        .filtered_logs()
+        .into_iter()
+        .filter(|(_, entry)| !entry.divider)
+        .map(|(_, entry)| entry.to_json())
+        .collect();
+
+    let file = std::fs::File::create(filename).map_err(|e| e.to_string())?;
+    let mut writer = std::io::BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, &entries).map_err(|e| e.to_string())?;
+    std::io::Write::write_all(&mut writer, b"\n").map_err(|e| e.to_string())?;
+    Ok(entries.len())
+}
+
+/// Write the visible entries to `filename` as one self-contained HTML page:
+/// theme colours as inline CSS, the active filter and search in a header,
+/// and the highlight patterns applied as `<mark>`s by an embedded script.
+fn export_visible_logs_html(app: &App, filename: &str) -> Result<usize, String> {
+    use std::fmt::Write as _;
+    use std::io::Write;
+
+    let theme = &app.theme;
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>logpulse: {}</title>\n<style>\n\
+         body {{ margin: 0; background: #111; color: #ddd; font: 13px/1.4 ui-monospace, Menlo, Consolas, monospace; }}\n\
+         header {{ padding: 8px 12px; border-bottom: 1px solid #444; color: {}; }}\n\
+         header .label {{ color: {}; }}\n\
+         .entry {{ padding: 0 12px; white-space: pre-wrap; word-break: break-all; }}\n\
+         .ts {{ color: {}; display: inline-block; min-width: 20ch; }}\n\
+         .lvl {{ display: inline-block; min-width: 8ch; font-weight: bold; }}\n\
+         .extra {{ color: {}; margin: 0 0 0 2ch; font: inherit; }}\n",
+        html_escape(&app.filename),
+        css_color(theme.header_fg),
+        css_color(theme.filter_fg),
+        css_color(theme.dim_fg),
+        css_color(theme.dim_fg),
+    );
+    for level in [
+        LogLevel::Fatal,
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+        LogLevel::Unknown,
+    ] {
+        let _ = writeln!(
+            html,
+            ".level-{} .lvl {{ color: {}; }}",
+            level_class(level),
+            css_color(theme.level_color(level))
+        );
+    }
+    let _ = write!(
+        html,
+        "</style>\n</head>\n<body>\n<header><span class=\"label\">Source:</span> {}",
+        html_escape(&app.filename)
+    );
+    if !app.filter_text.is_empty() {
+        let _ = write!(
+            html,
+            " &nbsp; <span class=\"label\">Filter:</span> {}",
+            html_escape(&app.filter_text)
+        );
+    }
+    if !app.search_text.is_empty() {
+        let _ = write!(
+            html,
+            " &nbsp; <span class=\"label\">Search:</span> {}",
+            html_escape(&app.search_text)
+        );
+    }
+    html.push_str("</header>\n<main>\n");
+
+    let mut count = 0;
+    for (_, entry) in app.filtered_logs() {
+        if entry.divider {
+            continue;
+        }
+        let _ = write!(
+            html,
+            "<div class=\"entry level-{}\"><span class=\"ts\">{}</span><span class=\"lvl\">{}</span><span class=\"msg\">{}</span>",
+            level_class(entry.level),
+            html_escape(entry.timestamp.as_deref().unwrap_or("")),
+            entry.level.name(),
+            html_escape(entry.message.as_deref().unwrap_or(&entry.raw)),
+        );
+        if !entry.extra_lines.is_empty() {
+            let _ = write!(
+                html,
+                "<pre class=\"extra\">{}</pre>",
+                html_escape(&entry.extra_lines.join("\n"))
+            );
+        }
+        html.push_str("</div>\n");
+        count += 1;
+    }
+    html.push_str("</main>\n");
+
+    // Highlights: Rust regex syntax is close enough to JS for the usual patterns
+    let highlights: Vec<serde_json::Value> = app
+        .highlights
+        .iter()
+        .map(|(re, color)| {
+            let pattern = re.as_str().strip_prefix("(?i)").unwrap_or(re.as_str());
+            serde_json::json!({ "pattern": pattern, "color": css_color(*color) })
+        })
+        .collect();
+    if !highlights.is_empty() {
+        // `</` can't appear inside a script element
+        let patterns = serde_json::to_string(&highlights)
+            .map_err(|e| e.to_string())?
+            .replace("</", "<\\/");
+        let _ = write!(
+            html,
+            "<script>\nconst highlights = {};\n{}</script>\n",
+            patterns, HTML_HIGHLIGHT_JS
+        );
+    }
+    html.push_str("</body>\n</html>\n");
+
+    let mut file = std::fs::File::create(filename).map_err(|e| e.to_string())?;
+    file.write_all(html.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+/// Wraps matches of each highlight in the messages and stack traces in `<mark>`.
+const HTML_HIGHLIGHT_JS: &str = r#"for (const { pattern, color } of highlights) {
+  let re;
+  try { re = new RegExp(pattern, "gi"); } catch (e) { continue; }
+  for (const el of document.querySelectorAll(".msg, .extra")) {
+    const walker = document.createTreeWalker(el, NodeFilter.SHOW_TEXT);
+    const nodes = [];
+    while (walker.nextNode()) nodes.push(walker.currentNode);
+    for (const node of nodes) {
+      const text = node.nodeValue;
+      const frag = document.createDocumentFragment();
+      let last = 0;
+      for (const m of text.matchAll(re)) {
+        if (m[0] === "") continue;
+        frag.append(text.slice(last, m.index));
+        const mark = document.createElement("mark");
+        mark.style.background = color;
+        mark.textContent = m[0];
+        frag.append(mark);
+        last = m.index + m[0].length;
+      }
+      if (last === 0) continue;
+      frag.append(text.slice(last));
+      node.replaceWith(frag);
+    }
+  }
+}
+"#;
+
+/// CSS class suffix for an entry's level (`level-error`, `level-unknown`).
+fn level_class(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Fatal => "fatal",
+        LogLevel::Error => "error",
+        LogLevel::Warn => "warn",
+        LogLevel::Info => "info",
+        LogLevel::Debug => "debug",
+        LogLevel::Trace => "trace",
+        LogLevel::Unknown => "unknown",
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Write the visible entries to `filename` as CSV with a header row; returns
+/// the entry and column counts. When every entry is a JSON object, its
+/// top-level keys follow the fixed columns.
+fn export_visible_logs_csv(app: &App, filename: &str) -> Result<(usize, usize), String> {
+    use std::io::Write;
+
+    let visible: Vec<_> = app
+        .filtered_logs()
+        .into_iter()
+        .filter(|(_, entry)| !entry.divider)
+        .map(|(_, entry)| entry)
+        .collect();
+
+    // First pass: JSON columns, in the order they're first seen
+    let objects: Option<Vec<serde_json::Map<String, serde_json::Value>>> = visible
+        .iter()
+        .map(|entry| match serde_json::from_str(entry.raw.trim()) {
+            Ok(serde_json::Value::Object(map)) => Some(map),
+            _ => None,
+        })
+        .collect();
+    let mut json_keys: Vec<&str> = Vec::new();
+    for map in objects.iter().flatten() {
+        for key in map.keys() {
+            if !json_keys.contains(&key.as_str()) {
+                json_keys.push(key);
+            }
+        }
+    }
+
+    let file = std::fs::File::create(filename).map_err(|e| e.to_string())?;
+    let mut writer = std::io::BufWriter::new(file);
+    let mut header = vec!["timestamp", "level", "message", "metadata", "raw"];
+    header.extend(&json_keys);
+    write_csv_row(&mut writer, header.iter().copied()).map_err(|e| e.to_string())?;
+
+    for (i, entry) in visible.iter().enumerate() {
+        let mut raw = entry.raw.clone();
+        for extra in &entry.extra_lines {
+            raw.push('\n');
+            raw.push_str(extra);
+        }
+        let level = if entry.level == LogLevel::Unknown {
+            String::new()
+        } else {
+            entry.level.name().to_ascii_lowercase()
+        };
+        let mut row = vec![
+            entry.timestamp.clone().unwrap_or_default(),
+            level,
+            entry.message.clone().unwrap_or_default(),
+            entry.metadata.clone().unwrap_or_default(),
+            raw,
+        ];
+        if let Some(objects) = &objects {
+            row.extend(json_keys.iter().map(|key| match objects[i].get(*key) {
+                Some(serde_json::Value::String(s)) => s.clone(),
+                Some(serde_json::Value::Null) | None => String::new(),
+                Some(value) => value.to_string(),
+            }));
+        }
+        write_csv_row(&mut writer, row.iter().map(String::as_str)).map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok((visible.len(), header.len()))
+}
+
+/// One CSV record; fields with commas, quotes or line breaks are quoted (RFC 4180).
+fn write_csv_row<'a>(
+    out: &mut impl std::io::Write,
+    fields: impl Iterator<Item = &'a str>,
+) -> std::io::Result<()> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(out, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            out.write_all(field.as_bytes())?;
+        }
+    }
+    out.write_all(b"\r\n")
+}
+
+/// Write all flagged entries (regardless of the current filter) to `filename`.
+fn export_flagged_logs(app: &App, filename: &str) -> Result<usize, String> {
+    use std::io::Write;
+
+    let mut file = std::fs::File::create(filename).map_err(|e| e.to_string())?;
+
+    let mut count = 0;
+    for &idx in &app.flagged {
+        let Some(entry) = app.logs.get(idx) else {
+            continue;
+        };
+        writeln!(file, "{}", entry.raw).map_err(|e| e.to_string())?;
+        for extra in &entry.extra_lines {
+            writeln!(file, "{}", extra).map_err(|e| e.to_string())?;
+        }
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::LogEntry;
+
+    #[test]
+    fn json_export_writes_visible_entries_with_nulls() {
+        let mut app = App::new("test".to_string());
+        // Unparsed first, so it isn't taken as a continuation of the error
+        app.add_log(LogEntry {
+            raw: "plain line".to_string(),
+            level: LogLevel::Unknown,
+            timestamp: None,
+            ts_parsed: None,
+            message: None,
+            metadata: None,
+            extra_lines: Vec::new(),
+            source: None,
+            status: None,
+            divider: false,
+        });
+        app.add_log(LogEntry {
+            raw: "2024-01-15 10:30:00 ERROR boom".to_string(),
+            level: LogLevel::Error,
+            timestamp: Some("2024-01-15 10:30:00".to_string()),
+            ts_parsed: None,
+            message: Some("boom".to_string()),
+            metadata: None,
+            extra_lines: vec!["  at main.rs:1".to_string()],
+            source: None,
+            status: None,
+            divider: false,
+        });
+        app.insert_divider("marker");
+
+        let path = std::env::temp_dir().join(format!("logpulse-{}.json", std::process::id()));
+        let filename = path.to_string_lossy().into_owned();
+        assert_eq!(export_visible_logs_json(&app, &filename), Ok(2));
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([
+                {
+                    "timestamp": null,
+                    "level": null,
+                    "message": null,
+                    "raw": "plain line",
+                    "extra_lines": [],
+                },
+                {
+                    "timestamp": "2024-01-15 10:30:00",
+                    "level": "error",
+                    "message": "boom",
+                    "raw": "2024-01-15 10:30:00 ERROR boom",
+                    "extra_lines": ["  at main.rs:1"],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn csv_export_quotes_fields_and_adds_json_columns() {
+        let mut app = App::new("test".to_string());
+        app.add_log(LogEntry {
+            raw: r#"{"msg":"a, \"b\"","code":7}"#.to_string(),
+            level: LogLevel::Error,
+            timestamp: None,
+            ts_parsed: None,
+            message: Some(r#"a, "b""#.to_string()),
+            metadata: None,
+            extra_lines: vec!["  at x".to_string()],
+            source: None,
+            status: None,
+            divider: false,
+        });
+        app.add_log(LogEntry {
+            raw: r#"{"user":"bob"}"#.to_string(),
+            level: LogLevel::Info,
+            message: None,
+            extra_lines: Vec::new(),
+            ..app.logs[0].clone()
+        });
+
+        let path = std::env::temp_dir().join(format!("logpulse-{}.csv", std::process::id()));
+        let filename = path.to_string_lossy().into_owned();
+        assert_eq!(export_visible_logs_csv(&app, &filename), Ok((2, 8)));
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.split("\r\n").collect();
+        assert_eq!(
+            lines[0],
+            "timestamp,level,message,metadata,raw,code,msg,user"
+        );
+        assert_eq!(
+            lines[1],
+            r#",error,"a, ""b""",,"{""msg"":""a, \""b\"""",""code"":7}
+  at x",7,"a, ""b""","#
+        );
+        assert_eq!(lines[2], r#",info,,,"{""user"":""bob""}",,,bob"#);
+
+        // Not every entry is JSON: only the fixed columns
+        app.add_log(LogEntry {
+            raw: "plain".to_string(),
+            ..app.logs[1].clone()
+        });
+        assert_eq!(export_visible_logs_csv(&app, &filename), Ok((3, 5)));
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(text.ends_with(",info,,,plain\r\n"));
+    }
+
+    #[test]
+    fn html_export_escapes_entries_and_embeds_highlights() {
+        let mut app = App::new("app.log".to_string());
+        app.add_log(LogEntry {
+            raw: "10:30:00 ERROR <script>alert(1)</script>".to_string(),
+            level: LogLevel::Error,
+            timestamp: Some("10:30:00".to_string()),
+            ts_parsed: None,
+            message: Some("<script>alert(1)</script>".to_string()),
+            metadata: None,
+            extra_lines: vec!["  at a & b".to_string()],
+            source: None,
+            status: None,
+            divider: false,
+        });
+        app.filter_text = "alert".to_string();
+        app.update_filter_regex();
+        app.add_highlight("</script>", None);
+
+        let path = std::env::temp_dir().join(format!("logpulse-{}.html", std::process::id()));
+        let filename = path.to_string_lossy().into_owned();
+        assert_eq!(export_visible_logs_html(&app, &filename), Ok(1));
+        let html = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(html.contains(concat!(
+            r#"<div class="entry level-error"><span class="ts">10:30:00</span>"#,
+            r#"<span class="lvl">ERROR</span><span class="msg">&lt;script&gt;alert(1)&lt;/script&gt;</span>"#,
+            r#"<pre class="extra">  at a &amp; b</pre></div>"#
+        )));
+        assert!(html.contains(".level-error .lvl { color: #cd0000; }"));
+        assert!(html.contains(r#"<span class="label">Filter:</span> alert"#));
+        // The pattern can't close the script element early
+        assert_eq!(html.matches("</script>").count(), 1);
+        assert!(
+            html.contains(r##"const highlights = [{"color":"#cd00cd","pattern":"<\/script>"}];"##)
+        );
+    }
+
+    #[test]
+    fn format_picker_keys_choose_a_candidate() {
+        let press = |app: &mut App, code| handle_key(app, KeyEvent::from(code));
+        let mut app = App::new("test".to_string());
+        app.format_choices = vec!["Laravel".into(), "Go".into(), "Plain".into()];
+        app.view_mode = ViewMode::FormatPicker;
+        // Feed keys don't apply while it's open
+        press(&mut app, KeyCode::Char('q'));
+        assert!(!app.should_quit);
+        for code in [KeyCode::Down, KeyCode::Down, KeyCode::Down, KeyCode::Up] {
+            press(&mut app, code);
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.format_picked, Some(1));
+        assert_eq!(app.view_mode, ViewMode::Feed);
+
+        app.view_mode = ViewMode::FormatPicker;
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.format_picked, Some(0));
+    }
+
+    #[test]
+    fn detail_search_jumps_between_matches_in_continuation_lines() {
+        let press = |app: &mut App, code| handle_key(app, KeyEvent::from(code));
+        let mut app = App::new("test".to_string());
+        app.add_log(LogEntry {
+            raw: "ERROR request failed".to_string(),
+            level: LogLevel::Error,
+            timestamp: None,
+            ts_parsed: None,
+            message: None,
+            metadata: None,
+            extra_lines: vec![
+                "  at Handler.run".to_string(),
+                "Caused by: Timeout".to_string(),
+                "  at Pool.get".to_string(),
+                "Caused by: SocketError".to_string(),
+            ],
+            source: None,
+            status: None,
+            divider: false,
+        });
+        app.open_detail();
+        let lines = selected_detail_lines(&app);
+        let caused: Vec<usize> = (0..lines.len())
+            .filter(|&i| lines[i].starts_with("Caused by"))
+            .collect();
+        assert_eq!(caused.len(), 2);
+
+        press(&mut app, KeyCode::Char('/'));
+        for c in "caused".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.detail_scroll, caused[0]);
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.detail_scroll, caused[1]);
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.detail_scroll, caused[0]);
+        press(&mut app, KeyCode::Char('N'));
+        assert_eq!(app.detail_scroll, caused[1]);
+
+        // The first Esc clears the search, the second closes the view
+        press(&mut app, KeyCode::Esc);
+        assert!(app.detail_search.is_none());
+        assert_eq!(app.view_mode, ViewMode::Detail);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.view_mode, ViewMode::Feed);
+    }
+
+    #[test]
+    fn copy_all_counts_continuation_lines() {
+        let mut app = App::new("test".to_string());
+        let mut entry = LogEntry {
+            raw: "ERROR boom".to_string(),
+            level: LogLevel::Error,
+            timestamp: None,
+            ts_parsed: None,
+            message: None,
+            metadata: None,
+            extra_lines: vec!["  at a".to_string(), "  at b".to_string()],
+            source: None,
+            status: None,
+            divider: false,
+        };
+        app.add_log(entry.clone());
+        app.insert_divider("live");
+        entry.raw = "INFO ok".to_string();
+        entry.level = LogLevel::Info;
+        entry.extra_lines.clear();
+        app.add_log(entry);
+
+        assert_eq!(
+            visible_text(&app),
+            ("ERROR boom\n  at a\n  at b\nINFO ok\n".to_string(), 4)
+        );
+        app.error_only = true;
+        assert_eq!(visible_text(&app).1, 3);
+    }
+}
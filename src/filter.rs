@@ -0,0 +1,151 @@
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::app::LogLevel;
+
+/// The regex terms of the filter: an entry is shown when it matches every
+/// include and none of the excludes.
+#[derive(Debug, Default)]
+pub struct FilterMatcher {
+    pub include: Vec<Regex>,
+    pub exclude: Vec<Regex>,
+}
+
+/// One whitespace-separated term of the filter text (`/` prompt).
+#[derive(Debug)]
+pub enum FilterTerm {
+    /// A regex (or `"quoted phrase"`, matched literally) every entry must match
+    Include(Result<Regex, String>),
+    /// `!term` / `!"phrase"`: entries matching it are hidden
+    Exclude(Result<Regex, String>),
+    /// `svc:NAME`: entries whose source starts with NAME
+    Source(String),
+    /// `level:NAME+`: a minimum level
+    MinLevel(LogLevel),
+}
+
+/// A term and where it sits in the filter text, for coloring the prompt.
+#[derive(Debug)]
+pub struct FilterToken {
+    pub range: Range<usize>,
+    pub term: FilterTerm,
+}
+
+/// Split filter text into terms. Whitespace separates terms except inside
+/// double quotes; an unclosed quote runs to the end (while it's being typed).
+pub fn parse_filter(text: &str) -> Vec<FilterToken> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    loop {
+        let trimmed = rest.trim_start();
+        if trimmed.is_empty() {
+            break;
+        }
+        let start = text.len() - trimmed.len();
+        let (negated, body) = match trimmed.strip_prefix('!') {
+            Some(body) => (true, body),
+            None => (false, trimmed),
+        };
+        let (pattern, len) = match body.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                let close = usize::from(end < quoted.len());
+                (regex::escape(&quoted[..end]), 1 + end + close)
+            }
+            None => {
+                let end = body.find(char::is_whitespace).unwrap_or(body.len());
+                (body[..end].to_string(), end)
+            }
+        };
+        let len = len + usize::from(negated);
+        let token = &trimmed[..len];
+        rest = &trimmed[len..];
+
+        let term = if negated {
+            if pattern.is_empty() {
+                continue;
+            }
+            FilterTerm::Exclude(compile(&pattern))
+        } else if let Some(name) = token.strip_prefix("svc:").filter(|n| !n.is_empty()) {
+            FilterTerm::Source(name.to_string())
+        } else if let Some(level) = token
+            .strip_prefix("level:")
+            .and_then(|l| l.strip_suffix('+'))
+            .and_then(LogLevel::from_name)
+        {
+            FilterTerm::MinLevel(level)
+        } else if pattern.is_empty() {
+            continue;
+        } else {
+            FilterTerm::Include(compile(&pattern))
+        };
+        tokens.push(FilterToken {
+            range: start..start + len,
+            term,
+        });
+    }
+    tokens
+}
+
+/// Case-insensitive, with regex errors cut down to their one-line summary.
+fn compile(pattern: &str) -> Result<Regex, String> {
+    Regex::new(&format!("(?i){}", pattern)).map_err(|e| {
+        let message = e.to_string();
+        let summary = message
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or("")
+            .trim()
+            .trim_start_matches("error: ")
+            .to_string();
+        format!("{}: {}", pattern, summary)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(text: &str) -> Vec<(&str, String)> {
+        parse_filter(text)
+            .into_iter()
+            .map(|t| {
+                let kind = match t.term {
+                    FilterTerm::Include(Ok(re)) => format!("+{}", re.as_str()),
+                    FilterTerm::Exclude(Ok(re)) => format!("-{}", re.as_str()),
+                    FilterTerm::Include(Err(e)) | FilterTerm::Exclude(Err(e)) => e,
+                    FilterTerm::Source(name) => format!("svc {}", name),
+                    FilterTerm::MinLevel(level) => format!("level {}", level.name()),
+                };
+                (&text[t.range], kind)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn terms_quotes_and_exclusions() {
+        assert_eq!(
+            terms(r#"api  !health "db down" !"GET /ping" svc:web level:warn+"#),
+            [
+                ("api", "+(?i)api".to_string()),
+                ("!health", "-(?i)health".to_string()),
+                (r#""db down""#, "+(?i)db down".to_string()),
+                (r#"!"GET /ping""#, "-(?i)GET /ping".to_string()),
+                ("svc:web", "svc web".to_string()),
+                ("level:warn+", "level WARN".to_string()),
+            ]
+        );
+        // Quoted text is literal; an unclosed quote runs to the end
+        assert_eq!(terms(r#""a.b"#), [(r#""a.b"#, r"+(?i)a\.b".to_string())]);
+        assert!(terms("! \"\"").is_empty());
+        assert_eq!(
+            terms("ok (unclosed"),
+            [
+                ("ok", "+(?i)ok".to_string()),
+                ("(unclosed", "(unclosed: unclosed group".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,98 @@
+const CONSECUTIVE_BONUS: i64 = 4;
+const WORD_START_BONUS: i64 = 3;
+/// Most a single gap between two matched characters costs.
+const MAX_GAP_PENALTY: i64 = 3;
+/// Entries scoring below this per query character are hidden.
+const MIN_SCORE_PER_CHAR: i64 = 1;
+/// Occurrences of the first query character tried as a starting point.
+const MAX_STARTS: usize = 64;
+
+#[derive(Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Char indices of the matched characters in the text
+    pub positions: Vec<usize>,
+}
+
+/// Best match of `query` in `text` for the Ctrl+F search: the query's
+/// characters must appear in order (case-insensitive), scoring higher when
+/// consecutive or at a word start and lower across gaps. None when they
+/// aren't all there.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().map(lower).collect();
+    let first = *query.first()?;
+    let text: Vec<char> = text.chars().collect();
+
+    let mut best: Option<FuzzyMatch> = None;
+    let starts = (0..text.len())
+        .filter(|&i| lower(text[i]) == first)
+        .take(MAX_STARTS);
+    for start in starts {
+        // No match from here means none from any later start either
+        let found = match_from(&query, &text, start)?;
+        if best.as_ref().is_none_or(|b| found.score > b.score) {
+            best = Some(found);
+        }
+    }
+    best
+}
+
+/// Score of `text` against `query` when it's good enough to show the entry.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let found = fuzzy_match(query, text)?;
+    (found.score >= found.positions.len() as i64 * MIN_SCORE_PER_CHAR).then_some(found.score)
+}
+
+/// Greedy left-to-right match with the first query character at `start`.
+fn match_from(query: &[char], text: &[char], start: usize) -> Option<FuzzyMatch> {
+    let mut positions: Vec<usize> = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut i = start;
+    for &q in query {
+        while i < text.len() && lower(text[i]) != q {
+            i += 1;
+        }
+        if i == text.len() {
+            return None;
+        }
+        score += 1;
+        match positions.last() {
+            Some(&prev) if prev + 1 == i => score += CONSECUTIVE_BONUS,
+            Some(&prev) => score -= ((i - prev - 1) as i64).min(MAX_GAP_PENALTY),
+            None => {}
+        }
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+        positions.push(i);
+        i += 1;
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+fn lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_consecutive_word_start_matches() {
+        let found = fuzzy_match("err", "server error").unwrap();
+        assert_eq!(found.positions, [7, 8, 9]);
+        assert!(
+            fuzzy_score("ERR", "Connection error").unwrap() > fuzzy_score("err", "e.r.r").unwrap()
+        );
+        assert_eq!(fuzzy_match("xyz", "server error"), None);
+        assert_eq!(fuzzy_match("", "anything"), None);
+    }
+
+    #[test]
+    fn scattered_matches_fall_below_threshold() {
+        assert!(fuzzy_match("dbt", "database timeout").is_some());
+        assert_eq!(fuzzy_score("abc", "axxxxbxxxxc"), None);
+        assert!(fuzzy_score("dbtime", "db timeout").is_some());
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Entries kept per prompt; the oldest drop off first.
+pub const PROMPT_HISTORY_CAP: usize = 100;
+
+/// Submitted values of one prompt, oldest first, stepped through with
+/// Up/Down like a shell history.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PromptHistory {
+    entries: VecDeque<String>,
+    /// Entry shown while stepping through; `None` when not navigating
+    #[serde(skip)]
+    pos: Option<usize>,
+}
+
+impl PromptHistory {
+    /// Record a submitted value as the newest entry, moving it up if it was
+    /// already there.
+    pub fn push(&mut self, text: &str) {
+        self.pos = None;
+        if text.is_empty() {
+            return;
+        }
+        self.entries.retain(|e| e != text);
+        while self.entries.len() >= PROMPT_HISTORY_CAP {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(text.to_string());
+    }
+
+    /// Step back to an older entry (Up arrow). `None` when there is none.
+    pub fn older(&mut self) -> Option<String> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let pos = match self.pos {
+            None => self.entries.len() - 1,
+            Some(p) => p.saturating_sub(1),
+        };
+        self.pos = Some(pos);
+        Some(self.entries[pos].clone())
+    }
+
+    /// Step forward to a newer entry (Down arrow); past the newest gives an
+    /// empty prompt. `None` when not navigating.
+    pub fn newer(&mut self) -> Option<String> {
+        let p = self.pos?;
+        if p + 1 < self.entries.len() {
+            self.pos = Some(p + 1);
+            Some(self.entries[p + 1].clone())
+        } else {
+            self.pos = None;
+            Some(String::new())
+        }
+    }
+
+    /// Stop navigating, e.g. when the prompt is edited or reopened.
+    pub fn reset(&mut self) {
+        self.pos = None;
+    }
+
+    /// Drop empty and repeated entries and apply the cap, keeping the
+    /// newest occurrence of each (for histories read from disk).
+    fn normalize(&mut self) {
+        let entries = std::mem::take(&mut self.entries);
+        for entry in entries {
+            self.push(&entry);
+        }
+    }
+}
+
+/// Filter, search and highlight histories, saved across runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptHistories {
+    pub filter: PromptHistory,
+    pub search: PromptHistory,
+    pub highlight: PromptHistory,
+}
+
+impl PromptHistories {
+    /// A missing file is an empty history.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = match std::fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        let mut histories: Self =
+            serde_json::from_str(&data).map_err(|e| format!("{}: {}", path.display(), e))?;
+        histories.filter.normalize();
+        histories.search.normalize();
+        histories.highlight.normalize();
+        Ok(histories)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let data = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, data).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// `$XDG_STATE_HOME/logpulse/history`, else `~/.local/state/logpulse/history`.
+pub fn history_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(base.join("logpulse").join("history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_steps_like_a_shell_and_dedups() {
+        let mut history = PromptHistory::default();
+        for text in ["timeout", "error", "", "timeout", "svc:api"] {
+            history.push(text);
+        }
+        assert_eq!(history.entries, ["error", "timeout", "svc:api"]);
+
+        assert_eq!(history.newer(), None);
+        assert_eq!(history.older().as_deref(), Some("svc:api"));
+        history.older();
+        assert_eq!(history.older().as_deref(), Some("error"));
+        assert_eq!(history.older().as_deref(), Some("error"));
+        assert_eq!(history.newer().as_deref(), Some("timeout"));
+        history.newer();
+        assert_eq!(history.newer().as_deref(), Some(""));
+        assert_eq!(history.newer(), None);
+
+        for i in 0..(PROMPT_HISTORY_CAP + 5) {
+            history.push(&format!("pattern{}", i));
+        }
+        assert_eq!(history.entries.len(), PROMPT_HISTORY_CAP);
+        assert_eq!(history.entries[0], "pattern5");
+    }
+
+    #[test]
+    fn histories_round_trip_through_the_file() {
+        let dir = std::env::temp_dir().join(format!("logpulse-history-{}", std::process::id()));
+        let path = dir.join("state").join("history");
+        assert!(PromptHistories::load(&path)
+            .unwrap()
+            .filter
+            .entries
+            .is_empty());
+
+        let mut histories = PromptHistories::default();
+        histories.filter.push("level:warn+ !health");
+        histories.filter.push("\"db down\"");
+        histories.highlight.push("user_id=\\d+");
+        histories.filter.older();
+        histories.save(&path).unwrap();
+
+        let loaded = PromptHistories::load(&path).unwrap();
+        assert_eq!(
+            loaded.filter.entries,
+            ["level:warn+ !health", "\"db down\""]
+        );
+        assert_eq!(loaded.filter.pos, None);
+        assert!(loaded.search.entries.is_empty());
+        assert_eq!(loaded.highlight.entries, ["user_id=\\d+"]);
+
+        // Hand-edited files are cleaned up; a missing prompt is empty
+        std::fs::write(&path, r#"{"search": ["a", "b", "a", ""]}"#).unwrap();
+        let loaded = PromptHistories::load(&path).unwrap();
+        assert_eq!(loaded.search.entries, ["b", "a"]);
+        std::fs::write(&path, "not json").unwrap();
+        assert!(PromptHistories::load(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,303 @@
+use serde_json::Value;
+
+use crate::app::LogEntry;
+use crate::fuzzy::fuzzy_score;
+use crate::parser::{JSON_LEVEL_KEYS, JSON_MSG_KEYS, JSON_TS_KEYS};
+
+/// A JSON entry in the detail view: a table of its fields by dot path, or
+/// (Tab) a collapsible tree.
+pub struct JsonTreeState {
+    pub value: Value,
+    /// Expanded flag per node, indexed in pre-order over the whole tree
+    pub expanded: Vec<bool>,
+    /// Selected row among the currently visible rows
+    pub cursor: usize,
+    /// Field table rather than the tree
+    pub table: bool,
+    /// Fuzzy filter on the table's field paths (`/`), and whether it's
+    /// still being typed
+    pub field_filter: String,
+    pub filtering: bool,
+}
+
+/// One visible line of the tree.
+pub struct TreeRow<'a> {
+    pub depth: usize,
+    /// Object key or `[i]` for array items; None for the root
+    pub key: Option<String>,
+    pub node: usize,
+    pub value: &'a Value,
+    /// Container whose children are shown
+    pub open: bool,
+}
+
+impl JsonTreeState {
+    /// Tree for a JSON object line, None for anything else.
+    pub fn from_entry(entry: &LogEntry) -> Option<Self> {
+        let raw = entry.raw.trim();
+        if !raw.starts_with('{') {
+            return None;
+        }
+        let value: Value = serde_json::from_str(raw).ok()?;
+        let nodes = count_nodes(&value);
+        Some(Self {
+            value,
+            expanded: vec![true; nodes],
+            cursor: 0,
+            table: true,
+            field_filter: String::new(),
+            filtering: false,
+        })
+    }
+
+    /// The table's rows: every leaf by dot path (`http.status`, `tags[0]`),
+    /// level, message and time first, narrowed by `field_filter`.
+    pub fn fields(&self) -> Vec<(String, &Value)> {
+        let mut fields = flatten_fields(&self.value);
+        // Stable, so the rest keep the JSON's order
+        fields.sort_by_key(|(path, _)| leading_rank(path));
+        if !self.field_filter.is_empty() {
+            fields.retain(|(path, _)| fuzzy_score(&self.field_filter, path).is_some());
+        }
+        fields
+    }
+
+    fn row_count(&self) -> usize {
+        if self.table {
+            self.fields().len()
+        } else {
+            self.rows().len()
+        }
+    }
+
+    /// Tab: switch between the field table and the tree.
+    pub fn toggle_table(&mut self) {
+        self.table = !self.table;
+        self.cursor = 0;
+    }
+
+    /// `/`: type a filter on the field paths (in the table).
+    pub fn start_filter(&mut self) {
+        self.table = true;
+        self.filtering = true;
+    }
+
+    pub fn set_field_filter(&mut self, filter: String) {
+        self.field_filter = filter;
+        self.cursor = 0;
+    }
+
+    pub fn rows(&self) -> Vec<TreeRow<'_>> {
+        let mut rows = Vec::new();
+        let mut next_id = 0;
+        self.collect(&self.value, None, 0, true, &mut next_id, &mut rows);
+        rows
+    }
+
+    fn collect<'a>(
+        &self,
+        value: &'a Value,
+        key: Option<String>,
+        depth: usize,
+        visible: bool,
+        next_id: &mut usize,
+        rows: &mut Vec<TreeRow<'a>>,
+    ) {
+        let node = *next_id;
+        *next_id += 1;
+        let open = visible && is_container(value) && self.expanded[node];
+        if visible {
+            rows.push(TreeRow {
+                depth,
+                key,
+                node,
+                value,
+                open,
+            });
+        }
+        // Hidden subtrees are still walked so node ids stay stable
+        match value {
+            Value::Object(map) => {
+                for (k, v) in map {
+                    self.collect(v, Some(k.clone()), depth + 1, open, next_id, rows);
+                }
+            }
+            Value::Array(items) => {
+                for (i, v) in items.iter().enumerate() {
+                    self.collect(v, Some(format!("[{}]", i)), depth + 1, open, next_id, rows);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        let last = self.row_count().saturating_sub(1);
+        self.cursor = (self.cursor + 1).min(last);
+    }
+
+    /// Expand or collapse the container under the cursor (tree only).
+    pub fn toggle(&mut self) {
+        if self.table {
+            return;
+        }
+        let node = match self.rows().get(self.cursor) {
+            Some(row) if is_container(row.value) => row.node,
+            _ => return,
+        };
+        self.expanded[node] = !self.expanded[node];
+    }
+
+    /// Text copied for the row under the cursor: strings unquoted,
+    /// containers as pretty JSON.
+    pub fn selected_text(&self) -> Option<String> {
+        let value = if self.table {
+            self.fields().get(self.cursor)?.1
+        } else {
+            self.rows().get(self.cursor)?.value
+        };
+        Some(match value {
+            Value::String(s) => s.clone(),
+            Value::Object(_) | Value::Array(_) => {
+                serde_json::to_string_pretty(value).unwrap_or_default()
+            }
+            other => other.to_string(),
+        })
+    }
+}
+
+/// Leaves of `value` with their dot paths, in document order.
+pub fn flatten_fields(value: &Value) -> Vec<(String, &Value)> {
+    let mut fields = Vec::new();
+    flatten(value, String::new(), &mut fields);
+    fields
+}
+
+/// Empty containers count as leaves.
+fn flatten<'a>(value: &'a Value, path: String, out: &mut Vec<(String, &'a Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                flatten(child, child_path, out);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, child) in items.iter().enumerate() {
+                flatten(child, format!("{}[{}]", path, i), out);
+            }
+        }
+        _ => out.push((path, value)),
+    }
+}
+
+/// Level, message and timestamp fields sort before the rest, in that order.
+fn leading_rank(path: &str) -> usize {
+    [JSON_LEVEL_KEYS, JSON_MSG_KEYS, JSON_TS_KEYS]
+        .iter()
+        .position(|keys| keys.contains(&path))
+        .unwrap_or(3)
+}
+
+pub fn is_container(value: &Value) -> bool {
+    matches!(value, Value::Object(_) | Value::Array(_))
+}
+
+fn count_nodes(value: &Value) -> usize {
+    1 + match value {
+        Value::Object(map) => map.values().map(count_nodes).sum(),
+        Value::Array(items) => items.iter().map(count_nodes).sum(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{JsonParser, LogParser};
+
+    fn tree(raw: &str) -> JsonTreeState {
+        let mut state = JsonTreeState::from_entry(&JsonParser::default().parse(raw)).unwrap();
+        state.table = false;
+        state
+    }
+
+    #[test]
+    fn rows_follow_expanded_state() {
+        let mut t =
+            tree(r#"{"level":"error","http":{"status":503,"path":"/api"},"tags":["a","b"]}"#);
+        let keys = |t: &JsonTreeState| -> Vec<String> {
+            t.rows()
+                .iter()
+                .map(|r| r.key.clone().unwrap_or_default())
+                .collect()
+        };
+        assert_eq!(
+            keys(&t),
+            ["", "http", "path", "status", "level", "tags", "[0]", "[1]"]
+        );
+
+        // Collapse "http": its children disappear, later node ids are unchanged
+        t.cursor = 1;
+        t.toggle();
+        assert_eq!(keys(&t), ["", "http", "level", "tags", "[0]", "[1]"]);
+        assert_eq!(t.rows()[3].node, 5);
+
+        t.cursor = 4;
+        assert_eq!(t.selected_text().as_deref(), Some("a"));
+        t.cursor = 1;
+        assert!(t.selected_text().unwrap().contains("\"status\": 503"));
+
+        t.cursor = 0;
+        t.toggle();
+        assert_eq!(t.rows().len(), 1);
+        t.move_down();
+        assert_eq!(t.cursor, 0);
+    }
+
+    #[test]
+    fn field_table_flattens_and_filters_paths() {
+        let raw = r#"{"http":{"status":503,"path":"/api"},"msg":"down","tags":["a"],"extra":{},"ts":"10:30:01","level":"error"}"#;
+        let mut t = JsonTreeState::from_entry(&JsonParser::default().parse(raw)).unwrap();
+        let paths = |t: &JsonTreeState| -> Vec<String> {
+            t.fields().into_iter().map(|(path, _)| path).collect()
+        };
+        assert_eq!(
+            paths(&t),
+            [
+                "level",
+                "msg",
+                "ts",
+                "extra",
+                "http.path",
+                "http.status",
+                "tags[0]"
+            ]
+        );
+
+        t.start_filter();
+        t.set_field_filter("hst".to_string());
+        assert_eq!(paths(&t), ["http.status"]);
+        assert_eq!(t.selected_text().as_deref(), Some("503"));
+        t.move_down();
+        assert_eq!(t.cursor, 0);
+
+        t.toggle_table();
+        assert!(!t.table);
+        assert_eq!(t.rows()[0].key, None);
+    }
+
+    #[test]
+    fn non_json_entries_have_no_tree() {
+        let entry = JsonParser::default().parse("plain text line");
+        assert!(JsonTreeState::from_entry(&entry).is_none());
+    }
+}
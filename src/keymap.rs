@@ -0,0 +1,434 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crossterm::event::KeyEvent;
+
+use crate::app::LogLevel;
+use crate::config::{KeyBinding, KeySpec};
+
+/// Something a key does in the log feed (Normal input mode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Help,
+    Stats,
+    Pause,
+    Divider,
+    TableView,
+    WrapLines,
+    RawLines,
+    SparkDetail,
+    EpsWindowWider,
+    EpsWindowNarrower,
+    ContextMore,
+    ContextLess,
+    ReplayFaster,
+    ReplaySlower,
+    Dedup,
+    GroupSimilar,
+    ErrorPeek,
+    Filter,
+    ErrorOnly,
+    /// Set that floor, or clear it when it's the current one
+    MinLevel(LogLevel),
+    CycleMinLevel,
+    StatusFacet,
+    MatchScope,
+    Detail,
+    Clear,
+    ScrollUp,
+    ScrollDown,
+    ScrollRight,
+    ScrollLeft,
+    /// One column at a time
+    ScrollRightFine,
+    ScrollLeftFine,
+    /// Back to column 0
+    ScrollHome,
+    FitWidth,
+    AutoFit,
+    PageDown,
+    PageUp,
+    JumpStart,
+    JumpEnd,
+    Search,
+    SearchNext,
+    SearchPrev,
+    FuzzySearch,
+    ClearFuzzy,
+    Copy,
+    CopyAll,
+    CopyJson,
+    TimeDisplay,
+    AgeDisplay,
+    Expand,
+    Bookmark,
+    NextBookmark,
+    Flag,
+    FlaggedOnly,
+    SnapshotErrors,
+    DiffMode,
+    ExportFlagged,
+    Highlight,
+    SaveSession,
+    Save,
+    SaveJson,
+    SaveCsv,
+    SaveHtml,
+    Sort,
+    TimeJump,
+    Correlation,
+}
+
+/// Every action with its `[keybindings]` name and default keys.
+const ACTIONS: &[(Action, &str, &[&str])] = &[
+    (Action::Quit, "quit", &["q"]),
+    (Action::Help, "help", &["f1", "H"]),
+    (Action::Stats, "stats", &["i"]),
+    (Action::Pause, "pause", &["space"]),
+    (Action::Divider, "divider", &["d"]),
+    (Action::TableView, "table_view", &["tab"]),
+    (Action::WrapLines, "wrap_lines", &["w"]),
+    (Action::RawLines, "raw_lines", &["R"]),
+    (Action::SparkDetail, "spark_detail", &["v"]),
+    (Action::EpsWindowWider, "eps_window_wider", &["+"]),
+    (Action::EpsWindowNarrower, "eps_window_narrower", &["-"]),
+    (Action::ContextMore, "context_more", &["]"]),
+    (Action::ContextLess, "context_less", &["["]),
+    (Action::ReplayFaster, "replay_faster", &[">"]),
+    (Action::ReplaySlower, "replay_slower", &["<"]),
+    (Action::Dedup, "dedup", &["D"]),
+    (Action::GroupSimilar, "group_similar", &["G"]),
+    (Action::ErrorPeek, "error_peek", &["p"]),
+    (Action::Filter, "filter", &["/"]),
+    (Action::ErrorOnly, "error_only", &["e"]),
+    (Action::MinLevel(LogLevel::Trace), "min_level_trace", &["1"]),
+    (Action::MinLevel(LogLevel::Debug), "min_level_debug", &["2"]),
+    (Action::MinLevel(LogLevel::Info), "min_level_info", &["3"]),
+    (Action::MinLevel(LogLevel::Warn), "min_level_warn", &["4"]),
+    (Action::MinLevel(LogLevel::Error), "min_level_error", &["5"]),
+    (Action::CycleMinLevel, "cycle_min_level", &["L"]),
+    (Action::StatusFacet, "status_facet", &["r"]),
+    (Action::MatchScope, "match_scope", &["M"]),
+    (Action::Detail, "detail", &["enter"]),
+    (Action::Clear, "clear", &["c"]),
+    (Action::ScrollUp, "scroll_up", &["up", "k"]),
+    (Action::ScrollDown, "scroll_down", &["down", "j"]),
+    (Action::ScrollRight, "scroll_right", &["right", "l"]),
+    (Action::ScrollLeft, "scroll_left", &["left", "h"]),
+    (
+        Action::ScrollRightFine,
+        "scroll_right_fine",
+        &["shift+right"],
+    ),
+    (Action::ScrollLeftFine, "scroll_left_fine", &["shift+left"]),
+    (Action::ScrollHome, "scroll_home", &["0", "^"]),
+    (Action::FitWidth, "fit_width", &["A"]),
+    (Action::AutoFit, "auto_fit", &["W"]),
+    (Action::PageDown, "page_down", &["pagedown"]),
+    (Action::PageUp, "page_up", &["pageup"]),
+    (Action::JumpStart, "jump_start", &["home"]),
+    (Action::JumpEnd, "jump_end", &["end"]),
+    (Action::Search, "search", &["?"]),
+    (Action::SearchNext, "search_next", &["n"]),
+    (Action::SearchPrev, "search_prev", &["N"]),
+    (Action::FuzzySearch, "fuzzy_search", &["ctrl+f"]),
+    (Action::ClearFuzzy, "clear_fuzzy", &["esc"]),
+    (Action::Copy, "copy", &["y"]),
+    (Action::CopyAll, "copy_all", &["Y"]),
+    (Action::CopyJson, "copy_json", &["ctrl+y"]),
+    (Action::TimeDisplay, "time_display", &["t"]),
+    (Action::AgeDisplay, "age_display", &["T"]),
+    (Action::Expand, "expand", &["x"]),
+    (Action::Bookmark, "bookmark", &["b"]),
+    (Action::NextBookmark, "next_bookmark", &["B"]),
+    (Action::Flag, "flag", &["f"]),
+    (Action::FlaggedOnly, "flagged_only", &["F"]),
+    (Action::SnapshotErrors, "snapshot_errors", &["z"]),
+    (Action::DiffMode, "diff_mode", &["Z"]),
+    (Action::ExportFlagged, "export_flagged", &["S"]),
+    (Action::Highlight, "highlight", &["*"]),
+    (Action::SaveSession, "save_session", &["ctrl+s"]),
+    (Action::Save, "save", &["s"]),
+    (Action::SaveJson, "save_json", &["J"]),
+    (Action::SaveCsv, "save_csv", &["C"]),
+    (Action::SaveHtml, "save_html", &["E"]),
+    (Action::Sort, "sort", &["o"]),
+    (Action::TimeJump, "time_jump", &["g"]),
+    (Action::Correlation, "correlation", &["I"]),
+];
+
+/// The help overlay's sections: what each action does, under its heading.
+/// Every action in [`ACTIONS`] appears once; its keys come from the active
+/// [`KeyMap`], so a rebound key shows up in its own row.
+pub const HELP: &[(&str, &[(Action, &str)])] = &[
+    (
+        "Navigation",
+        &[
+            (Action::ScrollDown, "Move selection down"),
+            (Action::ScrollUp, "Move selection up"),
+            (Action::ScrollRight, "Scroll right"),
+            (Action::ScrollLeft, "Scroll left"),
+            (Action::ScrollRightFine, "Scroll right one column"),
+            (Action::ScrollLeftFine, "Scroll left one column"),
+            (Action::ScrollHome, "Back to column 0"),
+            (Action::FitWidth, "Fit the longest line"),
+            (Action::AutoFit, "Auto-fit while following"),
+            (Action::PageDown, "Page down a screen"),
+            (Action::PageUp, "Page up a screen"),
+            (Action::JumpStart, "First entry"),
+            (Action::JumpEnd, "Last entry"),
+            (Action::TimeJump, "Jump to time (14:30, 2024-01-15)"),
+            (Action::Bookmark, "Toggle bookmark"),
+            (Action::NextBookmark, "Next bookmark"),
+        ],
+    ),
+    (
+        "Filtering",
+        &[
+            (
+                Action::Filter,
+                "Filter (regex terms, !exclude, \"phrase\", svc:name)",
+            ),
+            (Action::ErrorOnly, "Error-only mode"),
+            (
+                Action::MinLevel(LogLevel::Trace),
+                "Minimum level trace (same key = off)",
+            ),
+            (Action::MinLevel(LogLevel::Debug), "Minimum level debug"),
+            (Action::MinLevel(LogLevel::Info), "Minimum level info"),
+            (Action::MinLevel(LogLevel::Warn), "Minimum level warn"),
+            (Action::MinLevel(LogLevel::Error), "Minimum level error"),
+            (Action::CycleMinLevel, "Cycle the minimum level"),
+            (
+                Action::StatusFacet,
+                "HTTP status: all / 5xx / 4xx / 3xx / 2xx",
+            ),
+            (
+                Action::MatchScope,
+                "Match filter / search: raw line / message only",
+            ),
+            (Action::FlaggedOnly, "Flagged-only view"),
+            (Action::SnapshotErrors, "Snapshot the errors seen so far"),
+            (Action::DiffMode, "Show only errors new since the snapshot"),
+            (
+                Action::Correlation,
+                "Track a request / trace ID (n/N step through it)",
+            ),
+        ],
+    ),
+    (
+        "Search",
+        &[
+            (Action::Search, "Search (regex, highlighted inline)"),
+            (Action::SearchNext, "Next search match"),
+            (Action::SearchPrev, "Previous search match"),
+            (Action::FuzzySearch, "Fuzzy search (best matches first)"),
+            (Action::ClearFuzzy, "Clear the fuzzy search"),
+            (Action::Highlight, "Highlight pattern (empty = clear)"),
+        ],
+    ),
+    (
+        "View",
+        &[
+            (Action::Pause, "Pause / resume"),
+            (
+                Action::Detail,
+                "Detail view (y copies it, C the stack trace)",
+            ),
+            (
+                Action::Expand,
+                "Expand / collapse continuation lines inline",
+            ),
+            (Action::TimeDisplay, "Time: absolute / age / off"),
+            (Action::AgeDisplay, "Toggle age display"),
+            (Action::TableView, "Table layout (columns)"),
+            (Action::WrapLines, "Wrap long lines"),
+            (Action::RawLines, "Raw lines instead of parsed messages"),
+            (
+                Action::EpsWindowWider,
+                "Widen the activity window (up to 600s)",
+            ),
+            (
+                Action::EpsWindowNarrower,
+                "Narrow the activity window (down to 10s)",
+            ),
+            (
+                Action::SparkDetail,
+                "Per-level activity sparklines in the header",
+            ),
+            (
+                Action::ContextMore,
+                "More context lines around filter matches",
+            ),
+            (
+                Action::ContextLess,
+                "Fewer context lines around filter matches",
+            ),
+            (Action::ReplaySlower, "Replay slower (logpulse replay)"),
+            (Action::ReplayFaster, "Replay faster"),
+            (
+                Action::Stats,
+                "Statistics: levels, rates, top messages (Enter filters)",
+            ),
+            (Action::Dedup, "Collapse repeated lines"),
+            (
+                Action::GroupSimilar,
+                "Group similar messages (IPs, numbers, IDs masked)",
+            ),
+            (
+                Action::ErrorPeek,
+                "Peek at the last 10 errors (any key closes)",
+            ),
+            (Action::Sort, "Sort by number (regex capture) / undo"),
+            (Action::Divider, "Insert divider"),
+            (Action::Flag, "Toggle flag"),
+            (Action::Clear, "Clear buffer"),
+        ],
+    ),
+    (
+        "Export",
+        &[
+            (Action::Copy, "Copy entry to clipboard"),
+            (Action::CopyAll, "Copy all visible entries"),
+            (
+                Action::CopyJson,
+                "Copy entry as JSON with its parsed fields",
+            ),
+            (Action::Save, "Save visible entries to file"),
+            (Action::SaveJson, "Export visible entries as JSON"),
+            (Action::SaveCsv, "Export visible entries as CSV"),
+            (Action::SaveHtml, "Export visible entries as HTML"),
+            (Action::ExportFlagged, "Export flagged entries"),
+            (
+                Action::SaveSession,
+                "Save session snapshot (reopen with --open)",
+            ),
+        ],
+    ),
+    (
+        "General",
+        &[(Action::Help, "This help"), (Action::Quit, "Quit")],
+    ),
+];
+
+/// Feed key bindings: the defaults above with `[keybindings]` applied.
+pub struct KeyMap {
+    keys: HashMap<KeySpec, Action>,
+    /// Each action's keys as written (`ctrl+p / up`), for the help.
+    labels: HashMap<Action, String>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let keys = ACTIONS
+            .iter()
+            .flat_map(|(action, _, specs)| {
+                specs
+                    .iter()
+                    .map(move |spec| (spec.parse().expect("default key spec"), *action))
+            })
+            .collect();
+        let labels = ACTIONS
+            .iter()
+            .map(|(action, _, specs)| (*action, specs.join(" / ")))
+            .collect();
+        Self { keys, labels }
+    }
+}
+
+impl KeyMap {
+    /// Defaults overridden by the config's `[keybindings]`. Unknown actions
+    /// and invalid key specs are skipped and returned as warnings.
+    pub fn from_config(bindings: &BTreeMap<String, KeyBinding>) -> (Self, Vec<String>) {
+        let mut map = Self::default();
+        let mut warnings = Vec::new();
+        for (name, binding) in bindings {
+            let Some(&(action, _, _)) = ACTIONS.iter().find(|(_, n, _)| n == name) else {
+                warnings.push(format!("keybindings: unknown action '{}'", name));
+                continue;
+            };
+            let mut keys = Vec::new();
+            let mut written = Vec::new();
+            for spec in binding.specs() {
+                match spec.parse::<KeySpec>() {
+                    Ok(key) => {
+                        keys.push(key);
+                        written.push(spec.as_str());
+                    }
+                    Err(e) => warnings.push(format!("keybindings.{}: {}", name, e)),
+                }
+            }
+            // Nothing usable: keep the defaults rather than leave it unbound
+            if keys.is_empty() {
+                continue;
+            }
+            map.labels.insert(action, written.join(" / "));
+            map.keys.retain(|_, a| *a != action);
+            for key in keys {
+                map.keys.insert(key, action);
+            }
+        }
+        (map, warnings)
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.keys.get(&KeySpec::from_event(key)).copied()
+    }
+
+    /// The keys bound to `action`, as written in the defaults or the config.
+    pub fn keys_for(&self, action: Action) -> &str {
+        self.labels.get(&action).map_or("", String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn config_replaces_an_actions_default_keys() {
+        let bindings: BTreeMap<String, KeyBinding> = [
+            (
+                "scroll_up".to_string(),
+                KeyBinding::One("ctrl+p".to_string()),
+            ),
+            ("launch".to_string(), KeyBinding::One("x".to_string())),
+            ("quit".to_string(), KeyBinding::One("hyper+q".to_string())),
+        ]
+        .into();
+        let (map, warnings) = KeyMap::from_config(&bindings);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(map.keys_for(Action::ScrollUp), "ctrl+p");
+        assert_eq!(map.keys_for(Action::ScrollDown), "down / j");
+
+        let press = |code, modifiers| map.action(&KeyEvent::new(code, modifiers));
+        assert_eq!(
+            press(KeyCode::Char('p'), KeyModifiers::CONTROL),
+            Some(Action::ScrollUp)
+        );
+        assert_eq!(press(KeyCode::Char('k'), KeyModifiers::NONE), None);
+        assert_eq!(press(KeyCode::Up, KeyModifiers::NONE), None);
+        // Without a valid key the action keeps its default
+        assert_eq!(
+            press(KeyCode::Char('q'), KeyModifiers::NONE),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            press(KeyCode::Char('D'), KeyModifiers::SHIFT),
+            Some(Action::Dedup)
+        );
+    }
+
+    #[test]
+    fn help_lists_every_action_once() {
+        let listed: Vec<Action> = HELP
+            .iter()
+            .flat_map(|(_, rows)| rows.iter().map(|(action, _)| *action))
+            .collect();
+        assert_eq!(listed.len(), ACTIONS.len());
+        for (action, name, _) in ACTIONS {
+            assert!(listed.contains(action), "{} has no help row", name);
+        }
+    }
+}
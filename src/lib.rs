@@ -0,0 +1,18 @@
+//! logpulse's modules as a library, shared by the binary and the
+//! integration tests.
+
+pub mod ansi;
+pub mod app;
+pub mod config;
+pub mod event;
+pub mod filter;
+pub mod fuzzy;
+pub mod history;
+pub mod json_tree;
+pub mod keymap;
+pub mod parser;
+pub mod session;
+pub mod source;
+pub mod stats;
+pub mod theme;
+pub mod ui;
@@ -1,35 +1,55 @@
-mod app;
-mod event;
-mod parser;
-mod source;
-mod ui;
-
 use std::io;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
 
+use chrono::{DateTime, Local};
 use clap::{CommandFactory, Parser as ClapParser, Subcommand, ValueEnum};
 use clap_complete::{generate, Shell};
 use crossterm::{
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use tokio::sync::mpsc;
 
-use app::App;
-use parser::{detect_parser, get_parser_by_name, LogParser, PlainParser};
+use logpulse::{app, config, event, history, keymap, parser, session, source, theme, ui};
+
+use app::{App, ViewMode};
+use config::Config;
+use history::{history_path, PromptHistories};
+use keymap::KeyMap;
+use parser::{
+    detect_format, get_parser_by_name, split_dot_path, CustomRegexParser, Detection, DiffParser,
+    JsonFieldMap, JsonParser, LogParser, PlainParser,
+};
 
 #[derive(Debug, Clone, ValueEnum)]
 enum FormatArg {
     Json,
+    Clef,
+    Mongo,
     Laravel,
     Django,
     Go,
+    Zap,
     Nginx,
+    Envoy,
+    Cri,
+    Mysql,
     Plain,
     Auto,
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RuntimeArg {
+    Docker,
+    Podman,
+    Nerdctl,
+}
+
 #[derive(ClapParser)]
 #[command(name = "logpulse")]
 #[command(about = "High-performance TUI log analyzer with smart format detection")]
@@ -37,7 +57,21 @@ enum FormatArg {
 #[command(after_help = "\x1b[1mExamples:\x1b[0m
   logpulse /var/log/syslog                              # Local file
   logpulse app.log nginx.log                             # Multiple files
+  logpulse -R /var/log/nginx/                            # Every file in a directory
+  logpulse '/var/log/app/*.log'                          # Glob, picks up new files too
   logpulse --format laravel app.log                      # Force parser
+  logpulse --since 15m --tail 5000 app.log               # Last 15 minutes only
+  logpulse --full old-incident.log                       # Whole file, no follow
+  logpulse --poll 500 /mnt/nfs/app.log                   # Network mount: poll instead of inotify
+  logpulse --buffer 50000 --spill tmp app.log            # Bigger buffer, evicted lines to disk
+  logpulse --open logpulse-20240115-103001.lpsession     # Reopen a saved session
+  logpulse --diff staging.log prod.log                   # Lines of staging missing from prod
+  logpulse --print --level warn --grep timeout app.log   # No TUI: filtered entries to stdout
+  kubectl logs api | logpulse --print --json | jq .level # Parsed entries as JSON Lines
+  logpulse app.log --highlight 'user_id=\\d+' --highlight '\\b5\\d\\d\\b'  # Always-on highlights
+  logpulse --theme gruvbox app.log                       # Color theme
+  logpulse --config ./ops.toml app.log                   # Use another config file
+  logpulse --parser-regex '^(?P<level>[A-Z]+) (?P<message>.*)' app.log  # Custom format
   docker logs -f myapp 2>&1 | logpulse                   # Pipe stdin
   logpulse docker myapi                                  # Smart match (Swarm/Compose)
   logpulse docker myapi /var/log/app.log                 # File inside container
@@ -47,15 +81,32 @@ enum FormatArg {
   logpulse k8s my-pod -n staging                         # Kubernetes pod
   logpulse k8s -l app=api -n prod                        # K8s by label
   logpulse compose api                                   # Docker Compose service
+  logpulse compose -p mystack                            # Whole Compose stack
+  logpulse http https://logs.internal/stream             # HTTP / SSE stream
+  logpulse cloudwatch --log-group /ecs/api               # AWS CloudWatch Logs
+  logpulse replay incident.log --speed 10                # Replay at 10x its pace
 
 \x1b[1mHotkeys:\x1b[0m
   q        Quit              Space    Pause / Resume
   /        Filter (regex)    ?        Search (n/N navigate)
   e        Error-only mode   *        Highlight pattern
-  Enter    Detail view       y        Copy to clipboard
+  1-5 / L  Minimum level     /level:warn+  Level in filter
+  Enter    Detail view       y / Y    Copy entry / all
   c        Clear buffer      s        Save visible to file
   g        Jump to time      j/k ↑/↓  Navigate
-  PgDn/PgUp  Jump 50 lines   Home/End  First/Last
+  b        Toggle bookmark   B        Next bookmark
+  f        Toggle flag       F        Flagged-only view
+  S        Export flagged    t        Time: abs/age/off
+  Ctrl+S   Save session      F1 / H   Help overlay
+  d        Insert divider    D        Collapse repeats
+  w        Wrap long lines   Tab      Table layout
+  J        Export as JSON    C        Export as CSV
+  E        Export as HTML    R        Raw lines
+  + / -    Activity window   v        Level sparklines
+  I        Track request ID  ] / [    Context lines
+  < / >    Replay speed      G        Group similar
+  p        Peek at errors    0 / ^    Back to column 0
+  PgDn/PgUp  Page a screen  Home/End  First/Last
   ←→       Horizontal scroll Ctrl+C   Force quit
 
 \x1b[1mUpdate:\x1b[0m
@@ -64,12 +115,129 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// Log files to monitor (auto-detects stdin when piped)
+    /// Log files, directories or quoted globs to monitor (auto-detects stdin when piped)
     files: Vec<PathBuf>,
 
-    /// Force log format instead of auto-detection
-    #[arg(short, long, value_enum, default_value = "auto")]
-    format: FormatArg,
+    /// Also follow files in subdirectories of directory arguments
+    #[arg(short = 'R', long)]
+    recursive: bool,
+
+    /// Load whole files (up to the buffer size) instead of the tail, and don't follow them
+    #[arg(long, alias = "full", conflicts_with = "tail")]
+    no_tail: bool,
+
+    /// Check followed files for new lines every MS milliseconds instead of waiting for
+    /// inotify events, which network filesystems (NFS, CIFS) don't deliver
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "no_tail")]
+    poll: Option<u64>,
+
+    /// Force log format instead of auto-detection [default: from config, else auto]
+    #[arg(short, long, value_enum)]
+    format: Option<FormatArg>,
+
+    /// Parse lines with a regex using named groups: timestamp, level, message, metadata
+    /// [default: parser_regex from config]
+    #[arg(long, value_name = "PATTERN", conflicts_with = "format")]
+    parser_regex: Option<String>,
+
+    /// JSON: dot-path of the level field, e.g. 'log.level' (implies --format json)
+    #[arg(long, value_name = "DOTPATH")]
+    json_level_field: Option<String>,
+
+    /// JSON: dot-path of the message field (implies --format json)
+    #[arg(long, value_name = "DOTPATH")]
+    json_msg_field: Option<String>,
+
+    /// JSON: dot-path of the timestamp field (implies --format json)
+    #[arg(long, value_name = "DOTPATH")]
+    json_ts_field: Option<String>,
+
+    /// Lines of backlog to load (0 = only new lines) [default: from config, else 1000]
+    #[arg(long, value_name = "N", global = true)]
+    tail: Option<usize>,
+
+    /// Only show lines newer than a duration (5m, 2h, 1d) or timestamp
+    #[arg(long, value_name = "WHEN", global = true, value_parser = source::Since::parse)]
+    since: Option<source::Since>,
+
+    /// Container runtime for docker/compose sources [default: docker, else podman/nerdctl]
+    #[arg(long, value_enum, global = true)]
+    runtime: Option<RuntimeArg>,
+
+    /// Entries kept in memory [default: from config (max_lines), else 10000]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    buffer: Option<u64>,
+
+    /// Append entries evicted from memory to this new file (`tmp` for a temp file) and page them back in on scroll
+    #[arg(long, value_name = "PATH|tmp", conflicts_with = "open")]
+    spill: Option<String>,
+
+    /// Most lines parsed per frame: lower keeps the UI smooth on slow terminals, higher catches up faster.
+    /// With --adaptive-batch this is the most it grows to
+    #[arg(long, value_name = "N", default_value_t = 500, value_parser = clap::value_parser!(u64).range(1..))]
+    batch_size: u64,
+
+    /// Start at 50 lines per frame and double, up to --batch-size, while lines pile up
+    #[arg(long)]
+    adaptive_batch: bool,
+
+    /// Seconds of history in the activity sparkline, 10-600 [default: from config, else 60]
+    #[arg(long, value_name = "SECS", value_parser = parse_eps_window)]
+    eps_window: Option<usize>,
+
+    /// Always highlight matches of this regex (repeatable; also `[auto_highlight]` in config)
+    #[arg(long = "highlight", value_name = "REGEX", value_parser = parse_highlight)]
+    highlights: Vec<String>,
+
+    /// Color theme [default: from config, else default; mono when NO_COLOR is set]
+    #[arg(long, value_name = "NAME", value_parser = theme::THEME_NAMES)]
+    theme: Option<String>,
+
+    /// Don't capture the mouse (keeps the terminal's own text selection)
+    #[arg(long)]
+    no_mouse: bool,
+
+    /// Keep ANSI colour codes from the source and render them instead of stripping
+    #[arg(long, alias = "ansi")]
+    keep_ansi: bool,
+
+    /// Show the lines of FILE_A whose shape (numbers and UUIDs masked) isn't in FILE_B in
+    /// red, the others dimmed; read once, not followed
+    #[arg(long, num_args = 2, value_names = ["FILE_A", "FILE_B"], conflicts_with_all = ["files", "open"])]
+    diff: Option<Vec<PathBuf>>,
+
+    /// With --diff: show FILE_B's lines instead, those missing from FILE_A in green
+    #[arg(long, requires = "diff")]
+    reverse: bool,
+
+    /// Reopen a session snapshot saved with Ctrl+S (read-only, paused)
+    #[arg(long, value_name = "FILE", conflicts_with = "files")]
+    open: Option<PathBuf>,
+
+    /// Don't open the TUI: write parsed entries to stdout (files are read once, not followed)
+    #[arg(long, conflicts_with = "open")]
+    print: bool,
+
+    /// With --print: only entries matching this filter (regex terms, !exclude, svc:NAME, level:warn+)
+    /// [default: filter from config]
+    #[arg(long, value_name = "FILTER", requires = "print")]
+    grep: Option<String>,
+
+    /// With --print: minimum level (trace, debug, info, warn, error)
+    #[arg(long, value_name = "LEVEL", value_parser = parse_level, requires = "print")]
+    level: Option<app::LogLevel>,
+
+    /// With --print: one JSON object per entry (timestamp, level, message, raw, extra_lines)
+    #[arg(long, requires = "print")]
+    json: bool,
+
+    /// Read this config file instead of ~/.config/logpulse/config.toml
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Same as the print-config subcommand
+    #[arg(long)]
+    print_config: bool,
 
     /// Generate shell completions
     #[arg(long, value_enum)]
@@ -98,11 +266,16 @@ Auto-reconnects when container restarts or redeploys."
   logpulse ssh prod-server docker myapi                  # Via ~/.ssh/config
   logpulse ssh user@host -J bastion.corp.com docker api  # Via jump host
   logpulse ssh user@host -p 2222 -i ~/.ssh/id_ed25519 /var/log/app.log
+  logpulse ssh web1 web2 web3 /var/log/app.log           # Merge several hosts
+  logpulse ssh web1 --target web2 docker myapi           # Same, with --target
 
 Also respects ~/.ssh/config for keys, ports, ProxyJump, ProxyCommand.")]
     Ssh {
         /// SSH target (user@host or host from ~/.ssh/config)
         target: String,
+        /// Additional host to merge into the same feed (repeatable)
+        #[arg(long = "target", value_name = "TARGET")]
+        targets: Vec<String>,
         /// SSH port (default: 22)
         #[arg(short = 'p', long)]
         port: Option<u16>,
@@ -112,7 +285,10 @@ Also respects ~/.ssh/config for keys, ports, ProxyJump, ProxyCommand.")]
         /// Jump host (ProxyJump), e.g. bastion.corp.com
         #[arg(short = 'J', long)]
         jump: Option<String>,
-        /// 'docker <prefix> [file]' or '/path/to/file.log'
+        /// Don't reconnect when the connection or container goes away
+        #[arg(long)]
+        no_reconnect: bool,
+        /// More hosts, then 'docker <prefix> [file]' or '/path/to/file.log'
         args: Vec<String>,
     },
 
@@ -122,7 +298,10 @@ Also respects ~/.ssh/config for keys, ports, ProxyJump, ProxyCommand.")]
   logpulse k8s my-pod -n staging                         # Specific namespace
   logpulse k8s my-pod -c sidecar                         # Specific container
   logpulse k8s -l app=api -n prod                        # Find pod by label
-  logpulse k8s my-pod /var/log/app.log                   # File inside pod")]
+  logpulse k8s my-pod /var/log/app.log                   # File inside pod
+  logpulse k8s my-pod --previous                         # Crashed container's logs
+  logpulse k8s my-pod --all-containers                   # Every container, tagged
+  logpulse k8s -l app=api --context prod-eu              # Another cluster context")]
     K8s {
         /// Pod name (omit if using --label)
         pod: Option<String>,
@@ -137,19 +316,103 @@ Also respects ~/.ssh/config for keys, ports, ProxyJump, ProxyCommand.")]
         label: Option<String>,
         /// Path to log file inside pod (omit for stdout)
         file: Option<String>,
+        /// Show logs of the previous terminated container (no follow)
+        #[arg(long)]
+        previous: bool,
+        /// Follow all containers in the pod, tagging lines by container
+        #[arg(long, conflicts_with = "container")]
+        all_containers: bool,
+        /// kubeconfig context to use (without switching the global one)
+        #[arg(long)]
+        context: Option<String>,
+        /// Path to the kubeconfig file
+        #[arg(long)]
+        kubeconfig: Option<String>,
     },
 
     /// Monitor Docker Compose service
     #[command(after_help = "\x1b[1mExamples:\x1b[0m
   logpulse compose api                                   # Service logs
-  logpulse compose api -f docker-compose.prod.yml        # Custom compose file")]
+  logpulse compose api -f docker-compose.prod.yml        # Custom compose file
+  logpulse compose                                       # All services (filter with svc:api)
+  logpulse compose -p mystack                            # Stack outside the current dir")]
     Compose {
-        /// Service name
-        service: String,
+        /// Service name (omit to follow all services)
+        service: Option<String>,
         /// Path to compose file
         #[arg(short, long)]
         file: Option<String>,
+        /// Compose project name
+        #[arg(short = 'p', long)]
+        project_name: Option<String>,
     },
+
+    /// Tail an HTTP streaming endpoint (chunked text or Server-Sent Events)
+    #[command(after_help = "\x1b[1mExamples:\x1b[0m
+  logpulse http https://logs.internal/stream?app=api     # Chunked text or SSE
+  logpulse http URL -H \"Authorization: Bearer $TOKEN\"    # Custom header
+  logpulse http https://localhost:8443/events -k         # Self-signed certificate
+
+text/event-stream bodies show their data: payloads; anything else is split on newlines.
+Reconnects with backoff when the connection drops, resuming SSE from Last-Event-ID.")]
+    Http {
+        /// URL of the streaming endpoint
+        url: String,
+        /// Extra request header, e.g. 'Authorization: Bearer ...' (repeatable)
+        #[arg(short = 'H', long = "header", value_name = "HEADER")]
+        headers: Vec<String>,
+        /// Don't verify the server's TLS certificate
+        #[arg(short = 'k', long)]
+        insecure: bool,
+    },
+
+    /// Follow an AWS CloudWatch Logs group (via the aws CLI)
+    #[command(after_help = "\x1b[1mExamples:\x1b[0m
+  logpulse cloudwatch --log-group /ecs/api               # Whole group, last 10 minutes
+  logpulse cloudwatch --log-group /aws/lambda/fn --log-stream-prefix 2024/01/15
+  logpulse cloudwatch --log-group /ecs/api --profile prod-sso --since 1h
+
+Polls FilterLogEvents every 2 seconds (not Live Tail), re-reading a window that
+covers late events; lines are tagged with their log stream.
+Credentials come from the aws CLI's standard chain (env, profiles, SSO).
+Throttling backs off instead of filling the feed with errors.")]
+    Cloudwatch {
+        /// Log group name
+        #[arg(long, value_name = "GROUP")]
+        log_group: String,
+        /// Only follow streams whose name starts with this
+        #[arg(long, value_name = "PREFIX")]
+        log_stream_prefix: Option<String>,
+        /// AWS region [default: from the profile / AWS_REGION]
+        #[arg(long)]
+        region: Option<String>,
+        /// Named AWS profile (SSO profiles work after `aws sso login`)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Replay a log file at the pace it was written
+    #[command(after_help = "\x1b[1mExamples:\x1b[0m
+  logpulse replay incident.log                           # Original speed
+  logpulse replay incident.log --speed 10                # Ten times faster
+
+Gaps between lines follow their timestamps; lines without one follow after 1ms.
+Space pauses the replay, < / > step the speed between 0.1x and 100x.")]
+    Replay {
+        /// Log file to replay
+        file: PathBuf,
+        /// Playback speed, 0.1 to 100
+        #[arg(long, default_value_t = 1.0, value_parser = parse_replay_speed)]
+        speed: f32,
+    },
+
+    /// Print the effective configuration as TOML and exit
+    #[command(after_help = "\x1b[1mExamples:\x1b[0m
+  logpulse print-config                                  # Config file plus defaults
+  logpulse --config ./team.toml --theme light print-config
+
+Command-line flags given before it are applied, as they would be for a feed.")]
+    PrintConfig,
 }
 
 #[tokio::main]
@@ -162,26 +425,119 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    let format_name = match cli.format {
+    let mut config = Config::load(cli.config.as_deref())?;
+    let format_given = cli.format.is_some();
+    config.apply_overrides(config::Overrides {
+        max_lines: cli.buffer.map(|n| n as usize),
+        tail_lines: cli.tail,
+        default_format: cli
+            .format
+            .and_then(|f| f.to_possible_value())
+            .map(|v| v.get_name().to_string()),
+        eps_window_secs: cli.eps_window,
+        // NO_COLOR beats the config file but not an explicit --theme
+        theme: cli
+            .theme
+            .or_else(|| theme::no_color().then(|| "mono".to_string())),
+    });
+    for warning in &config.warnings {
+        eprintln!("logpulse: {}", warning);
+    }
+    if cli.print_config || matches!(cli.command, Some(Commands::PrintConfig)) {
+        print!("{}", config.to_toml());
+        return Ok(());
+    }
+    parser::set_level_keywords(config.level_keywords());
+
+    let format = FormatArg::from_str(&config.default_format, true)
+        .map_err(|e| format!("config default_format: {}", e))?;
+    let format_name = match format {
         FormatArg::Auto => None,
         FormatArg::Json => Some("json"),
+        FormatArg::Clef => Some("clef"),
+        FormatArg::Mongo => Some("mongo"),
         FormatArg::Laravel => Some("laravel"),
         FormatArg::Django => Some("django"),
         FormatArg::Go => Some("go"),
+        FormatArg::Zap => Some("zap"),
         FormatArg::Nginx => Some("nginx"),
+        FormatArg::Envoy => Some("envoy"),
+        FormatArg::Cri => Some("cri"),
+        FormatArg::Mysql => Some("mysql"),
         FormatArg::Plain => Some("plain"),
     };
 
+    // Merged streams whose lines carry a source tag prefix to split off
+    let mut tagged = false;
+    let mut flags: Option<source::FlagStore> = None;
+    let mut hosts: Vec<Arc<AtomicBool>> = Vec::new();
+    let mut replay: Option<source::ReplayControl> = None;
+
+    if let Some(path) = cli.open {
+        let session = session::Session::load(&path)?;
+        // No source: the channel is closed and the feed shows only the snapshot
+        let (_, rx) = mpsc::unbounded_channel();
+        let info = SourceInfo {
+            name: session.filename.clone(),
+            history: None,
+            flags: None,
+            tagged: false,
+            hosts,
+            conn: source::ConnStatus::default(),
+            replay: None,
+            session: Some(session),
+        };
+        let tui_opts = TuiOpts {
+            keep_ansi: false,
+            since: None,
+            mouse: mouse_enabled(cli.no_mouse),
+            highlights: cli.highlights,
+            spill: None,
+            batch_size: cli.batch_size as usize,
+            adaptive_batch: cli.adaptive_batch,
+        };
+        return run_tui(rx, info, Some(Box::new(PlainParser)), &config, tui_opts).await;
+    }
+    let since = cli.since;
+    let src_opts = source::SourceOpts {
+        tail_lines: config.tail_lines,
+        reconnect_initial: Duration::from_millis(config.reconnect_initial_delay_ms),
+        reconnect_max: Duration::from_millis(config.reconnect_max_delay_ms),
+        runtime: cli.runtime.map(|r| match r {
+            RuntimeArg::Docker => source::Runtime::Docker,
+            RuntimeArg::Podman => source::Runtime::Podman,
+            RuntimeArg::Nerdctl => source::Runtime::Nerdctl,
+        }),
+        since,
+        conn: source::ConnStatus::default(),
+    };
+    let conn = src_opts.conn.clone();
+    let tui_opts = TuiOpts {
+        keep_ansi: cli.keep_ansi,
+        since: since.map(source::Since::cutoff).transpose()?,
+        mouse: mouse_enabled(cli.no_mouse),
+        highlights: cli.highlights,
+        spill: match cli.spill {
+            Some(ref arg) => Some(
+                source::SpillFile::create(arg).map_err(|e| format!("--spill {}: {}", arg, e))?,
+            ),
+            None => None,
+        },
+        batch_size: cli.batch_size as usize,
+        adaptive_batch: cli.adaptive_batch,
+    };
+
     let (rx, name, history) = match cli.command {
         Some(Commands::Docker { container, file }) => {
-            let (rx, name) = source::start_docker_source(container, file).await?;
-            (rx, name, None)
+            source::start_docker_source(container, file, src_opts).await?
         }
         Some(Commands::Ssh {
             target,
+            targets,
             port,
             key,
             jump,
+            no_reconnect,
             args,
         }) => {
             let opts = source::SshOpts {
@@ -189,9 +545,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 port,
                 key,
                 jump,
+                no_reconnect,
+                connected: Arc::new(AtomicBool::new(false)),
             };
-            let (rx, name) = parse_ssh_args(opts, args).await?;
-            (rx, name, None)
+            let (rx, name, history, host_states) =
+                parse_ssh_args(opts, targets, args, src_opts, &config).await?;
+            tagged = host_states.len() > 1;
+            if tagged {
+                hosts = host_states;
+            }
+            (rx, name, history)
         }
         Some(Commands::K8s {
             pod,
@@ -199,19 +562,79 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             container,
             label,
             file,
+            previous,
+            all_containers,
+            context,
+            kubeconfig,
+        }) => {
+            tagged = all_containers;
+            let opts = source::K8sOpts {
+                namespace,
+                container,
+                label,
+                context,
+                kubeconfig,
+                previous,
+                all_containers,
+            };
+            let (rx, name) = source::start_k8s_source(opts, pod, file, src_opts).await?;
+            (rx, name, None)
+        }
+        Some(Commands::Compose {
+            service,
+            file,
+            project_name,
         }) => {
+            tagged = service.is_none();
             let (rx, name) =
-                source::start_k8s_source(pod, namespace, container, label, file).await?;
+                source::start_compose_source(service, file, project_name, src_opts).await?;
             (rx, name, None)
         }
-        Some(Commands::Compose { service, file }) => {
-            let (rx, name) = source::start_compose_source(service, file).await?;
+        Some(Commands::Http {
+            url,
+            headers,
+            insecure,
+        }) => {
+            let opts = source::HttpOpts {
+                url,
+                headers,
+                insecure,
+            };
+            let (rx, name) = source::start_http_source(opts, src_opts).await?;
             (rx, name, None)
         }
+        Some(Commands::Cloudwatch {
+            log_group,
+            log_stream_prefix,
+            region,
+            profile,
+        }) => {
+            tagged = true;
+            let opts = source::CloudWatchOpts {
+                log_group,
+                stream_prefix: log_stream_prefix,
+                region,
+                profile,
+            };
+            let (rx, name) = source::start_cloudwatch_source(opts, src_opts).await?;
+            (rx, name, None)
+        }
+        Some(Commands::Replay { file, speed }) => {
+            let control = source::ReplayControl::new(speed);
+            replay = Some(control.clone());
+            let parser = format_name.map(get_parser_by_name);
+            let (rx, name) = source::start_replay_source(file, parser, control).await?;
+            (rx, name, None)
+        }
+        Some(Commands::PrintConfig) => unreachable!("printed before any source starts"),
         None => {
             let is_tty = atty::is(atty::Stream::Stdin);
 
-            if cli.files.is_empty() && !is_tty {
+            if let Some([a, b]) = cli.diff.as_deref() {
+                let (rx, name) =
+                    source::start_diff_source(a.clone(), b.clone(), cli.reverse).await?;
+                (rx, name, None)
+            } else if cli.files.is_empty() && !is_tty {
                 let (rx, name) = source::start_stdin_source().await?;
                 (rx, name, None)
             } else if cli.files.is_empty() {
@@ -222,76 +645,541 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let (rx, name) = source::start_stdin_source().await?;
                 (rx, name, None)
             } else {
-                source::start_multi_file_source(cli.files).await?
+                let mut file_opts = source::FileOpts {
+                    recursive: cli.recursive,
+                    full: cli.no_tail,
+                    poll: cli.poll.map(Duration::from_millis),
+                };
+                let mut src_opts = src_opts;
+                if cli.print {
+                    // Read each file once, start to end, whatever the buffer size
+                    file_opts.full = true;
+                    src_opts.tail_lines = cli.tail.unwrap_or(usize::MAX);
+                } else if cli.no_tail {
+                    src_opts.tail_lines = config.max_lines;
+                }
+                let (rx, name, history, file_flags) =
+                    source::start_multi_file_source(cli.files, file_opts, src_opts).await?;
+                flags = file_flags;
+                (rx, name, history)
             }
         }
     };
 
-    run_tui(rx, name, format_name, history).await
+    let info = SourceInfo {
+        name,
+        history,
+        flags,
+        tagged,
+        hosts,
+        conn,
+        replay,
+        session: None,
+    };
+    let json_fields = [
+        &cli.json_level_field,
+        &cli.json_msg_field,
+        &cli.json_ts_field,
+    ];
+    let parser: Option<Box<dyn LogParser>> = if cli.diff.is_some() {
+        // The inner parser reads timestamps and messages when --format names one
+        let inner = format_name.map_or_else(|| Box::new(PlainParser) as Box<_>, get_parser_by_name);
+        Some(Box::new(DiffParser::new(inner)))
+    } else if let Some(pattern) = cli.parser_regex {
+        Some(Box::new(CustomRegexParser::new(&pattern)?))
+    } else if json_fields.iter().any(|f| f.is_some()) {
+        let mut map = JsonFieldMap::default();
+        if let Some(ref path) = cli.json_level_field {
+            map.level_paths.insert(0, split_dot_path(path));
+        }
+        if let Some(ref path) = cli.json_msg_field {
+            map.message_paths.insert(0, split_dot_path(path));
+        }
+        if let Some(ref path) = cli.json_ts_field {
+            map.timestamp_paths.insert(0, split_dot_path(path));
+        }
+        Some(Box::new(JsonParser::with_field_map(map)))
+    } else if let Some(pattern) = config.parser_regex.as_deref().filter(|_| !format_given) {
+        Some(Box::new(CustomRegexParser::new(pattern)?))
+    } else {
+        format_name.map(get_parser_by_name)
+    };
+
+    if cli.print {
+        let print_opts = PrintOpts {
+            grep: cli
+                .grep
+                .or_else(|| (!config.filter.is_empty()).then(|| config.filter.clone())),
+            min_level: cli.level,
+            json: cli.json,
+            since: tui_opts.since,
+        };
+        return run_print(rx, tagged, parser, print_opts).await;
+    }
+    run_tui(rx, info, parser, &config, tui_opts).await
+}
+
+/// Output options for --print.
+struct PrintOpts {
+    /// --grep: filter text, with the same syntax as the `/` prompt
+    grep: Option<String>,
+    /// --level: minimum level, as the 1-5 keys set it
+    min_level: Option<app::LogLevel>,
+    /// --json: JSON Lines instead of the raw lines
+    json: bool,
+    /// --since cutoff
+    since: Option<DateTime<Local>>,
+}
+
+/// Display options from the command line.
+struct TuiOpts {
+    /// Keep ANSI colour codes in raw lines and render them
+    keep_ansi: bool,
+    /// --since cutoff: older entries are dropped as they're parsed
+    since: Option<DateTime<Local>>,
+    /// Click to select, wheel to scroll (off with --no-mouse or without a tty)
+    mouse: bool,
+    /// --highlight patterns, colored from the `*` palette
+    highlights: Vec<String>,
+    /// --spill: where entries evicted from the buffer go
+    spill: Option<source::SpillFile>,
+    /// --batch-size / --adaptive-batch: lines drained from the channel per frame
+    batch_size: usize,
+    adaptive_batch: bool,
+}
+
+/// What a source hands to the TUI besides its line channel.
+struct SourceInfo {
+    name: String,
+    history: Option<source::History>,
+    flags: Option<source::FlagStore>,
+    /// Merged streams whose lines carry a source tag prefix to split off
+    tagged: bool,
+    /// Connection state of each host in a multi-host ssh session
+    hosts: Vec<Arc<AtomicBool>>,
+    /// Connection state of a remote source, for the header badge
+    conn: source::ConnStatus,
+    /// Pace of `logpulse replay`, paused and sped up from the TUI
+    replay: Option<source::ReplayControl>,
+    /// Snapshot reopened with --open
+    session: Option<session::Session>,
 }
 
-/// Parse SSH subcommand args: ssh user@host docker myapi [file] OR ssh user@host /path/to/file
+type SshSource = (
+    mpsc::UnboundedReceiver<String>,
+    String,
+    Option<source::History>,
+    Vec<Arc<AtomicBool>>,
+);
+
+/// Parse SSH subcommand args: ssh user@host [host2 ...] docker myapi [file]
+/// OR ssh user@host [host2 ...] /path/to/file
 async fn parse_ssh_args(
-    opts: source::SshOpts,
-    args: Vec<String>,
-) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
+    mut opts: source::SshOpts,
+    mut targets: Vec<String>,
+    mut args: Vec<String>,
+    src_opts: source::SourceOpts,
+    config: &Config,
+) -> Result<SshSource, Box<dyn std::error::Error>> {
+    // Leading args that aren't `docker` or a path are extra hosts
+    let host_count = args
+        .iter()
+        .take(args.len().saturating_sub(1))
+        .take_while(|a| *a != "docker" && !a.contains('/'))
+        .count();
+    targets.extend(args.drain(..host_count));
+
     if args.is_empty() {
         return Err("ssh requires additional arguments: docker <name> or /path/to/file".into());
     }
+    if args[0] == "docker" && args.len() < 2 {
+        return Err("usage: logpulse ssh <target> docker <container> [file]".into());
+    }
 
-    if args[0] == "docker" {
-        if args.len() < 2 {
-            return Err("usage: logpulse ssh <target> docker <container> [file]".into());
+    if targets.is_empty() {
+        if let Some(defaults) = config.ssh_defaults(&opts.target) {
+            defaults.apply(&mut opts);
+        }
+        let (rx, name, history) = start_ssh_target(opts, &args, src_opts).await?;
+        return Ok((rx, name, history, Vec::new()));
+    }
+
+    // Several hosts: one ssh per host, merged and tagged by short hostname.
+    // They start side by side; a host that fails to start is reported
+    // inline and the others keep going.
+    let mut all = vec![opts.clone()];
+    all.extend(targets.into_iter().map(|t| opts.with_target(t)));
+    let mut starts = Vec::new();
+    let mut states = Vec::new();
+    for mut host_opts in all {
+        if let Some(defaults) = config.ssh_defaults(&host_opts.target) {
+            defaults.apply(&mut host_opts);
         }
+        let tag = source::short_host(&host_opts.target).to_string();
+        states.push(host_opts.connected.clone());
+        // Each host reports through `connected`, counted in the header
+        let host_src_opts = source::SourceOpts {
+            conn: source::ConnStatus::default(),
+            ..src_opts.clone()
+        };
+        let args = args.clone();
+        let start = tokio::spawn(async move {
+            start_ssh_target(host_opts, &args, host_src_opts)
+                .await
+                .map(|(rx, _, _)| rx)
+                .map_err(|e| e.to_string())
+        });
+        starts.push((tag, start));
+    }
+    let mut streams = Vec::new();
+    for (tag, start) in starts {
+        let started = start.await.unwrap_or_else(|e| Err(e.to_string()));
+        let rx = started.unwrap_or_else(|e| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            let _ = tx.send(format!(">>> {}", e));
+            rx
+        });
+        streams.push((tag, rx));
+    }
+    let names: Vec<&str> = streams.iter().map(|(tag, _)| tag.as_str()).collect();
+    let name = format!("{}:{}", names.join(","), args.join(" "));
+    Ok((source::merge_tagged_streams(streams), name, None, states))
+}
+
+/// Start one ssh source from `docker <prefix> [file]` or `/path/to/file`.
+async fn start_ssh_target(
+    opts: source::SshOpts,
+    args: &[String],
+    src_opts: source::SourceOpts,
+) -> Result<source::SourceWithHistory, Box<dyn std::error::Error>> {
+    opts.check()?;
+    if args[0] == "docker" {
         let prefix = args[1].clone();
         let file = args.get(2).cloned();
-        source::start_ssh_docker_source(opts, prefix, file).await
+        source::start_ssh_docker_source(opts, prefix, file, src_opts).await
     } else {
-        source::start_ssh_file_source(opts, args[0].clone()).await
+        source::start_ssh_file_source(opts, args[0].clone(), src_opts).await
+    }
+}
+
+/// `--level` value parser: a level name as `level:NAME+` takes it.
+fn parse_level(value: &str) -> Result<app::LogLevel, String> {
+    app::LogLevel::from_name(value).ok_or_else(|| {
+        format!(
+            "'{}' is not a level (trace, debug, info, warn, error, fatal)",
+            value
+        )
+    })
+}
+
+/// Mouse capture needs a terminal on stdout and can be turned off with --no-mouse.
+fn mouse_enabled(no_mouse: bool) -> bool {
+    !no_mouse && atty::is(atty::Stream::Stdout)
+}
+
+/// `--highlight` value parser: the pattern must be a valid regex.
+fn parse_highlight(value: &str) -> Result<String, String> {
+    regex::Regex::new(value).map_err(|e| e.to_string())?;
+    Ok(value.to_string())
+}
+
+/// `--eps-window` value parser: a number of seconds within `EPS_WINDOW_RANGE`.
+fn parse_eps_window(value: &str) -> Result<usize, String> {
+    let secs = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number of seconds", value))?;
+    config::check_eps_window(secs)
+}
+
+/// `--speed` value parser: a replay speed within `REPLAY_SPEED_RANGE`.
+fn parse_replay_speed(value: &str) -> Result<f32, String> {
+    let speed: f32 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    let range = source::REPLAY_SPEED_RANGE;
+    if !range.contains(&speed) {
+        return Err(format!(
+            "must be between {} and {}",
+            range.start(),
+            range.end()
+        ));
     }
+    Ok(speed)
+}
+
+/// Split the source tag prefix off a line from a tagged (merged) stream.
+fn split_tag(line: &str, tagged: bool) -> (Option<&str>, &str) {
+    if tagged {
+        if let Some((tag, body)) = source::split_source_tag(line) {
+            return (Some(tag), body);
+        }
+    }
+    (None, line)
+}
+
+/// Parse one line from the channel, recording its tag as the entry's source.
+/// Parsers see the line without ANSI codes; with `keep_ansi` the raw line
+/// keeps them for display.
+fn parse_line(parser: &dyn LogParser, line: &str, tagged: bool, keep_ansi: bool) -> app::LogEntry {
+    let (tag, body) = split_tag(line, tagged);
+    let mut entry = parser.parse_from(tag, &parser::strip_ansi(body));
+    entry.source = tag.map(String::from);
+    if keep_ansi {
+        entry.raw = body.to_string();
+    }
+    entry
+}
+
+/// Where --adaptive-batch starts, and the least it shrinks back to
+const ADAPTIVE_BATCH_MIN: usize = 50;
+
+/// How many queued lines one loop turn parses, so the UI stays responsive.
+/// Fixed at --batch-size, or with --adaptive-batch starting small and
+/// doubling while lines pile up.
+struct DrainBatch {
+    size: usize,
+    max: usize,
+    adaptive: bool,
+}
+
+impl DrainBatch {
+    fn new(max: usize, adaptive: bool) -> Self {
+        let size = if adaptive {
+            ADAPTIVE_BATCH_MIN.min(max)
+        } else {
+            max
+        };
+        Self {
+            size,
+            max,
+            adaptive,
+        }
+    }
+
+    /// After a turn: double while more than ten batches are still queued,
+    /// halve once the queue has drained.
+    fn adjust(&mut self, queued: usize) {
+        if !self.adaptive {
+            return;
+        }
+        if queued > 10 * self.size {
+            self.size = (self.size * 2).min(self.max);
+        } else if queued == 0 {
+            self.size = (self.size / 2).max(ADAPTIVE_BATCH_MIN.min(self.max));
+        }
+    }
+}
+
+/// Batches at least this long are parsed on several threads; below it the
+/// threads cost more than they save. Four times the default --batch-size, so
+/// only a raised or adaptive batch catching up on a flood goes parallel, and
+/// each thread gets at least half of it.
+const PARALLEL_PARSE_THRESHOLD: usize = 2000;
+
+/// Parse `lines` into the app in order. A big batch for a stateless parser is
+/// parsed on scoped threads first; otherwise each line goes in as soon as it's
+/// parsed, since a parser that groups records itself answers
+/// `starts_new_entry` for the line it parsed last.
+fn add_batch(
+    app: &mut App,
+    parser: &dyn LogParser,
+    lines: &[String],
+    tagged: bool,
+    keep_ansi: bool,
+) {
+    let threads = parse_threads(parser, lines.len());
+    if threads < 2 {
+        for line in lines {
+            app.add_parsed_log(parse_line(parser, line, tagged, keep_ansi), parser);
+        }
+        return;
+    }
+    for entry in parse_batch(parser, lines, threads, tagged, keep_ansi) {
+        app.add_parsed_log(entry, parser);
+    }
+}
+
+/// How many threads a batch of `lines` is parsed on: one unless the batch is
+/// big and the parser keeps no state between lines.
+fn parse_threads(parser: &dyn LogParser, lines: usize) -> usize {
+    if lines < PARALLEL_PARSE_THRESHOLD || !parser.is_stateless() {
+        return 1;
+    }
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    cores.min(lines / (PARALLEL_PARSE_THRESHOLD / 2))
+}
+
+/// Parse `lines` across `threads` scoped threads, keeping their order.
+fn parse_batch(
+    parser: &dyn LogParser,
+    lines: &[String],
+    threads: usize,
+    tagged: bool,
+    keep_ansi: bool,
+) -> Vec<app::LogEntry> {
+    let chunk = lines.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = lines
+            .chunks(chunk)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|line| parse_line(parser, line, tagged, keep_ansi))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("parser thread panicked"))
+            .collect()
+    })
+}
+
+/// Score the formats against the first lines when no format was forced.
+fn detect_from_sample(lines: &[String], tagged: bool) -> Detection {
+    let sample_refs: Vec<&str> = lines
+        .iter()
+        .map(|s| split_tag(s, tagged).1)
+        .take(20)
+        .collect();
+    detect_format(&sample_refs)
+}
+
+/// Parse the lines read before the first draw into the buffer, marking where
+/// live lines begin (static sources have none).
+fn add_backlog(
+    app: &mut App,
+    parser: &dyn LogParser,
+    lines: &[String],
+    tagged: bool,
+    keep_ansi: bool,
+    live: bool,
+) {
+    add_batch(app, parser, lines, tagged, keep_ansi);
+    if !lines.is_empty() && live {
+        app.insert_divider("live");
+    }
+}
+
+fn prepend_history(app: &mut App, parser: &dyn LogParser, raw_lines: &[String], keep_ansi: bool) {
+    let entries: Vec<_> = raw_lines
+        .iter()
+        .map(|line| parse_line(parser, line, false, keep_ansi))
+        .collect();
+    app.prepend_logs(entries);
 }
 
 async fn run_tui(
     mut rx: mpsc::UnboundedReceiver<String>,
-    name: String,
-    format_override: Option<&str>,
-    history: Option<source::FileHistory>,
+    info: SourceInfo,
+    parser_override: Option<Box<dyn LogParser>>,
+    config: &Config,
+    tui_opts: TuiOpts,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let tagged = info.tagged;
+    let keep_ansi = tui_opts.keep_ansi;
+    let mut drain = DrainBatch::new(tui_opts.batch_size, tui_opts.adaptive_batch);
     let mut initial_lines: Vec<String> = Vec::new();
     while let Ok(line) = rx.try_recv() {
         initial_lines.push(line);
     }
 
-    let detected_parser: Box<dyn LogParser> = if let Some(fmt) = format_override {
-        get_parser_by_name(fmt)
-    } else {
-        let sample_refs: Vec<&str> = initial_lines.iter().map(|s| s.as_str()).take(20).collect();
-        if sample_refs.is_empty() {
-            Box::new(PlainParser)
-        } else {
-            detect_parser(&sample_refs)
+    // An unsure detection asks first; the backlog waits for the answer
+    let mut format_choices: Vec<Box<dyn LogParser>> = Vec::new();
+    let mut format_labels: Vec<String> = Vec::new();
+    let mut detected_parser = match parser_override {
+        Some(parser) => parser,
+        None => {
+            let detection = detect_from_sample(&initial_lines, tagged);
+            if detection.is_ambiguous() {
+                let sample_len = detection.sample_len;
+                for (parser, score) in detection.candidates {
+                    let label = format!("{:<14} {}/{} lines", parser.name(), score, sample_len);
+                    format_labels.push(label);
+                    format_choices.push(parser);
+                }
+                format_labels.push(format!("{:<14} lines as they are", "Plain"));
+                format_choices.push(Box::new(PlainParser));
+                // Stands in until the picker's answer replaces it
+                Box::new(PlainParser)
+            } else {
+                let parser = detection.into_parser();
+                eprintln!("Format: {}", parser.name());
+                parser
+            }
         }
     };
 
-    eprintln!("Format: {}", detected_parser.name());
+    let (keymap, warnings) = KeyMap::from_config(&config.keybindings);
+    for warning in warnings {
+        eprintln!("logpulse: {}", warning);
+    }
+    let history_path = history_path();
+    let prompt_history = match history_path.as_deref().map(PromptHistories::load) {
+        Some(Ok(history)) => history,
+        Some(Err(e)) => {
+            eprintln!("logpulse: ignoring prompt history: {}", e);
+            PromptHistories::default()
+        }
+        None => PromptHistories::default(),
+    };
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
+    if tui_opts.mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new(name);
-    app.history = history;
+    let mut app = App::new(info.name);
+    app.apply_config(config);
+    app.keymap = keymap;
+    app.prompt_history = prompt_history;
+    app.history = info.history;
+    if let Some(spill) = tui_opts.spill {
+        app.enable_spill(spill);
+    }
+    app.flag_store = info.flags;
+    app.hosts = info.hosts;
+    app.conn = info.conn;
+    app.replay = info.replay;
+    app.keep_ansi = keep_ansi;
+    app.since = tui_opts.since;
+    for pattern in &tui_opts.highlights {
+        app.add_highlight(pattern, None);
+    }
+    app.format_name = detected_parser.name().to_string();
+    if let Some(session) = info.session {
+        app.load_session(session);
+    }
 
-    for line in &initial_lines {
-        let entry = detected_parser.parse(line);
-        app.add_log(entry);
+    if format_choices.is_empty() {
+        let live = !rx.is_closed();
+        add_backlog(
+            &mut app,
+            detected_parser.as_ref(),
+            &initial_lines,
+            tagged,
+            keep_ansi,
+            live,
+        );
+        initial_lines = Vec::new();
+    } else {
+        app.format_choices = format_labels;
+        app.view_mode = ViewMode::FormatPicker;
     }
-    drop(initial_lines);
 
     loop {
         terminal.draw(|frame| ui::draw(frame, &app))?;
+        if app.auto_fit && app.is_following() {
+            app.fit_horizontal_scroll();
+        } else {
+            app.clamp_horizontal_scroll();
+        }
 
         if event::handle_events(&mut app)? {
             break;
@@ -301,40 +1189,310 @@ async fn run_tui(
             break;
         }
 
-        // Lazy history: load older lines when user scrolls to top
+        // The picker's answer: parse the held-back backlog with it
+        if let Some(choice) = app.format_picked.take() {
+            detected_parser = format_choices.swap_remove(choice);
+            format_choices.clear();
+            app.format_name = detected_parser.name().to_string();
+            let live = !rx.is_closed();
+            add_backlog(
+                &mut app,
+                detected_parser.as_ref(),
+                &initial_lines,
+                tagged,
+                keep_ansi,
+                live,
+            );
+            initial_lines = Vec::new();
+        }
+
+        // Lazy history: load older lines when user scrolls to top.
+        // Remote files are fetched in the background and picked up below.
         if app.needs_history_load {
             app.needs_history_load = false;
-            if let Some(ref mut hist) = app.history {
-                let raw_lines = hist.load_older(app::HISTORY_CHUNK);
-                let entries: Vec<_> = raw_lines
-                    .iter()
-                    .map(|line| detected_parser.parse(line))
-                    .collect();
-                app.prepend_logs(entries);
+            let loaded = app
+                .history
+                .as_mut()
+                .and_then(|hist| hist.load_older(app::HISTORY_CHUNK));
+            if let Some(raw_lines) = loaded {
+                prepend_history(&mut app, detected_parser.as_ref(), &raw_lines, keep_ansi);
+            } else if app.history.is_some() {
+                app.set_status("Loading older lines...".to_string());
             }
         }
+        if let Some(raw_lines) = app.history.as_mut().and_then(source::History::take_loaded) {
+            prepend_history(&mut app, detected_parser.as_ref(), &raw_lines, keep_ansi);
+        }
 
         // Drain available lines in batches to keep UI responsive.
-        // When frozen, leave lines in the channel (don't lose them).
-        if !app.frozen {
-            let mut drained = 0;
-            while let Ok(line) = rx.try_recv() {
-                let entry = detected_parser.parse(&line);
-                app.add_log(entry);
-                drained += 1;
-                if drained >= 5000 {
-                    break;
-                }
-            }
+        // Paused too: the ring buffer bounds memory, the view stays pinned.
+        // While the format picker is open they wait in the channel.
+        let mut batch = Vec::new();
+        while format_choices.is_empty() && batch.len() < drain.size {
+            let Ok(line) = rx.try_recv() else {
+                break;
+            };
+            batch.push(line);
         }
+        let parser = detected_parser.as_ref();
+        add_batch(&mut app, parser, &batch, tagged, keep_ansi);
+        drain.adjust(rx.len());
 
+        app.update_dedup_counts();
+        app.update_groups();
         app.tick_eps();
         app.clear_expired_status();
     }
 
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    if tui_opts.mouse {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    execute!(
+        terminal.backend_mut(),
+        DisableBracketedPaste,
+        LeaveAlternateScreen
+    )?;
     terminal.show_cursor()?;
 
+    if let Some(path) = history_path {
+        if let Err(e) = app.prompt_history.save(&path) {
+            eprintln!("logpulse: couldn't save prompt history: {}", e);
+        }
+    }
+    if app.eps_window_secs != config.eps_window_secs {
+        if let Err(e) = config::save_eps_window(config, app.eps_window_secs) {
+            eprintln!("logpulse: couldn't save the activity window: {}", e);
+        }
+    }
+
     Ok(())
 }
+
+/// How long --print waits for the lines it detects the format from.
+const PRINT_SAMPLE_WAIT: Duration = Duration::from_millis(500);
+
+/// --print: parse, group and filter like the feed does, writing each entry
+/// to stdout once the next one starts. Returns when the source ends.
+async fn run_print(
+    mut rx: mpsc::UnboundedReceiver<String>,
+    tagged: bool,
+    parser_override: Option<Box<dyn LogParser>>,
+    opts: PrintOpts,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let deadline = tokio::time::Instant::now() + PRINT_SAMPLE_WAIT;
+    let mut initial_lines: Vec<String> = Vec::new();
+    while initial_lines.len() < 20 {
+        match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Some(line)) => initial_lines.push(line),
+            _ => break,
+        }
+    }
+    let parser =
+        parser_override.unwrap_or_else(|| detect_from_sample(&initial_lines, tagged).into_parser());
+
+    // A headless App: continuation lines join their entry, the filters apply
+    let mut app = App::new(String::new());
+    app.since = opts.since;
+    app.min_level = opts.min_level;
+    if let Some(grep) = opts.grep {
+        app.filter_text = grep;
+        app.update_filter_regex();
+    }
+
+    let mut out = io::BufWriter::new(io::stdout());
+    let mut initial_lines = initial_lines.into_iter();
+    loop {
+        let line = match initial_lines.next() {
+            Some(line) => line,
+            None => match rx.try_recv() {
+                Ok(line) => line,
+                Err(_) => {
+                    // Caught up with a live source: show what we have
+                    if let Err(e) = io::Write::flush(&mut out) {
+                        return print_result(e);
+                    }
+                    match rx.recv().await {
+                        Some(line) => line,
+                        None => break,
+                    }
+                }
+            },
+        };
+        app.add_parsed_log(
+            parse_line(parser.as_ref(), &line, tagged, false),
+            parser.as_ref(),
+        );
+        // The entry before the newest one can't gain continuation lines any more
+        while app.logs.len() > 1 {
+            let entry = app.logs.pop_front().expect("len > 1");
+            if let Err(e) = print_entry(&mut out, &mut io::stderr(), &app, &entry, opts.json) {
+                return print_result(e);
+            }
+        }
+    }
+    if let Some(entry) = app.logs.pop_front() {
+        if let Err(e) = print_entry(&mut out, &mut io::stderr(), &app, &entry, opts.json) {
+            return print_result(e);
+        }
+    }
+    io::Write::flush(&mut out).or_else(print_result)
+}
+
+/// Write one entry if it passes the filters: the raw lines, or a JSON line.
+/// Lines from a merged stream keep their source tag in front, as in the TUI;
+/// `>>>` notices from the source go to `notices` (stderr) instead.
+fn print_entry(
+    out: &mut impl io::Write,
+    notices: &mut impl io::Write,
+    app: &App,
+    entry: &app::LogEntry,
+    json: bool,
+) -> io::Result<()> {
+    if entry.raw.starts_with(">>> ") {
+        let prefix = entry.source.as_deref().map(|s| format!("{} ", s));
+        return writeln!(notices, "{}{}", prefix.unwrap_or_default(), entry.raw);
+    }
+    if !app.passes_filters(entry) {
+        return Ok(());
+    }
+    if json {
+        serde_json::to_writer(&mut *out, &entry.to_json())?;
+        return writeln!(out);
+    }
+    let prefix = entry.source.as_deref().map(|s| format!("{} ", s));
+    let prefix = prefix.as_deref().unwrap_or("");
+    writeln!(out, "{}{}", prefix, entry.raw)?;
+    for extra in &entry.extra_lines {
+        writeln!(out, "{}{}", prefix, extra)?;
+    }
+    Ok(())
+}
+
+/// A closed pipe (`| head`) ends --print quietly; other write errors are reported.
+fn print_result(e: io::Error) -> Result<(), Box<dyn std::error::Error>> {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        Ok(())
+    } else {
+        Err(e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{CriParser, JsonParser, LaravelParser, PlainParser};
+
+    #[test]
+    fn print_tags_lines_and_sends_notices_to_stderr() {
+        let app = App::new(String::new());
+        let mut entry = PlainParser.parse("INFO started");
+        entry.source = Some("app.log".to_string());
+        entry.extra_lines.push("  more".to_string());
+        let mut notice = PlainParser.parse(">>> log rotated: app.log");
+        notice.source = Some("app.log".to_string());
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        print_entry(&mut out, &mut err, &app, &entry, false).unwrap();
+        print_entry(&mut out, &mut err, &app, &notice, false).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "app.log INFO started\napp.log   more\n"
+        );
+        assert_eq!(
+            String::from_utf8(err).unwrap(),
+            "app.log >>> log rotated: app.log\n"
+        );
+    }
+
+    #[test]
+    fn print_config_is_a_subcommand_and_a_flag() {
+        let cli = Cli::try_parse_from(["logpulse", "--theme", "light", "print-config"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::PrintConfig)));
+        assert_eq!(cli.theme.as_deref(), Some("light"));
+        assert!(
+            Cli::try_parse_from(["logpulse", "--print-config"])
+                .unwrap()
+                .print_config
+        );
+    }
+
+    #[test]
+    fn adaptive_batch_grows_under_load_and_shrinks_when_idle() {
+        let mut drain = DrainBatch::new(500, true);
+        assert_eq!(drain.size, ADAPTIVE_BATCH_MIN);
+        // Ten batches' worth queued isn't a backlog yet
+        drain.adjust(10 * ADAPTIVE_BATCH_MIN);
+        assert_eq!(drain.size, 50);
+        drain.adjust(10 * ADAPTIVE_BATCH_MIN + 1);
+        assert_eq!(drain.size, 100);
+        for _ in 0..5 {
+            drain.adjust(100_000);
+        }
+        assert_eq!(drain.size, 500, "capped at --batch-size");
+        drain.adjust(1);
+        assert_eq!(drain.size, 500, "a short queue keeps the size");
+        for _ in 0..5 {
+            drain.adjust(0);
+        }
+        assert_eq!(drain.size, ADAPTIVE_BATCH_MIN);
+
+        let mut small = DrainBatch::new(20, true);
+        assert_eq!(small.size, 20, "never starts above the maximum");
+        small.adjust(0);
+        assert_eq!(small.size, 20);
+
+        let mut fixed = DrainBatch::new(500, false);
+        fixed.adjust(100_000);
+        fixed.adjust(0);
+        assert_eq!(fixed.size, 500);
+    }
+
+    #[test]
+    fn ansi_codes_are_stripped_before_parsing() {
+        let line = "[2024-01-15 10:30:01] production.\x1b[1;31mERROR\x1b[0m: Connection refused";
+        let entry = parse_line(&LaravelParser, line, false, false);
+        assert_eq!(entry.level, app::LogLevel::Error);
+        assert_eq!(entry.message.as_deref(), Some("Connection refused"));
+        assert!(!entry.raw.contains('\x1b'));
+        assert_eq!(parse_line(&LaravelParser, line, false, true).raw, line);
+    }
+
+    #[test]
+    fn stateful_parsers_parse_big_batches_in_order() {
+        let cri = CriParser::default();
+        let lines: Vec<String> = (0..PARALLEL_PARSE_THRESHOLD * 2)
+            .map(|i| {
+                let (kind, half) = if i % 2 == 0 {
+                    ("P", "head")
+                } else {
+                    ("F", "tail")
+                };
+                format!("2024-01-15T10:30:01Z stdout {} {} {}", kind, half, i / 2)
+            })
+            .collect();
+        assert_eq!(parse_threads(&cri, lines.len()), 1);
+
+        let mut app = App::new("pod.log".to_string());
+        add_batch(&mut app, &cri, &lines, false, false);
+        assert_eq!(app.logs.len(), PARALLEL_PARSE_THRESHOLD);
+        assert_eq!(app.logs[7].message.as_deref(), Some("head 7"));
+        assert_eq!(app.logs[7].extra_lines, vec!["tail 7"]);
+    }
+
+    #[test]
+    fn parallel_parse_keeps_line_order() {
+        let json = JsonParser::default();
+        let lines: Vec<String> = (0..PARALLEL_PARSE_THRESHOLD)
+            .map(|i| format!(r#"{{"level":"info","msg":"line {}"}}"#, i))
+            .collect();
+        // A default-sized batch stays on this thread
+        assert_eq!(parse_threads(&json, 500), 1);
+        assert!(!parser::DiffParser::new(Box::new(CriParser::default())).is_stateless());
+        let entries = parse_batch(&json, &lines, 4, false, false);
+        assert_eq!(entries.len(), lines.len());
+        assert!(entries
+            .iter()
+            .enumerate()
+            .all(|(i, e)| e.message.as_deref() == Some(format!("line {}", i).as_str())));
+    }
+}
@@ -1,5 +1,9 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
 use regex::Regex;
-use std::sync::LazyLock;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex, OnceLock};
 
 use crate::app::{LogEntry, LogLevel};
 
@@ -7,6 +11,65 @@ pub trait LogParser: Send + Sync {
     fn name(&self) -> &str;
     fn can_parse(&self, line: &str) -> bool;
     fn parse(&self, line: &str) -> LogEntry;
+
+    /// Parse a line from the stream `source` of a merged feed. Parsers that
+    /// carry state from one line to the next keep it per source, so streams
+    /// interleaved line by line don't continue each other's records.
+    fn parse_from(&self, _source: Option<&str>, line: &str) -> LogEntry {
+        self.parse(line)
+    }
+
+    /// Whether `line` begins an entry of its own rather than continuing the
+    /// one before it (a stack frame, a pretty-printed body): by default, a
+    /// line in this format or one that leads with a timestamp. Parsers that
+    /// group records themselves answer for the line they parsed last, so ask
+    /// right after `parse`.
+    fn starts_new_entry(&self, line: &str) -> bool {
+        self.can_parse(line) || starts_with_timestamp(line)
+    }
+
+    /// Whether `parse` carries nothing from one line to the next, so a big
+    /// batch can be parsed on several threads at once. Parsers that keep
+    /// state between lines say no.
+    fn is_stateless(&self) -> bool {
+        true
+    }
+}
+
+static ANSI_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap());
+
+/// Remove ANSI escape sequences (colours, cursor movement) from a line.
+pub fn strip_ansi(s: &str) -> Cow<'_, str> {
+    if s.contains('\x1b') {
+        ANSI_RE.replace_all(s, "")
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+static LEADING_TS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\[?(\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}|\d{4}/\d{2}/\d{2} \d{2}:\d{2}:\d{2}|\d{2}/[A-Z][a-z]{2}/\d{4}[: ]\d{2}:\d{2}:\d{2}|[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2})",
+    )
+    .unwrap()
+});
+
+/// Whether `line` opens with a date and time (ISO 8601, Go's `2024/01/15`,
+/// Apache's `15/Jan/2024:`, syslog's `Jan 15`), optionally in brackets.
+pub fn starts_with_timestamp(line: &str) -> bool {
+    LEADING_TS_RE.is_match(line)
+}
+
+/// The state `source` left behind in a stateful parser, fresh for a new one.
+fn source_state<'a, T: Default>(
+    states: &'a mut HashMap<String, T>,
+    source: Option<&str>,
+) -> &'a mut T {
+    let key = source.unwrap_or("");
+    if !states.contains_key(key) {
+        states.insert(key.to_string(), T::default());
+    }
+    states.get_mut(key).expect("just inserted")
 }
 
 /// Case-insensitive substring check without allocating a new String.
@@ -21,7 +84,35 @@ fn contains_ci(haystack: &str, needle: &str) -> bool {
         .any(|window| window.eq_ignore_ascii_case(needle_bytes))
 }
 
+/// Words from the config's `[levels]` table, set once at startup.
+static LEVEL_KEYWORDS: OnceLock<Vec<(String, LogLevel)>> = OnceLock::new();
+
+/// Use `keywords` as extra level words, checked before the built-in ones.
+pub fn set_level_keywords(keywords: Vec<(String, LogLevel)>) {
+    let _ = LEVEL_KEYWORDS.set(keywords);
+}
+
+/// The level of the first of `keywords` found in `text` as a whole word
+/// (case-insensitive).
+fn keyword_level(text: &str, keywords: &[(String, LogLevel)]) -> Option<LogLevel> {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| !w.is_empty())
+        .collect();
+    keywords
+        .iter()
+        .find(|(keyword, _)| words.iter().any(|w| w.eq_ignore_ascii_case(keyword)))
+        .map(|(_, level)| *level)
+}
+
 fn detect_level(text: &str) -> LogLevel {
+    if let Some(level) = LEVEL_KEYWORDS
+        .get()
+        .filter(|keywords| !keywords.is_empty())
+        .and_then(|keywords| keyword_level(text, keywords))
+    {
+        return level;
+    }
     if contains_ci(text, "FATAL") || contains_ci(text, "EMERGENCY") || contains_ci(text, "CRITICAL")
     {
         LogLevel::Fatal
@@ -40,13 +131,132 @@ fn detect_level(text: &str) -> LogLevel {
     }
 }
 
+/// Parse the timestamp formats emitted by the built-in parsers into local time.
+/// Zone-less timestamps are assumed to be in the local timezone.
+pub fn parse_timestamp(ts: &str) -> Option<DateTime<Local>> {
+    let ts = ts.trim();
+    let ts = ts.strip_prefix("time=").unwrap_or(ts);
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(ts) {
+        return Some(dt.with_timezone(&Local));
+    }
+    // Zap's ISO 8601 encoder: 2024-01-15T10:30:01.123+0100
+    if let Ok(dt) = DateTime::parse_from_str(ts, "%Y-%m-%dT%H:%M:%S%.f%z") {
+        return Some(dt.with_timezone(&Local));
+    }
+    // Nginx/Apache: 15/Jan/2024:10:30:07 +0000
+    if let Ok(dt) = DateTime::parse_from_str(ts, "%d/%b/%Y:%H:%M:%S %z") {
+        return Some(dt.with_timezone(&Local));
+    }
+    for fmt in [
+        "%Y-%m-%d %H:%M:%S%.f", // Laravel
+        "%Y-%m-%dT%H:%M:%S%.f", // ISO 8601 without offset
+        "%d/%b/%Y %H:%M:%S",    // Django
+        "%Y/%m/%d %H:%M:%S",    // Go standard log
+    ] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(ts, fmt) {
+            return Local.from_local_datetime(&naive).earliest();
+        }
+    }
+    parse_epoch(ts)
+}
+
+/// Unix epoch in seconds, milliseconds, microseconds or nanoseconds, picked
+/// by magnitude (`1705314601`, `1705314601.123`, `1705314601123`).
+fn parse_epoch(ts: &str) -> Option<DateTime<Local>> {
+    let (int, frac) = ts.split_once('.').unwrap_or((ts, ""));
+    if !(10..=19).contains(&int.len())
+        || !int.bytes().all(|b| b.is_ascii_digit())
+        || !frac.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let value: f64 = ts.parse().ok()?;
+    let nanos = match int.len() {
+        10..=12 => value * 1e9,
+        13..=15 => value * 1e6,
+        16..=18 => value * 1e3,
+        _ => value,
+    };
+    Some(DateTime::from_timestamp_nanos(nanos as i64).with_timezone(&Local))
+}
+
 // --- Generic JSON Parser ---
-pub struct JsonParser;
+#[derive(Default)]
+pub struct JsonParser {
+    pub field_map: JsonFieldMap,
+}
+
+/// Where JsonParser finds its fields: candidate paths tried in order, each
+/// a list of keys into nested objects (`log.level` -> `["log", "level"]`).
+pub struct JsonFieldMap {
+    pub level_paths: Vec<Vec<String>>,
+    pub message_paths: Vec<Vec<String>>,
+    pub timestamp_paths: Vec<Vec<String>>,
+}
+
+impl Default for JsonFieldMap {
+    fn default() -> Self {
+        let paths = |keys: &[&str]| keys.iter().map(|k| split_dot_path(k)).collect();
+        Self {
+            level_paths: paths(JSON_LEVEL_KEYS),
+            message_paths: paths(JSON_MSG_KEYS),
+            timestamp_paths: paths(JSON_TS_KEYS),
+        }
+    }
+}
 
-static JSON_LEVEL_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r#""(?:level|severity|lvl)"\s*:\s*"([^"]+)""#).unwrap());
+/// Split a dot-path from the command line: `log.level` -> `["log", "level"]`.
+pub fn split_dot_path(path: &str) -> Vec<String> {
+    path.split('.').map(String::from).collect()
+}
+
+pub(crate) const JSON_LEVEL_KEYS: &[&str] = &["level", "severity", "lvl", "log.level", "loglevel"];
+pub(crate) const JSON_MSG_KEYS: &[&str] = &["msg", "message", "text", "@message"];
+pub(crate) const JSON_TS_KEYS: &[&str] = &["ts", "time", "timestamp", "@timestamp"];
+
+/// Follow `path` through nested objects. A flattened key such as
+/// `{"log.level": "info"}` (ECS) also matches `["log", "level"]`.
+pub fn walk_path<'a>(
+    value: &'a serde_json::Value,
+    path: &[String],
+) -> Option<&'a serde_json::Value> {
+    let Some((first, rest)) = path.split_first() else {
+        return Some(value);
+    };
+    if let Some(found) = value.get(first).and_then(|child| walk_path(child, rest)) {
+        return Some(found);
+    }
+    if rest.is_empty() {
+        return None;
+    }
+    value.get(path.join("."))
+}
+
+/// Text at the first of `paths` holding a string or number.
+fn json_field(value: &serde_json::Value, paths: &[Vec<String>]) -> Option<String> {
+    paths
+        .iter()
+        .filter_map(|path| walk_path(value, path))
+        .find_map(|found| match found {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+}
+
+impl JsonParser {
+    pub fn with_field_map(field_map: JsonFieldMap) -> Self {
+        Self { field_map }
+    }
+}
+
+// Fallback for lines that aren't valid JSON
+static JSON_LEVEL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#""(?:level|severity|lvl|log\.level|loglevel)"\s*:\s*"([^"]+)""#).unwrap()
+});
 static JSON_MSG_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r#""(?:msg|message|text)"\s*:\s*"([^"]+)""#).unwrap());
+    LazyLock::new(|| Regex::new(r#""(?:msg|message|text|@message)"\s*:\s*"([^"]+)""#).unwrap());
 
 impl LogParser for JsonParser {
     fn name(&self) -> &str {
@@ -59,20 +269,199 @@ impl LogParser for JsonParser {
     }
 
     fn parse(&self, line: &str) -> LogEntry {
-        let level = JSON_LEVEL_RE
-            .captures(line)
-            .map(|c| detect_level(&c[1]))
-            .unwrap_or_else(|| detect_level(line));
-
-        let message = JSON_MSG_RE.captures(line).map(|c| c[1].to_string());
+        let (level, message, timestamp) = match serde_json::from_str(line.trim()) {
+            Ok(value) => {
+                let map = &self.field_map;
+                let level = json_field(&value, &map.level_paths)
+                    .map(|l| detect_level(&l))
+                    .unwrap_or_else(|| detect_level(line));
+                (
+                    level,
+                    json_field(&value, &map.message_paths),
+                    json_field(&value, &map.timestamp_paths),
+                )
+            }
+            // Truncated or otherwise broken JSON: pick out what we can
+            Err(_) => {
+                let level = JSON_LEVEL_RE
+                    .captures(line)
+                    .map(|c| detect_level(&c[1]))
+                    .unwrap_or_else(|| detect_level(line));
+                (
+                    level,
+                    JSON_MSG_RE.captures(line).map(|c| c[1].to_string()),
+                    None,
+                )
+            }
+        };
 
         LogEntry {
             raw: line.to_string(),
             level,
-            timestamp: None,
+            ts_parsed: timestamp.as_deref().and_then(parse_timestamp),
+            timestamp,
             message,
             metadata: Some(line.to_string()),
             extra_lines: Vec::new(),
+            source: None,
+            status: None,
+            divider: false,
+        }
+    }
+}
+
+// --- Serilog CLEF Parser ---
+// Compact Log Event Format: {"@t":"...","@l":"Warning","@mt":"Disk {Drive} low","Drive":"C:"}
+pub struct ClefParser;
+
+/// Serilog level names; a missing `@l` means Information.
+fn clef_level(level: Option<&str>) -> LogLevel {
+    match level {
+        None => LogLevel::Info,
+        Some(l) if l.eq_ignore_ascii_case("verbose") => LogLevel::Trace,
+        Some(l) => detect_level(l),
+    }
+}
+
+/// Fill the `{Property}` holes of a message template from the event's
+/// properties, keeping holes with no matching property as written.
+/// `{{`/`}}` are literal braces; `{@Obj}`/`{$Obj}` and `{Name:format}` are
+/// rendered as the plain value.
+fn render_template(template: &str, event: &serde_json::Value) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        let hole = tail
+            .strip_prefix('{')
+            .and_then(|t| t.find('}').map(|end| &t[..end]));
+        let Some(hole) = hole else {
+            out.push_str(&tail[..1]);
+            rest = &tail[1..];
+            continue;
+        };
+        let name = hole.trim_start_matches(['@', '$']);
+        let name = name.split([':', ',']).next().unwrap_or(name);
+        match event.get(name) {
+            Some(serde_json::Value::String(s)) => out.push_str(s),
+            Some(value) => out.push_str(&value.to_string()),
+            None => {
+                out.push('{');
+                out.push_str(hole);
+                out.push('}');
+            }
+        }
+        rest = &tail[hole.len() + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+impl LogParser for ClefParser {
+    fn name(&self) -> &str {
+        "CLEF"
+    }
+
+    fn can_parse(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        if !(trimmed.starts_with('{') && trimmed.ends_with('}')) {
+            return false;
+        }
+        serde_json::from_str::<serde_json::Value>(trimmed)
+            .is_ok_and(|v| v.get("@t").is_some() || v.get("@mt").is_some())
+    }
+
+    fn parse(&self, line: &str) -> LogEntry {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+            return fallback_parse(line);
+        };
+        let text = |key: &str| event.get(key).and_then(|v| v.as_str());
+
+        let timestamp = text("@t").map(String::from);
+        let message = text("@m")
+            .map(String::from)
+            .or_else(|| text("@mt").map(|t| render_template(t, &event)));
+        let extra_lines = text("@x")
+            .map(|x| x.lines().map(String::from).collect())
+            .unwrap_or_default();
+
+        LogEntry {
+            raw: line.to_string(),
+            level: clef_level(text("@l")),
+            ts_parsed: timestamp.as_deref().and_then(parse_timestamp),
+            timestamp,
+            message,
+            metadata: Some(line.to_string()),
+            extra_lines,
+            source: None,
+            status: None,
+            divider: false,
+        }
+    }
+}
+
+// --- MongoDB Parser ---
+// Structured logs of MongoDB 4.4+:
+// {"t":{"$date":"2024-01-15T10:30:01.123+00:00"},"s":"E","c":"NETWORK","id":12345,"ctx":"conn1","msg":"..."}
+pub struct MongoParser;
+
+/// MongoDB severities: F, E, W, I, and D1-D5 for the debug verbosities.
+fn mongo_level(severity: &str) -> LogLevel {
+    match severity {
+        "F" => LogLevel::Fatal,
+        "E" => LogLevel::Error,
+        "W" => LogLevel::Warn,
+        "I" => LogLevel::Info,
+        s if s.starts_with('D') => LogLevel::Debug,
+        _ => LogLevel::Unknown,
+    }
+}
+
+impl LogParser for MongoParser {
+    fn name(&self) -> &str {
+        "MongoDB"
+    }
+
+    fn can_parse(&self, line: &str) -> bool {
+        line.trim_start().starts_with(r#"{"t":{"$date""#)
+    }
+
+    fn parse(&self, line: &str) -> LogEntry {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+            return fallback_parse(line);
+        };
+        let timestamp = event
+            .pointer("/t/$date")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        // Component and connection, plus the attributes the message refers to
+        let mut metadata = serde_json::Map::new();
+        for (key, name) in [("c", "component"), ("ctx", "ctx"), ("attr", "attr")] {
+            if let Some(value) = event.get(key) {
+                metadata.insert(name.to_string(), value.clone());
+            }
+        }
+
+        LogEntry {
+            raw: line.to_string(),
+            level: event
+                .get("s")
+                .and_then(|v| v.as_str())
+                .map_or(LogLevel::Unknown, mongo_level),
+            ts_parsed: timestamp.as_deref().and_then(parse_timestamp),
+            timestamp,
+            message: event.get("msg").and_then(|v| v.as_str()).map(String::from),
+            metadata: Some(serde_json::Value::Object(metadata).to_string()),
+            extra_lines: Vec::new(),
+            source: None,
+            status: None,
+            divider: false,
         }
     }
 }
@@ -100,9 +489,13 @@ impl LogParser for LaravelParser {
                 raw: line.to_string(),
                 level: detect_level(&caps[2]),
                 timestamp: Some(caps[1].to_string()),
+                ts_parsed: parse_timestamp(&caps[1]),
                 message: Some(caps[3].to_string()),
                 metadata: None,
                 extra_lines: Vec::new(),
+                source: None,
+                status: None,
+                divider: false,
             }
         } else {
             fallback_parse(line)
@@ -133,9 +526,13 @@ impl LogParser for DjangoParser {
                 raw: line.to_string(),
                 level: detect_level(&caps[2]),
                 timestamp: Some(caps[1].to_string()),
+                ts_parsed: parse_timestamp(&caps[1]),
                 message: Some(caps[4].to_string()),
                 metadata: Some(caps[3].to_string()),
                 extra_lines: Vec::new(),
+                source: None,
+                status: None,
+                divider: false,
             }
         } else {
             fallback_parse(line)
@@ -168,9 +565,13 @@ impl LogParser for GoLogParser {
                 raw: line.to_string(),
                 level: detect_level(&caps[2]),
                 timestamp: Some(caps[1].to_string()),
+                ts_parsed: parse_timestamp(&caps[1]),
                 message: Some(caps[3].to_string()),
                 metadata: None,
                 extra_lines: Vec::new(),
+                source: None,
+                status: None,
+                divider: false,
             };
         }
         if let Some(caps) = GO_STD_RE.captures(line) {
@@ -178,15 +579,85 @@ impl LogParser for GoLogParser {
                 raw: line.to_string(),
                 level: detect_level(&caps[2]),
                 timestamp: Some(caps[1].to_string()),
+                ts_parsed: parse_timestamp(&caps[1]),
                 message: Some(caps[2].to_string()),
                 metadata: None,
                 extra_lines: Vec::new(),
+                source: None,
+                status: None,
+                divider: false,
             };
         }
         fallback_parse(line)
     }
 }
 
+// --- Zap (Uber) Console Parser ---
+// Format: 2024-01-15T10:30:01.123Z\tERROR\tpkg/file.go:42\tmessage\t{"key": "val"}
+// An optional logger name sits between level and caller. Lines from Zap's
+// JSON encoder are handed to the JSON parser.
+pub struct ZapParser;
+
+static ZAP_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:?\d{2})?)\t([A-Za-z]+)\t(?:([^\t]+)\t)?(\S+\.go:\d+)\t(.*)$",
+    )
+    .unwrap()
+});
+
+fn zap_level(level: &str) -> LogLevel {
+    match level.to_ascii_uppercase().as_str() {
+        "DPANIC" | "PANIC" | "FATAL" => LogLevel::Fatal,
+        _ => detect_level(level),
+    }
+}
+
+impl LogParser for ZapParser {
+    fn name(&self) -> &str {
+        "Zap"
+    }
+
+    fn can_parse(&self, line: &str) -> bool {
+        ZAP_RE.is_match(line)
+    }
+
+    fn parse(&self, line: &str) -> LogEntry {
+        if line.trim_start().starts_with('{') {
+            return JsonParser::default().parse(line);
+        }
+        let Some(caps) = ZAP_RE.captures(line) else {
+            return fallback_parse(line);
+        };
+        // The context object is the last tab-separated field, when it parses
+        let rest = &caps[5];
+        let (message, context) = match rest.rsplit_once('\t') {
+            Some((message, json)) => match serde_json::from_str(json) {
+                Ok(serde_json::Value::Object(map)) => (message, map),
+                _ => (rest, serde_json::Map::new()),
+            },
+            None => (rest, serde_json::Map::new()),
+        };
+        let mut metadata = serde_json::Map::new();
+        if let Some(logger) = caps.get(3) {
+            metadata.insert("logger".to_string(), logger.as_str().into());
+        }
+        metadata.insert("caller".to_string(), caps[4].into());
+        metadata.extend(context);
+        LogEntry {
+            raw: line.to_string(),
+            level: zap_level(&caps[2]),
+            timestamp: Some(caps[1].to_string()),
+            ts_parsed: parse_timestamp(&caps[1]),
+            message: Some(message.to_string()),
+            metadata: Some(serde_json::Value::Object(metadata).to_string()),
+            extra_lines: Vec::new(),
+            source: None,
+            status: None,
+            divider: false,
+        }
+    }
+}
+
 // --- Nginx/Apache Parser ---
 // Combined log format: IP - - [timestamp] "METHOD /path HTTP/x.x" status size
 pub struct NginxApacheParser;
@@ -218,9 +689,61 @@ impl LogParser for NginxApacheParser {
                 raw: line.to_string(),
                 level,
                 timestamp: Some(caps[2].to_string()),
+                ts_parsed: parse_timestamp(&caps[2]),
                 message: Some(format!("{} -> {}", &caps[3], status)),
                 metadata: Some(caps[1].to_string()),
                 extra_lines: Vec::new(),
+                source: None,
+                status: Some(status),
+                divider: false,
+            }
+        } else {
+            fallback_parse(line)
+        }
+    }
+}
+
+// --- Envoy/Istio Access Log Parser ---
+// Format: [2024-01-15T10:30:01.123Z] "GET /api HTTP/2" 503 UF 0 0 12 - "-" "curl" ...
+pub struct EnvoyParser;
+
+static ENVOY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^\[(\d{4}-\d{2}-\d{2}T[^\]]+)\]\s+"(\S+) (\S+)[^"]*"\s+(\d+)\s+(\S+)"#).unwrap()
+});
+
+impl LogParser for EnvoyParser {
+    fn name(&self) -> &str {
+        "Envoy"
+    }
+
+    fn can_parse(&self, line: &str) -> bool {
+        ENVOY_RE.is_match(line)
+    }
+
+    fn parse(&self, line: &str) -> LogEntry {
+        if let Some(caps) = ENVOY_RE.captures(line) {
+            let status: u16 = caps[4].parse().unwrap_or(0);
+            let flags = &caps[5];
+            let level = match status {
+                200..=299 => LogLevel::Info,
+                300..=399 => LogLevel::Debug,
+                400..=499 => LogLevel::Warn,
+                500..=599 => LogLevel::Error,
+                // 0 = no response at all, e.g. upstream connect failure
+                0 if flags != "-" => LogLevel::Error,
+                _ => LogLevel::Unknown,
+            };
+            LogEntry {
+                raw: line.to_string(),
+                level,
+                timestamp: Some(caps[1].to_string()),
+                ts_parsed: parse_timestamp(&caps[1]),
+                message: Some(format!("{} {} -> {}", &caps[2], &caps[3], status)),
+                metadata: (flags != "-").then(|| flags.to_string()),
+                extra_lines: Vec::new(),
+                source: None,
+                status: (status != 0).then_some(status),
+                divider: false,
             }
         } else {
             fallback_parse(line)
@@ -228,6 +751,287 @@ impl LogParser for NginxApacheParser {
     }
 }
 
+// --- Containerd/CRI Parser ---
+// Format: 2024-01-15T10:30:01.123456789Z stdout F message
+// `P` marks a partial line continued by the next record(s) until an `F`.
+#[derive(Default)]
+pub struct CriParser {
+    /// Per source: the previous record was a `P` fragment, so the next one
+    /// continues it
+    partial_pending: Mutex<HashMap<String, bool>>,
+    /// The last parsed record continued a fragment
+    continued: AtomicBool,
+}
+
+static CRI_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4}-\d{2}-\d{2}T\S+)\s+(stdout|stderr)\s+([FP])(?:\s(.*))?$").unwrap()
+});
+
+impl LogParser for CriParser {
+    fn name(&self) -> &str {
+        "CRI"
+    }
+
+    fn can_parse(&self, line: &str) -> bool {
+        CRI_RE.is_match(line)
+    }
+
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn parse(&self, line: &str) -> LogEntry {
+        self.parse_from(None, line)
+    }
+
+    fn parse_from(&self, source: Option<&str>, line: &str) -> LogEntry {
+        let mut states = self.partial_pending.lock().expect("CRI state");
+        let partial_pending = source_state(&mut states, source);
+        let Some(caps) = CRI_RE.captures(line) else {
+            *partial_pending = false;
+            self.continued.store(false, Ordering::Relaxed);
+            return fallback_parse(line);
+        };
+        let message = caps.get(4).map_or("", |m| m.as_str());
+        let is_partial = &caps[3] == "P";
+        let continues_previous = std::mem::replace(partial_pending, is_partial);
+        self.continued.store(continues_previous, Ordering::Relaxed);
+
+        if continues_previous {
+            return LogEntry {
+                raw: message.to_string(),
+                level: LogLevel::Unknown,
+                timestamp: None,
+                ts_parsed: None,
+                message: None,
+                metadata: None,
+                extra_lines: Vec::new(),
+                source: None,
+                status: None,
+                divider: false,
+            };
+        }
+
+        // Every CRI record is structured: default to Info, and lean stderr toward Warn
+        let level = match (detect_level(message), &caps[2]) {
+            (LogLevel::Unknown, "stderr") => LogLevel::Warn,
+            (LogLevel::Unknown, _) => LogLevel::Info,
+            (level, _) => level,
+        };
+        LogEntry {
+            raw: line.to_string(),
+            level,
+            timestamp: Some(caps[1].to_string()),
+            ts_parsed: parse_timestamp(&caps[1]),
+            message: Some(message.to_string()),
+            metadata: Some(caps[2].to_string()),
+            extra_lines: Vec::new(),
+            source: None,
+            status: None,
+            divider: false,
+        }
+    }
+
+    /// A record after a `P` fragment continues it, even when its text leads
+    /// with a timestamp.
+    fn starts_new_entry(&self, _line: &str) -> bool {
+        !self.continued.load(Ordering::Relaxed)
+    }
+}
+
+// --- MySQL/MariaDB Parser ---
+// Error log: 2024-01-15T10:30:01.123456Z 0 [ERROR] [MY-012345] [Server] message
+// Slow query log: a `# Time:` / `# User@Host:` header followed by `# Query_time:`
+// and the SQL, grouped into one entry.
+#[derive(Default)]
+pub struct MysqlParser {
+    /// Where each source is in the slow-query log
+    states: Mutex<HashMap<String, SlowLogState>>,
+    /// The last parsed line belonged to the slow-query block before it
+    continued: AtomicBool,
+}
+
+#[derive(Default)]
+struct SlowLogState {
+    /// Inside a slow-query block: following lines belong to its entry
+    in_slow_block: bool,
+    /// The previous line was `# Time:`, so `# User@Host:` continues that block
+    after_time_header: bool,
+}
+
+static MYSQL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4}-\d{2}-\d{2}T\S+)\s+(\d+)\s+\[(\w+)\](?:\s+\[(MY-\d+)\])?(?:\s+\[(\w+)\])?\s*(.*)$")
+        .unwrap()
+});
+
+impl MysqlParser {
+    fn slow_query_entry(state: &mut SlowLogState, line: &str, timestamp: Option<&str>) -> LogEntry {
+        state.in_slow_block = true;
+        LogEntry {
+            raw: line.to_string(),
+            level: LogLevel::Warn,
+            timestamp: timestamp.map(String::from),
+            ts_parsed: timestamp.and_then(parse_timestamp),
+            message: Some("Slow query".to_string()),
+            metadata: None,
+            extra_lines: Vec::new(),
+            source: None,
+            status: None,
+            divider: false,
+        }
+    }
+}
+
+impl LogParser for MysqlParser {
+    fn name(&self) -> &str {
+        "MySQL"
+    }
+
+    fn can_parse(&self, line: &str) -> bool {
+        MYSQL_RE.is_match(line)
+            || line.starts_with("# Time:")
+            || line.starts_with("# User@Host:")
+            || line.starts_with("# Query_time:")
+    }
+
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn parse(&self, line: &str) -> LogEntry {
+        self.parse_from(None, line)
+    }
+
+    fn parse_from(&self, source: Option<&str>, line: &str) -> LogEntry {
+        let mut states = self.states.lock().expect("MySQL state");
+        let state = source_state(&mut states, source);
+        let after_time_header = std::mem::take(&mut state.after_time_header);
+        self.continued.store(false, Ordering::Relaxed);
+
+        if let Some(ts) = line.strip_prefix("# Time:") {
+            state.after_time_header = true;
+            return Self::slow_query_entry(state, line, Some(ts.trim()));
+        }
+        // MySQL only writes `# Time:` when the second changes, so a block
+        // can also start at `# User@Host:`
+        if line.starts_with("# User@Host:") && !after_time_header {
+            return Self::slow_query_entry(state, line, None);
+        }
+
+        if let Some(caps) = MYSQL_RE.captures(line) {
+            state.in_slow_block = false;
+            let level = match &caps[3] {
+                "ERROR" => LogLevel::Error,
+                "Warning" => LogLevel::Warn,
+                "Note" | "System" => LogLevel::Info,
+                other => detect_level(other),
+            };
+            let mut metadata: Vec<String> = Vec::new();
+            if let Some(code) = caps.get(4) {
+                metadata.push(code.as_str().to_string());
+            }
+            if let Some(subsystem) = caps.get(5) {
+                metadata.push(format!("[{}]", subsystem.as_str()));
+            }
+            metadata.push(format!("thread {}", &caps[2]));
+            return LogEntry {
+                raw: line.to_string(),
+                level,
+                timestamp: Some(caps[1].to_string()),
+                ts_parsed: parse_timestamp(&caps[1]),
+                message: Some(caps[6].to_string()),
+                metadata: Some(metadata.join(" ")),
+                extra_lines: Vec::new(),
+                source: None,
+                status: None,
+                divider: false,
+            };
+        }
+
+        if state.in_slow_block {
+            self.continued.store(true, Ordering::Relaxed);
+            return LogEntry {
+                raw: line.to_string(),
+                level: LogLevel::Unknown,
+                timestamp: None,
+                ts_parsed: None,
+                message: None,
+                metadata: None,
+                extra_lines: Vec::new(),
+                source: None,
+                status: None,
+                divider: false,
+            };
+        }
+        fallback_parse(line)
+    }
+
+    /// The `# User@Host:` / `# Query_time:` headers and the SQL after them
+    /// stay with the block's first line, though the headers are in the format.
+    fn starts_new_entry(&self, _line: &str) -> bool {
+        !self.continued.load(Ordering::Relaxed)
+    }
+}
+
+// --- Custom Regex Parser (--parser-regex) ---
+// Named groups: timestamp, level, message, metadata. All are optional;
+// without `message` the whole line is used.
+pub struct CustomRegexParser {
+    re: Regex,
+}
+
+impl CustomRegexParser {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let re = Regex::new(pattern).map_err(|e| format!("invalid --parser-regex: {}", e))?;
+        let known = ["timestamp", "level", "message", "metadata"];
+        if !re
+            .capture_names()
+            .flatten()
+            .any(|name| known.contains(&name))
+        {
+            return Err(
+                "--parser-regex needs a named group: timestamp, level, message or metadata"
+                    .to_string(),
+            );
+        }
+        Ok(Self { re })
+    }
+}
+
+impl LogParser for CustomRegexParser {
+    fn name(&self) -> &str {
+        "Custom regex"
+    }
+
+    fn can_parse(&self, line: &str) -> bool {
+        self.re.is_match(line)
+    }
+
+    fn parse(&self, line: &str) -> LogEntry {
+        let Some(caps) = self.re.captures(line) else {
+            return fallback_parse(line);
+        };
+        let group = |name: &str| caps.name(name).map(|m| m.as_str());
+        let message = group("message").unwrap_or(line);
+        let level = match group("level") {
+            Some(level) => detect_level(level),
+            None => detect_level(message),
+        };
+        LogEntry {
+            raw: line.to_string(),
+            level,
+            timestamp: group("timestamp").map(String::from),
+            ts_parsed: group("timestamp").and_then(parse_timestamp),
+            message: Some(message.to_string()),
+            metadata: group("metadata").map(String::from),
+            extra_lines: Vec::new(),
+            source: None,
+            status: None,
+            divider: false,
+        }
+    }
+}
+
 // --- Plain Fallback ---
 pub struct PlainParser;
 
@@ -243,6 +1047,12 @@ impl LogParser for PlainParser {
     fn parse(&self, line: &str) -> LogEntry {
         fallback_parse(line)
     }
+
+    // Any line parses, so only a level word or a timestamp marks a new
+    // entry; the rest (stack frames) continue the one before
+    fn starts_new_entry(&self, line: &str) -> bool {
+        detect_level(line) != LogLevel::Unknown || starts_with_timestamp(line)
+    }
 }
 
 fn fallback_parse(line: &str) -> LogEntry {
@@ -250,60 +1060,206 @@ fn fallback_parse(line: &str) -> LogEntry {
         raw: line.to_string(),
         level: detect_level(line),
         timestamp: None,
+        ts_parsed: None,
         message: Some(line.to_string()),
         metadata: None,
         extra_lines: Vec::new(),
+        source: None,
+        status: None,
+        divider: false,
+    }
+}
+
+// --- Diff (--diff) ---
+/// Reads the markers `start_diff_source` puts in front of each line: the
+/// level says which file has the line, the rest goes through `inner`.
+pub struct DiffParser {
+    inner: Box<dyn LogParser>,
+}
+
+impl DiffParser {
+    pub fn new(inner: Box<dyn LogParser>) -> Self {
+        Self { inner }
+    }
+}
+
+impl LogParser for DiffParser {
+    fn name(&self) -> &str {
+        "Diff"
+    }
+
+    fn is_stateless(&self) -> bool {
+        self.inner.is_stateless()
+    }
+
+    fn can_parse(&self, line: &str) -> bool {
+        DiffSide::split(line).is_some()
+    }
+
+    // Every line is its own shape, found in one file or both
+    fn starts_new_entry(&self, _line: &str) -> bool {
+        true
+    }
+
+    fn parse(&self, line: &str) -> LogEntry {
+        let Some((side, rest)) = DiffSide::split(line) else {
+            return self.inner.parse(line);
+        };
+        let mut entry = self.inner.parse(rest);
+        // Trace rather than Unknown for common lines: Unknown lines would be
+        // grouped under the entry before them as continuation lines
+        entry.level = match side {
+            DiffSide::OnlyA => LogLevel::Error,
+            DiffSide::OnlyB => LogLevel::Info,
+            DiffSide::Both => LogLevel::Trace,
+        };
+        entry
+    }
+}
+
+/// Which of the two `--diff` files a line's shape was found in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffSide {
+    OnlyA,
+    OnlyB,
+    Both,
+}
+
+impl DiffSide {
+    pub fn marker(self) -> &'static str {
+        match self {
+            DiffSide::OnlyA => "- ",
+            DiffSide::OnlyB => "+ ",
+            DiffSide::Both => "  ",
+        }
+    }
+
+    fn split(line: &str) -> Option<(Self, &str)> {
+        [DiffSide::OnlyA, DiffSide::OnlyB, DiffSide::Both]
+            .into_iter()
+            .find_map(|side| line.strip_prefix(side.marker()).map(|rest| (side, rest)))
     }
 }
 
 /// Get a parser by name (for --format flag).
 pub fn get_parser_by_name(name: &str) -> Box<dyn LogParser> {
     match name.to_lowercase().as_str() {
-        "json" => Box::new(JsonParser),
+        "json" => Box::new(JsonParser::default()),
+        "clef" | "serilog" => Box::new(ClefParser),
+        "mongo" | "mongodb" => Box::new(MongoParser),
         "laravel" => Box::new(LaravelParser),
         "django" => Box::new(DjangoParser),
         "go" => Box::new(GoLogParser),
+        "zap" => Box::new(ZapParser),
         "nginx" | "apache" => Box::new(NginxApacheParser),
+        "envoy" | "istio" => Box::new(EnvoyParser),
+        "cri" | "containerd" => Box::new(CriParser::default()),
+        "mysql" | "mariadb" => Box::new(MysqlParser::default()),
         _ => Box::new(PlainParser),
     }
 }
 
-/// Auto-detect the best parser from a set of sample lines.
-pub fn detect_parser(sample_lines: &[&str]) -> Box<dyn LogParser> {
+/// Below this share of sample lines matched, detection asks which format
+/// to use instead of trusting the best guess.
+pub const DETECT_CONFIDENCE: f64 = 0.5;
+
+/// Formats that matched some of the sample lines, best first (ties in
+/// detection order, so CLEF wins over the JSON it also is).
+pub struct Detection {
+    pub candidates: Vec<(Box<dyn LogParser>, usize)>,
+    pub sample_len: usize,
+}
+
+impl Detection {
+    /// Share of the sample the best candidate matched, 0.0 to 1.0.
+    pub fn confidence(&self) -> f64 {
+        match self.candidates.first() {
+            Some((_, score)) => *score as f64 / self.sample_len as f64,
+            None => 0.0,
+        }
+    }
+
+    /// Some format matched, but not enough of the sample to trust it.
+    /// Lines no format matches at all are plain text, not ambiguous.
+    pub fn is_ambiguous(&self) -> bool {
+        !self.candidates.is_empty() && self.confidence() < DETECT_CONFIDENCE
+    }
+
+    pub fn into_parser(self) -> Box<dyn LogParser> {
+        match self.candidates.into_iter().next() {
+            Some((parser, _)) => parser,
+            None => Box::new(PlainParser),
+        }
+    }
+}
+
+/// Score every built-in format against a set of sample lines.
+pub fn detect_format(sample_lines: &[&str]) -> Detection {
+    // CLEF and MongoDB first: their lines are also JSON, and ties go to the
+    // earlier parser
     let parsers: Vec<Box<dyn LogParser>> = vec![
-        Box::new(JsonParser),
+        Box::new(ClefParser),
+        Box::new(MongoParser),
+        Box::new(JsonParser::default()),
         Box::new(LaravelParser),
         Box::new(DjangoParser),
+        Box::new(CriParser::default()),
+        Box::new(MysqlParser::default()),
+        Box::new(ZapParser),
         Box::new(GoLogParser),
+        Box::new(EnvoyParser),
         Box::new(NginxApacheParser),
     ];
 
-    let mut best: Option<Box<dyn LogParser>> = None;
-    let mut best_score = 0;
-
-    for parser in parsers {
-        let score = sample_lines.iter().filter(|l| parser.can_parse(l)).count();
-        if score > best_score {
-            best_score = score;
-            best = Some(parser);
-        }
+    let mut candidates: Vec<(Box<dyn LogParser>, usize)> = parsers
+        .into_iter()
+        .map(|parser| {
+            let score = sample_lines
+                .iter()
+                .filter(|l| parser.can_parse(&strip_ansi(l)))
+                .count();
+            (parser, score)
+        })
+        .filter(|(_, score)| *score > 0)
+        .collect();
+    // Stable, so ties keep the order above
+    candidates.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    Detection {
+        candidates,
+        sample_len: sample_lines.len(),
     }
+}
 
-    if best_score > 0 {
-        best.unwrap()
-    } else {
-        Box::new(PlainParser)
-    }
+/// Auto-detect the best parser from a set of sample lines.
+pub fn detect_parser(sample_lines: &[&str]) -> Box<dyn LogParser> {
+    detect_format(sample_lines).into_parser()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn level_keywords_match_whole_words() {
+        let keywords = [
+            ("FAIL".to_string(), LogLevel::Error),
+            ("W".to_string(), LogLevel::Warn),
+        ];
+        assert_eq!(
+            keyword_level("job 42: fail (retrying)", &keywords),
+            Some(LogLevel::Error)
+        );
+        assert_eq!(
+            keyword_level("W disk 91% full", &keywords),
+            Some(LogLevel::Warn)
+        );
+        assert_eq!(keyword_level("failed over; warm cache", &keywords), None);
+    }
+
     // --- JSON Parser ---
     #[test]
     fn json_can_parse() {
-        let p = JsonParser;
+        let p = JsonParser::default();
         assert!(p.can_parse(r#"{"level":"error","msg":"fail"}"#));
         assert!(p.can_parse(r#"  {"key": "value"}  "#));
         assert!(!p.can_parse("not json at all"));
@@ -312,7 +1268,7 @@ mod tests {
 
     #[test]
     fn json_parse_fields() {
-        let p = JsonParser;
+        let p = JsonParser::default();
         let entry = p.parse(r#"{"level":"error","msg":"connection failed","service":"api"}"#);
         assert_eq!(entry.level, LogLevel::Error);
         assert_eq!(entry.message.as_deref(), Some("connection failed"));
@@ -320,12 +1276,50 @@ mod tests {
 
     #[test]
     fn json_parse_severity_alias() {
-        let p = JsonParser;
+        let p = JsonParser::default();
         let entry = p.parse(r#"{"severity":"WARNING","text":"slow query"}"#);
         assert_eq!(entry.level, LogLevel::Warn);
         assert_eq!(entry.message.as_deref(), Some("slow query"));
     }
 
+    #[test]
+    fn json_nested_field_paths() {
+        let mut map = JsonFieldMap::default();
+        map.message_paths
+            .insert(0, split_dot_path("event.original"));
+        let p = JsonParser::with_field_map(map);
+        let entry = p.parse(
+            r#"{"@timestamp":"2024-01-15T10:30:01Z","log":{"level":"warn"},"event":{"original":"disk 91%"}}"#,
+        );
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.message.as_deref(), Some("disk 91%"));
+        assert_eq!(entry.timestamp.as_deref(), Some("2024-01-15T10:30:01Z"));
+        assert!(entry.ts_parsed.is_some());
+
+        // ECS also writes the dotted name as a flat key
+        let entry = p.parse(r#"{"log.level":"error","message":"boom"}"#);
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.message.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn json_default_keys_cover_ecs_and_escapes() {
+        let p = JsonParser::default();
+        let entry = p.parse(r#"{"log":{"level":"error"},"message":"say \"hi\" {now}"}"#);
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.message.as_deref(), Some(r#"say "hi" {now}"#));
+
+        let entry = p.parse(r#"{"loglevel":"debug","@message":"cache miss","ts":1705314601}"#);
+        assert_eq!(entry.level, LogLevel::Debug);
+        assert_eq!(entry.message.as_deref(), Some("cache miss"));
+        assert_eq!(entry.timestamp.as_deref(), Some("1705314601"));
+
+        // Broken JSON still gets a level and message from the fallback
+        let entry = p.parse(r#"{"loglevel":"warn","msg":"cut off","user":{"id"#);
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.message.as_deref(), Some("cut off"));
+    }
+
     // --- Laravel Parser ---
     #[test]
     fn laravel_can_parse() {
@@ -399,6 +1393,85 @@ mod tests {
         );
     }
 
+    // --- Zap Parser ---
+    #[test]
+    fn zap_can_parse() {
+        let p = ZapParser;
+        assert!(p.can_parse("2024-01-15T10:30:01.123Z\tERROR\tdb/pool.go:42\tquery failed"));
+        assert!(p.can_parse(
+            "2024-01-15T10:30:01.123+0100\tINFO\tapi.http\tserver/main.go:88\tlistening"
+        ));
+        assert!(!p.can_parse("2024-01-15T10:30:01.123Z ERROR db/pool.go:42 query failed"));
+        assert!(!p.can_parse("time=2024-01-15T10:30:09Z level=ERROR msg=\"x\""));
+    }
+
+    #[test]
+    fn zap_parse_console_fields() {
+        let p = ZapParser;
+        let entry = p.parse(
+            "2024-01-15T10:30:01.123Z\tERROR\tdb/pool.go:42\tquery failed\t{\"table\": \"users\", \"retry\": 3}",
+        );
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.timestamp.as_deref(), Some("2024-01-15T10:30:01.123Z"));
+        assert!(entry.ts_parsed.is_some());
+        assert_eq!(entry.message.as_deref(), Some("query failed"));
+        let metadata: serde_json::Value =
+            serde_json::from_str(entry.metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            metadata,
+            serde_json::json!({"caller": "db/pool.go:42", "table": "users", "retry": 3})
+        );
+
+        // Logger name, zone offset without colon, no context, tab in the message
+        let entry = p
+            .parse("2024-01-15T10:30:01.123+0100\tDPANIC\tapi.http\tserver/main.go:88\tbad\tstate");
+        assert_eq!(entry.level, LogLevel::Fatal);
+        assert!(entry.ts_parsed.is_some());
+        assert_eq!(entry.message.as_deref(), Some("bad\tstate"));
+        assert_eq!(
+            entry.metadata.as_deref(),
+            Some(r#"{"caller":"server/main.go:88","logger":"api.http"}"#)
+        );
+    }
+
+    #[test]
+    fn zap_json_encoder_lines_use_the_json_parser() {
+        let entry = ZapParser.parse(
+            r#"{"level":"warn","ts":1705314601.123,"caller":"db/pool.go:42","msg":"slow query"}"#,
+        );
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.message.as_deref(), Some("slow query"));
+        assert!(entry.ts_parsed.is_some());
+        assert_eq!(get_parser_by_name("zap").name(), "Zap");
+        let lines = [
+            "2024-01-15T10:30:01.123Z\tINFO\tserver/main.go:88\tlistening\t{\"port\": 8080}",
+            "2024-01-15T10:30:02.001Z\tWARN\tdb/pool.go:42\tslow query",
+        ];
+        assert_eq!(detect_parser(&lines).name(), "Zap");
+    }
+
+    #[test]
+    fn mongo_structured_log() {
+        let line = r#"{"t":{"$date":"2024-01-15T10:30:01.123+00:00"},"s":"E","c":"NETWORK","id":12345,"ctx":"conn1","msg":"Connection reset","attr":{"remote":"10.0.0.5:41234"}}"#;
+        let entry = MongoParser.parse(line);
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.message.as_deref(), Some("Connection reset"));
+        assert_eq!(
+            entry.timestamp.as_deref(),
+            Some("2024-01-15T10:30:01.123+00:00")
+        );
+        assert!(entry.ts_parsed.is_some());
+        let metadata: serde_json::Value =
+            serde_json::from_str(entry.metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(metadata["component"], "NETWORK");
+        assert_eq!(metadata["ctx"], "conn1");
+        assert_eq!(metadata["attr"]["remote"], "10.0.0.5:41234");
+
+        let debug = r#"{"t":{"$date":"2024-01-15T10:30:02Z"},"s":"D2","c":"QUERY","ctx":"conn1","msg":"plan"}"#;
+        assert_eq!(MongoParser.parse(debug).level, LogLevel::Debug);
+        assert_eq!(detect_parser(&[line, debug]).name(), "MongoDB");
+    }
+
     // --- Nginx/Apache Parser ---
     #[test]
     fn nginx_can_parse() {
@@ -423,6 +1496,253 @@ mod tests {
         let e500 =
             p.parse(r#"10.0.0.1 - - [15/Jan/2024:10:30:07 +0000] "POST /api HTTP/1.1" 500 89"#);
         assert_eq!(e500.level, LogLevel::Error);
+        assert_eq!(e500.status, Some(500));
+    }
+
+    // --- Envoy Parser ---
+    #[test]
+    fn envoy_parse_ok() {
+        let p = EnvoyParser;
+        let line = r#"[2024-01-15T10:30:01.123Z] "GET /api/users HTTP/1.1" 200 - 0 512 4 3 "10.0.0.1" "curl/8.4" "req-1" "api.local" "10.0.0.7:8080""#;
+        assert!(p.can_parse(line));
+        let entry = p.parse(line);
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.message.as_deref(), Some("GET /api/users -> 200"));
+        assert_eq!(entry.status, Some(200));
+        assert_eq!(entry.metadata, None);
+        assert!(entry.ts_parsed.is_some());
+    }
+
+    #[test]
+    fn envoy_parse_upstream_failure() {
+        let p = EnvoyParser;
+        let line = r#"[2024-01-15T10:30:01.123Z] "GET /api HTTP/2" 503 UF 0 0 12 - "-" "curl" "req-id" "authority" "upstream""#;
+        let entry = p.parse(line);
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.message.as_deref(), Some("GET /api -> 503"));
+        assert_eq!(entry.metadata.as_deref(), Some("UF"));
+        assert_eq!(entry.timestamp.as_deref(), Some("2024-01-15T10:30:01.123Z"));
+
+        assert_eq!(detect_parser(&[line, line]).name(), "Envoy");
+    }
+
+    // --- CRI Parser ---
+    #[test]
+    fn cri_can_parse() {
+        let p = CriParser::default();
+        assert!(p.can_parse("2024-01-15T10:30:01.123456789Z stdout F hello"));
+        assert!(p.can_parse("2024-01-15T10:30:01.123456789Z stderr P part"));
+        assert!(!p.can_parse("2024-01-15T10:30:01Z something else"));
+    }
+
+    #[test]
+    fn cri_parse_fields() {
+        let p = CriParser::default();
+        let entry = p.parse("2024-01-15T10:30:01.123456789Z stdout F ERROR db down");
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.message.as_deref(), Some("ERROR db down"));
+        assert_eq!(
+            entry.timestamp.as_deref(),
+            Some("2024-01-15T10:30:01.123456789Z")
+        );
+        assert!(entry.ts_parsed.is_some());
+
+        let entry = p.parse("2024-01-15T10:30:01.123456789Z stderr F listening");
+        assert_eq!(entry.level, LogLevel::Warn);
+        let entry = p.parse("2024-01-15T10:30:01.123456789Z stdout F listening");
+        assert_eq!(entry.level, LogLevel::Info);
+    }
+
+    #[test]
+    fn cri_partial_lines_continue() {
+        let p = CriParser::default();
+        let first = p.parse("2024-01-15T10:30:01Z stdout P very long ");
+        assert_eq!(first.level, LogLevel::Info);
+        let rest = p.parse("2024-01-15T10:30:01Z stdout F line ends here");
+        assert_eq!(rest.level, LogLevel::Unknown);
+        assert_eq!(rest.raw, "line ends here");
+        let next = p.parse("2024-01-15T10:30:02Z stdout F new record");
+        assert_eq!(next.level, LogLevel::Info);
+    }
+
+    #[test]
+    fn cri_partial_lines_continue_per_source() {
+        let p = CriParser::default();
+        p.parse_from(Some("a"), "2024-01-15T10:30:01Z stdout P first half ");
+        // Another stream's record in between is a record of its own
+        let other = p.parse_from(Some("b"), "2024-01-15T10:30:01Z stdout F from b");
+        assert_eq!(other.level, LogLevel::Info);
+        assert!(p.starts_new_entry(&other.raw));
+        let rest = p.parse_from(Some("a"), "2024-01-15T10:30:01Z stdout F second half");
+        assert_eq!(rest.raw, "second half");
+        assert!(!p.starts_new_entry(&rest.raw));
+    }
+
+    #[test]
+    fn detect_parser_cri() {
+        let lines = vec![
+            "2024-01-15T10:30:01.123456789Z stdout F started",
+            "2024-01-15T10:30:02.123456789Z stderr F WARN slow",
+        ];
+        assert_eq!(detect_parser(&lines).name(), "CRI");
+    }
+
+    // --- MySQL Parser ---
+    #[test]
+    fn mysql_parse_error_log() {
+        let p = MysqlParser::default();
+        let entry =
+            p.parse("2024-01-15T10:30:01.123456Z 0 [ERROR] [MY-012345] [Server] Table is full");
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.message.as_deref(), Some("Table is full"));
+        assert_eq!(
+            entry.metadata.as_deref(),
+            Some("MY-012345 [Server] thread 0")
+        );
+        assert!(entry.ts_parsed.is_some());
+
+        let entry = p.parse("2024-01-15T10:30:02.000000Z 8 [Note] Aborted connection 8");
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.metadata.as_deref(), Some("thread 8"));
+        let entry = p.parse("2024-01-15T10:30:03.000000Z 0 [Warning] [MY-010068] [Server] CA certificate is self signed.");
+        assert_eq!(entry.level, LogLevel::Warn);
+    }
+
+    #[test]
+    fn mysql_slow_query_block_groups() {
+        let p = MysqlParser::default();
+        let head = p.parse("# Time: 2024-01-15T10:30:01.123456Z");
+        assert_eq!(head.level, LogLevel::Warn);
+        assert!(head.ts_parsed.is_some());
+        for line in [
+            "# User@Host: root[root] @ localhost []  Id:     8",
+            "# Query_time: 2.000123  Lock_time: 0.000000 Rows_sent: 1  Rows_examined: 0",
+            "SET timestamp=1705314601;",
+            "SELECT * FROM errors;",
+        ] {
+            assert_eq!(p.parse(line).level, LogLevel::Unknown, "{}", line);
+        }
+        // A new block without a `# Time:` header
+        let next = p.parse("# User@Host: app[app] @ 10.0.0.5 []  Id:     9");
+        assert_eq!(next.level, LogLevel::Warn);
+        // An error-log line ends the block
+        let err = p.parse("2024-01-15T10:30:05.000000Z 0 [ERROR] [MY-000001] [Server] boom");
+        assert_eq!(err.level, LogLevel::Error);
+    }
+
+    #[test]
+    fn mysql_slow_query_block_is_per_source() {
+        let p = MysqlParser::default();
+        p.parse_from(Some("db1"), "# Time: 2024-01-15T10:30:01.123456Z");
+        // Another host's plain line isn't taken into db1's block
+        let other = p.parse_from(Some("db2"), "mysqld: ready for connections");
+        assert!(p.starts_new_entry(&other.raw));
+        let user = p.parse_from(Some("db1"), "# User@Host: root[root] @ localhost []  Id: 8");
+        assert_eq!(user.level, LogLevel::Unknown);
+        assert!(!p.starts_new_entry(&user.raw));
+        let sql = p.parse_from(Some("db1"), "SELECT 1;");
+        assert!(!p.starts_new_entry(&sql.raw));
+    }
+
+    #[test]
+    fn detect_parser_mysql() {
+        let lines = vec![
+            "2024-01-15T10:30:01.123456Z 0 [System] [MY-010116] [Server] starting",
+            "2024-01-15T10:30:02.123456Z 0 [Warning] [MY-010068] [Server] self signed",
+        ];
+        assert_eq!(detect_parser(&lines).name(), "MySQL");
+    }
+
+    // --- Custom Regex Parser ---
+    #[test]
+    fn custom_regex_named_groups() {
+        let p = CustomRegexParser::new(
+            r"^(?P<timestamp>\S+ \S+) <(?P<level>\w+)> \[(?P<metadata>[^\]]+)\] (?P<message>.*)$",
+        )
+        .unwrap();
+        let line = "2024-01-15 10:30:01 <err> [billing] charge declined";
+        assert!(p.can_parse(line));
+        let entry = p.parse(line);
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.timestamp.as_deref(), Some("2024-01-15 10:30:01"));
+        assert!(entry.ts_parsed.is_some());
+        assert_eq!(entry.metadata.as_deref(), Some("billing"));
+        assert_eq!(entry.message.as_deref(), Some("charge declined"));
+
+        assert!(!p.can_parse("unrelated line"));
+        assert_eq!(
+            p.parse("unrelated line").message.as_deref(),
+            Some("unrelated line")
+        );
+    }
+
+    #[test]
+    fn custom_regex_rejects_bad_patterns() {
+        assert!(CustomRegexParser::new("(unclosed").is_err());
+        assert!(CustomRegexParser::new(r"^(\w+) (.*)$").is_err());
+    }
+
+    // --- ANSI stripping ---
+    #[test]
+    fn ansi_codes_are_stripped_before_parsing() {
+        assert_eq!(strip_ansi("\x1b[31mERROR\x1b[0m boom"), "ERROR boom");
+        assert!(matches!(strip_ansi("plain"), Cow::Borrowed(_)));
+
+        let line = "[2024-01-15 10:30:01] production.\x1b[1;31mERROR\x1b[0m: Connection refused";
+        assert_eq!(detect_parser(&[line]).name(), "Laravel");
+    }
+
+    // --- CLEF Parser ---
+    #[test]
+    fn clef_reads_serilog_fields() {
+        let p = ClefParser;
+        let line =
+            r#"{"@t":"2024-01-15T10:30:01.123Z","@l":"Warning","@m":"Disk C: low","Drive":"C:"}"#;
+        assert!(p.can_parse(line));
+        let entry = p.parse(line);
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.message.as_deref(), Some("Disk C: low"));
+        assert_eq!(entry.timestamp.as_deref(), Some("2024-01-15T10:30:01.123Z"));
+        assert!(entry.ts_parsed.is_some());
+
+        // No @l is Information; @x becomes continuation lines
+        let entry = p.parse(
+            r#"{"@t":"2024-01-15T10:30:02Z","@mt":"Request failed","@x":"System.Exception: boom\n   at Api.Run()"}"#,
+        );
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(
+            entry.extra_lines,
+            ["System.Exception: boom", "   at Api.Run()"]
+        );
+
+        assert_eq!(
+            p.parse(r#"{"@t":"x","@l":"Verbose"}"#).level,
+            LogLevel::Trace
+        );
+        assert!(!p.can_parse(r#"{"level":"info","msg":"plain json"}"#));
+        assert!(!p.can_parse("{not json}"));
+    }
+
+    #[test]
+    fn clef_renders_message_template() {
+        let event = serde_json::json!({"User": "bob", "Elapsed": 42, "Order": {"Id": 7}});
+        assert_eq!(
+            render_template("{User} took {Elapsed:0.0} ms on {@Order}", &event),
+            r#"bob took 42 ms on {"Id":7}"#
+        );
+        assert_eq!(
+            render_template("{{literal}} {Missing} {", &event),
+            "{literal} {Missing} {"
+        );
+    }
+
+    #[test]
+    fn detect_parser_clef() {
+        let lines = vec![
+            r#"{"@t":"2024-01-15T10:30:01Z","@mt":"Started"}"#,
+            r#"{"@t":"2024-01-15T10:30:02Z","@l":"Error","@mt":"Failed"}"#,
+        ];
+        assert_eq!(detect_parser(&lines).name(), "CLEF");
     }
 
     // --- Plain Parser ---
@@ -456,6 +1776,44 @@ mod tests {
         assert_eq!(p.name(), "Laravel");
     }
 
+    #[test]
+    fn detection_is_unsure_when_few_lines_match() {
+        let mut lines = vec!["[2024-01-15 10:30:00] production.ERROR: boom"; 2];
+        lines.extend(["free text"; 18]);
+        let detection = detect_format(&lines);
+        assert!((detection.confidence() - 0.1).abs() < f64::EPSILON);
+        assert!(detection.is_ambiguous());
+        assert_eq!(detection.candidates[0].0.name(), "Laravel");
+
+        // CLEF lines are JSON too: a tie, settled by detection order
+        let clef = r#"{"@t":"2024-01-15T10:30:00Z","@mt":"Hello"}"#;
+        let detection = detect_format(&[clef, clef]);
+        assert!(!detection.is_ambiguous());
+        let names: Vec<&str> = detection.candidates.iter().map(|(p, _)| p.name()).collect();
+        assert_eq!(names, ["CLEF", "JSON"]);
+
+        // Nothing matching at all is plain text, not a question
+        assert!(!detect_format(&["just text", "more text"]).is_ambiguous());
+    }
+
+    #[test]
+    fn starts_new_entry_by_format_or_timestamp() {
+        assert!(starts_with_timestamp("2024-01-15T10:30:01Z started"));
+        assert!(starts_with_timestamp(
+            "[2024-01-15 10:30:01] production.INFO: x"
+        ));
+        assert!(starts_with_timestamp("2024/01/15 10:30:01 listening"));
+        assert!(starts_with_timestamp("Jan  5 10:30:01 host sshd[1]: ok"));
+        assert!(!starts_with_timestamp("  at Foo.bar(Foo.java:10)"));
+
+        let laravel = LaravelParser;
+        assert!(laravel.starts_new_entry("[2024-01-15 10:30:00] production.ERROR: boom"));
+        assert!(!laravel.starts_new_entry("#0 /var/www/app.php(10): run()"));
+        assert!(JsonParser::default().starts_new_entry(r#"{"msg":"no level"}"#));
+        assert!(!PlainParser.starts_new_entry("    at Foo.bar(Foo.java:10)"));
+        assert!(PlainParser.starts_new_entry("WARN disk almost full"));
+    }
+
     #[test]
     fn detect_parser_mixed_falls_back() {
         let lines = vec!["just plain text", "another line", "nothing special"];
@@ -470,6 +1828,38 @@ mod tests {
         assert_eq!(p.name(), "Plain");
     }
 
+    // --- Timestamps ---
+    #[test]
+    fn parse_timestamp_formats() {
+        let utc = |ts: &str| parse_timestamp(ts).map(|dt| dt.naive_utc().to_string());
+        assert_eq!(
+            utc("time=2024-01-15T10:30:09Z").as_deref(),
+            Some("2024-01-15 10:30:09")
+        );
+        assert_eq!(
+            utc("15/Jan/2024:10:30:07 +0200").as_deref(),
+            Some("2024-01-15 08:30:07")
+        );
+        assert!(parse_timestamp("2024-01-15 10:30:01").is_some());
+        assert!(parse_timestamp("15/Jan/2024 10:30:11").is_some());
+        assert!(parse_timestamp("2024/01/15 10:30:01").is_some());
+        assert_eq!(utc("1705314609").as_deref(), Some("2024-01-15 10:30:09"));
+        assert_eq!(
+            utc("1705314609.5").as_deref(),
+            Some("2024-01-15 10:30:09.500")
+        );
+        assert_eq!(utc("1705314609000").as_deref(), Some("2024-01-15 10:30:09"));
+        assert!(parse_timestamp("12345").is_none());
+        assert!(parse_timestamp("yesterday-ish").is_none());
+    }
+
+    #[test]
+    fn parsers_fill_ts_parsed() {
+        let entry = LaravelParser.parse("[2024-01-15 10:30:01] production.ERROR: boom");
+        assert!(entry.ts_parsed.is_some());
+        assert!(PlainParser.parse("no time here").ts_parsed.is_none());
+    }
+
     // --- get_parser_by_name ---
     #[test]
     fn get_parser_by_name_works() {
@@ -478,6 +1868,7 @@ mod tests {
         assert_eq!(get_parser_by_name("django").name(), "Django");
         assert_eq!(get_parser_by_name("go").name(), "Go");
         assert_eq!(get_parser_by_name("nginx").name(), "Nginx/Apache");
+        assert_eq!(get_parser_by_name("cri").name(), "CRI");
         assert_eq!(get_parser_by_name("unknown").name(), "Plain");
     }
 
@@ -492,7 +1883,7 @@ mod tests {
 
     #[test]
     fn malformed_json() {
-        let p = JsonParser;
+        let p = JsonParser::default();
         // can_parse returns false for incomplete JSON
         assert!(!p.can_parse("{incomplete"));
         // But if forced, parse still works
@@ -0,0 +1,99 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::{App, LogEntry};
+
+const SESSION_VERSION: u32 = 1;
+
+/// Snapshot of the buffer written by Ctrl+S and reopened with `--open`.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    /// Source name shown in the header when the session was saved
+    pub filename: String,
+    /// Parser that produced the entries
+    pub format: String,
+    pub saved_at: String,
+    pub bookmarks: BTreeSet<usize>,
+    pub logs: Vec<LogEntry>,
+}
+
+impl Session {
+    pub fn from_app(app: &App) -> Self {
+        Self {
+            version: SESSION_VERSION,
+            filename: app.filename.clone(),
+            format: app.format_name.clone(),
+            saved_at: chrono::Local::now().to_rfc3339(),
+            bookmarks: app.bookmarks.clone(),
+            logs: app.logs.iter().cloned().collect(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let data = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, data).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let session: Self = serde_json::from_str(&data)
+            .map_err(|e| format!("{}: not a logpulse session: {}", path.display(), e))?;
+        if session.version > SESSION_VERSION {
+            return Err(format!(
+                "{}: session version {} is newer than this logpulse supports",
+                path.display(),
+                session.version
+            ));
+        }
+        Ok(session)
+    }
+}
+
+/// Default snapshot filename, e.g. `logpulse-20240115-103001.lpsession`.
+pub fn default_session_path() -> String {
+    format!(
+        "logpulse-{}.lpsession",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::LogLevel;
+    use crate::parser::{LaravelParser, LogParser};
+
+    #[test]
+    fn session_round_trip() {
+        let mut app = App::new("app.log".to_string());
+        app.format_name = "Laravel".to_string();
+        let entry =
+            LaravelParser.parse("[2024-01-15 10:30:01] production.ERROR: Connection refused");
+        app.add_log(entry);
+        app.insert_divider("live");
+        app.bookmarks.insert(0);
+
+        let path = std::env::temp_dir().join(format!("logpulse-{}.lpsession", std::process::id()));
+        Session::from_app(&app).save(&path).unwrap();
+        let session = Session::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(session.filename, "app.log");
+        assert_eq!(session.format, "Laravel");
+        assert_eq!(session.logs.len(), 2);
+        assert_eq!(session.logs[0].level, LogLevel::Error);
+        assert_eq!(session.logs[0].ts_parsed, app.logs[0].ts_parsed);
+        assert!(session.logs[1].divider);
+
+        let mut reopened = App::new(String::new());
+        reopened.load_session(session);
+        assert_eq!(reopened.logs.len(), 2);
+        assert_eq!(reopened.error_count, 1);
+        assert!(reopened.bookmarks.contains(&0));
+        assert!(reopened.frozen);
+    }
+}
@@ -1,14 +1,27 @@
+use std::collections::{HashMap, HashSet};
 use std::io::{Read, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+use std::sync::Arc;
 
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::sync::mpsc;
-use tokio::time::{sleep, Duration};
+use chrono::{DateTime, Local};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncSeekExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{sleep, Duration, Instant};
 
-const TAIL_LINES: usize = 1000;
+use crate::parser::{detect_parser, parse_timestamp, DiffSide, LogParser};
+use crate::stats::normalize_message;
+
+pub const TAIL_LINES: usize = 1000;
+/// How long a container source keeps trying to reconnect before giving up.
+const RECONNECT_GIVE_UP: Duration = Duration::from_secs(300);
 /// Chunk size for seeking backwards through large files.
 const TAIL_CHUNK: u64 = 64 * 1024;
+/// Files at least this big find their tail by scanning backwards for newline
+/// bytes only, then read it in one go, rather than decoding every chunk.
+const TAIL_SCAN_THRESHOLD: u64 = 10 * 1024 * 1024;
 
 /// Read the last `n` lines from a file by seeking from the end.
 /// For small files (< TAIL_CHUNK), reads the whole thing.
@@ -21,11 +34,25 @@ fn read_tail(path: &std::path::Path, n: usize) -> std::io::Result<Vec<String>> {
         return Ok(Vec::new());
     }
 
+    if file_len >= TAIL_SCAN_THRESHOLD {
+        let start = tail_start(&mut file, file_len, n)?;
+        file.seek(SeekFrom::Start(start))?;
+        let mut buf = Vec::with_capacity((file_len - start) as usize);
+        file.read_to_end(&mut buf)?;
+        return Ok(String::from_utf8_lossy(&buf)
+            .lines()
+            .map(String::from)
+            .collect());
+    }
+
     // Small file — just read it all
     if file_len <= TAIL_CHUNK {
-        let mut buf = String::new();
-        file.read_to_string(&mut buf)?;
-        let lines: Vec<String> = buf.lines().map(String::from).collect();
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        let lines: Vec<String> = String::from_utf8_lossy(&buf)
+            .lines()
+            .map(String::from)
+            .collect();
         let skip = lines.len().saturating_sub(n);
         return Ok(lines.into_iter().skip(skip).collect());
     }
@@ -63,6 +90,56 @@ fn read_tail(path: &std::path::Path, n: usize) -> std::io::Result<Vec<String>> {
     Ok(collected.into_iter().skip(skip).collect())
 }
 
+/// Byte offset where the last `n` lines of `file` start: just past the
+/// `n`th newline from the end, not counting one that ends the file.
+fn tail_start(file: &mut std::fs::File, file_len: u64, n: usize) -> std::io::Result<u64> {
+    if n == 0 {
+        return Ok(file_len);
+    }
+    let mut buf = vec![0u8; TAIL_CHUNK as usize];
+    let mut remaining = n;
+    let mut pos = file_len;
+    while pos > 0 {
+        let start = pos.saturating_sub(TAIL_CHUNK);
+        let chunk = &mut buf[..(pos - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        let mut end = chunk.len();
+        if pos == file_len && chunk.last() == Some(&b'\n') {
+            end -= 1;
+        }
+        while let Some(i) = memchr::memrchr(b'\n', &chunk[..end]) {
+            remaining -= 1;
+            if remaining == 0 {
+                return Ok(start + i as u64 + 1);
+            }
+            end = i;
+        }
+        pos = start;
+    }
+    Ok(0)
+}
+
+/// Number of lines in a file, counting a final unterminated line.
+fn count_lines(path: &Path) -> std::io::Result<usize> {
+    let mut file = std::fs::File::open(path)?;
+    let mut buf = vec![0u8; TAIL_CHUNK as usize];
+    let mut count = 0;
+    let mut last = b'\n';
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        count += buf[..n].iter().filter(|&&b| b == b'\n').count();
+        last = buf[n - 1];
+    }
+    if last != b'\n' {
+        count += 1;
+    }
+    Ok(count)
+}
+
 /// Read the last `n` lines, returning (lines, byte_offset_of_first_returned_line).
 /// `byte_offset` is 0 if the whole file was read; otherwise points to where reading started.
 fn read_tail_with_offset(path: &std::path::Path, n: usize) -> std::io::Result<(Vec<String>, u64)> {
@@ -72,44 +149,268 @@ fn read_tail_with_offset(path: &std::path::Path, n: usize) -> std::io::Result<(V
     if file_len == 0 {
         return Ok((Vec::new(), 0));
     }
+    // Only new lines: everything already in the file is history
+    if n == 0 {
+        return Ok((Vec::new(), file_len));
+    }
 
-    if file_len <= TAIL_CHUNK {
-        let mut buf = String::new();
-        file.read_to_string(&mut buf)?;
-        let lines: Vec<String> = buf.lines().map(String::from).collect();
-        let skip = lines.len().saturating_sub(n);
-        return Ok((lines.into_iter().skip(skip).collect(), 0));
+    let start = tail_start(&mut file, file_len, n)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut buf = Vec::with_capacity((file_len - start) as usize);
+    file.read_to_end(&mut buf)?;
+    let lines = String::from_utf8_lossy(&buf)
+        .lines()
+        .map(String::from)
+        .collect();
+    Ok((lines, start))
+}
+
+/// Settings shared by every source (from config / CLI).
+#[derive(Clone)]
+pub struct SourceOpts {
+    /// Lines of backlog to load on startup
+    pub tail_lines: usize,
+    pub reconnect_initial: Duration,
+    pub reconnect_max: Duration,
+    /// Container runtime; None picks docker, falling back to podman / nerdctl
+    pub runtime: Option<Runtime>,
+    /// --since: only show lines newer than this
+    pub since: Option<Since>,
+    /// Set by remote sources as they connect and reconnect
+    pub conn: ConnStatus,
+}
+
+impl Default for SourceOpts {
+    fn default() -> Self {
+        Self {
+            tail_lines: TAIL_LINES,
+            reconnect_initial: Duration::from_secs(1),
+            reconnect_max: Duration::from_secs(10),
+            runtime: None,
+            since: None,
+            conn: ConnStatus::default(),
+        }
     }
+}
 
-    let mut collected = Vec::new();
-    let mut pos = file_len;
+/// Whether a remote source is streaming right now (header badge).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnState {
+    Connected,
+    /// Lost, retrying with backoff
+    Reconnecting,
+    /// Ended or gave up
+    Disconnected,
+}
 
-    loop {
-        let read_start = pos.saturating_sub(TAIL_CHUNK);
-        let read_len = (pos - read_start) as usize;
-        file.seek(SeekFrom::Start(read_start))?;
+/// Connection state shared between a source's task and the TUI. Local
+/// files and stdin never set it, so they show no badge.
+#[derive(Clone, Default)]
+pub struct ConnStatus(Arc<AtomicU8>);
+
+impl ConnStatus {
+    pub fn set(&self, state: ConnState) {
+        let value = match state {
+            ConnState::Connected => 1,
+            ConnState::Reconnecting => 2,
+            ConnState::Disconnected => 3,
+        };
+        self.0.store(value, Ordering::Relaxed);
+    }
 
-        let mut buf = vec![0u8; read_len];
-        file.read_exact(&mut buf)?;
+    pub fn get(&self) -> Option<ConnState> {
+        match self.0.load(Ordering::Relaxed) {
+            1 => Some(ConnState::Connected),
+            2 => Some(ConnState::Reconnecting),
+            3 => Some(ConnState::Disconnected),
+            _ => None,
+        }
+    }
+}
 
-        let chunk = String::from_utf8_lossy(&buf);
-        let mut chunk_lines: Vec<String> = chunk.lines().map(String::from).collect();
+/// `--since` value: a relative duration (`90s`, `5m`, `1h30m`, `2d`) or a timestamp.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Since {
+    Ago(Duration),
+    Time(DateTime<Local>),
+}
 
-        if read_start > 0 && !chunk_lines.is_empty() {
-            chunk_lines.remove(0);
+impl Since {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        if let Some(ago) = parse_duration(s) {
+            let since = Since::Ago(ago);
+            return match since.cutoff() {
+                Ok(_) => Ok(since),
+                Err(_) => Err("reaches too far back".to_string()),
+            };
         }
+        parse_timestamp(s)
+            .map(Since::Time)
+            .ok_or_else(|| "use e.g. 5m, 2h, 1d or 2024-01-15T10:30:00".to_string())
+    }
 
-        chunk_lines.append(&mut collected);
-        collected = chunk_lines;
-        pos = read_start;
+    /// The absolute cutoff time, or an error when it's further back than
+    /// a date can go.
+    pub fn cutoff(self) -> Result<DateTime<Local>, String> {
+        match self {
+            Since::Ago(ago) => chrono::TimeDelta::from_std(ago)
+                .ok()
+                .and_then(|ago| Local::now().checked_sub_signed(ago))
+                .ok_or_else(|| format!("--since {}s reaches too far back", ago.as_secs())),
+            Since::Time(time) => Ok(time),
+        }
+    }
 
-        if collected.len() >= n || read_start == 0 {
-            break;
+    /// Value for `docker logs --since` / `docker compose logs --since`.
+    fn docker_arg(self) -> String {
+        match self {
+            Since::Ago(ago) => format!("{}s", ago.as_secs()),
+            Since::Time(time) => time.to_rfc3339(),
         }
     }
 
-    let skip = collected.len().saturating_sub(n);
-    Ok((collected.into_iter().skip(skip).collect(), pos))
+    /// `kubectl logs` flag: `--since=300s` or `--since-time=<RFC3339>`.
+    fn kubectl_arg(self) -> String {
+        match self {
+            Since::Ago(ago) => format!("--since={}s", ago.as_secs()),
+            Since::Time(time) => format!("--since-time={}", time.to_rfc3339()),
+        }
+    }
+}
+
+/// Parse `90s`, `5m`, `1h30m`, `2d` into a Duration.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in s.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return None,
+        };
+        let secs = digits.parse::<u64>().ok()?.checked_mul(unit)?;
+        total = total.checked_add(secs)?;
+        digits.clear();
+    }
+    if !digits.is_empty() || total == 0 {
+        return None;
+    }
+    Some(Duration::from_secs(total))
+}
+
+/// Docker-compatible container CLI used by the docker and compose sources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Runtime {
+    Docker,
+    Podman,
+    Nerdctl,
+}
+
+impl Runtime {
+    const ALL: [Runtime; 3] = [Runtime::Docker, Runtime::Podman, Runtime::Nerdctl];
+
+    pub fn binary(self) -> &'static str {
+        match self {
+            Runtime::Docker => "docker",
+            Runtime::Podman => "podman",
+            Runtime::Nerdctl => "nerdctl",
+        }
+    }
+
+    /// Program and leading args for compose: `docker compose`,
+    /// `podman-compose`, `nerdctl compose`.
+    fn compose_command(self) -> (&'static str, Option<&'static str>) {
+        match self {
+            Runtime::Docker => ("docker", Some("compose")),
+            Runtime::Podman => ("podman-compose", None),
+            Runtime::Nerdctl => ("nerdctl", Some("compose")),
+        }
+    }
+
+    /// The requested runtime, else docker if installed, else the first
+    /// alternative found on PATH.
+    fn resolve(requested: Option<Runtime>) -> Runtime {
+        requested.unwrap_or_else(|| {
+            Self::ALL
+                .into_iter()
+                .find(|r| on_path(r.binary()))
+                .unwrap_or(Runtime::Docker)
+        })
+    }
+
+    fn from_binary_path(path: &str) -> Option<Runtime> {
+        let name = path.trim().rsplit('/').next()?;
+        Self::ALL.into_iter().find(|r| r.binary() == name)
+    }
+}
+
+fn on_path(binary: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(binary).is_file()))
+}
+
+/// Poll `find` with exponential backoff until it returns a container name,
+/// or give up after RECONNECT_GIVE_UP.
+async fn reconnect_with_backoff<F, Fut>(opts: &SourceOpts, mut find: F) -> Option<String>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Option<String>>,
+{
+    let deadline = Instant::now() + RECONNECT_GIVE_UP;
+    let mut delay = opts.reconnect_initial;
+    while Instant::now() < deadline {
+        sleep(delay).await;
+        if let Some(name) = find().await {
+            return Some(name);
+        }
+        delay = (delay * 2).min(opts.reconnect_max);
+    }
+    None
+}
+
+/// Decode one raw line (with or without its trailing `\n` / `\r\n`) into a
+/// String, replacing invalid UTF-8 sequences with U+FFFD.
+fn decode_line(buf: &[u8]) -> String {
+    let line = buf.strip_suffix(b"\n").unwrap_or(buf);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line).into_owned()
+}
+
+/// Forward every line from `reader` into `tx` until EOF or the receiver is gone.
+/// Lines are read as raw bytes so a binary-ish line doesn't end the stream
+/// (`AsyncBufReadExt::lines` errors out on invalid UTF-8).
+async fn forward_lines<R: AsyncRead + Unpin>(reader: R, tx: &mpsc::UnboundedSender<String>) {
+    forward_lines_with(reader, tx, Some).await;
+}
+
+/// [`forward_lines`], sending what `map` makes of each line and skipping
+/// the lines it drops.
+async fn forward_lines_with<R: AsyncRead + Unpin>(
+    reader: R,
+    tx: &mpsc::UnboundedSender<String>,
+    map: impl Fn(String) -> Option<String>,
+) {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if let Some(line) = map(decode_line(&buf)) {
+                    if tx.send(line).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
 }
 
 /// Lazy history loader for local files. Reads older lines on demand
@@ -117,11 +418,16 @@ fn read_tail_with_offset(path: &std::path::Path, n: usize) -> std::io::Result<(V
 pub struct FileHistory {
     path: PathBuf,
     offset: u64, // byte offset in file — everything below this has been loaded
+    start: u64,
 }
 
 impl FileHistory {
     pub fn new(path: PathBuf, offset: u64) -> Self {
-        Self { path, offset }
+        Self {
+            path,
+            offset,
+            start: offset,
+        }
     }
 
     /// Returns true if there are older lines available to load.
@@ -179,110 +485,1043 @@ impl FileHistory {
         let skip = collected.len().saturating_sub(n);
         collected.into_iter().skip(skip).collect()
     }
+
+    /// Resume loading from `offset`, e.g. the end of a spill file that grew.
+    pub fn set_offset(&mut self, offset: u64) {
+        self.offset = offset;
+    }
+
+    /// Forget what was loaded: the next load ends where the first one did.
+    fn rewind(&mut self) {
+        self.offset = self.start;
+    }
+}
+
+/// Create a file only this user can read under a fresh random name in the
+/// temp dir, so nobody else can plant a link there first or read it after.
+pub fn create_private_temp(prefix: &str, ext: &str) -> std::io::Result<(PathBuf, std::fs::File)> {
+    use std::hash::{BuildHasher, Hasher};
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    loop {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u32(std::process::id());
+        let name = format!("{}-{:016x}.{}", prefix, hasher.finish(), ext);
+        let path = std::env::temp_dir().join(name);
+        match options.open(&path) {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            result => return result.map(|file| (path, file)),
+        }
+    }
+}
+
+/// `--spill`: entries evicted from the ring buffer, appended raw (continuation
+/// lines included) in arrival order so a [`FileHistory`] over the same file
+/// can page them back in. A `tmp` spill file is removed on exit.
+pub struct SpillFile {
+    path: PathBuf,
+    file: std::fs::File,
+    len: u64,
+    lines: u64,
+    temporary: bool,
+}
+
+impl SpillFile {
+    /// Create the spill file; `tmp` picks one in the temp dir. An existing
+    /// file is refused rather than truncated, since it may be a followed log.
+    pub fn create(arg: &str) -> std::io::Result<Self> {
+        let temporary = arg == "tmp";
+        let (path, file) = if temporary {
+            create_private_temp("logpulse-spill", "log")?
+        } else {
+            let file = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(arg)?;
+            (PathBuf::from(arg), file)
+        };
+        Ok(Self {
+            path,
+            file,
+            len: 0,
+            lines: 0,
+            temporary,
+        })
+    }
+
+    pub fn append(&mut self, raw: &str, extra_lines: &[String]) -> std::io::Result<()> {
+        use std::io::Write;
+        let mut buf = String::with_capacity(raw.len() + 1);
+        for line in std::iter::once(raw).chain(extra_lines.iter().map(String::as_str)) {
+            buf.push_str(line);
+            buf.push('\n');
+        }
+        self.file.write_all(buf.as_bytes())?;
+        self.len += buf.len() as u64;
+        self.lines += 1 + extra_lines.len() as u64;
+        Ok(())
+    }
+
+    /// Bytes written so far: where paging back in starts.
+    pub fn bytes(&self) -> u64 {
+        self.len
+    }
+
+    pub fn lines(&self) -> u64 {
+        self.lines
+    }
+
+    /// Scrollback over everything spilled so far.
+    pub fn history(&self) -> FileHistory {
+        FileHistory::new(self.path.clone(), self.len)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Scrollback for a file source: local files are read in place, remote ones
+/// are fetched in the background so the UI never waits on the network.
+pub enum History {
+    Local(FileHistory),
+    Remote(RemoteFileHistory),
+    /// `--spill`: the spill file first, then the source's own scrollback
+    Spilled(FileHistory, Option<Box<History>>),
+}
+
+impl History {
+    pub fn has_more(&self) -> bool {
+        match self {
+            History::Local(h) => h.has_more(),
+            History::Remote(h) => h.has_more(),
+            History::Spilled(spill, older) => {
+                spill.has_more() || older.as_ref().is_some_and(|h| h.has_more())
+            }
+        }
+    }
+
+    /// Load `n` older lines, oldest first; `None` when they are fetched in
+    /// the background and come from [`History::take_loaded`] later.
+    pub fn load_older(&mut self, n: usize) -> Option<Vec<String>> {
+        match self {
+            History::Local(h) => Some(h.load_older(n)),
+            History::Remote(h) => {
+                h.request_older(n);
+                None
+            }
+            History::Spilled(spill, Some(older)) if !spill.has_more() => older.load_older(n),
+            History::Spilled(spill, _) => Some(spill.load_older(n)),
+        }
+    }
+
+    /// Lines of a finished background load, oldest first.
+    pub fn take_loaded(&mut self) -> Option<Vec<String>> {
+        match self {
+            History::Local(_) | History::Spilled(_, None) => None,
+            History::Remote(h) => h.take_loaded(),
+            History::Spilled(_, Some(older)) => older.take_loaded(),
+        }
+    }
+
+    /// The spill file's part, which follows the end of the file as it grows.
+    pub fn spill_mut(&mut self) -> Option<&mut FileHistory> {
+        match self {
+            History::Spilled(spill, _) => Some(spill),
+            _ => None,
+        }
+    }
+
+    /// Everything paged in was dropped: start again right above the buffer,
+    /// with the spill ending at `spill_end`.
+    pub fn restart_spilled(&mut self, spill_end: u64) {
+        let History::Spilled(spill, older) = self else {
+            return;
+        };
+        spill.set_offset(spill_end);
+        match older.as_deref_mut() {
+            Some(History::Local(h)) => h.rewind(),
+            Some(History::Remote(h)) => h.rewind(),
+            _ => {}
+        }
+    }
+}
+
+/// Lazy history for a file behind ssh or `docker exec`: older lines are
+/// fetched with `sed -n` by a background task when the user scrolls up.
+pub struct RemoteFileHistory {
+    /// Command that runs the shell script given as its last argument
+    argv: Vec<String>,
+    /// Command the script is handed to, quoted, by that shell, e.g.
+    /// `docker exec c sh -c` over ssh
+    wrap: Option<String>,
+    path: String,
+    /// 1-based line number of the oldest line loaded so far
+    first_line: u64,
+    /// `first_line` before anything older was loaded
+    start_line: u64,
+    /// Running load and the `first_line` to restore if it fails
+    pending: Option<(u64, oneshot::Receiver<Option<Vec<String>>>)>,
+}
+
+impl RemoteFileHistory {
+    /// Count the file's lines to find where the initial `tail -n` starts.
+    async fn probe(
+        argv: Vec<String>,
+        wrap: Option<String>,
+        path: &str,
+        tail_lines: usize,
+    ) -> Option<Self> {
+        let script = format!("wc -l < {}", shell_quote(path));
+        let out = run_remote_script(&argv, wrap.as_deref(), &script).await?;
+        let total: u64 = out.trim().parse().ok()?;
+        let first_line = total.saturating_sub(tail_lines as u64) + 1;
+        Some(Self {
+            argv,
+            wrap,
+            path: path.to_string(),
+            first_line,
+            start_line: first_line,
+            pending: None,
+        })
+    }
+
+    /// Forget what was loaded, dropping a load still running.
+    fn rewind(&mut self) {
+        self.first_line = self.start_line;
+        self.pending = None;
+    }
+
+    /// Older lines exist and no load is running.
+    pub fn has_more(&self) -> bool {
+        self.pending.is_none() && self.first_line > 1
+    }
+
+    /// Start fetching the `n` lines before what's loaded.
+    pub fn request_older(&mut self, n: usize) {
+        if !self.has_more() || n == 0 {
+            return;
+        }
+        let end = self.first_line - 1;
+        let start = end.saturating_sub(n as u64 - 1).max(1);
+        let script = format!("sed -n {},{}p {}", start, end, shell_quote(&self.path));
+        let argv = self.argv.clone();
+        let wrap = self.wrap.clone();
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let lines = run_remote_script(&argv, wrap.as_deref(), &script)
+                .await
+                .map(|out| out.lines().map(String::from).collect());
+            let _ = tx.send(lines);
+        });
+        self.pending = Some((self.first_line, rx));
+        self.first_line = start;
+    }
+
+    /// Lines of a finished load, oldest first. A failed load is undone so
+    /// scrolling up again retries it.
+    pub fn take_loaded(&mut self) -> Option<Vec<String>> {
+        let (previous, rx) = self.pending.as_mut()?;
+        let result = match rx.try_recv() {
+            Err(oneshot::error::TryRecvError::Empty) => return None,
+            Ok(result) => result,
+            Err(oneshot::error::TryRecvError::Closed) => None,
+        };
+        if result.is_none() {
+            self.first_line = *previous;
+        }
+        self.pending = None;
+        result
+    }
+}
+
+/// Run `script` through `argv`, returning stdout if it succeeded.
+async fn run_remote_script(argv: &[String], wrap: Option<&str>, script: &str) -> Option<String> {
+    let (program, args) = argv.split_first()?;
+    let script = match wrap {
+        Some(wrap) => format!("{} {}", wrap, shell_quote(script)),
+        None => script.to_string(),
+    };
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .arg(script)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Flagged entries of a local file, persisted next to it as `<file>.logpulse-flags`
+/// (a JSON list of raw-line hashes) so flags survive reopening the file.
+pub struct FlagStore {
+    log_path: PathBuf,
+    hashes: HashSet<u64>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct FlagFile {
+    hashes: Vec<String>,
+}
+
+impl FlagStore {
+    /// Load the flag file adjacent to `log_path`, starting empty if there is none.
+    pub fn load_for(log_path: &Path) -> Self {
+        let mut store = Self {
+            log_path: log_path.to_path_buf(),
+            hashes: HashSet::new(),
+        };
+        if let Ok(data) = std::fs::read_to_string(store.flags_path()) {
+            if let Ok(file) = serde_json::from_str::<FlagFile>(&data) {
+                store.hashes = file
+                    .hashes
+                    .iter()
+                    .filter_map(|h| u64::from_str_radix(h, 16).ok())
+                    .collect();
+            }
+        }
+        store
+    }
+
+    pub fn log_path(&self) -> &Path {
+        &self.log_path
+    }
+
+    fn flags_path(&self) -> PathBuf {
+        let mut name = self.log_path.as_os_str().to_owned();
+        name.push(".logpulse-flags");
+        PathBuf::from(name)
+    }
+
+    pub fn contains(&self, raw: &str) -> bool {
+        !self.hashes.is_empty() && self.hashes.contains(&line_hash(raw))
+    }
+
+    /// Record the flag state of a line and write the flag file.
+    pub fn set(&mut self, raw: &str, flagged: bool) -> std::io::Result<()> {
+        let hash = line_hash(raw);
+        if flagged {
+            self.hashes.insert(hash);
+        } else {
+            self.hashes.remove(&hash);
+        }
+        let mut hashes: Vec<String> = self.hashes.iter().map(|h| format!("{:016x}", h)).collect();
+        hashes.sort();
+        let json = serde_json::to_string(&FlagFile { hashes }).map_err(std::io::Error::other)?;
+        std::fs::write(self.flags_path(), json)
+    }
+}
+
+/// FNV-1a — stable across builds, unlike `DefaultHasher`.
+fn line_hash(raw: &str) -> u64 {
+    raw.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
 }
 
 // ---------------------------------------------------------------------------
 // Multi-file source (local)
 // ---------------------------------------------------------------------------
 
+/// Receiver, display name, and the lazy history / flag store for a single local file.
+type FileSource = (
+    mpsc::UnboundedReceiver<String>,
+    String,
+    Option<History>,
+    Option<FlagStore>,
+);
+
+/// Receiver, display name, and scrollback for sources that can load older lines.
+pub type SourceWithHistory = (mpsc::UnboundedReceiver<String>, String, Option<History>);
+
+/// Options that only apply to local file sources.
+#[derive(Clone, Copy, Default)]
+pub struct FileOpts {
+    /// Also follow files in subdirectories of directory arguments
+    pub recursive: bool,
+    /// --full: load whole files (up to `tail_lines`, the buffer size) and don't follow them
+    pub full: bool,
+    /// --poll: follow files by checking them on this interval instead of through inotify
+    pub poll: Option<Duration>,
+}
+
 pub async fn start_multi_file_source(
     paths: Vec<PathBuf>,
-) -> Result<
-    (mpsc::UnboundedReceiver<String>, String, Option<FileHistory>),
-    Box<dyn std::error::Error>,
-> {
+    file_opts: FileOpts,
+    src_opts: SourceOpts,
+) -> Result<FileSource, Box<dyn std::error::Error>> {
     let (tx, rx) = mpsc::unbounded_channel();
+    let recursive = file_opts.recursive;
+
+    let (files, roots) = expand_file_args(&paths, recursive)?;
+    if files.is_empty() && roots.is_empty() {
+        return Err("no log files to follow".into());
+    }
 
+    // Pipes (mkfifo, `<(cmd)`, /dev/stdin) can't be seeked or watched: stream them as they come
+    let (pipes, files): (Vec<PathBuf>, Vec<PathBuf>) = files.into_iter().partition(|f| is_pipe(f));
     let mut names: Vec<String> = Vec::new();
-    let mut mux = linemux::MuxedLines::new()?;
-    let mut history: Option<FileHistory> = None;
+    for pipe in pipes {
+        names.push(pipe.display().to_string());
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            // Opening blocks until the pipe has a writer
+            match tokio::fs::File::open(&pipe).await {
+                Ok(file) => forward_lines(file, &tx).await,
+                Err(e) => {
+                    let _ = tx.send(format!(">>> cannot read {}: {}", pipe.display(), e));
+                }
+            }
+        });
+    }
 
-    for path in &paths {
-        let path = path.canonicalize().unwrap_or(path.clone());
-        if !path.exists() {
-            return Err(format!("file not found: {}", path.display()).into());
+    // Polling needs no watcher: files are keyed by the path they were given as
+    let mut watcher = match file_opts.poll {
+        Some(_) => None,
+        None => Some(linemux::MuxedEvents::new()?),
+    };
+    let mut followers: HashMap<PathBuf, FollowedFile> = HashMap::new();
+    let mut known: HashSet<PathBuf> = HashSet::new();
+    let mut history: Option<History> = None;
+    let mut flags: Option<FlagStore> = None;
+    // History and flags only make sense for a single fixed file
+    let single = files.len() == 1 && roots.is_empty() && names.is_empty();
+
+    for path in &files {
+        names.push(file_display_name(path));
+
+        if file_opts.full {
+            let total = count_lines(path)?;
+            if total > src_opts.tail_lines {
+                let _ = tx.send(format!(
+                    ">>> {} has {} lines; only the last {} fit in the buffer (max_lines)",
+                    file_display_name(path),
+                    total,
+                    src_opts.tail_lines
+                ));
+            }
         }
 
-        let name = path
-            .file_name()
-            .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_else(|| "unknown".to_string());
-        names.push(name);
+        // Open the follower before reading the tail so nothing written in
+        // between is lost.
+        let follower = FollowedFile::open_at_end(path).await?;
 
-        // Read only the last TAIL_LINES lines to avoid loading huge files.
+        // Read only the last `tail_lines` lines to avoid loading huge files.
         // For single files, also track offset for lazy history loading.
-        if paths.len() == 1 {
-            if let Ok((lines, offset)) = read_tail_with_offset(&path, TAIL_LINES) {
+        if single {
+            if let Ok((lines, offset)) = read_tail_with_offset(path, src_opts.tail_lines) {
                 for line in &lines {
                     let _ = tx.send(line.clone());
                 }
-                history = Some(FileHistory::new(path.clone(), offset));
+                history = Some(History::Local(FileHistory::new(path.clone(), offset)));
             }
-        } else if let Ok(lines) = read_tail(&path, TAIL_LINES) {
+            flags = Some(FlagStore::load_for(path));
+        } else if let Ok(lines) = read_tail(path, src_opts.tail_lines) {
             for line in lines {
                 let _ = tx.send(line);
             }
         }
 
-        mux.add_file(&path).await?;
+        if !file_opts.full {
+            let watched = match watcher {
+                Some(ref mut watcher) => watcher.add_file(path).await?,
+                None => path.clone(),
+            };
+            followers.insert(watched, follower);
+            known.insert(path.clone());
+        }
     }
 
-    let display_name = if names.len() == 1 {
-        names[0].clone()
-    } else {
-        format!("{} files ({})", names.len(), names.join(", "))
+    let display_name = match (names.len(), roots.len()) {
+        (1, 0) => names[0].clone(),
+        (_, 1) if paths.len() == 1 => {
+            format!("{} ({} files)", paths[0].display(), names.len())
+        }
+        _ => format!("{} files ({})", names.len(), names.join(", ")),
     };
 
+    // Static files: everything is loaded, nothing to follow
+    if file_opts.full {
+        return Ok((rx, display_name, history, flags));
+    }
+
     tokio::spawn(async move {
+        // Every file followed so far, rotated-away ones included
+        let mut followed: HashSet<FileId> = followers.values().filter_map(|f| f.id).collect();
+        // With --poll every file is checked on the poll tick; the watcher's
+        // events are never awaited
+        let mut poll = tokio::time::interval(file_opts.poll.unwrap_or(RESCAN_INTERVAL));
+        poll.tick().await;
+        let mut rescan = tokio::time::interval(RESCAN_INTERVAL);
+        rescan.tick().await;
         loop {
-            match mux.next_line().await {
-                Ok(Some(line)) => {
-                    if tx.send(line.line().to_string()).is_err() {
-                        break;
+            tokio::select! {
+                event = next_watch_event(&mut watcher), if watcher.is_some() && !followers.is_empty() => {
+                    let Some(paths) = event else { return };
+                    for path in &paths {
+                        if let Some(follower) = followers.get_mut(path) {
+                            if !follow_file(watcher.as_mut(), follower, &tx).await {
+                                return;
+                            }
+                            followed.extend(follower.id);
+                        }
+                    }
+                }
+                _ = poll.tick(), if watcher.is_none() => {
+                    for follower in followers.values_mut() {
+                        if !follow_file(None, follower, &tx).await {
+                            return;
+                        }
+                        followed.extend(follower.id);
+                    }
+                }
+                _ = rescan.tick() => {
+                    // Catches rotations the watcher missed
+                    for follower in followers.values_mut() {
+                        if !follow_file(watcher.as_mut(), follower, &tx).await {
+                            return;
+                        }
+                        followed.extend(follower.id);
+                    }
+                    for path in roots.iter().flat_map(|r| r.scan(recursive)) {
+                        if !known.insert(path.clone()) {
+                            continue;
+                        }
+                        let Some(mut follower) = open_new_file(&path, &followed).await else {
+                            continue;
+                        };
+                        followed.extend(follower.id);
+                        let watched = match watcher {
+                            Some(ref mut watcher) => match watcher.add_file(&path).await {
+                                Ok(watched) => watched,
+                                Err(_) => continue,
+                            },
+                            None => path.clone(),
+                        };
+                        let notice = format!(">>> now watching {}", file_display_name(&path));
+                        if tx.send(notice).is_err() || !follower.read_new_lines(&tx).await {
+                            return;
+                        }
+                        followers.insert(watched, follower);
                     }
                 }
-                Ok(None) => break,
-                Err(_) => break,
             }
         }
     });
 
-    Ok((rx, display_name, history))
+    Ok((rx, display_name, history, flags))
 }
 
-// ---------------------------------------------------------------------------
-// Stdin source
-// ---------------------------------------------------------------------------
+/// How often followed files are checked for rotation and watched directories
+/// and glob patterns are rescanned for new files.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
 
-pub async fn start_stdin_source(
-) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
-    let (tx, rx) = mpsc::unbounded_channel();
+/// A directory or glob pattern from the command line that may gain files later.
+#[derive(Debug, PartialEq)]
+enum WatchRoot {
+    Dir(PathBuf),
+    Glob(String),
+}
 
-    tokio::spawn(async move {
-        let stdin = tokio::io::stdin();
-        let reader = BufReader::new(stdin);
-        let mut lines = reader.lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            if tx.send(line).is_err() {
-                break;
+impl WatchRoot {
+    /// Current log files under this root, canonicalized and sorted.
+    fn scan(&self, recursive: bool) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        match self {
+            WatchRoot::Dir(dir) => list_dir(dir, recursive, &mut files),
+            WatchRoot::Glob(pattern) => {
+                if let Ok(entries) = glob::glob(pattern) {
+                    files.extend(entries.flatten().filter(|p| is_log_file(p)));
+                }
             }
         }
-    });
+        let mut files: Vec<PathBuf> = files
+            .into_iter()
+            .filter_map(|p| p.canonicalize().ok())
+            .collect();
+        files.sort();
+        files
+    }
+}
 
-    Ok((rx, "stdin".to_string()))
+fn list_dir(dir: &Path, recursive: bool, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if recursive && !is_hidden(&path) {
+                list_dir(&path, recursive, out);
+            }
+        } else if is_log_file(&path) {
+            out.push(path);
+        }
+    }
 }
 
-// ---------------------------------------------------------------------------
-// Docker source — smart prefix match + auto-reconnect
-// ---------------------------------------------------------------------------
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+}
 
-/// Find a running container whose name starts with `prefix`.
-async fn find_container_by_prefix(prefix: &str) -> Option<String> {
-    let output = tokio::process::Command::new("docker")
-        .args([
-            "ps",
-            "--format",
-            "{{.Names}}",
+/// Regular, readable-as-text files: skips hidden files, compressed rotations
+/// and logpulse's own sidecar files.
+fn is_log_file(path: &Path) -> bool {
+    const SKIP_EXT: &[&str] = &[
+        "gz",
+        "bz2",
+        "xz",
+        "zst",
+        "zip",
+        "logpulse-flags",
+        "lpsession",
+    ];
+    if !path.is_file() || is_hidden(path) {
+        return false;
+    }
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    !SKIP_EXT.contains(&ext.as_str())
+}
+
+/// Expand command-line paths into the files to follow now and the roots to
+/// rescan for new files. Literal paths must exist; globs may match nothing yet.
+fn expand_file_args(
+    paths: &[PathBuf],
+    recursive: bool,
+) -> Result<(Vec<PathBuf>, Vec<WatchRoot>), String> {
+    let mut files = Vec::new();
+    let mut roots = Vec::new();
+    for path in paths {
+        let text = path.to_string_lossy();
+        let root = if path.is_dir() {
+            WatchRoot::Dir(path.clone())
+        } else if path.exists() {
+            check_file_kind(path)?;
+            // /proc/self/fd/0 and friends canonicalize to `pipe:[…]`, which can't be opened
+            if is_pipe(path) {
+                files.push(path.clone());
+            } else {
+                files.push(path.canonicalize().unwrap_or(path.clone()));
+            }
+            continue;
+        } else if text.contains(['*', '?', '[']) {
+            glob::Pattern::new(&text).map_err(|e| format!("bad glob {}: {}", text, e))?;
+            WatchRoot::Glob(text.to_string())
+        } else {
+            return Err(format!("file not found: {}", path.display()));
+        };
+        files.extend(root.scan(recursive));
+        roots.push(root);
+    }
+    let mut seen = HashSet::new();
+    files.retain(|f| seen.insert(f.clone()));
+    Ok((files, roots))
+}
+
+/// Whether `path` is a FIFO, as with `mkfifo`, `<(cmd)` or a piped /dev/stdin.
+#[cfg(unix)]
+fn is_pipe(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_pipe(_path: &Path) -> bool {
+    false
+}
+
+/// Reject paths that exist but can't be followed as a log, with a clearer
+/// message than the io error reading them would give.
+#[cfg(unix)]
+fn check_file_kind(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::FileTypeExt;
+    let Ok(meta) = std::fs::metadata(path) else {
+        return Ok(());
+    };
+    let file_type = meta.file_type();
+    let kind = if file_type.is_char_device() {
+        "a character device"
+    } else if file_type.is_block_device() {
+        "a block device"
+    } else if file_type.is_socket() {
+        "a socket"
+    } else {
+        return Ok(());
+    };
+    Err(format!(
+        "{} is {}, not a log file (pipe its output in instead: cmd | logpulse)",
+        path.display(),
+        kind
+    ))
+}
+
+#[cfg(not(unix))]
+fn check_file_kind(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+fn file_display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// A local file being followed from a byte position. Bytes are read raw and
+/// decoded per line, so invalid UTF-8 can't kill the follower.
+struct FollowedFile {
+    path: PathBuf,
+    reader: BufReader<tokio::fs::File>,
+    /// Device and inode of the open file, to notice the path being replaced
+    id: Option<FileId>,
+    /// Bytes consumed so far, to notice the file being truncated
+    pos: u64,
+    /// Bytes of a line that hasn't been terminated by `\n` yet.
+    partial: Vec<u8>,
+}
+
+impl FollowedFile {
+    async fn open_at_end(path: &std::path::Path) -> std::io::Result<Self> {
+        let mut follower = Self::open_at_start(path).await?;
+        follower.pos = follower.reader.seek(SeekFrom::End(0)).await?;
+        Ok(follower)
+    }
+
+    async fn open_at_start(path: &std::path::Path) -> std::io::Result<Self> {
+        let file = tokio::fs::File::open(path).await?;
+        let id = file_id(&file.metadata().await?);
+        Ok(Self {
+            path: path.to_path_buf(),
+            reader: BufReader::new(file),
+            id,
+            pos: 0,
+            partial: Vec::new(),
+        })
+    }
+
+    /// Send every complete line appended since the last call.
+    /// Returns false once the receiver is gone.
+    async fn read_new_lines(&mut self, tx: &mpsc::UnboundedSender<String>) -> bool {
+        loop {
+            match self.reader.read_until(b'\n', &mut self.partial).await {
+                Ok(0) | Err(_) => return true,
+                Ok(n) => {
+                    self.pos += n as u64;
+                    if !self.partial.ends_with(b"\n") {
+                        // Writer hasn't finished the line yet — wait for more
+                        return true;
+                    }
+                    if tx.send(decode_line(&self.partial)).is_err() {
+                        return false;
+                    }
+                    self.partial.clear();
+                }
+            }
+        }
+    }
+
+    /// Start over from the top if the file was rotated: replaced by a new
+    /// file (rename-and-recreate) or shrunk below our position (copytruncate).
+    /// Lines left in a renamed-away file are sent first. Returns true on rotation.
+    async fn reopen_if_rotated(&mut self, tx: &mpsc::UnboundedSender<String>) -> bool {
+        // Gone for now: the writer hasn't recreated it yet
+        let Ok(meta) = tokio::fs::metadata(&self.path).await else {
+            return false;
+        };
+        if file_id(&meta) != self.id {
+            self.read_new_lines(tx).await;
+            let Ok(fresh) = Self::open_at_start(&self.path).await else {
+                return false;
+            };
+            *self = fresh;
+            true
+        } else if meta.len() < self.pos {
+            if self.reader.seek(SeekFrom::Start(0)).await.is_err() {
+                return false;
+            }
+            self.pos = 0;
+            self.partial.clear();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Device and inode: the same file under another name after a rotation.
+type FileId = (u64, u64);
+
+#[cfg(unix)]
+fn file_id(meta: &std::fs::Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &std::fs::Metadata) -> Option<FileId> {
+    None
+}
+
+/// Open a file that appeared under a watched root from the start, so its
+/// first lines aren't lost, unless it is one already followed under another
+/// name (`access.log` rotated to `access.log.1`).
+async fn open_new_file(path: &Path, followed: &HashSet<FileId>) -> Option<FollowedFile> {
+    let follower = FollowedFile::open_at_start(path).await.ok()?;
+    match follower.id {
+        Some(id) if followed.contains(&id) => None,
+        _ => Some(follower),
+    }
+}
+
+/// Paths of the watcher's next event; `None` once it fails or ends.
+async fn next_watch_event(watcher: &mut Option<linemux::MuxedEvents>) -> Option<Vec<PathBuf>> {
+    let event = watcher.as_mut()?.next_event().await.ok()??;
+    Some(event.paths)
+}
+
+/// Read what `follower` gained, re-registering its path with the watcher
+/// (if any) after a rotation. Returns false once the receiver is gone.
+async fn follow_file(
+    watcher: Option<&mut linemux::MuxedEvents>,
+    follower: &mut FollowedFile,
+    tx: &mpsc::UnboundedSender<String>,
+) -> bool {
+    // The size check behind rotation detection doubles as the poll: a file
+    // that shrank is read again from the start
+    if follower.reopen_if_rotated(tx).await {
+        if let Some(watcher) = watcher {
+            let _ = watcher.add_file(&follower.path).await;
+        }
+        let notice = format!(">>> log rotated: {}", file_display_name(&follower.path));
+        if tx.send(notice).is_err() {
+            return false;
+        }
+    }
+    follower.read_new_lines(tx).await
+}
+
+// ---------------------------------------------------------------------------
+// Stdin source
+// ---------------------------------------------------------------------------
+
+pub async fn start_stdin_source(
+) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        forward_lines(tokio::io::stdin(), &tx).await;
+    });
+
+    Ok((rx, "stdin".to_string()))
+}
+
+// ---------------------------------------------------------------------------
+// Replay source — a file re-emitted at the pace it was written
+// ---------------------------------------------------------------------------
+
+/// Accepted replay speeds (--speed).
+pub const REPLAY_SPEED_RANGE: std::ops::RangeInclusive<f32> = 0.1..=100.0;
+/// Speeds the `<` / `>` keys step through, within `REPLAY_SPEED_RANGE`.
+const REPLAY_SPEEDS: [f32; 10] = [0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0];
+/// Delay before a line without a timestamp (continuation lines, mostly).
+const REPLAY_UNTIMED_GAP: Duration = Duration::from_millis(1);
+/// How often a replay waiting out a gap rechecks pause and speed.
+const REPLAY_TICK: Duration = Duration::from_millis(50);
+
+/// Replay state shared between the source task and the TUI. Pausing and
+/// speed changes apply in the middle of a long gap too.
+#[derive(Clone)]
+pub struct ReplayControl {
+    paused: Arc<AtomicBool>,
+    speed: Arc<AtomicU32>, // f32 bits
+}
+
+impl ReplayControl {
+    pub fn new(speed: f32) -> Self {
+        let control = Self {
+            paused: Arc::new(AtomicBool::new(false)),
+            speed: Arc::new(AtomicU32::new(0)),
+        };
+        control.set_speed(speed);
+        control
+    }
+
+    pub fn speed(&self) -> f32 {
+        f32::from_bits(self.speed.load(Ordering::Relaxed))
+    }
+
+    pub fn set_speed(&self, speed: f32) {
+        let speed = speed.clamp(*REPLAY_SPEED_RANGE.start(), *REPLAY_SPEED_RANGE.end());
+        self.speed.store(speed.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Step to the next faster or slower of `REPLAY_SPEEDS`.
+    pub fn step_speed(&self, faster: bool) {
+        let speed = self.speed();
+        let next = if faster {
+            REPLAY_SPEEDS.iter().find(|&&s| s > speed)
+        } else {
+            REPLAY_SPEEDS.iter().rev().find(|&&s| s < speed)
+        };
+        if let Some(&next) = next {
+            self.set_speed(next);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+}
+
+/// Emit the lines of `path` with the gaps between their timestamps, divided
+/// by the replay speed. Timestamps are read with `parser`, or the parser
+/// detected from the first lines.
+pub async fn start_replay_source(
+    path: PathBuf,
+    parser: Option<Box<dyn LogParser>>,
+    control: ReplayControl,
+) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let lines: Vec<String> = bytes
+        .split_inclusive(|&b| b == b'\n')
+        .map(decode_line)
+        .collect();
+    let parser = parser.unwrap_or_else(|| {
+        let sample: Vec<&str> = lines.iter().take(20).map(String::as_str).collect();
+        detect_parser(&sample)
+    });
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut prev: Option<DateTime<Local>> = None;
+        for line in lines {
+            let ts = parser
+                .parse(&line)
+                .timestamp
+                .as_deref()
+                .and_then(parse_timestamp);
+            let gap = match (prev, ts) {
+                // Out-of-order lines follow straight away
+                (Some(prev), Some(ts)) => (ts - prev).to_std().unwrap_or(Duration::ZERO),
+                (None, Some(_)) => Duration::ZERO,
+                (_, None) => REPLAY_UNTIMED_GAP,
+            };
+            prev = ts.or(prev);
+            replay_wait(gap, &control).await;
+            if tx.send(line).is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok((rx, path.display().to_string()))
+}
+
+/// Wait out `gap` of log time at the current speed, in short steps so a
+/// pause or speed change takes effect without waiting for the next line.
+async fn replay_wait(gap: Duration, control: &ReplayControl) {
+    let mut left = gap.as_secs_f64();
+    while left > 0.0 || control.is_paused() {
+        if control.is_paused() {
+            sleep(REPLAY_TICK).await;
+            continue;
+        }
+        let speed = f64::from(control.speed());
+        let step = (left / speed).min(REPLAY_TICK.as_secs_f64());
+        sleep(Duration::from_secs_f64(step)).await;
+        left -= step * speed;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Diff source — lines of one file compared with another
+// ---------------------------------------------------------------------------
+
+/// Emit every line of `a` (of `b` with `reverse`), marked with the
+/// [`DiffSide`] of its shape: lines are compared with numbers and UUIDs
+/// masked, so timestamps and IDs don't make every line unique. Both files
+/// are read once; nothing is followed.
+pub async fn start_diff_source(
+    a: PathBuf,
+    b: PathBuf,
+    reverse: bool,
+) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
+    let read = |path: PathBuf| async move {
+        let bytes = tokio::fs::read(&path)
+            .await
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let lines: Vec<String> = bytes
+            .split_inclusive(|&b| b == b'\n')
+            .map(decode_line)
+            .collect();
+        Ok::<_, String>(lines)
+    };
+    let (shown, other, only) = if reverse {
+        (
+            read(b.clone()).await?,
+            read(a.clone()).await?,
+            DiffSide::OnlyB,
+        )
+    } else {
+        (
+            read(a.clone()).await?,
+            read(b.clone()).await?,
+            DiffSide::OnlyA,
+        )
+    };
+    let other_shapes: HashSet<u64> = other.iter().map(|line| shape_hash(line)).collect();
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    for line in shown {
+        let side = if other_shapes.contains(&shape_hash(&line)) {
+            DiffSide::Both
+        } else {
+            only
+        };
+        let _ = tx.send(format!("{}{}", side.marker(), line));
+    }
+
+    let (first, second) = if reverse { (&b, &a) } else { (&a, &b) };
+    Ok((rx, format!("{} vs {}", first.display(), second.display())))
+}
+
+fn shape_hash(line: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    normalize_message(line).hash(&mut hasher);
+    hasher.finish()
+}
+
+// ---------------------------------------------------------------------------
+// Docker source — smart prefix match + auto-reconnect
+// ---------------------------------------------------------------------------
+
+/// Find a running container whose name starts with `prefix`.
+async fn find_container_by_prefix(runtime: Runtime, prefix: &str) -> Option<String> {
+    let output = tokio::process::Command::new(runtime.binary())
+        .args([
+            "ps",
+            "--format",
+            "{{.Names}}",
             "--filter",
             &format!("name={}", prefix),
             "--filter",
@@ -297,11 +1536,31 @@ async fn find_container_by_prefix(prefix: &str) -> Option<String> {
     names.lines().next().map(|s| s.to_string())
 }
 
+/// Runtime available on the remote host: docker, else podman, else nerdctl.
+async fn detect_remote_runtime(opts: &SshOpts) -> Runtime {
+    let mut args = ssh_base_args(opts);
+    args.push("command -v docker || command -v podman || command -v nerdctl".to_string());
+    let output = tokio::process::Command::new("ssh")
+        .args(&args)
+        .stderr(Stdio::null())
+        .output()
+        .await;
+    output
+        .ok()
+        .and_then(|o| Runtime::from_binary_path(&String::from_utf8_lossy(&o.stdout)))
+        .unwrap_or(Runtime::Docker)
+}
+
 /// Find a running container whose name starts with `prefix`, via SSH.
-async fn find_container_by_prefix_ssh(opts: &SshOpts, prefix: &str) -> Option<String> {
+/// Fails with ssh's own message when it couldn't connect (exit status 255).
+async fn find_container_by_prefix_ssh(
+    opts: &SshOpts,
+    runtime: Runtime,
+    prefix: &str,
+) -> Result<Option<String>, String> {
     let mut args = ssh_base_args(opts);
     args.extend([
-        "docker".to_string(),
+        runtime.binary().to_string(),
         "ps".to_string(),
         "--format".to_string(),
         "{{.Names}}".to_string(),
@@ -315,31 +1574,46 @@ async fn find_container_by_prefix_ssh(opts: &SshOpts, prefix: &str) -> Option<St
         .args(&args)
         .output()
         .await
-        .ok()?;
+        .map_err(|e| format!("ssh error: {}", e))?;
+    if output.status.code() == Some(255) {
+        return Err(ssh_error_message(
+            &String::from_utf8_lossy(&output.stderr),
+            output.status,
+        ));
+    }
 
     let names = String::from_utf8_lossy(&output.stdout);
-    names.lines().next().map(|s| s.to_string())
+    Ok(names.lines().next().map(|s| s.to_string()))
 }
 
 /// Stream docker logs from a specific container. Returns the child process.
 fn spawn_docker_logs(
+    runtime: Runtime,
     container: &str,
     file_path: Option<&str>,
+    src_opts: &SourceOpts,
 ) -> std::io::Result<tokio::process::Child> {
+    let mut log_args = vec!["logs".to_string(), "-f".to_string(), "--tail".to_string()];
+    log_args.push(src_opts.tail_lines.to_string());
+    if let Some(since) = src_opts.since {
+        log_args.push("--since".to_string());
+        log_args.push(since.docker_arg());
+    }
+    log_args.push(container.to_string());
     match file_path {
-        Some(fp) => tokio::process::Command::new("docker")
+        Some(fp) => tokio::process::Command::new(runtime.binary())
             .args([
                 "exec",
                 container,
                 "sh",
                 "-c",
-                &format!("tail -n +1 -f {}", fp),
+                &format!("tail -n {} -f {}", src_opts.tail_lines, shell_quote(fp)),
             ])
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
             .spawn(),
-        None => tokio::process::Command::new("docker")
-            .args(["logs", "-f", "--tail", "1000", container])
+        None => tokio::process::Command::new(runtime.binary())
+            .args(&log_args)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn(),
@@ -349,12 +1623,25 @@ fn spawn_docker_logs(
 /// Stream docker logs from a container via SSH.
 fn spawn_docker_logs_ssh(
     opts: &SshOpts,
+    runtime: Runtime,
     container: &str,
     file_path: Option<&str>,
+    src_opts: &SourceOpts,
 ) -> std::io::Result<tokio::process::Child> {
+    let bin = runtime.binary();
+    let since = src_opts
+        .since
+        .map(|s| format!(" --since {}", s.docker_arg()))
+        .unwrap_or_default();
     let docker_cmd = match file_path {
-        Some(fp) => format!("docker exec {} sh -c 'tail -n +1 -f {}'", container, fp),
-        None => format!("docker logs -f --tail 1000 {}", container),
+        Some(fp) => {
+            let tail = format!("tail -n {} -f {}", src_opts.tail_lines, shell_quote(fp));
+            format!("{} exec {} sh -c {}", bin, container, shell_quote(&tail))
+        }
+        None => format!(
+            "{} logs -f --tail {}{} {}",
+            bin, src_opts.tail_lines, since, container
+        ),
     };
     let mut args = ssh_base_args(opts);
     args.push(docker_cmd);
@@ -375,13 +1662,7 @@ async fn pipe_child_to_tx(
     if let Some(stdout) = child.stdout.take() {
         let tx_out = tx.clone();
         tokio::spawn(async move {
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                if tx_out.send(line).is_err() {
-                    break;
-                }
-            }
+            forward_lines(stdout, &tx_out).await;
         });
     }
 
@@ -389,13 +1670,7 @@ async fn pipe_child_to_tx(
         if let Some(stderr) = child.stderr.take() {
             let tx_err = tx.clone();
             tokio::spawn(async move {
-                let reader = BufReader::new(stderr);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    if tx_err.send(line).is_err() {
-                        break;
-                    }
-                }
+                forward_lines(stderr, &tx_err).await;
             });
         }
     }
@@ -406,9 +1681,19 @@ async fn pipe_child_to_tx(
 pub async fn start_docker_source(
     prefix: String,
     file_path: Option<String>,
-) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
+    src_opts: SourceOpts,
+) -> Result<SourceWithHistory, Box<dyn std::error::Error>> {
+    let runtime = Runtime::resolve(src_opts.runtime);
+    if !on_path(runtime.binary()) {
+        return Err(format!(
+            "'{}' not found in PATH (install docker, podman or nerdctl, or pass --runtime)",
+            runtime.binary()
+        )
+        .into());
+    }
+
     // Find container by prefix
-    let container = find_container_by_prefix(&prefix)
+    let container = find_container_by_prefix(runtime, &prefix)
         .await
         .ok_or_else(|| format!("no running container matching '{}'", prefix))?;
 
@@ -417,20 +1702,35 @@ pub async fn start_docker_source(
         None => format!("{} ({})", prefix, &container[..container.len().min(20)]),
     };
 
+    let history = match file_path {
+        Some(ref fp) => {
+            let argv = [runtime.binary(), "exec", &container, "sh", "-c"]
+                .map(String::from)
+                .to_vec();
+            RemoteFileHistory::probe(argv, None, fp, src_opts.tail_lines)
+                .await
+                .map(History::Remote)
+        }
+        None => None,
+    };
+
     let (tx, rx) = mpsc::unbounded_channel();
     let _ = tx.send(format!(">>> connected to container: {}", container));
+    let conn = src_opts.conn.clone();
+    conn.set(ConnState::Connected);
 
     let fp = file_path.clone();
     let prefix_owned = prefix.clone();
     tokio::spawn(async move {
         let mut current_container = container;
         loop {
-            let child = spawn_docker_logs(&current_container, fp.as_deref());
+            let child = spawn_docker_logs(runtime, &current_container, fp.as_deref(), &src_opts);
             if let Ok(child) = child {
                 pipe_child_to_tx(child, &tx, fp.is_none()).await;
             }
 
             // Container died — try to reconnect
+            conn.set(ConnState::Reconnecting);
             if tx
                 .send(">>> container stopped, reconnecting...".to_string())
                 .is_err()
@@ -438,25 +1738,26 @@ pub async fn start_docker_source(
                 break;
             }
 
-            let mut reconnected = false;
-            for _ in 0..150 {
-                // Try for 5 minutes
-                sleep(Duration::from_secs(2)).await;
-                if let Some(new_container) = find_container_by_prefix(&prefix_owned).await {
+            match reconnect_with_backoff(&src_opts, || {
+                find_container_by_prefix(runtime, &prefix_owned)
+            })
+            .await
+            {
+                Some(new_container) => {
+                    conn.set(ConnState::Connected);
                     let _ = tx.send(format!(">>> reconnected to container: {}", new_container));
                     current_container = new_container;
-                    reconnected = true;
+                }
+                None => {
+                    conn.set(ConnState::Disconnected);
+                    let _ = tx.send(">>> gave up reconnecting after 5 minutes".to_string());
                     break;
                 }
             }
-            if !reconnected {
-                let _ = tx.send(">>> gave up reconnecting after 5 minutes".to_string());
-                break;
-            }
         }
     });
 
-    Ok((rx, display_name))
+    Ok((rx, display_name, history))
 }
 
 // ---------------------------------------------------------------------------
@@ -469,11 +1770,86 @@ pub struct SshOpts {
     pub port: Option<u16>,
     pub key: Option<String>,
     pub jump: Option<String>,
+    /// Stop when the connection drops instead of reconnecting
+    pub no_reconnect: bool,
+    /// Set while the ssh process is running (shown in the header for multi-host)
+    pub connected: Arc<AtomicBool>,
+}
+
+impl SshOpts {
+    /// Refuse a user, host or jump host that ssh would take for an option
+    /// (`-oProxyCommand=...`).
+    pub fn check(&self) -> Result<(), String> {
+        let (user, host) = match self.target.rsplit_once('@') {
+            Some((user, host)) => (Some(user), host),
+            None => (None, self.target.as_str()),
+        };
+        for (what, value) in [
+            ("user", user),
+            ("host", Some(host)),
+            ("jump host", self.jump.as_deref()),
+        ] {
+            if value.is_some_and(|v| v.starts_with('-')) {
+                return Err(format!(
+                    "ssh: {} '{}' can't start with '-'",
+                    what,
+                    value.unwrap_or_default()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Same connection options for another host.
+    pub fn with_target(&self, target: String) -> Self {
+        Self {
+            target,
+            connected: Arc::new(AtomicBool::new(false)),
+            ..self.clone()
+        }
+    }
+}
+
+/// Short display name for an ssh target: `deploy@web1.corp.com` -> `web1`.
+/// IP addresses are kept whole.
+pub fn short_host(target: &str) -> &str {
+    let host = target.rsplit('@').next().unwrap_or(target);
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return host;
+    }
+    host.split('.').next().unwrap_or(host)
+}
+
+/// Merge per-host streams into one, prefixing each line with `host | `
+/// (the compose-style tag understood by `split_source_tag`).
+pub fn merge_tagged_streams(
+    streams: Vec<(String, mpsc::UnboundedReceiver<String>)>,
+) -> mpsc::UnboundedReceiver<String> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    for (tag, mut host_rx) in streams {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            while let Some(line) = host_rx.recv().await {
+                if tx.send(format!("{} | {}", tag, line)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    rx
 }
 
-/// Build the base ssh args: [-p port] [-i key] [-J jump] target
+/// Build the base ssh args: [-p port] [-i key] [-J jump] -- target
 fn ssh_base_args(opts: &SshOpts) -> Vec<String> {
-    let mut args = Vec::new();
+    // Keepalives make a dead connection (sleep, VPN flap) exit instead of hanging
+    let mut args: Vec<String> = [
+        "-o",
+        "ServerAliveInterval=15",
+        "-o",
+        "ServerAliveCountMax=3",
+    ]
+    .map(String::from)
+    .to_vec();
     if let Some(port) = opts.port {
         args.push("-p".to_string());
         args.push(port.to_string());
@@ -486,6 +1862,8 @@ fn ssh_base_args(opts: &SshOpts) -> Vec<String> {
         args.push("-J".to_string());
         args.push(jump.clone());
     }
+    // Nothing after this is read as an ssh option
+    args.push("--".to_string());
     args.push(opts.target.clone());
     args
 }
@@ -497,150 +1875,399 @@ fn ssh_base_args(opts: &SshOpts) -> Vec<String> {
 pub async fn start_ssh_file_source(
     opts: SshOpts,
     file_path: String,
-) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
+    src_opts: SourceOpts,
+) -> Result<SourceWithHistory, Box<dyn std::error::Error>> {
     let display_name = format!("{}:{}", opts.target, file_path);
     let (tx, rx) = mpsc::unbounded_channel();
 
-    let mut args = ssh_base_args(&opts);
-    args.push(format!("tail -n 1000 -f {}", file_path));
-
-    let mut child = tokio::process::Command::new("ssh")
-        .args(&args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()?;
-
-    let stdout = child.stdout.take().expect("stdout piped");
-    tokio::spawn(async move {
-        let reader = BufReader::new(stdout);
-        let mut lines = reader.lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            if tx.send(line).is_err() {
-                break;
-            }
-        }
-        let _ = child.wait().await;
-    });
-
-    Ok((rx, display_name))
-}
-
-pub async fn start_ssh_docker_source(
-    opts: SshOpts,
-    prefix: String,
-    file_path: Option<String>,
-) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
-    let container = find_container_by_prefix_ssh(&opts, &prefix)
+    let mut argv = vec!["ssh".to_string()];
+    argv.extend(ssh_base_args(&opts));
+    let history = RemoteFileHistory::probe(argv, None, &file_path, src_opts.tail_lines)
         .await
-        .ok_or_else(|| {
-            format!(
-                "no running container matching '{}' on {}",
-                prefix, opts.target
-            )
-        })?;
-
-    let display_name = match &file_path {
-        Some(fp) => format!("ssh://{}:{}:{}", opts.target, prefix, fp),
-        None => format!("ssh://{}:{}", opts.target, prefix),
-    };
-
-    let (tx, rx) = mpsc::unbounded_channel();
-    let _ = tx.send(format!(
-        ">>> connected via ssh to {}:{}",
-        opts.target, container
-    ));
+        .map(History::Remote);
 
-    let fp = file_path.clone();
-    let prefix_owned = prefix.clone();
+    let child = spawn_ssh_tail(&opts, &file_path, src_opts.tail_lines)?;
+    let mut child = ssh_startup(child).await?;
     tokio::spawn(async move {
-        let mut current_container = container;
+        let mut delay = src_opts.reconnect_initial;
+        let mut failing_since: Option<Instant> = None;
+        let conn = &src_opts.conn;
         loop {
-            let child = spawn_docker_logs_ssh(&opts, &current_container, fp.as_deref());
-            if let Ok(child) = child {
-                pipe_child_to_tx(child, &tx, fp.is_none()).await;
+            let started = Instant::now();
+            opts.connected.store(true, Ordering::Relaxed);
+            conn.set(ConnState::Connected);
+            pipe_ssh_child_to_tx(child, &tx, false).await;
+            opts.connected.store(false, Ordering::Relaxed);
+
+            if opts.no_reconnect {
+                conn.set(ConnState::Disconnected);
+                let _ = tx.send(">>> ssh connection closed".to_string());
+                break;
             }
-
+            conn.set(ConnState::Reconnecting);
             if tx
-                .send(">>> container stopped, reconnecting...".to_string())
+                .send(">>> ssh connection lost, reconnecting...".to_string())
                 .is_err()
             {
                 break;
             }
 
-            let mut reconnected = false;
-            for _ in 0..150 {
-                sleep(Duration::from_secs(2)).await;
-                if let Some(new_c) = find_container_by_prefix_ssh(&opts, &prefix_owned).await {
-                    let _ = tx.send(format!(">>> reconnected to {}:{}", opts.target, new_c));
-                    current_container = new_c;
-                    reconnected = true;
-                    break;
-                }
+            // A connection that stayed up for a while resets the backoff
+            if started.elapsed() > src_opts.reconnect_max {
+                delay = src_opts.reconnect_initial;
+                failing_since = None;
             }
-            if !reconnected {
-                let _ = tx.send(">>> gave up reconnecting after 5 minutes".to_string());
-                break;
+            let since = *failing_since.get_or_insert(started);
+
+            loop {
+                if since.elapsed() > RECONNECT_GIVE_UP {
+                    conn.set(ConnState::Disconnected);
+                    let _ = tx.send(">>> gave up reconnecting after 5 minutes".to_string());
+                    return;
+                }
+                sleep(delay).await;
+                delay = (delay * 2).min(src_opts.reconnect_max);
+                // Resume from the live end: the backlog is already on screen
+                match spawn_ssh_tail(&opts, &file_path, 0) {
+                    Ok(c) => {
+                        child = SshChild::from(c);
+                        break;
+                    }
+                    Err(e) => {
+                        if tx.send(format!(">>> ssh: {}", e)).is_err() {
+                            return;
+                        }
+                    }
+                }
             }
         }
     });
 
-    Ok((rx, display_name))
+    Ok((rx, display_name, history))
 }
 
-// ---------------------------------------------------------------------------
+/// Quote `s` as one word for a POSIX shell: `it's` → `'it'\''s'`.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+fn spawn_ssh_tail(
+    opts: &SshOpts,
+    file_path: &str,
+    tail_lines: usize,
+) -> std::io::Result<tokio::process::Child> {
+    let mut args = ssh_base_args(opts);
+    args.push(format!(
+        "tail -n {} -f {}",
+        tail_lines,
+        shell_quote(file_path)
+    ));
+    tokio::process::Command::new("ssh")
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+/// How long a new ssh child gets to print something or exit before it counts
+/// as connected; ssh that can't resolve, connect or authenticate exits sooner.
+const SSH_STARTUP_GRACE: Duration = Duration::from_secs(2);
+
+/// An ssh child with its stdout taken out, so output read while checking
+/// startup isn't lost.
+struct SshChild {
+    child: tokio::process::Child,
+    stdout: Option<BufReader<tokio::process::ChildStdout>>,
+}
+
+impl From<tokio::process::Child> for SshChild {
+    fn from(mut child: tokio::process::Child) -> Self {
+        let stdout = child.stdout.take().map(BufReader::new);
+        Self { child, stdout }
+    }
+}
+
+/// Tell "connected but quiet" from "failed immediately": a child that prints
+/// output or stays up for [`SSH_STARTUP_GRACE`] is fine; one that exits with
+/// an error status and no output fails with its stderr as the message.
+async fn ssh_startup(child: tokio::process::Child) -> Result<SshChild, String> {
+    let mut started = SshChild::from(child);
+    let has_output = tokio::select! {
+        has_output = stdout_ready(&mut started.stdout) => has_output,
+        _ = sleep(SSH_STARTUP_GRACE) => return Ok(started),
+    };
+    if has_output {
+        return Ok(started);
+    }
+    // Stdout closed without a line: wait for the exit status
+    let status = match tokio::time::timeout(SSH_STARTUP_GRACE, started.child.wait()).await {
+        Ok(Ok(status)) if !status.success() => status,
+        _ => return Ok(started),
+    };
+    let mut stderr = String::new();
+    if let Some(mut pipe) = started.child.stderr.take() {
+        let _ = tokio::io::AsyncReadExt::read_to_string(&mut pipe, &mut stderr).await;
+    }
+    Err(ssh_error_message(&stderr, status))
+}
+
+/// Wait for the first output on `stdout`: true once there is some, false at
+/// EOF. Never resolves when stdout isn't piped.
+async fn stdout_ready(stdout: &mut Option<BufReader<tokio::process::ChildStdout>>) -> bool {
+    match stdout {
+        Some(reader) => reader.fill_buf().await.is_ok_and(|buf| !buf.is_empty()),
+        None => std::future::pending().await,
+    }
+}
+
+/// `ssh error: ...` from a failed child's stderr (known-hosts chatter
+/// dropped, lines joined), or its exit status when stderr was empty.
+fn ssh_error_message(stderr: &str, status: std::process::ExitStatus) -> String {
+    let lines: Vec<&str> = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("Warning: Permanently added"))
+        .collect();
+    if lines.is_empty() {
+        format!("ssh error: exited with {}", status)
+    } else {
+        format!("ssh error: {}", lines.join("; "))
+    }
+}
+
+/// Forward an ssh child's stdout as log lines and its stderr as `>>> ssh:`
+/// notices, or as log lines too with `stderr_as_lines` (a container's own
+/// stderr from `docker logs`). Returns when the child exits.
+async fn pipe_ssh_child_to_tx(
+    started: SshChild,
+    tx: &mpsc::UnboundedSender<String>,
+    stderr_as_lines: bool,
+) {
+    let SshChild { mut child, stdout } = started;
+    let stderr_task = child.stderr.take().map(|stderr| {
+        let tx_err = tx.clone();
+        tokio::spawn(async move {
+            if stderr_as_lines {
+                forward_lines(stderr, &tx_err).await;
+            } else {
+                forward_lines_with(stderr, &tx_err, |line| ssh_stderr_notice(&line)).await;
+            }
+        })
+    });
+    if let Some(stdout) = stdout {
+        forward_lines(stdout, tx).await;
+    }
+    let _ = child.wait().await;
+    if let Some(task) = stderr_task {
+        let _ = task.await;
+    }
+}
+
+/// Turn an ssh stderr line into a feed notice, dropping blank lines and
+/// known-hosts chatter.
+fn ssh_stderr_notice(line: &str) -> Option<String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with("Warning: Permanently added") {
+        return None;
+    }
+    Some(format!(">>> ssh: {}", line))
+}
+
+pub async fn start_ssh_docker_source(
+    opts: SshOpts,
+    prefix: String,
+    file_path: Option<String>,
+    src_opts: SourceOpts,
+) -> Result<SourceWithHistory, Box<dyn std::error::Error>> {
+    let runtime = match src_opts.runtime {
+        Some(runtime) => runtime,
+        None => detect_remote_runtime(&opts).await,
+    };
+    let container = find_container_by_prefix_ssh(&opts, runtime, &prefix)
+        .await?
+        .ok_or_else(|| {
+            format!(
+                "no running container matching '{}' on {}",
+                prefix, opts.target
+            )
+        })?;
+
+    let display_name = match &file_path {
+        Some(fp) => format!("ssh://{}:{}:{}", opts.target, prefix, fp),
+        None => format!("ssh://{}:{}", opts.target, prefix),
+    };
+
+    let history = match file_path {
+        Some(ref fp) => {
+            let mut argv = vec!["ssh".to_string()];
+            argv.extend(ssh_base_args(&opts));
+            let wrap = format!("{} exec {} sh -c", runtime.binary(), container);
+            RemoteFileHistory::probe(argv, Some(wrap), fp, src_opts.tail_lines)
+                .await
+                .map(History::Remote)
+        }
+        None => None,
+    };
+
+    let child = spawn_docker_logs_ssh(&opts, runtime, &container, file_path.as_deref(), &src_opts)?;
+    let mut first = Some(ssh_startup(child).await?);
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let _ = tx.send(format!(
+        ">>> connected via ssh to {}:{}",
+        opts.target, container
+    ));
+
+    let fp = file_path.clone();
+    let prefix_owned = prefix.clone();
+    tokio::spawn(async move {
+        let mut current_container = container;
+        loop {
+            let child = first.take().or_else(|| {
+                spawn_docker_logs_ssh(&opts, runtime, &current_container, fp.as_deref(), &src_opts)
+                    .ok()
+                    .map(SshChild::from)
+            });
+            if let Some(child) = child {
+                opts.connected.store(true, Ordering::Relaxed);
+                src_opts.conn.set(ConnState::Connected);
+                pipe_ssh_child_to_tx(child, &tx, fp.is_none()).await;
+                opts.connected.store(false, Ordering::Relaxed);
+            }
+
+            if opts.no_reconnect {
+                src_opts.conn.set(ConnState::Disconnected);
+                let _ = tx.send(">>> container stopped".to_string());
+                break;
+            }
+            src_opts.conn.set(ConnState::Reconnecting);
+            if tx
+                .send(">>> container stopped, reconnecting...".to_string())
+                .is_err()
+            {
+                break;
+            }
+
+            let found = reconnect_with_backoff(&src_opts, || async {
+                find_container_by_prefix_ssh(&opts, runtime, &prefix_owned)
+                    .await
+                    .ok()
+                    .flatten()
+            })
+            .await;
+            match found {
+                Some(new_c) => {
+                    let _ = tx.send(format!(">>> reconnected to {}:{}", opts.target, new_c));
+                    current_container = new_c;
+                }
+                None => {
+                    src_opts.conn.set(ConnState::Disconnected);
+                    let _ = tx.send(">>> gave up reconnecting after 5 minutes".to_string());
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok((rx, display_name, history))
+}
+
+// ---------------------------------------------------------------------------
 // Kubernetes source
 // ---------------------------------------------------------------------------
 
+#[derive(Clone)]
+pub struct K8sOpts {
+    pub namespace: String,
+    pub container: Option<String>,
+    pub label: Option<String>,
+    pub context: Option<String>,
+    pub kubeconfig: Option<String>,
+    pub previous: bool,
+    pub all_containers: bool,
+}
+
+/// Build the base kubectl args: [--kubeconfig path] [--context name]
+fn kubectl_base_args(opts: &K8sOpts) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(ref kubeconfig) = opts.kubeconfig {
+        args.push("--kubeconfig".to_string());
+        args.push(kubeconfig.clone());
+    }
+    if let Some(ref context) = opts.context {
+        args.push("--context".to_string());
+        args.push(context.clone());
+    }
+    args
+}
+
 /// Find a pod by label selector in a namespace.
-async fn find_pod_by_label(namespace: &str, label: &str) -> Option<String> {
+/// Errors carry kubectl's own message (e.g. unknown context) when it fails.
+async fn find_pod_by_label(opts: &K8sOpts, label: &str) -> Result<String, String> {
+    let mut args = kubectl_base_args(opts);
+    args.extend([
+        "get".to_string(),
+        "pods".to_string(),
+        "-n".to_string(),
+        opts.namespace.clone(),
+        "-l".to_string(),
+        label.to_string(),
+        "--field-selector=status.phase=Running".to_string(),
+        "-o".to_string(),
+        "jsonpath={.items[0].metadata.name}".to_string(),
+    ]);
     let output = tokio::process::Command::new("kubectl")
-        .args([
-            "get",
-            "pods",
-            "-n",
-            namespace,
-            "-l",
-            label,
-            "--field-selector=status.phase=Running",
-            "-o",
-            "jsonpath={.items[0].metadata.name}",
-        ])
+        .args(&args)
         .output()
         .await
-        .ok()?;
+        .map_err(|e| format!("failed to run kubectl: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("kubectl: {}", stderr));
+    }
 
     let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
     if name.is_empty() {
-        None
+        Err(format!(
+            "no running pod matching label '{}' in namespace '{}'",
+            label, opts.namespace
+        ))
     } else {
-        Some(name)
+        Ok(name)
     }
 }
 
 pub async fn start_k8s_source(
+    opts: K8sOpts,
     pod: Option<String>,
-    namespace: String,
-    container: Option<String>,
-    label: Option<String>,
     file_path: Option<String>,
+    src_opts: SourceOpts,
 ) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
+    if (opts.previous || opts.all_containers) && file_path.is_some() {
+        return Err(
+            "--previous and --all-containers only apply to pod stdout, not files inside the pod"
+                .into(),
+        );
+    }
+
     // Resolve pod name
     let pod_name = if let Some(p) = pod {
         p
-    } else if let Some(lbl) = &label {
-        find_pod_by_label(&namespace, lbl).await.ok_or_else(|| {
-            format!(
-                "no running pod matching label '{}' in namespace '{}'",
-                lbl, namespace
-            )
-        })?
+    } else if let Some(lbl) = &opts.label {
+        find_pod_by_label(&opts, lbl).await?
     } else {
         return Err("either pod name or --label is required".into());
     };
 
+    let location = match &opts.context {
+        Some(ctx) => format!("{}/{}/{}", ctx, opts.namespace, pod_name),
+        None => format!("{}/{}", opts.namespace, pod_name),
+    };
     let display_name = match &file_path {
-        Some(fp) => format!("k8s:{}/{}:{}", namespace, pod_name, fp),
-        None => format!("k8s:{}/{}", namespace, pod_name),
+        Some(fp) => format!("k8s:{}:{}", location, fp),
+        None if opts.previous => format!("k8s:{} (previous)", location),
+        None => format!("k8s:{}", location),
     };
 
     let (tx, rx) = mpsc::unbounded_channel();
@@ -648,15 +2275,21 @@ pub async fn start_k8s_source(
     match file_path {
         Some(fp) => {
             // Read file inside pod via kubectl exec
-            let mut args = vec!["exec".to_string(), pod_name, "-n".to_string(), namespace];
-            if let Some(c) = container {
+            let mut args = kubectl_base_args(&opts);
+            args.extend([
+                "exec".to_string(),
+                pod_name,
+                "-n".to_string(),
+                opts.namespace,
+            ]);
+            if let Some(c) = opts.container {
                 args.push("-c".to_string());
                 args.push(c);
             }
             args.push("--".to_string());
             args.push("sh".to_string());
             args.push("-c".to_string());
-            args.push(format!("tail -n +1 -f {}", fp));
+            args.push(format!("tail -n +1 -f {}", shell_quote(&fp)));
 
             let mut child = tokio::process::Command::new("kubectl")
                 .args(&args)
@@ -665,28 +2298,34 @@ pub async fn start_k8s_source(
                 .spawn()?;
 
             let stdout = child.stdout.take().expect("stdout piped");
+            let conn = src_opts.conn.clone();
+            conn.set(ConnState::Connected);
             tokio::spawn(async move {
-                let reader = BufReader::new(stdout);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    if tx.send(line).is_err() {
-                        break;
-                    }
-                }
+                forward_lines(stdout, &tx).await;
+                conn.set(ConnState::Disconnected);
                 let _ = child.wait().await;
             });
         }
         None => {
-            // kubectl logs -f
-            let mut args = vec![
-                "logs".to_string(),
-                "-f".to_string(),
-                "--tail=1000".to_string(),
-                pod_name,
-                "-n".to_string(),
-                namespace,
-            ];
-            if let Some(c) = container {
+            // kubectl logs -f, or a one-shot dump of the previous terminated container
+            let previous = opts.previous;
+            let mut args = kubectl_base_args(&opts);
+            args.push("logs".to_string());
+            if previous {
+                args.push("--previous".to_string());
+            } else {
+                args.push("-f".to_string());
+                args.push(format!("--tail={}", src_opts.tail_lines));
+            }
+            if let Some(since) = src_opts.since {
+                args.push(since.kubectl_arg());
+            }
+            args.extend([pod_name, "-n".to_string(), opts.namespace]);
+            if opts.all_containers {
+                // --prefix tags each line with [pod/<pod>/<container>] (see split_source_tag)
+                args.push("--all-containers".to_string());
+                args.push("--prefix".to_string());
+            } else if let Some(c) = opts.container {
                 args.push("-c".to_string());
                 args.push(c);
             }
@@ -699,25 +2338,19 @@ pub async fn start_k8s_source(
 
             let stdout = child.stdout.take().expect("stdout piped");
             let tx2 = tx.clone();
+            let conn = src_opts.conn.clone();
+            conn.set(ConnState::Connected);
             tokio::spawn(async move {
-                let reader = BufReader::new(stdout);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    if tx.send(line).is_err() {
-                        break;
-                    }
+                forward_lines(stdout, &tx).await;
+                conn.set(ConnState::Disconnected);
+                if previous {
+                    let _ = tx.send(">>> previous container log ended".to_string());
                 }
             });
 
             if let Some(stderr) = child.stderr.take() {
                 tokio::spawn(async move {
-                    let reader = BufReader::new(stderr);
-                    let mut lines = reader.lines();
-                    while let Ok(Some(line)) = lines.next_line().await {
-                        if tx2.send(line).is_err() {
-                            break;
-                        }
-                    }
+                    forward_lines(stderr, &tx2).await;
                     let _ = child.wait().await;
                 });
             }
@@ -732,50 +2365,1420 @@ pub async fn start_k8s_source(
 // ---------------------------------------------------------------------------
 
 pub async fn start_compose_source(
-    service: String,
+    service: Option<String>,
     compose_file: Option<String>,
+    project_name: Option<String>,
+    src_opts: SourceOpts,
 ) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
-    let display_name = format!("compose:{}", service);
+    let display_name = match (&service, &project_name) {
+        (Some(svc), _) => format!("compose:{}", svc),
+        (None, Some(project)) => format!("compose:{} (all services)", project),
+        (None, None) => "compose (all services)".to_string(),
+    };
     let (tx, rx) = mpsc::unbounded_channel();
 
-    let mut args = vec!["compose".to_string()];
+    let (program, subcommand) = Runtime::resolve(src_opts.runtime).compose_command();
+    let mut args: Vec<String> = subcommand.into_iter().map(String::from).collect();
+    if let Some(p) = project_name {
+        args.push("-p".to_string());
+        args.push(p);
+    }
     if let Some(f) = compose_file {
         args.push("-f".to_string());
         args.push(f);
     }
-    args.extend(["logs", "-f", "--tail", "1000", "--no-log-prefix"].map(String::from));
-    args.push(service);
+    args.extend(["logs", "-f", "--tail"].map(String::from));
+    args.push(src_opts.tail_lines.to_string());
+    if let Some(since) = src_opts.since {
+        args.push("--since".to_string());
+        args.push(since.docker_arg());
+    }
+    args.push("--no-color".to_string());
+    // For the whole stack, keep the `svc-1  | ` prefix so each line can be
+    // attributed to its service (see split_source_tag)
+    if let Some(svc) = service {
+        args.push("--no-log-prefix".to_string());
+        args.push(svc);
+    }
 
-    let mut child = tokio::process::Command::new("docker")
+    let mut child = tokio::process::Command::new(program)
         .args(&args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .spawn()?;
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
 
     let stdout = child.stdout.take().expect("stdout piped");
     let stderr = child.stderr.take().expect("stderr piped");
 
     let tx2 = tx.clone();
+    let conn = src_opts.conn.clone();
+    conn.set(ConnState::Connected);
     tokio::spawn(async move {
-        let reader = BufReader::new(stdout);
-        let mut lines = reader.lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            if tx.send(line).is_err() {
-                break;
+        forward_lines(stdout, &tx).await;
+        conn.set(ConnState::Disconnected);
+    });
+
+    tokio::spawn(async move {
+        forward_lines(stderr, &tx2).await;
+        let _ = child.wait().await;
+    });
+
+    Ok((rx, display_name))
+}
+
+// ---------------------------------------------------------------------------
+// HTTP source — chunked text or Server-Sent Events
+// ---------------------------------------------------------------------------
+
+pub struct HttpOpts {
+    pub url: String,
+    /// Extra request headers as `Name: value`
+    pub headers: Vec<String>,
+    /// Skip TLS certificate verification
+    pub insecure: bool,
+}
+
+/// Longest line a streamed body may hold back; past this the line is dropped.
+const STREAM_MAX_LINE: usize = 1024 * 1024;
+
+/// Splits a streamed body into lines; for `text/event-stream` keeps only the
+/// `data:` payloads and remembers the last event id.
+struct StreamDecoder {
+    sse: bool,
+    buf: Vec<u8>,
+    /// Leading bytes of `buf` already known to hold no newline
+    scanned: usize,
+    /// Discarding the rest of an overlong line up to its newline
+    skipping: bool,
+    last_event_id: Option<String>,
+}
+
+impl StreamDecoder {
+    fn new(sse: bool) -> Self {
+        Self {
+            sse,
+            buf: Vec::new(),
+            scanned: 0,
+            skipping: false,
+            last_event_id: None,
+        }
+    }
+
+    /// Lines completed by `chunk`.
+    fn feed(&mut self, mut chunk: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.skipping {
+            let Some(end) = chunk.iter().position(|&b| b == b'\n') else {
+                return lines;
+            };
+            self.skipping = false;
+            chunk = &chunk[end + 1..];
+        }
+        let mut buf = std::mem::take(&mut self.buf);
+        buf.extend_from_slice(chunk);
+        let mut start = 0;
+        let mut scan = self.scanned;
+        while let Some(pos) = buf[scan..].iter().position(|&b| b == b'\n') {
+            let end = scan + pos;
+            if let Some(line) = self.decode(&buf[start..=end]) {
+                lines.push(line);
+            }
+            start = end + 1;
+            scan = start;
+        }
+        buf.drain(..start);
+        if buf.len() > STREAM_MAX_LINE {
+            buf.clear();
+            self.skipping = true;
+            lines.push(format!(
+                ">>> http: dropped a line longer than {} bytes",
+                STREAM_MAX_LINE
+            ));
+        }
+        self.scanned = buf.len();
+        self.buf = buf;
+        lines
+    }
+
+    /// Whatever is left once the body ends without a trailing newline.
+    fn finish(&mut self) -> Option<String> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let raw = std::mem::take(&mut self.buf);
+        self.decode(&raw)
+    }
+
+    fn decode(&mut self, raw: &[u8]) -> Option<String> {
+        let line = decode_line(raw);
+        if !self.sse {
+            return Some(line);
+        }
+        // Blank lines end an event; `event:`, `retry:` and `:` comments are ignored
+        let (field, value) = line.split_once(':')?;
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "data" => Some(value.to_string()),
+            "id" => {
+                self.last_event_id = Some(value.to_string());
+                None
             }
+            _ => None,
+        }
+    }
+}
+
+fn parse_header(header: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| format!("invalid header '{}': expected 'Name: value'", header))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|e| format!("invalid header name '{}': {}", name.trim(), e))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|e| format!("invalid value for header '{}': {}", name, e))?;
+    Ok((name, value))
+}
+
+pub async fn start_http_source(
+    opts: HttpOpts,
+    src_opts: SourceOpts,
+) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
+    let url = reqwest::Url::parse(&opts.url).map_err(|e| format!("{}: {}", opts.url, e))?;
+    let mut headers = HeaderMap::new();
+    for header in &opts.headers {
+        let (name, value) = parse_header(header)?;
+        headers.append(name, value);
+    }
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .connect_timeout(Duration::from_secs(10))
+        .tls_danger_accept_invalid_certs(opts.insecure)
+        .build()?;
+
+    let display_name = opts.url.clone();
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut delay = src_opts.reconnect_initial;
+        let mut failing_since: Option<Instant> = None;
+        let mut last_event_id: Option<String> = None;
+        loop {
+            let started = Instant::now();
+            let mut request = client.get(url.clone());
+            if let Some(ref id) = last_event_id {
+                request = request.header("Last-Event-ID", id);
+            }
+            let notice = match request.send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    src_opts.conn.set(ConnState::Connected);
+                    let sse = resp
+                        .headers()
+                        .get(CONTENT_TYPE)
+                        .and_then(|v| v.to_str().ok())
+                        .is_some_and(|v| v.starts_with("text/event-stream"));
+                    let mut decoder = StreamDecoder::new(sse);
+                    decoder.last_event_id = last_event_id.take();
+                    let result = forward_http_body(resp, &mut decoder, &tx).await;
+                    last_event_id = decoder.last_event_id;
+                    match result {
+                        Ok(true) => ">>> http stream ended, reconnecting...".to_string(),
+                        Ok(false) => return,
+                        Err(e) => format!(">>> http: {}, reconnecting...", error_chain(&e)),
+                    }
+                }
+                Ok(resp) => format!(">>> http: {}", resp.status()),
+                Err(e) => format!(">>> http: {}", error_chain(&e)),
+            };
+            src_opts.conn.set(ConnState::Reconnecting);
+            if tx.send(notice).is_err() {
+                return;
+            }
+
+            // A connection that stayed up for a while resets the backoff
+            if started.elapsed() > src_opts.reconnect_max {
+                delay = src_opts.reconnect_initial;
+                failing_since = None;
+            }
+            if failing_since.get_or_insert(started).elapsed() > RECONNECT_GIVE_UP {
+                src_opts.conn.set(ConnState::Disconnected);
+                let _ = tx.send(">>> gave up reconnecting after 5 minutes".to_string());
+                return;
+            }
+            sleep(delay).await;
+            delay = (delay * 2).min(src_opts.reconnect_max);
         }
     });
 
+    Ok((rx, display_name))
+}
+
+/// reqwest's own message hides the cause (DNS, refused, TLS...); append it.
+fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut msg = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        msg.push_str(": ");
+        msg.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    msg
+}
+
+/// Forward a response body line by line. Ok(false) once the receiver is gone.
+async fn forward_http_body(
+    mut resp: reqwest::Response,
+    decoder: &mut StreamDecoder,
+    tx: &mpsc::UnboundedSender<String>,
+) -> Result<bool, reqwest::Error> {
+    while let Some(chunk) = resp.chunk().await? {
+        for line in decoder.feed(&chunk) {
+            if tx.send(line).is_err() {
+                return Ok(false);
+            }
+        }
+    }
+    if let Some(line) = decoder.finish() {
+        if tx.send(line).is_err() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// ---------------------------------------------------------------------------
+// CloudWatch Logs source — polls FilterLogEvents through the aws CLI
+// ---------------------------------------------------------------------------
+
+/// How often the log group is polled once caught up.
+const CLOUDWATCH_POLL: Duration = Duration::from_secs(2);
+/// Backlog window when --since isn't given.
+const CLOUDWATCH_DEFAULT_SINCE: Duration = Duration::from_secs(600);
+/// How far back each poll re-reads at least, for events ingested late.
+const CLOUDWATCH_LATE_WINDOW: Duration = Duration::from_secs(120);
+/// The most the re-read window grows to while events keep arriving late.
+const CLOUDWATCH_MAX_LATE_WINDOW: Duration = Duration::from_secs(3600);
+/// Pages the startup backlog reads before skipping ahead to the live edge.
+const CLOUDWATCH_BACKLOG_PAGES: usize = 20;
+/// Throttled tries the startup backlog makes before giving up.
+const CLOUDWATCH_BACKLOG_RETRIES: u32 = 5;
+
+pub struct CloudWatchOpts {
+    pub log_group: String,
+    pub stream_prefix: Option<String>,
+    pub region: Option<String>,
+    /// Named profile; credentials otherwise come from the standard chain
+    pub profile: Option<String>,
+}
+
+impl CloudWatchOpts {
+    /// `aws logs filter-log-events` args for one page starting at `start_ms`.
+    fn filter_args(&self, start_ms: i64, next_token: Option<&str>) -> Vec<String> {
+        let mut args: Vec<String> = [
+            "logs",
+            "filter-log-events",
+            "--output",
+            "json",
+            "--no-paginate",
+            "--log-group-name",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        args.push(self.log_group.clone());
+        args.push("--start-time".to_string());
+        args.push(start_ms.to_string());
+        if let Some(ref prefix) = self.stream_prefix {
+            args.push("--log-stream-name-prefix".to_string());
+            args.push(prefix.clone());
+        }
+        if let Some(token) = next_token {
+            args.push("--next-token".to_string());
+            args.push(token.to_string());
+        }
+        if let Some(ref region) = self.region {
+            args.push("--region".to_string());
+            args.push(region.clone());
+        }
+        if let Some(ref profile) = self.profile {
+            args.push("--profile".to_string());
+            args.push(profile.clone());
+        }
+        args
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct CloudWatchEvent {
+    id: String,
+    stream: String,
+    timestamp: i64,
+    /// When CloudWatch received it; the gap to `timestamp` is how late it was
+    ingestion_time: Option<i64>,
+    message: String,
+}
+
+#[derive(Debug)]
+enum CloudWatchError {
+    Throttled,
+    Failed(String),
+}
+
+/// Parse one FilterLogEvents response into its events and the next page token.
+fn parse_filter_page(json: &str) -> Result<(Vec<CloudWatchEvent>, Option<String>), String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("unexpected aws output: {}", e))?;
+    let events = value["events"]
+        .as_array()
+        .map(|events| {
+            events
+                .iter()
+                .filter_map(|e| {
+                    Some(CloudWatchEvent {
+                        id: e["eventId"].as_str()?.to_string(),
+                        stream: e["logStreamName"].as_str().unwrap_or("-").to_string(),
+                        timestamp: e["timestamp"].as_i64()?,
+                        ingestion_time: e["ingestionTime"].as_i64(),
+                        message: e["message"].as_str().unwrap_or("").to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let next_token = value["nextToken"].as_str().map(str::to_string);
+    Ok((events, next_token))
+}
+
+/// Throttling shows up as ThrottlingException / "Rate exceeded" on stderr.
+fn is_throttled(stderr: &str) -> bool {
+    stderr.contains("ThrottlingException") || stderr.contains("Rate exceeded")
+}
+
+async fn fetch_filter_page(
+    opts: &CloudWatchOpts,
+    start_ms: i64,
+    next_token: Option<&str>,
+) -> Result<(Vec<CloudWatchEvent>, Option<String>), CloudWatchError> {
+    let output = tokio::process::Command::new("aws")
+        .args(opts.filter_args(start_ms, next_token))
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| CloudWatchError::Failed(format!("failed to run aws: {}", e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if is_throttled(&stderr) {
+            return Err(CloudWatchError::Throttled);
+        }
+        return Err(CloudWatchError::Failed(stderr));
+    }
+    parse_filter_page(&String::from_utf8_lossy(&output.stdout)).map_err(CloudWatchError::Failed)
+}
+
+/// Tracks the polling position. FilterLogEvents selects by event time, so
+/// each query reaches back a window from the newest event to catch events
+/// ingested late; the window grows to the longest delay seen (ingestion time
+/// minus event time) plus CLOUDWATCH_LATE_WINDOW, up to
+/// CLOUDWATCH_MAX_LATE_WINDOW. Ids already sent inside it are skipped.
+struct CloudWatchCursor {
+    /// Never query before this (the --since cutoff)
+    floor_ms: i64,
+    newest_ms: i64,
+    late_window_ms: i64,
+    seen: HashMap<String, i64>,
+}
+
+impl CloudWatchCursor {
+    fn new(cutoff_ms: i64) -> Self {
+        Self {
+            floor_ms: cutoff_ms,
+            newest_ms: cutoff_ms,
+            late_window_ms: CLOUDWATCH_LATE_WINDOW.as_millis() as i64,
+            seen: HashMap::new(),
+        }
+    }
+
+    fn query_start(&self) -> i64 {
+        (self.newest_ms - self.late_window_ms).max(self.floor_ms)
+    }
+
+    /// Leave the rest of the backlog unread and poll from `now_ms` on.
+    fn skip_to(&mut self, now_ms: i64) {
+        self.newest_ms = self.newest_ms.max(now_ms);
+    }
+
+    /// The events of a page not sent before, remembering them.
+    fn take_new(&mut self, events: Vec<CloudWatchEvent>) -> Vec<CloudWatchEvent> {
+        let fresh: Vec<CloudWatchEvent> = events
+            .into_iter()
+            .filter(|e| e.timestamp >= self.floor_ms && !self.seen.contains_key(&e.id))
+            .collect();
+        for event in &fresh {
+            self.seen.insert(event.id.clone(), event.timestamp);
+            self.newest_ms = self.newest_ms.max(event.timestamp);
+            if let Some(ingested) = event.ingestion_time {
+                let window = (ingested - event.timestamp)
+                    .saturating_add(CLOUDWATCH_LATE_WINDOW.as_millis() as i64)
+                    .min(CLOUDWATCH_MAX_LATE_WINDOW.as_millis() as i64);
+                self.late_window_ms = self.late_window_ms.max(window);
+            }
+        }
+        let horizon = self.query_start();
+        self.seen.retain(|_, ts| *ts >= horizon);
+        fresh
+    }
+}
+
+/// Lines for one event, tagged with the stream name; a multi-line message
+/// (stack trace) becomes several lines so it groups under its first one.
+fn cloudwatch_lines(event: &CloudWatchEvent) -> impl Iterator<Item = String> + '_ {
+    event
+        .message
+        .trim_end_matches(['\r', '\n'])
+        .lines()
+        .map(move |line| format!("{} | {}", event.stream, line))
+}
+
+pub async fn start_cloudwatch_source(
+    opts: CloudWatchOpts,
+    src_opts: SourceOpts,
+) -> Result<(mpsc::UnboundedReceiver<String>, String), Box<dyn std::error::Error>> {
+    if !on_path("aws") {
+        return Err("aws CLI not found on PATH (needed for the cloudwatch source)".into());
+    }
+    let cutoff = src_opts
+        .since
+        .unwrap_or(Since::Ago(CLOUDWATCH_DEFAULT_SINCE))
+        .cutoff()?;
+    let mut cursor = CloudWatchCursor::new(cutoff.timestamp_millis());
+
+    // Backlog: the pages since the cutoff, up to CLOUDWATCH_BACKLOG_PAGES,
+    // keeping the last tail_lines. Failing here (bad credentials, unknown
+    // group, throttled throughout) aborts before the TUI starts.
+    let mut backlog: std::collections::VecDeque<String> = std::collections::VecDeque::new();
+    // A page token is only valid with the query that returned it
+    let query_start = cursor.query_start();
+    let mut next_token: Option<String> = None;
+    let mut delay = src_opts.reconnect_initial;
+    let mut pages = 0;
+    let mut retries = 0;
+    loop {
+        match fetch_filter_page(&opts, query_start, next_token.as_deref()).await {
+            Ok((events, token)) => {
+                for event in cursor.take_new(events) {
+                    backlog.extend(cloudwatch_lines(&event));
+                    while backlog.len() > src_opts.tail_lines {
+                        backlog.pop_front();
+                    }
+                }
+                next_token = token;
+                pages += 1;
+                if next_token.is_none() {
+                    break;
+                }
+                if pages == CLOUDWATCH_BACKLOG_PAGES {
+                    cursor.skip_to(Local::now().timestamp_millis());
+                    backlog.push_back(format!(
+                        ">>> cloudwatch: backlog stopped after {} pages, following from now",
+                        pages
+                    ));
+                    break;
+                }
+            }
+            Err(CloudWatchError::Throttled) if retries < CLOUDWATCH_BACKLOG_RETRIES => {
+                retries += 1;
+                sleep(delay).await;
+                delay = (delay * 2).min(src_opts.reconnect_max);
+            }
+            Err(CloudWatchError::Throttled) => {
+                return Err(
+                    "cloudwatch: throttled while reading the backlog, try again later".into(),
+                )
+            }
+            Err(CloudWatchError::Failed(e)) => return Err(format!("cloudwatch: {}", e).into()),
+        }
+    }
+
+    let display_name = match opts.stream_prefix {
+        Some(ref prefix) => format!("{} ({}*)", opts.log_group, prefix),
+        None => opts.log_group.clone(),
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    for line in backlog {
+        let _ = tx.send(line);
+    }
+
     tokio::spawn(async move {
-        let reader = BufReader::new(stderr);
-        let mut lines = reader.lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            if tx2.send(line).is_err() {
-                break;
+        let mut delay = src_opts.reconnect_initial;
+        let mut failing_since: Option<Instant> = None;
+        let mut throttled = false;
+        let mut next_token: Option<String> = None;
+        let mut query_start = cursor.query_start();
+        loop {
+            if next_token.is_none() {
+                query_start = cursor.query_start();
+            }
+            match fetch_filter_page(&opts, query_start, next_token.as_deref()).await {
+                Ok((events, token)) => {
+                    src_opts.conn.set(ConnState::Connected);
+                    for event in cursor.take_new(events) {
+                        for line in cloudwatch_lines(&event) {
+                            if tx.send(line).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    delay = src_opts.reconnect_initial;
+                    failing_since = None;
+                    throttled = false;
+                    // More pages right away; once caught up, wait for new events
+                    next_token = token;
+                    if next_token.is_none() {
+                        sleep(CLOUDWATCH_POLL).await;
+                    }
+                    continue;
+                }
+                Err(CloudWatchError::Throttled) => {
+                    // Announce once per throttled spell, then back off quietly
+                    if !throttled {
+                        throttled = true;
+                        if tx
+                            .send(">>> cloudwatch: throttled, backing off".to_string())
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                }
+                Err(CloudWatchError::Failed(e)) => {
+                    src_opts.conn.set(ConnState::Reconnecting);
+                    if tx.send(format!(">>> cloudwatch: {}", e)).is_err() {
+                        return;
+                    }
+                }
+            }
+            // Throttled or failing, the source gives up after RECONNECT_GIVE_UP
+            if failing_since.get_or_insert_with(Instant::now).elapsed() > RECONNECT_GIVE_UP {
+                src_opts.conn.set(ConnState::Disconnected);
+                let _ = tx.send(">>> gave up retrying after 5 minutes".to_string());
+                return;
             }
+            sleep(delay).await;
+            delay = (delay * 2).min(src_opts.reconnect_max);
         }
-        let _ = child.wait().await;
     });
 
     Ok((rx, display_name))
 }
+
+/// Split a tagged line from a merged stream into (tag, message):
+/// - compose: `api-1  | message` -> `api-1`
+/// - kubectl `--prefix`: `[pod/web-7d9f/nginx] message` -> `nginx`
+///
+/// Returns None for lines without a prefix (e.g. the tool's own stderr output).
+pub fn split_source_tag(line: &str) -> Option<(&str, &str)> {
+    if let Some(rest) = line.strip_prefix("[pod/") {
+        let (path, body) = rest.split_once("] ")?;
+        let container = path.rsplit('/').next()?;
+        return Some((container, body));
+    }
+
+    let (head, body) = line.split_once('|')?;
+    let service = head.trim_end();
+    if service.is_empty() || service.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((service, body.strip_prefix(' ').unwrap_or(body)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_file_args_directories_and_globs() {
+        let dir = std::env::temp_dir().join(format!("logpulse-expand-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        for name in [
+            "a.log",
+            "b.log",
+            "old.log.gz",
+            "a.log.logpulse-flags",
+            "nested/c.log",
+        ] {
+            std::fs::write(dir.join(name), "line\n").unwrap();
+        }
+        let names = |files: &[PathBuf]| -> Vec<String> {
+            files.iter().map(|f| file_display_name(f)).collect()
+        };
+
+        let (files, roots) = expand_file_args(std::slice::from_ref(&dir), false).unwrap();
+        assert_eq!(names(&files), ["a.log", "b.log"]);
+        assert_eq!(roots, [WatchRoot::Dir(dir.clone())]);
+
+        let (files, _) = expand_file_args(std::slice::from_ref(&dir), true).unwrap();
+        assert_eq!(names(&files), ["a.log", "b.log", "c.log"]);
+
+        let pattern = dir.join("*.log");
+        let (files, roots) =
+            expand_file_args(&[pattern.clone(), dir.join("a.log")], false).unwrap();
+        assert_eq!(names(&files), ["a.log", "b.log"]);
+        assert_eq!(roots.len(), 1);
+
+        // A glob with no matches yet is watched; a missing literal path is an error
+        let (files, roots) = expand_file_args(&[dir.join("*.txt")], false).unwrap();
+        assert!(files.is_empty());
+        assert_eq!(roots.len(), 1);
+        assert!(expand_file_args(&[dir.join("missing.log")], false).is_err());
+
+        std::fs::write(dir.join("access.log.1"), "line\n").unwrap();
+        assert_eq!(names(&roots[0].scan(false)), Vec::<String>::new());
+        assert_eq!(names(&WatchRoot::Dir(dir.clone()).scan(false)).len(), 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn decode_line_strips_terminators() {
+        assert_eq!(decode_line(b"hello\n"), "hello");
+        assert_eq!(decode_line(b"hello\r\n"), "hello");
+        assert_eq!(decode_line(b"hello"), "hello");
+    }
+
+    #[test]
+    fn decode_line_replaces_invalid_utf8() {
+        assert_eq!(
+            decode_line(b"bad \xff\xfe byte\n"),
+            "bad \u{FFFD}\u{FFFD} byte"
+        );
+    }
+
+    #[tokio::test]
+    async fn forward_lines_survives_invalid_utf8() {
+        let input: &[u8] = b"first\nbad \xc3\x28 seq\nlast";
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        forward_lines(input, &tx).await;
+
+        assert_eq!(rx.try_recv().unwrap(), "first");
+        assert_eq!(rx.try_recv().unwrap(), "bad \u{FFFD}( seq");
+        assert_eq!(rx.try_recv().unwrap(), "last");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn ssh_stderr_survives_invalid_utf8() {
+        let input: &[u8] = b"Warning: Permanently added 'h'\nbad \xff byte\nConnection reset\n";
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        forward_lines_with(input, &tx, |line| ssh_stderr_notice(&line)).await;
+
+        assert_eq!(rx.try_recv().unwrap(), ">>> ssh: bad \u{FFFD} byte");
+        assert_eq!(rx.try_recv().unwrap(), ">>> ssh: Connection reset");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn since_durations_and_timestamps() {
+        assert_eq!(Since::parse("90s"), Ok(Since::Ago(Duration::from_secs(90))));
+        assert_eq!(
+            Since::parse("1h30m"),
+            Ok(Since::Ago(Duration::from_secs(5400)))
+        );
+        assert_eq!(Since::parse("2d").unwrap().docker_arg(), "172800s");
+        assert!(matches!(
+            Since::parse("2024-01-15T10:30:00Z"),
+            Ok(Since::Time(_))
+        ));
+        assert!(Since::parse("5 minutes").is_err());
+        assert!(Since::parse("10").is_err());
+        assert!(Since::parse("99999999d").is_err());
+        assert!(Since::parse("99999999999999999s").is_err());
+        assert!(Since::parse("999999999999999999d").is_err());
+        assert_eq!(Since::parse("5m").unwrap().kubectl_arg(), "--since=300s");
+    }
+
+    #[test]
+    fn runtime_from_probe_output() {
+        assert_eq!(
+            Runtime::from_binary_path("/usr/bin/podman\n"),
+            Some(Runtime::Podman)
+        );
+        assert_eq!(Runtime::from_binary_path("docker"), Some(Runtime::Docker));
+        assert_eq!(Runtime::from_binary_path(""), None);
+        assert_eq!(Runtime::resolve(Some(Runtime::Nerdctl)), Runtime::Nerdctl);
+    }
+
+    #[test]
+    fn short_host_names() {
+        assert_eq!(short_host("deploy@web1.corp.com"), "web1");
+        assert_eq!(short_host("web2"), "web2");
+        assert_eq!(short_host("root@10.0.1.50"), "10.0.1.50");
+    }
+
+    #[tokio::test]
+    async fn merged_streams_are_tagged() {
+        let (tx1, rx1) = mpsc::unbounded_channel();
+        let (tx2, rx2) = mpsc::unbounded_channel();
+        let mut merged = merge_tagged_streams(vec![("web1".into(), rx1), ("web2".into(), rx2)]);
+        tx1.send("hello".to_string()).unwrap();
+        drop(tx1);
+        drop(tx2);
+
+        let line = merged.recv().await.unwrap();
+        assert_eq!(line, "web1 | hello");
+        assert_eq!(split_source_tag(&line), Some(("web1", "hello")));
+        assert!(merged.recv().await.is_none());
+    }
+
+    #[test]
+    fn ssh_target_cannot_pass_options() {
+        let opts = |target: &str, jump: Option<&str>| SshOpts {
+            target: target.to_string(),
+            port: Some(2222),
+            key: None,
+            jump: jump.map(str::to_string),
+            no_reconnect: false,
+            connected: Default::default(),
+        };
+        let args = ssh_base_args(&opts("deploy@web1", None));
+        assert_eq!(&args[args.len() - 2..], ["--", "deploy@web1"]);
+
+        assert!(opts("deploy@web1", Some("bastion")).check().is_ok());
+        assert!(opts("-oProxyCommand=touch x #@web1", None).check().is_err());
+        assert!(opts("deploy@-oProxyCommand=x", None).check().is_err());
+        assert!(opts("-web1", None).check().is_err());
+        assert!(opts("web1", Some("-oProxyJump=x")).check().is_err());
+    }
+
+    #[test]
+    fn ssh_stderr_notices() {
+        assert_eq!(
+            ssh_stderr_notice("user@host: Permission denied (publickey)."),
+            Some(">>> ssh: user@host: Permission denied (publickey).".to_string())
+        );
+        assert_eq!(
+            ssh_stderr_notice(
+                "Warning: Permanently added 'host' (ED25519) to the list of known hosts."
+            ),
+            None
+        );
+        assert_eq!(ssh_stderr_notice("  "), None);
+    }
+
+    #[tokio::test]
+    async fn ssh_startup_reports_immediate_failures() {
+        let spawn = |script: &str| {
+            tokio::process::Command::new("sh")
+                .args(["-c", script])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap()
+        };
+        let err = ssh_startup(spawn(
+            "echo 'Warning: Permanently added x' >&2; echo 'user@x: Permission denied (publickey).' >&2; exit 255",
+        ))
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(err, "ssh error: user@x: Permission denied (publickey).");
+
+        // Output before exiting is a connection, and nothing read is lost
+        let started = ssh_startup(spawn("echo first; echo second")).await.unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        pipe_ssh_child_to_tx(started, &tx, false).await;
+        assert_eq!(rx.try_recv().unwrap(), "first");
+        assert_eq!(rx.try_recv().unwrap(), "second");
+    }
+
+    #[tokio::test]
+    async fn replay_keeps_the_gaps_between_timestamps() {
+        let path = std::env::temp_dir().join(format!("logpulse-replay-{}.log", std::process::id()));
+        std::fs::write(
+            &path,
+            "[2024-01-15 10:30:00] production.INFO: start\n\
+             #0 continuation\n\
+             [2024-01-15 10:30:01] production.INFO: one second later\n\
+             [2024-01-15 10:30:03] production.ERROR: two more\n",
+        )
+        .unwrap();
+        let control = ReplayControl::new(100.0);
+        control.step_speed(true);
+        assert_eq!(control.speed(), 100.0);
+        control.step_speed(false);
+        assert_eq!(control.speed(), 50.0);
+
+        // Paused before the first line: nothing comes until it resumes
+        control.set_paused(true);
+        let started = Instant::now();
+        let (mut rx, _) = start_replay_source(path.clone(), None, control.clone())
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(120)).await;
+        assert!(rx.try_recv().is_err());
+        control.set_paused(false);
+        assert!(rx.recv().await.unwrap().ends_with("start"));
+        assert_eq!(rx.recv().await.unwrap(), "#0 continuation");
+        assert!(rx.recv().await.unwrap().ends_with("one second later"));
+        assert!(rx.recv().await.unwrap().ends_with("two more"));
+        assert!(rx.recv().await.is_none());
+        // 3s of log time at 50x, plus the pause
+        assert!(started.elapsed() >= Duration::from_millis(180));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn diff_marks_lines_by_the_file_with_their_shape() {
+        use crate::app::LogLevel;
+        use crate::parser::{DiffParser, PlainParser};
+
+        let dir = std::env::temp_dir();
+        let a = dir.join(format!("logpulse-diff-a-{}.log", std::process::id()));
+        let b = dir.join(format!("logpulse-diff-b-{}.log", std::process::id()));
+        std::fs::write(&a, "10:30:01 user 42 logged in\n10:30:02 cache miss\n").unwrap();
+        std::fs::write(&b, "11:00:00 user 7 logged in\n11:00:01 disk full\n").unwrap();
+        let parser = DiffParser::new(Box::new(PlainParser));
+        let parse_all = |mut rx: mpsc::UnboundedReceiver<String>| {
+            let mut entries = Vec::new();
+            while let Ok(line) = rx.try_recv() {
+                let entry = parser.parse(&line);
+                entries.push((entry.raw, entry.level));
+            }
+            entries
+        };
+
+        let (rx, name) = start_diff_source(a.clone(), b.clone(), false)
+            .await
+            .unwrap();
+        assert!(name.contains(" vs "));
+        assert_eq!(
+            parse_all(rx),
+            [
+                ("10:30:01 user 42 logged in".to_string(), LogLevel::Trace),
+                ("10:30:02 cache miss".to_string(), LogLevel::Error),
+            ]
+        );
+        let (rx, _) = start_diff_source(a.clone(), b.clone(), true).await.unwrap();
+        assert_eq!(
+            parse_all(rx),
+            [
+                ("11:00:00 user 7 logged in".to_string(), LogLevel::Trace),
+                ("11:00:01 disk full".to_string(), LogLevel::Info),
+            ]
+        );
+        std::fs::remove_file(&a).unwrap();
+        std::fs::remove_file(&b).unwrap();
+    }
+
+    #[test]
+    fn spill_tmp_is_a_fresh_private_file() {
+        let first = SpillFile::create("tmp").unwrap();
+        let second = SpillFile::create("tmp").unwrap();
+        assert_ne!(first.path, second.path);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&first.path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let path = first.path.clone();
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn spill_refuses_an_existing_file() {
+        let path = std::env::temp_dir().join(format!("logpulse-spill-{}.log", std::process::id()));
+        std::fs::write(&path, "keep me\n").unwrap();
+        let err = SpillFile::create(path.to_str().unwrap()).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn tail_start_finds_lines_across_chunks() {
+        let path = std::env::temp_dir().join(format!("logpulse-tail-{}.log", std::process::id()));
+        // Lines of 1000 bytes, so the scan crosses several 64K chunks
+        let line = |i: usize| format!("{:04} {}", i, "x".repeat(994));
+        let text: String = (0..300).map(|i| line(i) + "\n").collect();
+        std::fs::write(&path, &text).unwrap();
+        let mut file = std::fs::File::open(&path).unwrap();
+        let len = text.len() as u64;
+
+        assert_eq!(tail_start(&mut file, len, 0).unwrap(), len);
+        assert_eq!(tail_start(&mut file, len, 1).unwrap(), len - 1000);
+        assert_eq!(tail_start(&mut file, len, 150).unwrap(), 150 * 1000);
+        assert_eq!(tail_start(&mut file, len, 300).unwrap(), 0);
+        assert_eq!(tail_start(&mut file, len, 5000).unwrap(), 0);
+
+        // Without a final newline the last, unterminated line counts
+        let len = len - 1;
+        assert_eq!(tail_start(&mut file, len, 1).unwrap(), len - 999);
+
+        // The single-file path starts its history right where the tail does
+        let (lines, offset) = read_tail_with_offset(&path, 150).unwrap();
+        assert_eq!(offset, 150 * 1000);
+        assert_eq!(lines.len(), 150);
+        assert_eq!(lines[0], line(150));
+        assert_eq!(lines[149], line(299));
+        assert_eq!(
+            read_tail_with_offset(&path, 0).unwrap(),
+            (Vec::new(), 300 * 1000)
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn followed_file_waits_for_complete_lines() {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("logpulse-follow-{}.log", std::process::id()));
+        std::fs::write(&path, "old line\n").unwrap();
+        let mut follower = FollowedFile::open_at_end(&path).await.unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(b"new \xff line\npart").unwrap();
+        assert!(follower.read_new_lines(&tx).await);
+        assert_eq!(rx.try_recv().unwrap(), "new \u{FFFD} line");
+        assert!(rx.try_recv().is_err());
+
+        file.write_all(b"ial\n").unwrap();
+        assert!(follower.read_new_lines(&tx).await);
+        assert_eq!(rx.try_recv().unwrap(), "partial");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn fifo_is_streamed_instead_of_tailed() {
+        let path = std::env::temp_dir().join(format!("logpulse-fifo-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let made = std::process::Command::new("mkfifo").arg(&path).status();
+        if !made.is_ok_and(|s| s.success()) {
+            return;
+        }
+        assert!(is_pipe(&path));
+
+        let (mut rx, name, history, flags) = start_multi_file_source(
+            vec![path.clone()],
+            FileOpts::default(),
+            SourceOpts::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(name, path.display().to_string());
+        assert!(history.is_none() && flags.is_none());
+
+        let writer = path.clone();
+        std::thread::spawn(move || std::fs::write(writer, "one\ntwo\n").unwrap());
+        assert_eq!(rx.recv().await.unwrap(), "one");
+        assert_eq!(rx.recv().await.unwrap(), "two");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn character_devices_are_rejected_clearly() {
+        let err = expand_file_args(&[PathBuf::from("/dev/null")], false).unwrap_err();
+        assert!(err.contains("is a character device"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn full_mode_loads_whole_file_without_following() {
+        let path = std::env::temp_dir().join(format!("logpulse-full-{}.log", std::process::id()));
+        std::fs::write(&path, "one\ntwo\nthree\nfour").unwrap();
+        assert_eq!(count_lines(&path).unwrap(), 4);
+
+        let opts = SourceOpts {
+            tail_lines: 3,
+            ..SourceOpts::default()
+        };
+        let file_opts = FileOpts {
+            full: true,
+            ..FileOpts::default()
+        };
+        let (mut rx, _, _, _) = start_multi_file_source(vec![path.clone()], file_opts, opts)
+            .await
+            .unwrap();
+        let mut lines = Vec::new();
+        while let Some(line) = rx.recv().await {
+            lines.push(line);
+        }
+        assert!(lines[0].starts_with(">>> "));
+        assert!(lines[0].contains("has 4 lines"));
+        assert_eq!(lines[1..], ["two", "three", "four"]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn poll_mode_follows_appends_and_truncation() {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("logpulse-poll-{}.log", std::process::id()));
+        std::fs::write(&path, "old\n").unwrap();
+        let file_opts = FileOpts {
+            poll: Some(Duration::from_millis(10)),
+            ..FileOpts::default()
+        };
+        let (mut rx, _, _, _) =
+            start_multi_file_source(vec![path.clone()], file_opts, SourceOpts::default())
+                .await
+                .unwrap();
+        assert_eq!(rx.recv().await.unwrap(), "old");
+
+        let append = |text: &str| {
+            std::fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .unwrap()
+                .write_all(text.as_bytes())
+                .unwrap();
+        };
+        async fn recv(rx: &mut mpsc::UnboundedReceiver<String>) -> String {
+            tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .unwrap()
+                .unwrap()
+        }
+        append("first\nsecond\n");
+        assert_eq!(recv(&mut rx).await, "first");
+        assert_eq!(recv(&mut rx).await, "second");
+
+        // Shrunk below the offset: read again from the top
+        std::fs::write(&path, "new\n").unwrap();
+        assert!(recv(&mut rx).await.starts_with(">>> log rotated"));
+        assert_eq!(recv(&mut rx).await, "new");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn remote_history_fetches_older_chunks() {
+        let path = std::env::temp_dir().join(format!("logpulse-remote-{}.log", std::process::id()));
+        let lines: Vec<String> = (1..=10).map(|i| format!("line {}", i)).collect();
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        // `sh -c <script>` stands in for ssh / docker exec
+        let argv = vec!["sh".to_string(), "-c".to_string()];
+        let path_str = path.display().to_string();
+        let mut history = RemoteFileHistory::probe(argv, None, &path_str, 3)
+            .await
+            .unwrap();
+        assert!(history.has_more());
+
+        async fn wait_loaded(history: &mut RemoteFileHistory) -> Vec<String> {
+            loop {
+                if let Some(lines) = history.take_loaded() {
+                    return lines;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+        }
+        history.request_older(4);
+        assert!(!history.has_more());
+        assert_eq!(
+            wait_loaded(&mut history).await,
+            ["line 4", "line 5", "line 6", "line 7"]
+        );
+        history.request_older(4);
+        assert_eq!(
+            wait_loaded(&mut history).await,
+            ["line 1", "line 2", "line 3"]
+        );
+        assert!(!history.has_more());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn remote_history_quotes_the_path() {
+        let path = std::env::temp_dir().join(format!(
+            "logpulse remote 'q' $(id) {}.log",
+            std::process::id()
+        ));
+        std::fs::write(&path, "line 1\nline 2\nline 3\n").unwrap();
+        let path_str = path.display().to_string();
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+
+        // Handed straight to the shell, then through a second `sh -c` as
+        // `docker exec c sh -c` does over ssh
+        for wrap in [None, Some("sh -c".to_string())] {
+            let argv = vec!["sh".to_string(), "-c".to_string()];
+            let mut history = RemoteFileHistory::probe(argv, wrap, &path_str, 1)
+                .await
+                .unwrap();
+            history.request_older(5);
+            let lines = loop {
+                if let Some(lines) = history.take_loaded() {
+                    break lines;
+                }
+                sleep(Duration::from_millis(10)).await;
+            };
+            assert_eq!(lines, ["line 1", "line 2"]);
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn rotated_file_is_not_followed_again() {
+        let dir = std::env::temp_dir().join(format!("logpulse-rescan-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("access.log");
+        std::fs::write(&path, "already shown\n").unwrap();
+        let follower = FollowedFile::open_at_end(&path).await.unwrap();
+        let followed: HashSet<FileId> = follower.id.into_iter().collect();
+
+        let rotated = dir.join("access.log.1");
+        std::fs::rename(&path, &rotated).unwrap();
+        std::fs::write(&path, "new\n").unwrap();
+        #[cfg(unix)]
+        assert!(open_new_file(&rotated, &followed).await.is_none());
+        let fresh = dir.join("error.log");
+        std::fs::write(&fresh, "first\n").unwrap();
+        let mut opened = open_new_file(&fresh, &followed).await.unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        opened.read_new_lines(&tx).await;
+        assert_eq!(rx.try_recv().unwrap(), "first");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn followed_file_survives_rotation() {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("logpulse-rotate-{}.log", std::process::id()));
+        std::fs::write(&path, "before\n").unwrap();
+        let mut follower = FollowedFile::open_at_end(&path).await.unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        // copytruncate: same file, shrunk below our position
+        std::fs::write(&path, "").unwrap();
+        assert!(follower.reopen_if_rotated(&tx).await);
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"after truncate\n")
+            .unwrap();
+        assert!(!follower.reopen_if_rotated(&tx).await);
+        follower.read_new_lines(&tx).await;
+        assert_eq!(rx.try_recv().unwrap(), "after truncate");
+
+        // rename and recreate: the tail of the old file is still delivered
+        let rotated = path.with_extension("log.1");
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"last old line\n")
+            .unwrap();
+        std::fs::rename(&path, &rotated).unwrap();
+        std::fs::write(&path, "first new line\n").unwrap();
+        assert!(follower.reopen_if_rotated(&tx).await);
+        follower.read_new_lines(&tx).await;
+        assert_eq!(rx.try_recv().unwrap(), "last old line");
+        assert_eq!(rx.try_recv().unwrap(), "first new line");
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&rotated);
+    }
+
+    #[test]
+    fn split_source_tag_extracts_service() {
+        assert_eq!(
+            split_source_tag("api-1     | GET /health 200"),
+            Some(("api-1", "GET /health 200"))
+        );
+        assert_eq!(split_source_tag("db-1  |"), Some(("db-1", "")));
+        assert_eq!(split_source_tag("web-1  | a | b"), Some(("web-1", "a | b")));
+        assert_eq!(split_source_tag("no prefix here"), None);
+        assert_eq!(split_source_tag("two words | x"), None);
+    }
+
+    #[test]
+    fn split_source_tag_extracts_kubectl_container() {
+        assert_eq!(
+            split_source_tag("[pod/web-7d9f/nginx] GET / 200"),
+            Some(("nginx", "GET / 200"))
+        );
+        assert_eq!(split_source_tag("[pod/web-7d9f/nginx]"), None);
+    }
+
+    #[test]
+    fn flag_store_round_trips() {
+        let path = std::env::temp_dir().join(format!("logpulse-flags-{}.log", std::process::id()));
+        let mut store = FlagStore::load_for(&path);
+        store.set("ERROR boom", true).unwrap();
+        store.set("INFO fine", true).unwrap();
+        store.set("INFO fine", false).unwrap();
+
+        let reloaded = FlagStore::load_for(&path);
+        assert!(reloaded.contains("ERROR boom"));
+        assert!(!reloaded.contains("INFO fine"));
+
+        let _ = std::fs::remove_file(reloaded.flags_path());
+    }
+
+    #[test]
+    fn sse_decoder_keeps_data_and_event_id() {
+        let mut decoder = StreamDecoder::new(true);
+        let lines = decoder.feed(b": keepalive\nevent: log\nid: 41\ndata: first\n\nda");
+        assert_eq!(lines, ["first"]);
+        assert_eq!(decoder.feed(b"ta:second\r\n"), ["second"]);
+        assert_eq!(decoder.last_event_id.as_deref(), Some("41"));
+
+        let mut plain = StreamDecoder::new(false);
+        assert_eq!(plain.feed(b"a: b\nhalf"), ["a: b"]);
+        assert_eq!(plain.finish().as_deref(), Some("half"));
+    }
+
+    #[test]
+    fn stream_decoder_drops_overlong_lines() {
+        let mut decoder = StreamDecoder::new(false);
+        let chunk = vec![b'x'; 64 * 1024];
+        let mut notices = Vec::new();
+        for _ in 0..(STREAM_MAX_LINE / chunk.len() + 1) {
+            notices.extend(decoder.feed(&chunk));
+        }
+        assert_eq!(
+            notices,
+            [format!(
+                ">>> http: dropped a line longer than {} bytes",
+                STREAM_MAX_LINE
+            )]
+        );
+        // The rest of the long line is skipped up to its newline
+        assert!(decoder.feed(&chunk).is_empty());
+        assert_eq!(decoder.feed(b"xx\nnext\nha"), ["next"]);
+        assert_eq!(decoder.finish().as_deref(), Some("ha"));
+    }
+
+    #[tokio::test]
+    async fn http_source_resumes_sse_and_reports_errors() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/stream", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            let responses = [
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\nid: 7\ndata: hello\n\n",
+                "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            ];
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                requests.push(String::from_utf8_lossy(&buf[..n]).to_lowercase());
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+
+        let opts = HttpOpts {
+            url,
+            headers: vec!["X-Token: secret".to_string()],
+            insecure: false,
+        };
+        let src_opts = SourceOpts {
+            reconnect_initial: Duration::from_millis(10),
+            ..SourceOpts::default()
+        };
+        let conn = src_opts.conn.clone();
+        assert_eq!(conn.get(), None);
+        let (mut rx, _) = start_http_source(opts, src_opts).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), "hello");
+        assert!(rx
+            .recv()
+            .await
+            .unwrap()
+            .starts_with(">>> http stream ended"));
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            ">>> http: 503 Service Unavailable"
+        );
+        assert_eq!(conn.get(), Some(ConnState::Reconnecting));
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains("x-token: secret"));
+        assert!(requests[1].contains("last-event-id: 7"));
+    }
+
+    #[test]
+    fn filter_page_events_and_token() {
+        let json = r#"{
+            "events": [
+                {"logStreamName": "api/web/abc", "timestamp": 1705314601000,
+                 "message": "ERROR boom\n  at Foo.bar\n", "ingestionTime": 1705314602000,
+                 "eventId": "1"},
+                {"logStreamName": "api/web/def", "timestamp": 1705314601500,
+                 "message": "INFO ok", "eventId": "2"}
+            ],
+            "searchedLogStreams": [],
+            "nextToken": "tok"
+        }"#;
+        let (events, token) = parse_filter_page(json).unwrap();
+        assert_eq!(token.as_deref(), Some("tok"));
+        assert_eq!(events.len(), 2);
+        let lines: Vec<String> = cloudwatch_lines(&events[0]).collect();
+        assert_eq!(
+            lines,
+            ["api/web/abc | ERROR boom", "api/web/abc |   at Foo.bar"]
+        );
+        assert_eq!(
+            split_source_tag(&lines[0]),
+            Some(("api/web/abc", "ERROR boom"))
+        );
+
+        let (events, token) = parse_filter_page(r#"{"events": []}"#).unwrap();
+        assert!(events.is_empty() && token.is_none());
+        assert!(parse_filter_page("Unable to locate credentials").is_err());
+    }
+
+    #[test]
+    fn cloudwatch_cursor_skips_repeats_and_rereads_late_window() {
+        let event = |id: &str, timestamp| CloudWatchEvent {
+            id: id.to_string(),
+            stream: "s".to_string(),
+            timestamp,
+            ingestion_time: None,
+            message: String::new(),
+        };
+        let mut cursor = CloudWatchCursor::new(1_000);
+        let fresh = cursor.take_new(vec![event("a", 500), event("b", 2_000)]);
+        assert_eq!(fresh, [event("b", 2_000)]);
+        assert_eq!(cursor.query_start(), 1_000);
+
+        let fresh = cursor.take_new(vec![event("b", 2_000), event("c", 600_000)]);
+        assert_eq!(fresh, [event("c", 600_000)]);
+        assert_eq!(cursor.query_start(), 480_000);
+        // A late event inside the re-read window still comes through once
+        let late = cursor.take_new(vec![event("d", 500_000), event("c", 600_000)]);
+        assert_eq!(late, [event("d", 500_000)]);
+
+        // An event ingested five minutes late widens the window past that
+        let delayed = CloudWatchEvent {
+            ingestion_time: Some(610_000),
+            ..event("e", 310_000)
+        };
+        assert_eq!(cursor.take_new(vec![delayed]).len(), 1);
+        assert_eq!(cursor.query_start(), 600_000 - 420_000);
+        assert!(cursor.take_new(vec![event("d", 500_000)]).is_empty());
+
+        // A backlog cut short polls from the live edge, less the window
+        cursor.skip_to(5_000_000);
+        assert_eq!(cursor.query_start(), 5_000_000 - 420_000);
+        assert!(is_throttled(
+            "An error occurred (ThrottlingException): Rate exceeded"
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Rows in the stats view's top messages table.
+pub const TOP_MESSAGES: usize = 10;
+
+/// Distinct message shapes tracked before rare ones are dropped.
+const MESSAGE_STATS_CAP: usize = 5000;
+
+const UUID_MARK: &str = "<uuid>";
+const NUMBER_MARK: &str = "<n>";
+
+/// UUIDs, hex literals and numbers: the parts of a message that vary per line.
+static VARIABLE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?P<uuid>\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b)|(?P<num>\b0x[0-9a-f]+\b|\d+(?:\.\d+)?)",
+    )
+    .unwrap()
+});
+
+/// What `message_template` masks: IPv4 addresses (with a port), UUIDs,
+/// quoted strings and numbers.
+static TEMPLATE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)(?P<ip>\b\d{1,3}(?:\.\d{1,3}){3}(?::\d+)?\b)|(?P<uuid>\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b)|(?P<str>"[^"]*"|'[^']*')|(?P<num>\b0x[0-9a-f]+\b|\d+(?:\.\d+)?)"#,
+    )
+    .unwrap()
+});
+
+/// Running counts of messages by shape (numbers and UUIDs masked), kept as
+/// lines arrive so the stats view (i key) can rank them without a scan.
+#[derive(Debug, Default)]
+pub struct MessageStats {
+    counts: HashMap<String, u64>,
+}
+
+impl MessageStats {
+    pub fn record(&mut self, message: &str) {
+        let shape = normalize_message(message);
+        if let Some(count) = self.counts.get_mut(&shape) {
+            *count += 1;
+            return;
+        }
+        if self.counts.len() >= MESSAGE_STATS_CAP {
+            // Make room by forgetting the shapes seen only once
+            self.counts.retain(|_, count| *count > 1);
+            if self.counts.len() >= MESSAGE_STATS_CAP {
+                return;
+            }
+        }
+        self.counts.insert(shape, 1);
+    }
+
+    /// The `n` most frequent shapes, most frequent first (ties alphabetically).
+    pub fn top(&self, n: usize) -> Vec<(&str, u64)> {
+        let mut top: Vec<(&str, u64)> = self
+            .counts
+            .iter()
+            .map(|(shape, &count)| (shape.as_str(), count))
+            .collect();
+        top.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        top.truncate(n);
+        top
+    }
+}
+
+/// `user 42 took 1.5s` → `user <n> took <n>s`.
+pub fn normalize_message(message: &str) -> String {
+    VARIABLE_RE
+        .replace_all(message.trim(), |caps: &regex::Captures| {
+            if caps.name("uuid").is_some() {
+                UUID_MARK
+            } else {
+                NUMBER_MARK
+            }
+        })
+        .into_owned()
+}
+
+/// `connection to 10.0.0.1:5432 refused for "bob"` → `connection to <IP>
+/// refused for <STR>`: what similar messages share, for grouping them (G key).
+pub fn message_template(message: &str) -> String {
+    TEMPLATE_RE
+        .replace_all(message.trim(), |caps: &regex::Captures| {
+            if caps.name("ip").is_some() {
+                "<IP>"
+            } else if caps.name("uuid").is_some() {
+                "<UUID>"
+            } else if caps.name("str").is_some() {
+                "<STR>"
+            } else {
+                "<N>"
+            }
+        })
+        .into_owned()
+}
+
+/// A filter regex matching every message of a shape from [`normalize_message`].
+/// Spaces become `\s` so the filter reads it as one term.
+pub fn shape_filter(shape: &str) -> String {
+    let mut pattern = String::new();
+    let mut rest = shape;
+    loop {
+        let uuid = rest.find(UUID_MARK);
+        let number = rest.find(NUMBER_MARK);
+        let (at, mark, replacement) = match (uuid, number) {
+            (Some(u), Some(n)) if u < n => (u, UUID_MARK, r"[0-9a-f-]{36}"),
+            (_, Some(n)) => (n, NUMBER_MARK, r"(?:0x[0-9a-f]+|\d+(?:\.\d+)?)"),
+            (Some(u), None) => (u, UUID_MARK, r"[0-9a-f-]{36}"),
+            (None, None) => break,
+        };
+        pattern.push_str(&regex::escape(&rest[..at]));
+        pattern.push_str(replacement);
+        rest = &rest[at + mark.len()..];
+    }
+    pattern.push_str(&regex::escape(rest));
+    pattern.replace(' ', r"\s")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_are_counted_by_shape() {
+        let mut stats = MessageStats::default();
+        stats.record("user 42 logged in");
+        stats.record("user 7 logged in");
+        stats.record("request 0f8fad5b-d9cb-469f-a165-70867728950e failed after 1.5s");
+        stats.record("request 7c9e6679-7425-40de-944b-e07fc1f90ae7 failed after 0.2s");
+        stats.record("request 7c9e6679-7425-40de-944b-e07fc1f90ae7 failed after 3s");
+        stats.record("cache warmed");
+
+        assert_eq!(
+            stats.top(2),
+            [
+                ("request <uuid> failed after <n>s", 3),
+                ("user <n> logged in", 2)
+            ]
+        );
+        assert_eq!(stats.top(10).len(), 3);
+
+        let filter = Regex::new(&format!(
+            "(?i){}",
+            shape_filter("request <uuid> failed after <n>s")
+        ))
+        .unwrap();
+        assert!(filter.is_match("request 0f8fad5b-d9cb-469f-a165-70867728950e failed after 12s"));
+        assert!(!filter.is_match("request 42 failed after 12s"));
+        assert_eq!(
+            shape_filter("a.b <n>"),
+            r"a\.b\s(?:0x[0-9a-f]+|\d+(?:\.\d+)?)"
+        );
+    }
+
+    #[test]
+    fn templates_mask_addresses_ids_strings_and_numbers() {
+        assert_eq!(
+            message_template("connection to 10.0.0.1:5432 refused after 3 tries"),
+            "connection to <IP> refused after <N> tries"
+        );
+        assert_eq!(
+            message_template(
+                r#"user "bob" not found in 'eu' (req 0f8fad5b-d9cb-469f-a165-70867728950e)"#
+            ),
+            "user <STR> not found in <STR> (req <UUID>)"
+        );
+    }
+}
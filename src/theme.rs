@@ -0,0 +1,520 @@
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::app::{LogLevel, HIGHLIGHT_COLORS};
+
+/// Built-in themes, for `--theme` and `theme = "..."` in the config.
+pub const THEME_NAMES: [&str; 8] = [
+    "default",
+    "dark",
+    "light",
+    "mono",
+    "solarized",
+    "gruvbox",
+    "nord",
+    "monokai",
+];
+
+/// Colors for every part of the UI. Built-in themes come from
+/// [`Theme::builtin`]; a `[theme]` config table overrides single fields.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub fatal_color: Color,
+    pub error_color: Color,
+    pub warn_color: Color,
+    pub info_color: Color,
+    pub debug_color: Color,
+    pub trace_color: Color,
+    /// Lines without a recognised level
+    pub unknown_color: Color,
+    /// Feed, stats and help borders
+    pub border_fg: Color,
+    /// Feed border while paused
+    pub paused_fg: Color,
+    pub header_fg: Color,
+    pub sparkline_fg: Color,
+    /// Key hints, table header, dividers, detail view border
+    pub accent_fg: Color,
+    /// Filter and search prompts, help section titles
+    pub filter_fg: Color,
+    /// Highlight prompt
+    pub highlight_fg: Color,
+    /// Status messages and the save prompt
+    pub status_fg: Color,
+    pub search_hl_fg: Color,
+    pub search_hl_bg: Color,
+    pub selected_bg: Color,
+    /// Timestamps, continuation lines, repeat counts
+    pub dim_fg: Color,
+    pub metadata_fg: Color,
+    pub bookmark_fg: Color,
+    pub flag_fg: Color,
+    pub fuzzy_fg: Color,
+    pub json_key_fg: Color,
+    pub json_string_fg: Color,
+    pub json_number_fg: Color,
+    pub json_bool_fg: Color,
+    /// `*` highlights in turn, unless `highlight_colors` is configured, and
+    /// source tags of merged streams
+    pub palette: [Color; 8],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fatal_color: Color::Red,
+            error_color: Color::Red,
+            warn_color: Color::Yellow,
+            info_color: Color::Green,
+            debug_color: Color::Blue,
+            trace_color: Color::DarkGray,
+            unknown_color: Color::White,
+            border_fg: Color::White,
+            paused_fg: Color::Yellow,
+            header_fg: Color::Cyan,
+            sparkline_fg: Color::Green,
+            accent_fg: Color::Cyan,
+            filter_fg: Color::Yellow,
+            highlight_fg: Color::Magenta,
+            status_fg: Color::Green,
+            search_hl_fg: Color::Black,
+            search_hl_bg: Color::Yellow,
+            selected_bg: Color::DarkGray,
+            dim_fg: Color::DarkGray,
+            metadata_fg: Color::Gray,
+            bookmark_fg: Color::LightYellow,
+            flag_fg: Color::LightRed,
+            fuzzy_fg: Color::LightCyan,
+            json_key_fg: Color::LightBlue,
+            json_string_fg: Color::Green,
+            json_number_fg: Color::Yellow,
+            json_bool_fg: Color::Magenta,
+            palette: HIGHLIGHT_COLORS,
+        }
+    }
+}
+
+/// A colour as CSS for HTML exports; named colours use the xterm palette.
+pub fn css_color(color: Color) -> String {
+    let hex = match color {
+        Color::Rgb(r, g, b) => return format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Reset => return "inherit".to_string(),
+        Color::Black => 0x000000,
+        Color::Red => 0xcd0000,
+        Color::Green => 0x00cd00,
+        Color::Yellow => 0xcdcd00,
+        Color::Blue => 0x0000ee,
+        Color::Magenta => 0xcd00cd,
+        Color::Cyan => 0x00cdcd,
+        Color::Gray => 0xe5e5e5,
+        Color::DarkGray => 0x7f7f7f,
+        Color::LightRed => 0xff0000,
+        Color::LightGreen => 0x00ff00,
+        Color::LightYellow => 0xffff00,
+        Color::LightBlue => 0x5c5cff,
+        Color::LightMagenta => 0xff00ff,
+        Color::LightCyan => 0x00ffff,
+        Color::White => 0xffffff,
+        Color::Indexed(i) => indexed_rgb(i),
+    };
+    format!("#{:06x}", hex)
+}
+
+/// The xterm 256-colour palette: 16 system colours, a 6x6x6 cube, 24 greys.
+fn indexed_rgb(i: u8) -> u32 {
+    const SYSTEM: [u32; 16] = [
+        0x000000, 0xcd0000, 0x00cd00, 0xcdcd00, 0x0000ee, 0xcd00cd, 0x00cdcd, 0xe5e5e5, 0x7f7f7f,
+        0xff0000, 0x00ff00, 0xffff00, 0x5c5cff, 0xff00ff, 0x00ffff, 0xffffff,
+    ];
+    match i {
+        0..=15 => SYSTEM[i as usize],
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + 40 * n as u32 };
+            let n = i - 16;
+            (level(n / 36) << 16) | (level(n / 6 % 6) << 8) | level(n % 6)
+        }
+        _ => {
+            let grey = 8 + 10 * (i - 232) as u32;
+            (grey << 16) | (grey << 8) | grey
+        }
+    }
+}
+
+const fn rgb(hex: u32) -> Color {
+    Color::Rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+}
+
+impl Theme {
+    /// A built-in theme by name (case-insensitive).
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "default" | "dark" => Some(Self::default()),
+            "light" => Some(Self::light()),
+            "mono" => Some(Self::mono()),
+            "solarized" => Some(Self::solarized()),
+            "gruvbox" => Some(Self::gruvbox()),
+            "nord" => Some(Self::nord()),
+            "monokai" => Some(Self::monokai()),
+            _ => None,
+        }
+    }
+
+    /// Darker shades that stay readable on a white or light grey background.
+    fn light() -> Self {
+        let (fg, gray, muted) = (rgb(0x24292f), rgb(0x57606a), rgb(0x6e7781));
+        let (red, dark_red, orange, yellow) =
+            (rgb(0xcf222e), rgb(0x82071e), rgb(0xbc4c00), rgb(0x9a6700));
+        let (green, blue, dark_blue, purple) =
+            (rgb(0x1a7f37), rgb(0x0969da), rgb(0x0550ae), rgb(0x8250df));
+        let teal = rgb(0x1b7c83);
+        Self {
+            fatal_color: dark_red,
+            error_color: red,
+            warn_color: yellow,
+            info_color: green,
+            debug_color: dark_blue,
+            trace_color: muted,
+            unknown_color: fg,
+            border_fg: gray,
+            paused_fg: orange,
+            header_fg: blue,
+            sparkline_fg: green,
+            accent_fg: blue,
+            filter_fg: yellow,
+            highlight_fg: purple,
+            status_fg: green,
+            search_hl_fg: fg,
+            search_hl_bg: rgb(0xfff8c5),
+            selected_bg: rgb(0xddf4ff),
+            dim_fg: muted,
+            metadata_fg: gray,
+            bookmark_fg: yellow,
+            flag_fg: red,
+            fuzzy_fg: purple,
+            json_key_fg: dark_blue,
+            json_string_fg: green,
+            json_number_fg: orange,
+            json_bool_fg: purple,
+            palette: [purple, teal, orange, red, green, blue, yellow, dark_red],
+        }
+    }
+
+    /// No colors at all (`NO_COLOR`): the terminal's own foreground and
+    /// background, with the selection and search matches in reverse video.
+    fn mono() -> Self {
+        let none = Color::Reset;
+        Self {
+            fatal_color: none,
+            error_color: none,
+            warn_color: none,
+            info_color: none,
+            debug_color: none,
+            trace_color: none,
+            unknown_color: none,
+            border_fg: none,
+            paused_fg: none,
+            header_fg: none,
+            sparkline_fg: none,
+            accent_fg: none,
+            filter_fg: none,
+            highlight_fg: none,
+            status_fg: none,
+            search_hl_fg: none,
+            search_hl_bg: none,
+            selected_bg: none,
+            dim_fg: none,
+            metadata_fg: none,
+            bookmark_fg: none,
+            flag_fg: none,
+            fuzzy_fg: none,
+            json_key_fg: none,
+            json_string_fg: none,
+            json_number_fg: none,
+            json_bool_fg: none,
+            palette: [none; 8],
+        }
+    }
+
+    fn solarized() -> Self {
+        let (base02, base01, base0, base1) =
+            (rgb(0x073642), rgb(0x586e75), rgb(0x839496), rgb(0x93a1a1));
+        let (yellow, orange, red, magenta) =
+            (rgb(0xb58900), rgb(0xcb4b16), rgb(0xdc322f), rgb(0xd33682));
+        let (violet, blue, cyan, green) =
+            (rgb(0x6c71c4), rgb(0x268bd2), rgb(0x2aa198), rgb(0x859900));
+        Self {
+            fatal_color: magenta,
+            error_color: red,
+            warn_color: yellow,
+            info_color: green,
+            debug_color: blue,
+            trace_color: base01,
+            unknown_color: base0,
+            border_fg: base1,
+            paused_fg: orange,
+            header_fg: cyan,
+            sparkline_fg: green,
+            accent_fg: cyan,
+            filter_fg: yellow,
+            highlight_fg: violet,
+            status_fg: green,
+            search_hl_fg: base02,
+            search_hl_bg: yellow,
+            selected_bg: base02,
+            dim_fg: base01,
+            metadata_fg: base0,
+            bookmark_fg: yellow,
+            flag_fg: orange,
+            fuzzy_fg: violet,
+            json_key_fg: blue,
+            json_string_fg: green,
+            json_number_fg: orange,
+            json_bool_fg: magenta,
+            palette: HIGHLIGHT_COLORS,
+        }
+    }
+
+    fn gruvbox() -> Self {
+        let (bg1, bg2, gray, fg) = (rgb(0x3c3836), rgb(0x504945), rgb(0x928374), rgb(0xebdbb2));
+        let (red, green, yellow, blue) =
+            (rgb(0xfb4934), rgb(0xb8bb26), rgb(0xfabd2f), rgb(0x83a598));
+        let (purple, aqua, orange, dark_red) =
+            (rgb(0xd3869b), rgb(0x8ec07c), rgb(0xfe8019), rgb(0xcc241d));
+        Self {
+            fatal_color: dark_red,
+            error_color: red,
+            warn_color: yellow,
+            info_color: green,
+            debug_color: blue,
+            trace_color: gray,
+            unknown_color: fg,
+            border_fg: fg,
+            paused_fg: orange,
+            header_fg: aqua,
+            sparkline_fg: green,
+            accent_fg: aqua,
+            filter_fg: yellow,
+            highlight_fg: purple,
+            status_fg: green,
+            search_hl_fg: bg1,
+            search_hl_bg: yellow,
+            selected_bg: bg2,
+            dim_fg: gray,
+            metadata_fg: gray,
+            bookmark_fg: yellow,
+            flag_fg: orange,
+            fuzzy_fg: aqua,
+            json_key_fg: blue,
+            json_string_fg: green,
+            json_number_fg: purple,
+            json_bool_fg: orange,
+            palette: HIGHLIGHT_COLORS,
+        }
+    }
+
+    fn nord() -> Self {
+        let (nord1, nord2, nord3, nord4) =
+            (rgb(0x3b4252), rgb(0x434c5e), rgb(0x4c566a), rgb(0xd8dee9));
+        let (nord7, nord8, nord9, nord10) =
+            (rgb(0x8fbcbb), rgb(0x88c0d0), rgb(0x81a1c1), rgb(0x5e81ac));
+        let (nord11, nord12, nord13) = (rgb(0xbf616a), rgb(0xd08770), rgb(0xebcb8b));
+        let (nord14, nord15) = (rgb(0xa3be8c), rgb(0xb48ead));
+        Self {
+            fatal_color: nord15,
+            error_color: nord11,
+            warn_color: nord13,
+            info_color: nord14,
+            debug_color: nord10,
+            trace_color: nord3,
+            unknown_color: nord4,
+            border_fg: nord4,
+            paused_fg: nord12,
+            header_fg: nord8,
+            sparkline_fg: nord14,
+            accent_fg: nord8,
+            filter_fg: nord13,
+            highlight_fg: nord15,
+            status_fg: nord14,
+            search_hl_fg: nord1,
+            search_hl_bg: nord13,
+            selected_bg: nord2,
+            dim_fg: nord3,
+            metadata_fg: nord9,
+            bookmark_fg: nord13,
+            flag_fg: nord11,
+            fuzzy_fg: nord7,
+            json_key_fg: nord9,
+            json_string_fg: nord14,
+            json_number_fg: nord15,
+            json_bool_fg: nord12,
+            palette: HIGHLIGHT_COLORS,
+        }
+    }
+
+    fn monokai() -> Self {
+        let (bg, selection, comment, fg) =
+            (rgb(0x272822), rgb(0x49483e), rgb(0x75715e), rgb(0xf8f8f2));
+        let (pink, orange, yellow, green) =
+            (rgb(0xf92672), rgb(0xfd971f), rgb(0xe6db74), rgb(0xa6e22e));
+        let (blue, purple) = (rgb(0x66d9ef), rgb(0xae81ff));
+        Self {
+            fatal_color: purple,
+            error_color: pink,
+            warn_color: orange,
+            info_color: green,
+            debug_color: blue,
+            trace_color: comment,
+            unknown_color: fg,
+            border_fg: fg,
+            paused_fg: orange,
+            header_fg: blue,
+            sparkline_fg: green,
+            accent_fg: blue,
+            filter_fg: yellow,
+            highlight_fg: purple,
+            status_fg: green,
+            search_hl_fg: bg,
+            search_hl_bg: yellow,
+            selected_bg: selection,
+            dim_fg: comment,
+            metadata_fg: comment,
+            bookmark_fg: yellow,
+            flag_fg: pink,
+            fuzzy_fg: blue,
+            json_key_fg: blue,
+            json_string_fg: yellow,
+            json_number_fg: purple,
+            json_bool_fg: orange,
+            palette: HIGHLIGHT_COLORS,
+        }
+    }
+
+    /// The field a `[theme]` config key sets, by its field name.
+    pub fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        Some(match name {
+            "fatal_color" => &mut self.fatal_color,
+            "error_color" => &mut self.error_color,
+            "warn_color" => &mut self.warn_color,
+            "info_color" => &mut self.info_color,
+            "debug_color" => &mut self.debug_color,
+            "trace_color" => &mut self.trace_color,
+            "unknown_color" => &mut self.unknown_color,
+            "border_fg" => &mut self.border_fg,
+            "paused_fg" => &mut self.paused_fg,
+            "header_fg" => &mut self.header_fg,
+            "sparkline_fg" => &mut self.sparkline_fg,
+            "accent_fg" => &mut self.accent_fg,
+            "filter_fg" => &mut self.filter_fg,
+            "highlight_fg" => &mut self.highlight_fg,
+            "status_fg" => &mut self.status_fg,
+            "search_hl_fg" => &mut self.search_hl_fg,
+            "search_hl_bg" => &mut self.search_hl_bg,
+            "selected_bg" => &mut self.selected_bg,
+            "dim_fg" => &mut self.dim_fg,
+            "metadata_fg" => &mut self.metadata_fg,
+            "bookmark_fg" => &mut self.bookmark_fg,
+            "flag_fg" => &mut self.flag_fg,
+            "fuzzy_fg" => &mut self.fuzzy_fg,
+            "json_key_fg" => &mut self.json_key_fg,
+            "json_string_fg" => &mut self.json_string_fg,
+            "json_number_fg" => &mut self.json_number_fg,
+            "json_bool_fg" => &mut self.json_bool_fg,
+            _ => return None,
+        })
+    }
+
+    /// The selected feed, stats or tree row; reverse video when the theme
+    /// has no selection color.
+    pub fn selected_style(&self) -> Style {
+        reverse_if_unset(Style::default().bg(self.selected_bg), self.selected_bg)
+    }
+
+    pub fn search_hl_style(&self) -> Style {
+        reverse_if_unset(
+            Style::default().bg(self.search_hl_bg).fg(self.search_hl_fg),
+            self.search_hl_bg,
+        )
+    }
+
+    /// Stable per-source color so merged streams are easy to tell apart.
+    pub fn source_color(&self, name: &str) -> Color {
+        let hash = name.bytes().fold(0usize, |acc, b| {
+            acc.wrapping_mul(31).wrapping_add(b as usize)
+        });
+        self.palette[hash % self.palette.len()]
+    }
+
+    pub fn level_color(&self, level: LogLevel) -> Color {
+        match level {
+            LogLevel::Fatal => self.fatal_color,
+            LogLevel::Error => self.error_color,
+            LogLevel::Warn => self.warn_color,
+            LogLevel::Info => self.info_color,
+            LogLevel::Debug => self.debug_color,
+            LogLevel::Trace => self.trace_color,
+            LogLevel::Unknown => self.unknown_color,
+        }
+    }
+}
+
+fn reverse_if_unset(style: Style, bg: Color) -> Style {
+    if bg == Color::Reset {
+        style.add_modifier(Modifier::REVERSED)
+    } else {
+        style
+    }
+}
+
+/// `NO_COLOR` set to anything non-empty (https://no-color.org).
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_themes_by_name() {
+        for name in THEME_NAMES {
+            assert!(Theme::builtin(name).is_some(), "{}", name);
+        }
+        assert_eq!(Theme::builtin("Nord").unwrap().info_color, rgb(0xa3be8c));
+        assert_eq!(Theme::builtin("dracula"), None);
+
+        let mut theme = Theme::default();
+        *theme.color_mut("selected_bg").unwrap() = Color::Blue;
+        assert_eq!(theme.selected_bg, Color::Blue);
+        assert!(theme.color_mut("nope").is_none());
+    }
+
+    #[test]
+    fn mono_theme_marks_selection_without_color() {
+        let mono = Theme::builtin("mono").unwrap();
+        assert_eq!(mono.level_color(LogLevel::Error), Color::Reset);
+        assert_eq!(mono.source_color("web1"), Color::Reset);
+        assert!(mono
+            .selected_style()
+            .add_modifier
+            .contains(Modifier::REVERSED));
+        assert!(mono
+            .search_hl_style()
+            .add_modifier
+            .contains(Modifier::REVERSED));
+
+        let light = Theme::builtin("light").unwrap();
+        assert_eq!(
+            light.selected_style(),
+            Style::default().bg(light.selected_bg)
+        );
+        assert_eq!(Theme::builtin("dark"), Some(Theme::default()));
+    }
+
+    #[test]
+    fn css_colors() {
+        assert_eq!(css_color(rgb(0xa3be8c)), "#a3be8c");
+        assert_eq!(css_color(Color::Red), "#cd0000");
+        assert_eq!(css_color(Color::Indexed(9)), "#ff0000");
+        assert_eq!(css_color(Color::Indexed(208)), "#ff8700");
+        assert_eq!(css_color(Color::Indexed(244)), "#808080");
+    }
+}
@@ -1,23 +1,48 @@
+use std::collections::HashSet;
+
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Sparkline, Wrap},
+    text::{Line, Span, Text},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Sparkline, Wrap,
+    },
     Frame,
 };
 use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-use crate::app::{App, InputMode, LogEntry, LogLevel, ViewMode};
+use crate::ansi::ansi_to_spans;
+use crate::app::{
+    App, ExportFormat, InputMode, LogEntry, LogLevel, MatchScope, TimeDisplay, ViewMode,
+};
+use crate::event::CONTEXT_KEYS;
+use crate::filter::{parse_filter, FilterTerm};
+use crate::fuzzy::fuzzy_match;
+use crate::json_tree::{flatten_fields, is_container, JsonTreeState, TreeRow};
+use crate::keymap;
+use crate::parser::strip_ansi;
+use crate::source::ConnState;
+use crate::stats::TOP_MESSAGES;
+use crate::theme::Theme;
 
-pub fn draw(frame: &mut Frame, app: &App) {
-    let chunks = Layout::default()
+/// Screen split into header, log feed and footer / filter bar.
+fn screen_chunks(app: &App, area: Rect) -> [Rect; 3] {
+    let header = if app.spark_detail { 9 } else { 3 };
+    Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3), // header
-            Constraint::Min(5),    // log feed
-            Constraint::Length(3), // footer / filter bar
+            Constraint::Length(header), // header
+            Constraint::Min(5),         // log feed
+            Constraint::Length(3),      // footer / filter bar
         ])
-        .split(frame.area());
+        .areas(area)
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let chunks = screen_chunks(app, frame.area());
 
     draw_header(frame, app, chunks[0]);
 
@@ -25,23 +50,42 @@ pub fn draw(frame: &mut Frame, app: &App) {
     let visible = app.visible_logs();
 
     // Collect highlight regexes for rendering
+    let theme = &app.theme;
     let mut hl_patterns: Vec<(&Regex, Style)> = Vec::new();
     if let Some(ref re) = app.search_regex {
-        hl_patterns.push((re, Style::default().bg(Color::Yellow).fg(Color::Black)));
+        hl_patterns.push((re, theme.search_hl_style()));
     }
     for (re, color) in &app.highlights {
         hl_patterns.push((re, Style::default().fg(*color).add_modifier(Modifier::BOLD)));
     }
 
-    draw_log_feed(frame, app, &visible, &hl_patterns, chunks[1]);
+    if app.view_mode == ViewMode::Stats {
+        draw_stats(frame, app, chunks[1]);
+    } else {
+        draw_log_feed(frame, app, &visible, &hl_patterns, chunks[1]);
+    }
     draw_footer(frame, app, chunks[2]);
 
-    if app.view_mode == ViewMode::Detail {
-        draw_detail_modal(frame, app, &visible);
+    match app.view_mode {
+        ViewMode::Detail => draw_detail_modal(frame, app, &visible),
+        ViewMode::Help => draw_help_modal(frame, app),
+        ViewMode::FormatPicker => draw_format_picker(frame, app),
+        ViewMode::Feed | ViewMode::Stats => {}
+    }
+    if app.error_peek {
+        draw_error_peek_modal(frame, app);
     }
 }
 
 fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
+    let area = if app.spark_detail {
+        let [top, levels] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+        draw_level_sparklines(frame, app, levels);
+        top
+    } else {
+        area
+    };
     let header_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
@@ -51,36 +95,185 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
         .split(area);
 
     // Stats
-    let frozen_indicator = if app.frozen { " [PAUSED]" } else { "" };
+    let frozen_indicator = match (app.frozen, app.paused_new) {
+        (false, _) => String::new(),
+        (true, 0) => " [PAUSED]".to_string(),
+        (true, n) => format!(" [PAUSED +{} new]", n),
+    };
     let error_only_indicator = if app.error_only { " [ERRORS]" } else { "" };
+    let min_level_indicator = match app.effective_min_level() {
+        Some(level) => format!(" [≥{}]", level.name()),
+        None => String::new(),
+    };
+    let status_indicator = match app.status_facet {
+        Some(class) => format!(" [{}xx]", class),
+        None => String::new(),
+    };
+    let flagged_only_indicator = if app.flagged_only { " [FLAGGED]" } else { "" };
+    let dedup_indicator = if app.deduplicate { " [DEDUP]" } else { "" };
+    let group_indicator = if app.group_similar { " [GROUPED]" } else { "" };
+    let diff_indicator = if app.diff_mode {
+        format!(" [DIFF: {} new]", app.new_error_count())
+    } else {
+        String::new()
+    };
+    // Memory per entry tells how far --buffer can safely go
+    let spill_indicator = match app.spill {
+        Some(ref spill) => format!(
+            " [SPILL {} on disk, ~{} B/entry]",
+            spill.lines(),
+            app.bytes_per_entry
+        ),
+        None => String::new(),
+    };
+    let hosts_indicator = if app.hosts.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}/{} hosts]", app.connected_hosts(), app.hosts.len())
+    };
+    let conn_badge = match app.conn.get() {
+        Some(ConnState::Connected) => Some(("● connected", app.theme.info_color)),
+        Some(ConnState::Reconnecting) => Some(("◌ reconnecting", app.theme.warn_color)),
+        Some(ConnState::Disconnected) => Some(("○ disconnected", app.theme.error_color)),
+        None => None,
+    };
+    let replay_indicator = match (&app.replay, &app.last_timestamp) {
+        (Some(replay), Some(ts)) => format!(" [REPLAY {:.1}x @ {}]", replay.speed(), ts),
+        (Some(replay), None) => format!(" [REPLAY {:.1}x]", replay.speed()),
+        (None, _) => String::new(),
+    };
+    let project_indicator = if app.project_config {
+        " [project config]"
+    } else {
+        ""
+    };
 
-    let stats_text = format!(
-        " {} | EPS: {} | Errors: {} | Total: {}{}{}",
-        app.filename,
-        app.current_eps,
-        app.error_count,
+    let rate = |percent: f64| {
+        Span::styled(
+            format!("{:.1}%", percent),
+            Style::default().fg(error_rate_color(percent, &app.theme)),
+        )
+    };
+    let rest = format!(
+        " | Total: {}{}{}{}{}{}{}{}{}{}{}{}{}",
         app.total_count,
         frozen_indicator,
-        error_only_indicator
+        error_only_indicator,
+        min_level_indicator,
+        status_indicator,
+        flagged_only_indicator,
+        dedup_indicator,
+        group_indicator,
+        diff_indicator,
+        spill_indicator,
+        hosts_indicator,
+        replay_indicator,
+        project_indicator
     );
 
+    let stats_text = Line::from(vec![
+        Span::raw(format!(
+            " {} | EPS: {} | Errors: {} (",
+            app.filename, app.current_eps, app.error_count
+        )),
+        rate(app.error_rate_percent()),
+        Span::raw(" / "),
+        rate(app.recent_error_rate_percent()),
+        Span::raw(format!(" last {}s)", app.eps_window_secs)),
+        Span::raw(rest),
+    ]);
+    // The badge sits in the title so a long status line can't push it off
+    let mut title = vec![Span::raw(" LogPulse ")];
+    if let Some((badge, color)) = conn_badge {
+        title.push(Span::styled(
+            format!("{} ", badge),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+    }
     let stats = Paragraph::new(stats_text).block(
         Block::default()
             .borders(Borders::ALL)
-            .title(" LogPulse ")
-            .style(Style::default().fg(Color::Cyan)),
+            .title(Line::from(title))
+            .style(Style::default().fg(app.theme.header_fg)),
     );
     frame.render_widget(stats, header_chunks[0]);
 
     // Sparkline
     let spark_data: Vec<u64> = app.eps_history.iter().copied().collect();
     let sparkline = Sparkline::default()
-        .block(Block::default().borders(Borders::ALL).title(" Activity "))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Activity ({}s) ", app.eps_window_secs)),
+        )
         .data(&spark_data)
-        .style(Style::default().fg(Color::Green));
+        .style(Style::default().fg(app.theme.sparkline_fg));
     frame.render_widget(sparkline, header_chunks[1]);
 }
 
+/// Red above 5% errors, yellow above 1%, green below.
+fn error_rate_color(percent: f64, theme: &Theme) -> Color {
+    if percent > 5.0 {
+        theme.error_color
+    } else if percent > 1.0 {
+        theme.warn_color
+    } else {
+        theme.info_color
+    }
+}
+
+/// Levels with a sparkline in the expanded header (v key); fatal counts
+/// toward error so a crash can't hide in a column of its own.
+const HEADER_SPARK_LEVELS: [LogLevel; 6] = [
+    LogLevel::Error,
+    LogLevel::Warn,
+    LogLevel::Info,
+    LogLevel::Debug,
+    LogLevel::Trace,
+    LogLevel::Unknown,
+];
+
+/// One bordered sparkline per level side by side, titled with the level
+/// and its rate over the last second.
+fn draw_level_sparklines(frame: &mut Frame, app: &App, area: Rect) {
+    let columns =
+        Layout::horizontal([Constraint::Ratio(1, HEADER_SPARK_LEVELS.len() as u32); 6]).split(area);
+    for (&level, &column) in HEADER_SPARK_LEVELS.iter().zip(columns.iter()) {
+        let mut data: Vec<u64> = app.level_eps_counters[level as usize]
+            .iter()
+            .copied()
+            .collect();
+        if level == LogLevel::Error {
+            let fatal = &app.level_eps_counters[LogLevel::Fatal as usize];
+            for (sum, n) in data.iter_mut().zip(fatal) {
+                *sum += n;
+            }
+        }
+        let name = match level.name() {
+            "" => "OTHER",
+            name => name,
+        };
+        let color = app.theme.level_color(level);
+        // Only the part of the window the column can show, newest last
+        let width = column.width.saturating_sub(2) as usize;
+        let data = &data[data.len().saturating_sub(width)..];
+        let sparkline = Sparkline::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(app.theme.border_fg))
+                    .title(format!(
+                        " {} {}/s ",
+                        name,
+                        data.last().copied().unwrap_or(0)
+                    )),
+            )
+            .data(data)
+            .style(Style::default().fg(color));
+        frame.render_widget(sparkline, column);
+    }
+}
+
 fn draw_log_feed(
     frame: &mut Frame,
     app: &App,
@@ -88,210 +281,1355 @@ fn draw_log_feed(
     hl_patterns: &[(&Regex, Style)],
     area: Rect,
 ) {
+    let mut title = " Log Feed ".to_string();
+    if app.table_view {
+        title.push_str("[TABLE] ");
+    } else if app.wrap_lines {
+        title.push_str("[WRAP] ");
+    }
+    if app.show_raw && !app.table_view {
+        title.push_str("[RAW] ");
+    }
+    if let Some(ref re) = app.sort_regex {
+        title.push_str(&format!("[SORTED ↓ {}] ", re.as_str()));
+    }
+    if !app.fuzzy_search_query.is_empty() {
+        title.push_str(&format!("[FUZZY {}] ", app.fuzzy_search_query));
+    }
+    if app.context_lines > 0 {
+        title.push_str(&format!("[CONTEXT ±{}] ", app.context_lines));
+    }
+    if feed_scroll(app) > 0 {
+        title.push_str(&format!("[→{}] ", app.horizontal_scroll));
+    }
+    if app.frozen {
+        title.push_str("[PAUSED - Space to resume] ");
+    }
+    let theme = &app.theme;
     let total_visible = visible.len();
+    if let Some(label) = app.tracking_label() {
+        let entries = visible.iter().filter(|(_, e)| !e.divider).count();
+        title.push_str(&format!(
+            "[Tracking: {} ({} entries) n/N to step] ",
+            label, entries
+        ));
+    }
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().fg(if app.frozen {
+            theme.paused_fg
+        } else {
+            theme.border_fg
+        }));
+    if let Some(label) = filtered_fraction(total_visible, app.logs.len()) {
+        block = block.title_bottom(Line::from(label).right_aligned());
+    }
+    frame.render_widget(block, area);
+
     if total_visible == 0 {
-        let list = List::new(Vec::<ListItem>::new()).block(
-            Block::default().borders(Borders::ALL).title(if app.frozen {
-                " Log Feed [PAUSED - Space to resume] "
-            } else {
-                " Log Feed "
-            }),
-        );
-        frame.render_widget(list, area);
+        app.max_visible_line_len.set(0);
         return;
     }
 
+    let (header_area, inner) = feed_list_area(app, area);
+    app.feed_width.set(inner.width as usize);
+
     // Defensive clamp — prevents panic if selected_index is stale
     let selected = app.selected_index.min(total_visible - 1);
 
     // Calculate viewport BEFORE creating ListItems
-    let height = area.height.saturating_sub(2) as usize; // borders
-    let offset = if app.frozen || selected < total_visible.saturating_sub(height) {
-        selected.saturating_sub(height / 2)
-    } else {
-        total_visible.saturating_sub(height)
-    };
+    let height = inner.height as usize;
+    let width = inner.width as usize;
+    let offset = feed_offset(app, visible, selected, height, width);
 
     // Only create ListItems for the visible window
     let window_end = (offset + height + 1).min(total_visible);
-    let items: Vec<ListItem> = visible[offset..window_end]
+    let window = &visible[offset..window_end];
+    let widths = column_widths(window.iter().map(|(_, e)| e));
+    if let Some(header) = header_area {
+        let line = table_header(widths, app.horizontal_scroll, theme);
+        frame.render_widget(Paragraph::new(line), header);
+    }
+
+    let now = chrono::Local::now();
+    let mut longest = 0;
+    let items: Vec<ListItem> = window
         .iter()
         .enumerate()
-        .map(|(i, (_orig_idx, entry))| {
+        .map(|(i, (orig_idx, entry))| {
             let display_idx = offset + i;
-            let line = colorize_entry(entry, app.horizontal_scroll, hl_patterns);
+            if entry.divider {
+                let line = divider_line(entry, inner.width as usize, theme);
+                let style = if display_idx == selected {
+                    theme.selected_style()
+                } else {
+                    Style::default()
+                };
+                return ListItem::new(line).style(style);
+            }
+            let mut line = if app.table_view {
+                table_row(
+                    entry,
+                    widths,
+                    app.horizontal_scroll,
+                    hl_patterns,
+                    entry_marks(app, *orig_idx),
+                    theme,
+                )
+            } else {
+                feed_row(app, *orig_idx, entry, hl_patterns, now)
+            };
+            if app.is_context_line(*orig_idx, entry) {
+                for span in &mut line.spans {
+                    span.style = span.style.fg(theme.dim_fg);
+                }
+            }
+            let scrolled = app
+                .horizontal_scroll
+                .min(scrollable_width(entry, app, widths));
+            longest = longest.max(line.width() + scrolled);
             let style = if display_idx == selected {
-                Style::default()
-                    .bg(Color::DarkGray)
-                    .add_modifier(Modifier::BOLD)
+                theme.selected_style().add_modifier(Modifier::BOLD)
             } else {
                 Style::default()
             };
-            ListItem::new(line).style(style)
+            let mut lines = vec![line];
+            if app.expanded.contains(orig_idx) {
+                lines.extend(continuation_lines(entry, feed_scroll(app), theme));
+            }
+            if wraps(app) {
+                lines = lines
+                    .into_iter()
+                    .flat_map(|line| wrap_line(line, width, theme))
+                    .collect();
+            }
+            ListItem::new(Text::from(lines)).style(style)
         })
         .collect();
 
-    let title = if app.frozen {
-        " Log Feed [PAUSED - Space to resume] "
+    frame.render_widget(List::new(items), inner);
+    app.max_visible_line_len.set(longest);
+    let page = if !app.expanded.is_empty() || wraps(app) {
+        let mut rows = 0;
+        window
+            .iter()
+            .take_while(|e| {
+                rows += feed_rows(app, e, width);
+                rows <= height
+            })
+            .count()
     } else {
-        " Log Feed "
+        height
     };
+    app.viewport_height.set(page.max(1));
 
-    let list = List::new(items).block(Block::default().borders(Borders::ALL).title(title).style(
-        Style::default().fg(if app.frozen {
-            Color::Yellow
-        } else {
-            Color::White
+    // Scrollbar on the right border; ↓ at the bottom while following the tail
+    let following = !app.frozen && selected == total_visible - 1;
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(following.then_some("↓"));
+    let mut state = ScrollbarState::new(total_visible).position(selected);
+    frame.render_stateful_widget(
+        scrollbar,
+        area.inner(Margin {
+            vertical: 1,
+            horizontal: 0,
         }),
+        &mut state,
+    );
+}
+
+/// The feed's list rows inside its border, plus the column header row in
+/// table layout.
+fn feed_list_area(app: &App, area: Rect) -> (Option<Rect>, Rect) {
+    let inner = area.inner(Margin {
+        vertical: 1,
+        horizontal: 1,
+    });
+    if app.table_view {
+        let [header, rows] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+        (Some(header), rows)
+    } else {
+        (None, inner)
+    }
+}
+
+/// Rows an entry takes in a feed `width` columns wide: expanded entries
+/// show their stack trace, and wrapped long lines take several.
+fn feed_rows(app: &App, (idx, entry): &(usize, &LogEntry), width: usize) -> usize {
+    let expanded = app.expanded.contains(idx);
+    if !wraps(app) || entry.divider {
+        return 1 + if expanded { entry.extra_lines.len() } else { 0 };
+    }
+    let row = feed_row(app, *idx, entry, &[], chrono::Local::now());
+    let mut rows = wrapped_rows(line_chars(&row), width);
+    if expanded {
+        rows += continuation_lines(entry, 0, &app.theme)
+            .iter()
+            .map(|line| wrapped_rows(line_chars(line), width))
+            .sum::<usize>();
+    }
+    rows
+}
+
+/// Wrap mode (w key) applies to the line layout, not the table.
+fn wraps(app: &App) -> bool {
+    app.wrap_lines && !app.table_view
+}
+
+/// Horizontal scroll of feed rows; wrapped rows always start at column 0.
+fn feed_scroll(app: &App) -> usize {
+    if wraps(app) {
+        0
+    } else {
+        app.horizontal_scroll
+    }
+}
+
+fn line_chars(line: &Line) -> usize {
+    line.spans.iter().map(|s| s.content.chars().count()).sum()
+}
+
+/// Rows [`wrap_line`] splits a line of `chars` characters into.
+fn wrapped_rows(chars: usize, width: usize) -> usize {
+    if chars <= width || width <= 2 {
+        1
+    } else {
+        1 + (chars - width).div_ceil(width - 2)
+    }
+}
+
+/// Split `line` into rows of at most `width` characters, keeping span
+/// styles; rows after the first start with a dim `↳ `.
+fn wrap_line(line: Line<'static>, width: usize, theme: &Theme) -> Vec<Line<'static>> {
+    if width <= 2 || line_chars(&line) <= width {
+        return vec![line];
+    }
+    let marker = Span::styled("↳ ", Style::default().fg(theme.dim_fg));
+    let mut rows = Vec::new();
+    let mut current = Vec::new();
+    let (mut used, mut limit) = (0, width);
+    for span in line.spans {
+        let mut rest: &str = &span.content;
+        while !rest.is_empty() {
+            if used == limit {
+                rows.push(Line::from(std::mem::take(&mut current)));
+                current.push(marker.clone());
+                (used, limit) = (0, width - 2);
+            }
+            let split = rest
+                .char_indices()
+                .nth(limit - used)
+                .map_or(rest.len(), |(i, _)| i);
+            let (head, tail) = rest.split_at(split);
+            current.push(Span::styled(head.to_string(), span.style));
+            used += head.chars().count();
+            rest = tail;
+        }
+    }
+    rows.push(Line::from(current));
+    rows
+}
+
+/// First entry shown in a viewport of `height` rows: the tail while
+/// following, else centred on the selection.
+fn feed_offset(
+    app: &App,
+    visible: &[(usize, &LogEntry)],
+    selected: usize,
+    height: usize,
+    width: usize,
+) -> usize {
+    let total_visible = visible.len();
+    let mut offset = if app.frozen || selected < total_visible.saturating_sub(height) {
+        selected.saturating_sub(height / 2)
+    } else {
+        total_visible.saturating_sub(height)
+    };
+    // Expanded and wrapped entries take several rows; keep the selection on screen
+    if !app.expanded.is_empty() || wraps(app) {
+        let rows: Vec<usize> = visible[offset..=selected]
+            .iter()
+            .map(|e| feed_rows(app, e, width))
+            .collect();
+        let mut total: usize = rows.iter().sum();
+        for row in rows {
+            if offset >= selected || total <= height {
+                break;
+            }
+            total -= row;
+            offset += 1;
+        }
+    }
+    offset
+}
+
+/// Index (into the visible entries) of the feed entry drawn at terminal
+/// `row`, for mouse clicks. `screen` is the whole terminal area.
+pub fn feed_entry_at(app: &App, screen: Rect, row: u16) -> Option<usize> {
+    let (_, list) = feed_list_area(app, screen_chunks(app, screen)[1]);
+    if row < list.y || row >= list.bottom() {
+        return None;
+    }
+    let visible = app.visible_logs();
+    if visible.is_empty() {
+        return None;
+    }
+    let selected = app.selected_index.min(visible.len() - 1);
+    let width = list.width as usize;
+    let offset = feed_offset(app, &visible, selected, list.height as usize, width);
+    let mut top = list.y as usize;
+    for (i, entry) in visible.iter().enumerate().skip(offset) {
+        top += feed_rows(app, entry, width);
+        if (row as usize) < top {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Key hints in the footer; clicking one acts like pressing its key.
+const FOOTER_HINTS: [(&str, &str); 12] = [
+    ("q", "quit"),
+    ("Space", "pause"),
+    ("/", "filter"),
+    ("?", "search"),
+    ("e", "errors"),
+    ("y", "copy"),
+    ("*", "mark"),
+    ("s", "save"),
+    ("J", "json"),
+    ("g", "goto"),
+    ("Enter", "detail"),
+    ("F1", "help"),
+];
+
+/// Whether terminal `row` falls on the footer bar.
+pub fn in_footer(app: &App, screen: Rect, row: u16) -> bool {
+    let footer = screen_chunks(app, screen)[2];
+    row >= footer.y && row < footer.bottom()
+}
+
+/// The footer hint key under terminal `column`, when the hints are shown.
+pub fn footer_hint_at(app: &App, screen: Rect, column: u16) -> Option<&'static str> {
+    if app.input_mode != InputMode::Normal || app.status_message.is_some() {
+        return None;
+    }
+    // Border, then the leading space
+    let mut x = screen_chunks(app, screen)[2].x as usize + 2;
+    for (key, label) in FOOTER_HINTS {
+        let width = key.chars().count() + 1 + label.len() + 1;
+        if (x..x + width).contains(&(column as usize)) {
+            return Some(key);
+        }
+        x += width;
+    }
+    None
+}
+
+/// "shown / buffered (pct%)" for the feed border, when a filter hides entries.
+fn filtered_fraction(shown: usize, buffered: usize) -> Option<String> {
+    if shown >= buffered {
+        return None;
+    }
+    Some(format!(
+        " {} / {} ({}%) ",
+        shown,
+        buffered,
+        shown * 100 / buffered
+    ))
+}
+
+/// Continuation lines of an expanded entry, indented under it.
+fn continuation_lines(entry: &LogEntry, h_scroll: usize, theme: &Theme) -> Vec<Line<'static>> {
+    let dim = Style::default().fg(theme.dim_fg);
+    entry
+        .extra_lines
+        .iter()
+        .map(|line| {
+            Line::from(vec![
+                Span::styled("  │ ", dim),
+                Span::styled(skip_columns(&strip_ansi(line), h_scroll), dim),
+            ])
+        })
+        .collect()
+}
+
+/// Levels with a sparkline in the stats view, most severe first.
+const STATS_LEVELS: [LogLevel; 6] = [
+    LogLevel::Fatal,
+    LogLevel::Error,
+    LogLevel::Warn,
+    LogLevel::Info,
+    LogLevel::Debug,
+    LogLevel::Trace,
+];
+
+/// Statistics view (i key): per-level table and buffer info on the left,
+/// one EPS sparkline per level on the right, the top messages below.
+fn draw_stats(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Statistics (↑/↓ message, Enter filter, any other key to return) ")
+        .style(Style::default().fg(app.theme.border_fg));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [upper, lower] = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(TOP_MESSAGES as u16 + 2),
+    ])
+    .areas(inner);
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(upper);
+
+    let header = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::styled(
+        format!(
+            "{:<8} {:>10} {:>7} {:>10}",
+            "Level", "Count", "%", "Rate(EPS)"
+        ),
+        header,
+    )];
+    let levels = STATS_LEVELS.iter().chain([&LogLevel::Unknown]);
+    for &level in levels {
+        let count = app.level_counts[level as usize];
+        let percent = if app.total_count == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / app.total_count as f64
+        };
+        let rate = app.level_eps_counters[level as usize]
+            .back()
+            .copied()
+            .unwrap_or(0);
+        let name = match level.name() {
+            "" => "OTHER",
+            name => name,
+        };
+        lines.push(Line::styled(
+            format!("{:<8} {:>10} {:>6.1}% {:>10}", name, count, percent, rate),
+            Style::default().fg(app.theme.level_color(level)),
+        ));
+    }
+
+    let filters = app.active_filters();
+    let (eps_min, eps_avg, eps_max) = app.eps_summary();
+    let span = match app.buffer_time_span() {
+        Some((first, last)) => format!(
+            "{} → {} ({})",
+            first.format("%H:%M:%S"),
+            last.format("%H:%M:%S"),
+            format_span(last - first)
+        ),
+        None => "-".to_string(),
+    };
+    lines.extend([
+        Line::raw(""),
+        Line::raw(format!("Total lines received  {}", app.total_count)),
+        Line::raw(format!(
+            "EPS min / avg / max   {} / {:.1} / {} (last {}s)",
+            eps_min,
+            eps_avg,
+            eps_max,
+            app.eps_history.len()
+        )),
+        Line::raw(format!("Buffer time span      {}", span)),
+        Line::raw(format!(
+            "Buffer usage          {} / {}",
+            app.logs.len(),
+            app.max_lines
+        )),
+        Line::raw(format!(
+            "First timestamp seen  {}",
+            app.first_timestamp.as_deref().unwrap_or("-")
+        )),
+        Line::raw(format!(
+            "Last timestamp seen   {}",
+            app.last_timestamp.as_deref().unwrap_or("-")
+        )),
+        Line::raw(format!(
+            "Active filters        {}",
+            if filters.is_empty() {
+                "none".to_string()
+            } else {
+                filters.join(", ")
+            }
+        )),
+    ]);
+    frame.render_widget(Paragraph::new(lines), left);
+
+    let rows = Layout::vertical([Constraint::Ratio(1, STATS_LEVELS.len() as u32); 6]).split(right);
+    for (level, row) in STATS_LEVELS.iter().zip(rows.iter()) {
+        let data: Vec<u64> = app.level_eps_counters[*level as usize]
+            .iter()
+            .copied()
+            .collect();
+        let sparkline = Sparkline::default()
+            .block(Block::default().title(format!(" {} ", level.name())))
+            .data(&data)
+            .style(Style::default().fg(app.theme.level_color(*level)));
+        frame.render_widget(sparkline, *row);
+    }
+
+    let top = app.message_stats.top(TOP_MESSAGES);
+    let mut lines = vec![Line::styled(
+        format!(
+            "{:>8}  {}",
+            "Count", "Top messages (numbers and UUIDs masked)"
+        ),
+        header,
+    )];
+    for (i, (shape, count)) in top.iter().enumerate() {
+        let style = if i == app.stats_selected {
+            app.theme.selected_style().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::styled(format!("{:>8}  {}", count, shape), style));
+    }
+    let block = Block::default().borders(Borders::TOP);
+    frame.render_widget(Paragraph::new(lines).block(block), lower);
+}
+
+/// Length of the buffered time range, like `32m 43s` or `2h 05m`.
+fn format_span(span: chrono::TimeDelta) -> String {
+    let secs = span.num_seconds().max(0);
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        3600..=86_399 => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {:02}h", secs / 86_400, secs % 86_400 / 3600),
+    }
+}
+
+const TABLE_HEADERS: [&str; 4] = ["Timestamp", "Level", "Message", "Metadata"];
+/// Longest a table cell gets before it's cut off, so Metadata stays reachable.
+const MAX_COLUMN_WIDTH: usize = 120;
+/// Widest key column in the detail view's field tables
+const FIELD_KEY_WIDTH: usize = 40;
+const COLUMN_GAP: &str = "  ";
+
+/// Table layout cells: timestamp, level, message, metadata.
+fn table_cells(entry: &LogEntry) -> [&str; 4] {
+    [
+        entry.timestamp.as_deref().unwrap_or(""),
+        entry.level.name(),
+        entry.message.as_deref().unwrap_or(&entry.raw),
+        entry.metadata.as_deref().unwrap_or(""),
+    ]
+}
+
+/// Width of each table column: its widest cell (or header) in the window.
+fn column_widths<'a>(entries: impl Iterator<Item = &'a &'a LogEntry>) -> [u16; 4] {
+    let mut widths = TABLE_HEADERS.map(|h| h.len());
+    for entry in entries.filter(|e| !e.divider) {
+        for (width, cell) in widths.iter_mut().zip(table_cells(entry)) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    widths.map(|w| w.min(MAX_COLUMN_WIDTH) as u16)
+}
+
+fn table_header(widths: [u16; 4], h_scroll: usize, theme: &Theme) -> Line<'static> {
+    let style = Style::default()
+        .fg(theme.accent_fg)
+        .add_modifier(Modifier::BOLD);
+    let mut cells = Vec::new();
+    for (i, (header, width)) in TABLE_HEADERS.iter().zip(widths).enumerate() {
+        if i > 0 {
+            cells.push(Span::raw(COLUMN_GAP));
+        }
+        cells.push(Span::styled(pad_cell(header, width), style));
+    }
+    let mut spans = vec![Span::raw("  ")];
+    spans.extend(scroll_spans(cells, h_scroll));
+    Line::from(spans)
+}
+
+/// One entry as padded columns; horizontal scroll shifts every column but
+/// not the bookmark/flag gutter.
+fn table_row(
+    entry: &LogEntry,
+    widths: [u16; 4],
+    h_scroll: usize,
+    hl_patterns: &[(&Regex, Style)],
+    marks: EntryMarks,
+    theme: &Theme,
+) -> Line<'static> {
+    let color = theme.level_color(entry.level);
+    let [ts, level, message, metadata] = table_cells(entry);
+    let mut message = pad_cell(message, widths[2]);
+    if let Some(count) = marks.repeats {
+        message = pad_cell(&format!("{} [×{}]", message.trim_end(), count), widths[2]);
+    }
+    if let Some(count) = marks.similar {
+        message = pad_cell(
+            &format!("{} [×{} similar]", message.trim_end(), count),
+            widths[2],
+        );
+    }
+
+    let mut cells = vec![
+        Span::styled(pad_cell(ts, widths[0]), Style::default().fg(theme.dim_fg)),
+        Span::raw(COLUMN_GAP),
+        Span::styled(
+            pad_cell(level, widths[1]),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(COLUMN_GAP),
+    ];
+    let message_style = Style::default().fg(color);
+    if hl_patterns.is_empty() {
+        cells.push(Span::styled(message, message_style));
+    } else {
+        cells.extend(apply_highlights(&message, message_style, hl_patterns));
+    }
+    cells.push(Span::raw(COLUMN_GAP));
+    cells.push(Span::styled(
+        pad_cell(metadata, widths[3]),
+        Style::default().fg(theme.metadata_fg),
     ));
 
-    frame.render_widget(list, area);
+    let gutter = if marks.bookmarked {
+        Span::styled("★ ", Style::default().fg(theme.bookmark_fg))
+    } else if marks.flagged {
+        Span::styled("⚑ ", Style::default().fg(theme.flag_fg))
+    } else {
+        Span::raw("  ")
+    };
+    let mut spans = vec![gutter];
+    spans.extend(scroll_spans(cells, h_scroll));
+    Line::from(spans)
+}
+
+/// Pad or cut `text` to exactly `width` characters (`…` marks a cut).
+fn pad_cell(text: &str, width: u16) -> String {
+    let width = width as usize;
+    if text.chars().count() > width {
+        let cut: String = text.chars().take(width.saturating_sub(1)).collect();
+        format!("{}…", cut)
+    } else {
+        format!("{:<width$}", text)
+    }
+}
+
+/// Drop the first `n` display columns across a run of spans.
+fn scroll_spans(spans: Vec<Span<'static>>, mut n: usize) -> Vec<Span<'static>> {
+    let mut out = Vec::with_capacity(spans.len());
+    for span in spans {
+        let len = span.content.width();
+        if n >= len {
+            n -= len;
+            continue;
+        }
+        out.push(Span::styled(skip_columns(&span.content, n), span.style));
+        n = 0;
+    }
+    out
+}
+
+/// Shown after "Filter" / "Search" while they only match the message (M key).
+fn scope_label(app: &App) -> &'static str {
+    match app.match_scope {
+        MatchScope::Raw => "",
+        MatchScope::Message => " [message]",
+    }
+}
+
+/// A prompt: its label, then the text being edited with the cursor drawn on
+/// its character (`_` past the end). Long text scrolls to keep the cursor in view.
+fn prompt_line<'a>(label: Span<'a>, app: &'a App, color: Color, area: Rect) -> Line<'a> {
+    styled_prompt_line(label, app, color, area, |_| Style::default(), None)
+}
+
+/// A prompt whose text is styled per byte offset by `style_at`, with an
+/// optional `suffix` (e.g. an error) kept in view after the text.
+fn styled_prompt_line<'a>(
+    label: Span<'a>,
+    app: &'a App,
+    color: Color,
+    area: Rect,
+    style_at: impl Fn(usize) -> Style,
+    suffix: Option<Span<'a>>,
+) -> Line<'a> {
+    let text = app.input_text();
+    let cursor = app.input_cursor_pos();
+    // Inside the border, after the label and suffix, leaving a column for the cursor
+    let suffix_width = suffix.as_ref().map_or(0, Span::width);
+    let room = (area.width as usize).saturating_sub(label.width() + suffix_width + 3);
+    let mut spans = vec![label];
+    let hidden = text[..cursor]
+        .chars()
+        .count()
+        .saturating_sub(room.saturating_sub(1));
+    let mut start = 0;
+    if hidden > 0 && room > 1 {
+        start = text[..cursor]
+            .char_indices()
+            .nth(hidden)
+            .map_or(cursor, |(i, _)| i);
+        spans.push(Span::styled("…", Style::default().fg(color)));
+    }
+    push_styled_runs(&mut spans, text, start..cursor, &style_at);
+    match text[cursor..].chars().next() {
+        Some(c) => {
+            spans.push(Span::styled(
+                c.to_string(),
+                style_at(cursor).add_modifier(Modifier::REVERSED),
+            ));
+            push_styled_runs(
+                &mut spans,
+                text,
+                cursor + c.len_utf8()..text.len(),
+                &style_at,
+            );
+        }
+        None => spans.push(Span::styled("_", Style::default().fg(color))),
+    }
+    spans.extend(suffix);
+    Line::from(spans)
+}
+
+/// Push `text[range]` as spans, one per run of characters sharing a style.
+fn push_styled_runs<'a>(
+    spans: &mut Vec<Span<'a>>,
+    text: &'a str,
+    range: std::ops::Range<usize>,
+    style_at: &impl Fn(usize) -> Style,
+) {
+    let mut run_start = range.start;
+    let mut run_style = style_at(run_start);
+    for (i, _) in text[range.clone()].char_indices() {
+        let style = style_at(range.start + i);
+        if style != run_style {
+            if range.start + i > run_start {
+                spans.push(Span::styled(&text[run_start..range.start + i], run_style));
+            }
+            run_start = range.start + i;
+            run_style = style;
+        }
+    }
+    if range.end > run_start {
+        spans.push(Span::styled(&text[run_start..range.end], run_style));
+    }
+}
+
+/// Prompt colours for the filter terms: includes, `!` excludes, `svc:`/`level:`.
+fn filter_term_styles(app: &App) -> Vec<(std::ops::Range<usize>, Style)> {
+    let theme = &app.theme;
+    parse_filter(app.input_text())
+        .into_iter()
+        .map(|token| {
+            let style = match token.term {
+                FilterTerm::Include(Err(_)) | FilterTerm::Exclude(Err(_)) => Style::default()
+                    .fg(theme.error_color)
+                    .add_modifier(Modifier::UNDERLINED),
+                FilterTerm::Include(Ok(_)) => Style::default().fg(theme.filter_fg),
+                FilterTerm::Exclude(Ok(_)) => Style::default().fg(theme.error_color),
+                FilterTerm::Source(_) | FilterTerm::MinLevel(_) => {
+                    Style::default().fg(theme.accent_fg)
+                }
+            };
+            (token.range, style)
+        })
+        .collect()
 }
 
 fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let (content, title) = match app.input_mode {
         InputMode::Filter => {
-            let input_line = Line::from(vec![
-                Span::styled(" Filter: ", Style::default().fg(Color::Yellow)),
-                Span::raw(&app.filter_text),
-                Span::styled("_", Style::default().fg(Color::Yellow)),
-            ]);
+            let term_styles = filter_term_styles(app);
+            let error = app.filter_error.as_ref().map(|e| {
+                Span::styled(format!("  ✗ {}", e), Style::default().fg(theme.error_color))
+            });
+            let input_line = styled_prompt_line(
+                Span::styled(
+                    format!(" Filter{}: ", scope_label(app)),
+                    Style::default().fg(theme.filter_fg),
+                ),
+                app,
+                theme.filter_fg,
+                area,
+                |at| {
+                    term_styles
+                        .iter()
+                        .find(|(range, _)| range.contains(&at))
+                        .map_or(Style::default(), |(_, style)| *style)
+                },
+                error,
+            );
             (
                 Paragraph::new(input_line),
-                " Filter Mode (Esc cancel, Enter apply) ",
+                " Filter Mode (Esc cancel, Enter apply, ↑/↓ history) ",
             )
         }
         InputMode::Search => {
-            let input_line = Line::from(vec![
-                Span::styled(" Search: ", Style::default().fg(Color::Yellow)),
-                Span::raw(&app.input_buffer),
-                Span::styled("_", Style::default().fg(Color::Yellow)),
-            ]);
+            let input_line = prompt_line(
+                Span::styled(
+                    format!(" Search{}: ", scope_label(app)),
+                    Style::default().fg(theme.filter_fg),
+                ),
+                app,
+                theme.filter_fg,
+                area,
+            );
             (
                 Paragraph::new(input_line),
                 " Search Mode (Esc cancel, Enter apply, n/N navigate) ",
             )
         }
         InputMode::Highlight => {
-            let input_line = Line::from(vec![
+            let input_line = prompt_line(
                 Span::styled(
                     format!(" Highlight ({} active): ", app.highlights.len()),
-                    Style::default().fg(Color::Magenta),
+                    Style::default().fg(theme.highlight_fg),
                 ),
-                Span::raw(&app.input_buffer),
-                Span::styled("_", Style::default().fg(Color::Magenta)),
-            ]);
+                app,
+                theme.highlight_fg,
+                area,
+            );
             (
                 Paragraph::new(input_line),
                 " Highlight Mode (Esc cancel, Enter empty=clear) ",
             )
         }
         InputMode::SavePrompt => {
-            let input_line = Line::from(vec![
-                Span::styled(" Save to: ", Style::default().fg(Color::Green)),
-                Span::raw(&app.input_buffer),
-                Span::styled("_", Style::default().fg(Color::Green)),
-            ]);
+            let input_line = prompt_line(
+                Span::styled(" Save to: ", Style::default().fg(theme.status_fg)),
+                app,
+                theme.status_fg,
+                area,
+            );
             (
                 Paragraph::new(input_line),
                 " Save Mode (Esc cancel, Enter save) ",
             )
         }
-        InputMode::TimeJump => {
+        InputMode::ExportPrompt(format) => {
+            let input_line = prompt_line(
+                Span::styled(
+                    format!(" Save {} to: ", format.name()),
+                    Style::default().fg(theme.status_fg),
+                ),
+                app,
+                theme.status_fg,
+                area,
+            );
+            let title = match format {
+                ExportFormat::Json => " Save Mode (Esc cancel, Enter export JSON) ",
+                ExportFormat::Csv => " Save Mode (Esc cancel, Enter export CSV) ",
+                ExportFormat::Html => " Save Mode (Esc cancel, Enter export HTML) ",
+            };
+            (Paragraph::new(input_line), title)
+        }
+        InputMode::ConfirmCopy(lines) => {
             let input_line = Line::from(vec![
-                Span::styled(" Jump to time: ", Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!(" Copy {} lines? ", lines),
+                    Style::default().fg(theme.status_fg),
+                ),
+                Span::styled("(y/N)", Style::default().fg(theme.accent_fg)),
+            ]);
+            (Paragraph::new(input_line), " Copy All ")
+        }
+        InputMode::SaveScope => {
+            let input_line = Line::from(vec![
+                Span::styled(" Save to ", Style::default().fg(theme.status_fg)),
                 Span::raw(&app.input_buffer),
-                Span::styled("_", Style::default().fg(Color::Cyan)),
+                Span::styled(": ", Style::default().fg(theme.status_fg)),
+                Span::styled("a", Style::default().fg(theme.accent_fg)),
+                Span::raw(":all visible "),
+                Span::styled("b", Style::default().fg(theme.accent_fg)),
+                Span::raw(format!(":bookmarked only ({})", app.bookmarks.len())),
             ]);
+            (
+                Paragraph::new(input_line),
+                " Save Mode (Esc cancel, Enter all) ",
+            )
+        }
+        InputMode::SortPrompt => {
+            let input_line = prompt_line(
+                Span::styled(" Sort by: ", Style::default().fg(theme.accent_fg)),
+                app,
+                theme.accent_fg,
+                area,
+            );
+            (
+                Paragraph::new(input_line),
+                " Numeric Sort (regex, e.g. latency=(\\d+); largest first) ",
+            )
+        }
+        InputMode::FuzzySearch => {
+            let input_line = prompt_line(
+                Span::styled(" Fuzzy: ", Style::default().fg(theme.fuzzy_fg)),
+                app,
+                theme.fuzzy_fg,
+                area,
+            );
+            (
+                Paragraph::new(input_line),
+                " Fuzzy Search (Esc clear, Enter select best match) ",
+            )
+        }
+        InputMode::CorrelationSearch => {
+            let input_line = prompt_line(
+                Span::styled(" Track ID: ", Style::default().fg(theme.filter_fg)),
+                app,
+                theme.filter_fg,
+                area,
+            );
+            (
+                Paragraph::new(input_line),
+                " Correlation (request / trace ID; empty stops tracking) ",
+            )
+        }
+        InputMode::DetailSearch => {
+            let input_line = prompt_line(
+                Span::styled(" Find in detail: ", Style::default().fg(theme.filter_fg)),
+                app,
+                theme.filter_fg,
+                area,
+            );
+            (
+                Paragraph::new(input_line),
+                " Detail Search (Esc cancel, Enter find, then n/N) ",
+            )
+        }
+        InputMode::TimeJump => {
+            let input_line = prompt_line(
+                Span::styled(" Jump to time: ", Style::default().fg(theme.accent_fg)),
+                app,
+                theme.accent_fg,
+                area,
+            );
             (
                 Paragraph::new(input_line),
                 " Time Jump (e.g. 14:30, 2024-01-15) ",
             )
         }
-        InputMode::Normal => {
-            // Show status message if active, otherwise show help
-            if let Some((ref msg, _)) = app.status_message {
-                let status_line = Line::from(Span::styled(
-                    format!(" {}", msg),
-                    Style::default().fg(Color::Green),
-                ));
-                (Paragraph::new(status_line), " Status ")
-            } else {
-                let help = Line::from(vec![
-                    Span::styled(" q", Style::default().fg(Color::Cyan)),
-                    Span::raw(":quit "),
-                    Span::styled("Space", Style::default().fg(Color::Cyan)),
-                    Span::raw(":pause "),
-                    Span::styled("/", Style::default().fg(Color::Cyan)),
-                    Span::raw(":filter "),
-                    Span::styled("?", Style::default().fg(Color::Cyan)),
-                    Span::raw(":search "),
-                    Span::styled("e", Style::default().fg(Color::Cyan)),
-                    Span::raw(":errors "),
-                    Span::styled("y", Style::default().fg(Color::Cyan)),
-                    Span::raw(":copy "),
-                    Span::styled("*", Style::default().fg(Color::Cyan)),
-                    Span::raw(":mark "),
-                    Span::styled("s", Style::default().fg(Color::Cyan)),
-                    Span::raw(":save "),
-                    Span::styled("g", Style::default().fg(Color::Cyan)),
-                    Span::raw(":goto "),
-                    Span::styled("Enter", Style::default().fg(Color::Cyan)),
-                    Span::raw(":detail"),
-                ]);
-                (Paragraph::new(help), " Help ")
-            }
+        InputMode::Normal => {
+            // Show status message if active, otherwise show help
+            if let Some((ref msg, _)) = app.status_message {
+                let status_line = Line::from(Span::styled(
+                    format!(" {}", msg),
+                    Style::default().fg(theme.status_fg),
+                ));
+                (Paragraph::new(status_line), " Status ")
+            } else {
+                let mut spans = vec![Span::raw(" ")];
+                for (i, (key, label)) in FOOTER_HINTS.iter().enumerate() {
+                    let sep = if i + 1 < FOOTER_HINTS.len() { " " } else { "" };
+                    spans.push(Span::styled(*key, Style::default().fg(theme.accent_fg)));
+                    spans.push(Span::raw(format!(":{}{}", label, sep)));
+                }
+                (Paragraph::new(Line::from(spans)), " Help ")
+            }
+        }
+    };
+
+    let block = Block::default().borders(Borders::ALL).title(title);
+    frame.render_widget(content.block(block), area);
+}
+
+fn draw_detail_modal(frame: &mut Frame, app: &App, visible: &[(usize, &LogEntry)]) {
+    let (idx, entry) = match visible.get(app.selected_index) {
+        Some((i, e)) => (i, e),
+        None => return,
+    };
+
+    let area = centered_rect(80, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    if let Some(ref tree) = app.detail_tree {
+        draw_json_tree(frame, app, tree, *idx, entry, area);
+        return;
+    }
+
+    let lines = detail_lines(app, *idx, entry);
+    let patterns: Vec<(&Regex, Style)> = app
+        .detail_search
+        .iter()
+        .map(|re| (re, app.theme.search_hl_style()))
+        .collect();
+    let matching = app
+        .detail_search
+        .as_ref()
+        .map(|re| lines.iter().filter(|line| re.is_match(line)).count());
+    let text: Vec<Line> = lines
+        .iter()
+        .skip(app.detail_scroll.min(lines.len().saturating_sub(1)))
+        .map(|line| Line::from(apply_highlights(line, Style::default(), &patterns)))
+        .collect();
+    let title = match matching {
+        Some(count) => format!(
+            " Detail View — {} matching line{} (n/N next/prev, Esc clear search) ",
+            count,
+            if count == 1 { "" } else { "s" }
+        ),
+        None => " Detail View (↑/↓ scroll, / search, y copy, Esc close) ".to_string(),
+    };
+
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default().fg(app.theme.accent_fg)),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(paragraph, area);
+}
+
+/// The plain detail view's text, one string per line: the entry's marks,
+/// fields, raw line and continuation lines.
+pub fn detail_lines(app: &App, idx: usize, entry: &LogEntry) -> Vec<String> {
+    let idx = &idx;
+    let content = build_detail_text(entry);
+    let content = match (app.bookmarks.contains(idx), app.flagged.contains(idx)) {
+        (true, true) => format!("★ Bookmarked  ⚑ Flagged\n\n{}", content),
+        (true, false) => format!("★ Bookmarked\n\n{}", content),
+        (false, true) => format!("⚑ Flagged\n\n{}", content),
+        (false, false) => content,
+    };
+    let content = match app.dedup_counts.get(idx) {
+        Some(count) => format!("Repeated {} times\n\n{}", count, content),
+        None => content,
+    };
+    let content = match app.groups.get(idx) {
+        Some(group) => format!(
+            "{} similar: {}\n\n{}",
+            group.indices.len(),
+            group.template,
+            content
+        ),
+        None => content,
+    };
+    // Raw lines may carry colour codes under --keep-ansi
+    strip_ansi(&content).lines().map(String::from).collect()
+}
+
+/// JSON entries: one row per key, containers fold with Enter.
+fn draw_json_tree(
+    frame: &mut Frame,
+    app: &App,
+    tree: &JsonTreeState,
+    idx: usize,
+    entry: &LogEntry,
+    area: Rect,
+) {
+    let dim = Style::default().fg(app.theme.dim_fg);
+    let mut items: Vec<ListItem> = Vec::new();
+    let mut notes = Vec::new();
+    if let Some(count) = app.dedup_counts.get(&idx) {
+        notes.push(format!("Repeated {} times", count));
+    }
+    if let Some(group) = app.groups.get(&idx) {
+        notes.push(format!("{} similar", group.indices.len()));
+    }
+    if app.bookmarks.contains(&idx) {
+        notes.push("★ Bookmarked".to_string());
+    }
+    if app.flagged.contains(&idx) {
+        notes.push("⚑ Flagged".to_string());
+    }
+    if !notes.is_empty() {
+        items.push(ListItem::new(Line::styled(notes.join("  "), dim)));
+        items.push(ListItem::new(""));
+    }
+
+    if tree.filtering || !tree.field_filter.is_empty() {
+        let cursor = if tree.filtering { "_" } else { "" };
+        items.push(ListItem::new(Line::styled(
+            format!(
+                "Filter: {}{}  ({} fields)",
+                tree.field_filter,
+                cursor,
+                tree.fields().len()
+            ),
+            Style::default().fg(app.theme.accent_fg),
+        )));
+        items.push(ListItem::new(""));
+    }
+
+    let first_row = items.len();
+    if tree.table {
+        let fields = tree.fields();
+        let width = fields
+            .iter()
+            .map(|(path, _)| path.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(FIELD_KEY_WIDTH);
+        for (path, value) in fields {
+            items.push(ListItem::new(field_row_line(
+                &path, value, width, &app.theme,
+            )));
+        }
+    } else {
+        for row in tree.rows() {
+            items.push(ListItem::new(tree_row_line(&row, &app.theme)));
         }
-    };
+    }
+    if !entry.extra_lines.is_empty() {
+        items.push(ListItem::new(""));
+        items.push(ListItem::new(Line::styled("--- Continuation ---", dim)));
+        for line in &entry.extra_lines {
+            items.push(ListItem::new(Line::styled(
+                strip_ansi(line).into_owned(),
+                dim,
+            )));
+        }
+    }
 
-    let block = Block::default().borders(Borders::ALL).title(title);
-    frame.render_widget(content.block(block), area);
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(if tree.table {
+                    " Detail View (↑/↓ move, / filter fields, Tab tree, Space copy value, y copy JSON, Esc close) "
+                } else {
+                    " Detail View (↑/↓ move, Enter fold, Tab table, Space copy value, y copy JSON, Esc close) "
+                })
+                .style(Style::default().fg(app.theme.accent_fg)),
+        )
+        .highlight_style(app.theme.selected_style());
+    let mut state = ListState::default().with_selected(Some(first_row + tree.cursor));
+    frame.render_stateful_widget(list, area, &mut state);
 }
 
-fn draw_detail_modal(frame: &mut Frame, app: &App, visible: &[(usize, &LogEntry)]) {
-    let entry = match visible.get(app.selected_index) {
-        Some((_, e)) => e,
-        None => return,
+/// A row of the detail view's field table: the dot path padded to `width`,
+/// then the value.
+fn field_row_line(
+    path: &str,
+    value: &serde_json::Value,
+    width: usize,
+    theme: &Theme,
+) -> Line<'static> {
+    let (text, color) = json_value_text(value, theme);
+    Line::from(vec![
+        Span::styled(
+            format!("{:<width$}  ", path, width = width),
+            Style::default().fg(theme.json_key_fg),
+        ),
+        Span::styled(strip_ansi(&text).into_owned(), Style::default().fg(color)),
+    ])
+}
+
+fn json_value_text(value: &serde_json::Value, theme: &Theme) -> (String, Color) {
+    match value {
+        serde_json::Value::Object(map) => (format!("{{{}}}", map.len()), theme.dim_fg),
+        serde_json::Value::Array(items) => (format!("[{}]", items.len()), theme.dim_fg),
+        serde_json::Value::String(_) => (value.to_string(), theme.json_string_fg),
+        serde_json::Value::Number(n) => (n.to_string(), theme.json_number_fg),
+        serde_json::Value::Bool(b) => (b.to_string(), theme.json_bool_fg),
+        serde_json::Value::Null => ("null".to_string(), theme.dim_fg),
+    }
+}
+
+fn tree_row_line(row: &TreeRow, theme: &Theme) -> Line<'static> {
+    let mut spans = vec![Span::raw("  ".repeat(row.depth))];
+    let arrow = if !is_container(row.value) {
+        "  "
+    } else if row.open {
+        "▼ "
+    } else {
+        "▶ "
     };
+    spans.push(Span::raw(arrow));
+    if let Some(ref key) = row.key {
+        spans.push(Span::styled(
+            format!("{}: ", key),
+            Style::default().fg(theme.json_key_fg),
+        ));
+    }
+    let (text, color) = json_value_text(row.value, theme);
+    spans.push(Span::styled(
+        strip_ansi(&text).into_owned(),
+        Style::default().fg(color),
+    ));
+    Line::from(spans)
+}
 
-    let area = centered_rect(80, 80, frame.area());
+fn draw_help_modal(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let area = centered_rect(60, 80, frame.area());
     frame.render_widget(Clear, area);
+    let inner = area.inner(Margin {
+        vertical: 1,
+        horizontal: 1,
+    });
 
-    let content = if entry.raw.trim().starts_with('{') {
-        // Try to pretty-print JSON
-        match serde_json::from_str::<serde_json::Value>(entry.raw.trim()) {
-            Ok(val) => {
-                let mut s =
-                    serde_json::to_string_pretty(&val).unwrap_or_else(|_| entry.raw.clone());
-                if !entry.extra_lines.is_empty() {
-                    s.push_str("\n\n--- Continuation ---\n");
-                    for line in &entry.extra_lines {
-                        s.push_str(line);
-                        s.push('\n');
-                    }
-                }
-                s
-            }
-            Err(_) => build_detail_text(entry),
+    // Each action with the keys it's bound to now, `[keybindings]` included
+    let mut groups: Vec<(&str, Vec<(&str, &str)>)> = keymap::HELP
+        .iter()
+        .map(|(category, rows)| {
+            let rows = rows
+                .iter()
+                .map(|(action, text)| (app.keymap.keys_for(*action), *text));
+            (*category, rows.collect())
+        })
+        .collect();
+    groups.push(("Prompts and detail view", CONTEXT_KEYS.to_vec()));
+    let mut lines: Vec<Line> = Vec::new();
+    for (i, (category, bindings)) in groups.into_iter().enumerate() {
+        if i > 0 {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            category,
+            Style::default()
+                .fg(theme.filter_fg)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for (key, action) in bindings {
+            let line = Line::from(vec![
+                Span::styled(
+                    format!("  {:<12}", key),
+                    Style::default().fg(theme.accent_fg),
+                ),
+                Span::raw(action.to_string()),
+            ]);
+            lines.extend(wrap_line(line, inner.width as usize, theme));
         }
+    }
+
+    // Taller than the modal: remember how far it scrolls for the key handler
+    let max_scroll = lines.len().saturating_sub(inner.height as usize);
+    app.help_max_scroll.set(max_scroll);
+    let scroll = app.help_scroll.min(max_scroll);
+    let title = if max_scroll > 0 {
+        " Key Bindings (j/k scroll, Esc to close) "
     } else {
-        build_detail_text(entry)
+        " Key Bindings (Esc to close) "
     };
-
-    let paragraph = Paragraph::new(content)
+    let paragraph = Paragraph::new(lines)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" Detail View (Esc to close) ")
-                .style(Style::default().fg(Color::Cyan)),
+                .title(title)
+                .style(Style::default().fg(theme.border_fg)),
         )
-        .wrap(Wrap { trim: false });
+        .scroll((scroll as u16, 0));
+    frame.render_widget(paragraph, area);
+
+    if max_scroll > 0 {
+        let mut state = ScrollbarState::new(max_scroll + 1).position(scroll);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut state,
+        );
+    }
+}
+
+/// Startup question when format detection is unsure, one row per candidate.
+fn draw_format_picker(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let rows = app.format_choices.len() as u16;
+    let full = frame.area();
+    let area = centered_rect(50, 100, full);
+    let height = (rows + 4).min(full.height);
+    let area = Rect::new(
+        area.x,
+        full.y + (full.height - height) / 2,
+        area.width,
+        height,
+    );
+    frame.render_widget(Clear, area);
 
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Few lines matched a known format. Parse them as:",
+            Style::default().fg(theme.dim_fg),
+        )),
+        Line::from(""),
+    ];
+    for (i, choice) in app.format_choices.iter().enumerate() {
+        let style = if i == app.format_selected {
+            theme.selected_style().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(format!(" {} ", choice), style)));
+    }
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Format (↑/↓, Enter; Esc keeps the best guess) ")
+            .style(Style::default().fg(theme.border_fg)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+/// How many entries the recent-errors popup lists
+const ERROR_PEEK_COUNT: usize = 10;
+
+fn draw_error_peek_modal(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let errors = app.recent_errors(ERROR_PEEK_COUNT);
+    let full = frame.area();
+    let area = centered_rect(80, 100, full);
+    let height = (errors.len().max(1) as u16 + 2).min(full.height);
+    let area = Rect::new(
+        area.x,
+        full.y + (full.height - height) / 2,
+        area.width,
+        height,
+    );
+    frame.render_widget(Clear, area);
+
+    let lines: Vec<Line> = if errors.is_empty() {
+        vec![Line::styled(
+            "No errors in the buffer",
+            Style::default().fg(theme.dim_fg),
+        )]
+    } else {
+        errors
+            .iter()
+            .map(|&idx| {
+                let entry = &app.logs[idx];
+                let raw = strip_ansi(&entry.raw);
+                let text = raw.lines().next().unwrap_or_default().to_string();
+                Line::styled(text, Style::default().fg(theme.level_color(entry.level)))
+            })
+            .collect()
+    };
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                " Last {} errors (any key closes) ",
+                ERROR_PEEK_COUNT
+            ))
+            .style(Style::default().fg(theme.border_fg)),
+    );
     frame.render_widget(paragraph, area);
 }
 
+/// What `y` copies in the detail view: the pretty-printed JSON when the entry
+/// is shown as a tree, else the same text as the plain detail view.
+pub fn detail_copy_text(entry: &LogEntry, tree: Option<&JsonTreeState>) -> String {
+    let text = match tree {
+        Some(tree) => serde_json::to_string_pretty(&tree.value).unwrap_or_default(),
+        None => build_detail_text(entry),
+    };
+    strip_ansi(&text).into_owned()
+}
+
 fn build_detail_text(entry: &LogEntry) -> String {
     let mut detail = String::new();
+    if let Some(src) = &entry.source {
+        detail.push_str(&format!("Source: {}\n", src));
+    }
     if let Some(ts) = &entry.timestamp {
         detail.push_str(&format!("Timestamp: {}\n", ts));
     }
@@ -301,6 +1639,19 @@ fn build_detail_text(entry: &LogEntry) -> String {
     }
     if let Some(meta) = &entry.metadata {
         detail.push_str(&format!("Metadata: {}\n", meta));
+        let fields = metadata_fields(meta);
+        if !fields.is_empty() {
+            let width = fields
+                .iter()
+                .map(|(key, _)| key.chars().count())
+                .max()
+                .unwrap_or(0)
+                .min(FIELD_KEY_WIDTH);
+            detail.push_str("\n--- Fields ---\n");
+            for (key, value) in fields {
+                detail.push_str(&format!("{:<width$}  {}\n", key, value, width = width));
+            }
+        }
     }
     detail.push_str(&format!("\n--- Raw ---\n{}", entry.raw));
     if !entry.extra_lines.is_empty() {
@@ -313,13 +1664,155 @@ fn build_detail_text(entry: &LogEntry) -> String {
     detail
 }
 
+/// Metadata as key/value rows: a JSON object by dot path, or `key=value`
+/// pairs (values may be double-quoted). Empty for anything else.
+fn metadata_fields(meta: &str) -> Vec<(String, String)> {
+    if let Ok(value @ serde_json::Value::Object(_)) = serde_json::from_str(meta) {
+        return flatten_fields(&value)
+            .into_iter()
+            .map(|(path, value)| match value {
+                serde_json::Value::String(s) => (path, s.clone()),
+                other => (path, other.to_string()),
+            })
+            .collect();
+    }
+    let mut fields = Vec::new();
+    let mut rest = meta.trim_start();
+    while !rest.is_empty() {
+        let token_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let Some(eq) = rest[..token_end].find('=').filter(|&eq| eq > 0) else {
+            rest = rest[token_end..].trim_start();
+            continue;
+        };
+        let key = rest[..eq].to_string();
+        let after = &rest[eq + 1..];
+        let (value, next) = match after.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(close) => (&quoted[..close], &quoted[close + 1..]),
+                None => (quoted, ""),
+            },
+            None => {
+                let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        fields.push((key, value.to_string()));
+        rest = next.trim_start();
+    }
+    fields
+}
+
+/// Per-entry annotations drawn around the log text.
+struct EntryMarks {
+    bookmarked: bool,
+    flagged: bool,
+    /// Dedup mode: how many identical lines this row stands for
+    repeats: Option<usize>,
+    /// Grouping mode: how many entries with its message template it stands for
+    similar: Option<usize>,
+}
+
+fn entry_marks(app: &App, idx: usize) -> EntryMarks {
+    EntryMarks {
+        bookmarked: app.bookmarks.contains(&idx),
+        flagged: app.flagged.contains(&idx),
+        repeats: app.dedup_counts.get(&idx).copied(),
+        similar: app.groups.get(&idx).map(|group| group.indices.len()),
+    }
+}
+
+/// `logs[idx]` as a line-layout feed row, with its marks and time label.
+fn feed_row(
+    app: &App,
+    idx: usize,
+    entry: &LogEntry,
+    hl_patterns: &[(&Regex, Style)],
+    now: chrono::DateTime<chrono::Local>,
+) -> Line<'static> {
+    let time_label = time_label(entry, app.time_display, app.timestamp_width, now);
+    colorize_entry(entry, app, hl_patterns, entry_marks(app, idx), time_label)
+}
+
+/// One feed row; scroll, --keep-ansi, fuzzy query and theme come from `app`.
 fn colorize_entry(
     entry: &LogEntry,
-    h_scroll: usize,
+    app: &App,
     hl_patterns: &[(&Regex, Style)],
+    marks: EntryMarks,
+    time_label: Option<String>,
 ) -> Line<'static> {
-    let color = level_color(entry.level);
-    let level_tag = match entry.level {
+    let theme = &app.theme;
+    let h_scroll = feed_scroll(app);
+    let fuzzy_query = app.fuzzy_search_query.as_str();
+    let color = theme.level_color(entry.level);
+    let level_tag = feed_tag(entry, app);
+
+    let mut spans = if app.keep_ansi && entry.raw.contains('\x1b') {
+        ansi_spans(level_tag, color, &entry.raw, h_scroll)
+    } else {
+        plain_spans(entry, level_tag, color, h_scroll, hl_patterns)
+    };
+    if !fuzzy_query.is_empty() {
+        spans = mark_fuzzy_matches(spans, fuzzy_query, theme.fuzzy_fg);
+    }
+    // Prefix with time and source tag (not affected by horizontal scroll)
+    if let Some(ref src) = entry.source {
+        spans.insert(
+            0,
+            Span::styled(
+                format!("{} ", src),
+                Style::default().fg(theme.source_color(src)),
+            ),
+        );
+    }
+    if let Some(label) = time_label {
+        spans.insert(0, Span::styled(label, Style::default().fg(theme.dim_fg)));
+    }
+    if marks.flagged {
+        spans.insert(0, Span::styled("⚑ ", Style::default().fg(theme.flag_fg)));
+    }
+    if marks.bookmarked {
+        spans.insert(
+            0,
+            Span::styled("★ ", Style::default().fg(theme.bookmark_fg)),
+        );
+    }
+
+    // Append multiline indicator
+    if !entry.extra_lines.is_empty() {
+        spans.push(Span::styled(
+            format!(" [+{} lines]", entry.extra_lines.len()),
+            Style::default().fg(theme.dim_fg),
+        ));
+    }
+    if let Some(count) = marks.repeats {
+        spans.push(Span::styled(
+            format!(" [×{}]", count),
+            Style::default().fg(theme.dim_fg),
+        ));
+    }
+    if let Some(count) = marks.similar {
+        spans.push(Span::styled(
+            format!(" [×{} similar]", count),
+            Style::default().fg(theme.dim_fg),
+        ));
+    }
+
+    Line::from(spans)
+}
+
+/// The level tag a feed row starts with; none in raw mode (R key), where
+/// the row is the raw line colored by level.
+fn feed_tag(entry: &LogEntry, app: &App) -> &'static str {
+    if app.show_raw {
+        ""
+    } else {
+        level_tag(entry.level)
+    }
+}
+
+fn level_tag(level: LogLevel) -> &'static str {
+    match level {
         LogLevel::Fatal => "[FATAL] ",
         LogLevel::Error => "[ERROR] ",
         LogLevel::Warn => "[WARN]  ",
@@ -327,8 +1820,69 @@ fn colorize_entry(
         LogLevel::Debug => "[DEBUG] ",
         LogLevel::Trace => "[TRACE] ",
         LogLevel::Unknown => "",
+    }
+}
+
+/// Characters of a feed row that horizontal scroll can move off screen:
+/// everything but the mark, time and source prefixes.
+fn scrollable_width(entry: &LogEntry, app: &App, widths: [u16; 4]) -> usize {
+    if app.table_view {
+        return widths.iter().map(|&w| w as usize).sum::<usize>() + 3 * COLUMN_GAP.len();
+    }
+    let tag = feed_tag(entry, app).width();
+    if app.keep_ansi && entry.raw.contains('\x1b') {
+        tag + strip_ansi(&entry.raw).width()
+    } else if tag == 0 {
+        entry.raw.width()
+    } else {
+        tag + entry.message.as_deref().unwrap_or(&entry.raw).width()
+    }
+}
+
+/// Split `spans` so the characters matched by the fuzzy query stand out.
+fn mark_fuzzy_matches(spans: Vec<Span<'static>>, query: &str, color: Color) -> Vec<Span<'static>> {
+    let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+    let Some(found) = fuzzy_match(query, &text) else {
+        return spans;
     };
+    let matched: HashSet<usize> = found.positions.into_iter().collect();
+    let fuzzy_style = Style::default()
+        .fg(color)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+    let mut marked = Vec::new();
+    let mut pos = 0;
+    for span in spans {
+        // Runs of matched / unmatched characters within the span
+        let mut runs: Vec<(bool, String)> = Vec::new();
+        for c in span.content.chars() {
+            let is_matched = matched.contains(&pos);
+            match runs.last_mut() {
+                Some((m, run)) if *m == is_matched => run.push(c),
+                _ => runs.push((is_matched, c.to_string())),
+            }
+            pos += 1;
+        }
+        marked.extend(runs.into_iter().map(|(is_matched, run)| {
+            let style = if is_matched {
+                span.style.patch(fuzzy_style)
+            } else {
+                span.style
+            };
+            Span::styled(run, style)
+        }));
+    }
+    marked
+}
 
+/// Level tag + message spans, with search/highlight matches split out.
+fn plain_spans(
+    entry: &LogEntry,
+    level_tag: &str,
+    color: Color,
+    h_scroll: usize,
+    hl_patterns: &[(&Regex, Style)],
+) -> Vec<Span<'static>> {
     // Build base display text
     let msg = entry.message.as_deref().unwrap_or(&entry.raw);
     let base_text = if level_tag.is_empty() {
@@ -338,16 +1892,16 @@ fn colorize_entry(
     };
 
     // Apply horizontal scroll
-    let display_text = skip_chars(&base_text, h_scroll);
+    let display_text = skip_columns(&base_text, h_scroll);
 
     if display_text.is_empty() {
-        return Line::from(Span::raw(""));
+        return Vec::new();
     }
 
     let base_style = Style::default().fg(color);
 
     // Build spans — with or without inline highlighting
-    let mut spans = if hl_patterns.is_empty() {
+    if hl_patterns.is_empty() {
         // Fast path: no highlights
         if !level_tag.is_empty() && h_scroll < level_tag.len() {
             let tag_end = level_tag.len() - h_scroll;
@@ -370,17 +1924,38 @@ fn colorize_entry(
     } else {
         // Highlight path: find all match ranges, split into spans
         apply_highlights(&display_text, base_style, hl_patterns)
-    };
-
-    // Append multiline indicator
-    if !entry.extra_lines.is_empty() {
-        spans.push(Span::styled(
-            format!(" [+{} lines]", entry.extra_lines.len()),
-            Style::default().fg(Color::DarkGray),
-        ));
     }
+}
 
-    Line::from(spans)
+/// --keep-ansi: render the raw line with the producer's own colours.
+/// Text without an explicit colour keeps the level colour.
+fn ansi_spans(level_tag: &str, color: Color, raw: &str, h_scroll: usize) -> Vec<Span<'static>> {
+    let base_style = Style::default().fg(color);
+    let mut spans = vec![Span::styled(
+        level_tag.to_string(),
+        base_style.add_modifier(Modifier::BOLD),
+    )];
+    spans.extend(
+        ansi_to_spans(raw)
+            .into_iter()
+            .map(|span| Span::styled(span.content, base_style.patch(span.style))),
+    );
+
+    // Horizontal scroll across span boundaries
+    let mut skip = h_scroll;
+    spans
+        .into_iter()
+        .filter_map(|span| {
+            let len = span.content.width();
+            if skip >= len {
+                skip -= len;
+                return None;
+            }
+            let content = skip_columns(&span.content, skip);
+            skip = 0;
+            Some(Span::styled(content, span.style))
+        })
+        .collect()
 }
 
 /// Split text into spans at highlight match boundaries.
@@ -401,6 +1976,18 @@ fn apply_highlights(
         return vec![Span::styled(text.to_string(), base_style)];
     }
 
+    // Widen each match to whole graphemes, so a combining mark is never
+    // split from its letter into a differently styled span
+    let bounds: Vec<usize> = text
+        .grapheme_indices(true)
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    for r in &mut ranges {
+        r.0 = bounds[bounds.partition_point(|&b| b <= r.0) - 1];
+        r.1 = bounds[bounds.partition_point(|&b| b < r.1)];
+    }
+
     ranges.sort_by_key(|r| (r.0, std::cmp::Reverse(r.1)));
 
     // Remove overlapping ranges (first match wins)
@@ -428,27 +2015,71 @@ fn apply_highlights(
     spans
 }
 
-fn level_color(level: LogLevel) -> Color {
-    match level {
-        LogLevel::Fatal => Color::Red,
-        LogLevel::Error => Color::Red,
-        LogLevel::Warn => Color::Yellow,
-        LogLevel::Info => Color::Green,
-        LogLevel::Debug => Color::Blue,
-        LogLevel::Trace => Color::DarkGray,
-        LogLevel::Unknown => Color::White,
+/// Full-width dashed rule with the divider's label: `── 14:03:22 resumed ─────`
+fn divider_line(entry: &LogEntry, width: usize, theme: &Theme) -> Line<'static> {
+    let label = format!(" {} ", entry.message.as_deref().unwrap_or(""));
+    let tail = width.saturating_sub(label.chars().count() + 2);
+    Line::from(Span::styled(
+        format!("──{}{}", label, "─".repeat(tail)),
+        Style::default().fg(theme.accent_fg),
+    ))
+}
+
+/// Timestamp prefix for the feed according to the current time display mode.
+fn time_label(
+    entry: &LogEntry,
+    mode: TimeDisplay,
+    width: usize,
+    now: chrono::DateTime<chrono::Local>,
+) -> Option<String> {
+    match mode {
+        TimeDisplay::None => None,
+        TimeDisplay::Absolute if width == 0 => None,
+        TimeDisplay::Absolute => {
+            let ts = entry.timestamp.as_deref().unwrap_or("");
+            let ts: String = ts.chars().take(width).collect();
+            Some(format!("{:<width$} ", ts))
+        }
+        TimeDisplay::Relative => Some(match entry.ts_parsed {
+            Some(ts) => format!("{:>4} ", format_age(now.signed_duration_since(ts))),
+            None => "   ? ".to_string(),
+        }),
+    }
+}
+
+/// Compact age like `-3s`, `-2m`, `-1h`, `-4d` (`+` for timestamps in the future).
+fn format_age(age: chrono::TimeDelta) -> String {
+    let secs = age.num_seconds();
+    let sign = if secs < 0 { '+' } else { '-' };
+    let secs = secs.unsigned_abs();
+    match secs {
+        0..=59 => format!("{}{}s", sign, secs),
+        60..=3599 => format!("{}{}m", sign, secs / 60),
+        3600..=86_399 => format!("{}{}h", sign, secs / 3600),
+        _ => format!("{}{}d", sign, secs / 86_400),
     }
 }
 
-/// Skip first `n` chars, returning the remainder as an owned String.
-fn skip_chars(s: &str, n: usize) -> String {
+/// Skip the first `n` display columns, returning the remainder as an owned
+/// String. Whole graphemes are dropped (an accent stays with its letter); a
+/// wide one cut by the edge leaves spaces for its visible half so columns
+/// stay aligned.
+fn skip_columns(s: &str, n: usize) -> String {
     if n == 0 {
         return s.to_string();
     }
-    match s.char_indices().nth(n) {
-        Some((byte_pos, _)) => s[byte_pos..].to_string(),
-        None => String::new(),
+    let mut column = 0;
+    for (byte_pos, grapheme) in s.grapheme_indices(true) {
+        if column >= n {
+            return s[byte_pos..].to_string();
+        }
+        column += grapheme.width();
+        if column > n {
+            let rest = &s[byte_pos + grapheme.len()..];
+            return format!("{}{}", " ".repeat(column - n), rest);
+        }
     }
+    String::new()
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -470,3 +2101,422 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{LaravelParser, LogParser};
+    use chrono::TimeDelta;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn table_row_pads_columns_and_scrolls_together() {
+        let mut entry = LaravelParser.parse("[2024-01-15 10:30:01] production.ERROR: boom");
+        entry.metadata = Some("production".to_string());
+        let widths = column_widths([&entry].iter());
+        assert_eq!(widths, [19, 5, 7, 10]);
+
+        let text =
+            |line: Line| -> String { line.spans.iter().map(|s| s.content.as_ref()).collect() };
+        let marks = || EntryMarks {
+            bookmarked: true,
+            flagged: false,
+            repeats: None,
+            similar: None,
+        };
+        assert_eq!(
+            text(table_row(
+                &entry,
+                widths,
+                0,
+                &[],
+                marks(),
+                &Theme::default()
+            )),
+            "★ 2024-01-15 10:30:01  ERROR  boom     production"
+        );
+        assert_eq!(
+            text(table_row(
+                &entry,
+                widths,
+                21,
+                &[],
+                marks(),
+                &Theme::default()
+            )),
+            "★ ERROR  boom     production"
+        );
+        assert_eq!(pad_cell("abcdef", 4), "abc…");
+    }
+
+    #[test]
+    fn detail_copy_text_is_pretty_json_for_tree_entries() {
+        let entry = LogEntry {
+            raw: r#"{"level":"error","msg":"boom"}"#.to_string(),
+            ..LogEntry::divider("x")
+        };
+        let tree = JsonTreeState::from_entry(&entry);
+        assert_eq!(
+            detail_copy_text(&entry, tree.as_ref()),
+            "{\n  \"level\": \"error\",\n  \"msg\": \"boom\"\n}"
+        );
+        let plain = LaravelParser.parse("[2024-01-15 10:30:01] production.ERROR: boom");
+        assert!(detail_copy_text(&plain, None).starts_with("Timestamp: 2024-01-15 10:30:01\n"));
+    }
+
+    #[test]
+    fn mouse_rows_map_to_feed_entries_and_footer_hints() {
+        let mut app = App::new("test".to_string());
+        for i in 0..5 {
+            app.add_log(
+                LaravelParser.parse(&format!("[2024-01-15 10:30:0{}] production.ERROR: boom", i)),
+            );
+        }
+        app.logs[1].extra_lines = vec!["#0 a".to_string(), "#1 b".to_string()];
+        let screen = Rect::new(0, 0, 80, 20);
+        // Header takes rows 0-2, the feed border row 3
+        assert_eq!(feed_entry_at(&app, screen, 3), None);
+        assert_eq!(feed_entry_at(&app, screen, 4), Some(0));
+        assert_eq!(feed_entry_at(&app, screen, 8), Some(4));
+        assert_eq!(feed_entry_at(&app, screen, 9), None);
+        app.expanded.insert(1);
+        assert_eq!(feed_entry_at(&app, screen, 7), Some(1));
+        assert_eq!(feed_entry_at(&app, screen, 8), Some(2));
+
+        assert!(in_footer(&app, screen, 18) && !in_footer(&app, screen, 16));
+        assert_eq!(footer_hint_at(&app, screen, 2), Some("q"));
+        assert_eq!(footer_hint_at(&app, screen, 21), Some("/"));
+        assert_eq!(footer_hint_at(&app, screen, 200), None);
+    }
+
+    #[test]
+    fn feed_records_longest_line_for_horizontal_fit() {
+        let mut app = App::new("test".to_string());
+        app.add_log(LaravelParser.parse("[2024-01-15 10:30:00] production.ERROR: boom"));
+        app.add_log(LaravelParser.parse(&format!(
+            "[2024-01-15 10:30:01] production.INFO: {}",
+            "x".repeat(200)
+        )));
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        // "[INFO]  " and 200 characters, in the 118 columns inside the border
+        assert_eq!(app.max_visible_line_len.get(), 208);
+        app.fit_horizontal_scroll();
+        assert_eq!(app.horizontal_scroll, 90);
+        // Scrolled rows still report their full width
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        assert_eq!(app.max_visible_line_len.get(), 208);
+    }
+
+    #[test]
+    fn header_title_shows_the_connection_badge() {
+        let app = App::new("test".to_string());
+        let title = |app: &App| -> String {
+            let mut terminal = Terminal::new(TestBackend::new(120, 12)).unwrap();
+            terminal.draw(|frame| draw(frame, app)).unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..60).map(|x| buffer[(x, 0)].symbol()).collect()
+        };
+        assert!(!title(&app).contains("connected"));
+        app.conn.set(ConnState::Reconnecting);
+        assert!(title(&app).contains("LogPulse ◌ reconnecting"));
+        app.conn.set(ConnState::Connected);
+        assert!(title(&app).contains("● connected"));
+    }
+
+    #[test]
+    fn spark_detail_grows_the_header_with_level_sparklines() {
+        let mut app = App::new("test".to_string());
+        app.add_log(LaravelParser.parse("[2024-01-15 10:30:00] production.ERROR: boom"));
+        *app.level_eps_counters[LogLevel::Error as usize]
+            .back_mut()
+            .unwrap() = 2;
+        *app.level_eps_counters[LogLevel::Fatal as usize]
+            .back_mut()
+            .unwrap() = 1;
+        *app.level_eps_counters[LogLevel::Info as usize]
+            .back_mut()
+            .unwrap() = 40;
+        let screen = Rect::new(0, 0, 120, 20);
+        assert_eq!(feed_entry_at(&app, screen, 4), Some(0));
+
+        app.spark_detail = true;
+        assert_eq!(feed_entry_at(&app, screen, 4), None);
+        assert_eq!(feed_entry_at(&app, screen, 10), Some(0));
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let row = |y: u16| -> String { (0..120).map(|x| buffer[(x, y)].symbol()).collect() };
+        assert!(row(1).contains("EPS:"));
+        let titles = row(3);
+        for title in [" ERROR 3/s ", " WARN 0/s ", " INFO 40/s ", " OTHER 0/s "] {
+            assert!(titles.contains(title), "{}", titles);
+        }
+        assert!(row(9).contains("Log Feed"));
+    }
+
+    #[test]
+    fn help_overlay_scrolls_to_its_last_binding() {
+        let mut app = App::new("test".to_string());
+        app.view_mode = ViewMode::Help;
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        let screen = |terminal: &Terminal<TestBackend>| -> String {
+            let buffer = terminal.backend().buffer();
+            (0..30)
+                .flat_map(|y| (0..100).map(move |x| (x, y)))
+                .map(|(x, y)| buffer[(x, y)].symbol().to_string())
+                .collect()
+        };
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        assert!(app.help_max_scroll.get() > 0);
+        assert!(screen(&terminal).contains("Navigation"));
+        assert!(!screen(&terminal).contains("This help"));
+
+        app.scroll_help(isize::MAX);
+        assert_eq!(app.help_scroll, app.help_max_scroll.get());
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        assert!(screen(&terminal).contains("This help"));
+        assert!(!screen(&terminal).contains("Navigation"));
+    }
+
+    #[test]
+    fn metadata_fields_read_json_and_key_value_pairs() {
+        assert_eq!(
+            metadata_fields(r#"{"req":{"id":7},"user":"bob"}"#),
+            [
+                ("req.id".to_string(), "7".to_string()),
+                ("user".to_string(), "bob".to_string())
+            ]
+        );
+        assert_eq!(
+            metadata_fields(r#"at=db op="select users" ms=12 stray"#),
+            [
+                ("at".to_string(), "db".to_string()),
+                ("op".to_string(), "select users".to_string()),
+                ("ms".to_string(), "12".to_string())
+            ]
+        );
+        assert!(metadata_fields("GET /index.html 200").is_empty());
+    }
+
+    #[test]
+    fn horizontal_scroll_counts_display_columns() {
+        // a b 漢(2) 字(2) é (e + combining acute) z
+        let line = "ab漢字e\u{301}z";
+        assert_eq!(skip_columns(line, 2), "漢字e\u{301}z");
+        // Half a wide character leaves a space in its place
+        assert_eq!(skip_columns(line, 3), " 字e\u{301}z");
+        assert_eq!(skip_columns(line, 6), "e\u{301}z");
+        // The accent goes with its letter
+        assert_eq!(skip_columns(line, 7), "z");
+        assert_eq!(skip_columns(line, 100), "");
+
+        let spans = scroll_spans(vec![Span::raw("漢字"), Span::raw("e\u{301}z")], 5);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].content, "z");
+    }
+
+    #[test]
+    fn wrapped_rows_keep_styles_and_map_clicks() {
+        let theme = Theme::default();
+        let red = Style::default().fg(Color::Red);
+        let line = Line::from(vec![Span::styled("abcdef", red), Span::raw("ghijklm")]);
+        let rows = wrap_line(line, 6, &theme);
+        let text: Vec<String> = rows
+            .iter()
+            .map(|row| row.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert_eq!(text, ["abcdef", "↳ ghij", "↳ klm"]);
+        assert_eq!(rows[1].spans[1].style, Style::default());
+        assert_eq!(wrapped_rows(13, 6), rows.len());
+        assert_eq!(wrapped_rows(6, 6), 1);
+
+        let mut app = App::new("test".to_string());
+        app.wrap_lines = true;
+        app.add_log(LaravelParser.parse(&format!(
+            "[2024-01-15 10:30:00] production.ERROR: {}",
+            "x".repeat(100)
+        )));
+        app.add_log(LaravelParser.parse("[2024-01-15 10:30:01] production.INFO: short"));
+        // 78 columns inside the border: "[ERROR] " + 100 takes two rows
+        let screen = Rect::new(0, 0, 80, 20);
+        assert_eq!(feed_entry_at(&app, screen, 5), Some(0));
+        assert_eq!(feed_entry_at(&app, screen, 6), Some(1));
+    }
+
+    #[test]
+    fn page_size_counts_wrapped_entries_as_their_rows() {
+        let mut app = App::new("test".to_string());
+        for i in 0..40 {
+            app.add_log(LaravelParser.parse(&format!(
+                "[2024-01-15 10:30:{:02}] production.INFO: {}",
+                i,
+                "x".repeat(100)
+            )));
+        }
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let rows = app.viewport_height.get();
+        assert!(rows > 2);
+
+        // "[INFO]  " and 100 characters wrap onto two rows of 78 columns
+        app.wrap_lines = true;
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        assert_eq!(app.viewport_height.get(), rows / 2);
+    }
+
+    #[test]
+    fn fuzzy_matches_are_split_into_marked_spans() {
+        let spans = mark_fuzzy_matches(vec![Span::raw("db timeout")], "dbt", Color::LightCyan);
+        let text: Vec<(&str, bool)> = spans
+            .iter()
+            .map(|s| {
+                let marked = s.style.add_modifier.contains(Modifier::UNDERLINED);
+                (s.content.as_ref(), marked)
+            })
+            .collect();
+        assert_eq!(
+            text,
+            [("db", true), (" ", false), ("t", true), ("imeout", false)]
+        );
+    }
+
+    #[test]
+    fn highlights_keep_combining_marks_with_their_letter() {
+        // "e" + U+0301: a match on the bare letter takes the accent along
+        let re = Regex::new("cafe").unwrap();
+        let hl = Style::default().fg(Color::Yellow);
+        let spans = apply_highlights("cafe\u{301} ok", Style::default(), &[(&re, hl)]);
+        let text: Vec<(&str, bool)> = spans
+            .iter()
+            .map(|s| (s.content.as_ref(), s.style == hl))
+            .collect();
+        assert_eq!(text, [("cafe\u{301}", true), (" ok", false)]);
+
+        // A match starting on the mark itself grows back to the letter
+        let re = Regex::new("\u{301} ok").unwrap();
+        let spans = apply_highlights("cafe\u{301} ok", Style::default(), &[(&re, hl)]);
+        let text: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, ["caf", "e\u{301} ok"]);
+    }
+
+    #[test]
+    fn filtered_fraction_only_when_entries_are_hidden() {
+        assert_eq!(filtered_fraction(10, 10), None);
+        assert_eq!(filtered_fraction(0, 0), None);
+        assert_eq!(
+            filtered_fraction(250, 10_000).as_deref(),
+            Some(" 250 / 10000 (2%) ")
+        );
+    }
+
+    #[test]
+    fn time_label_pads_and_truncates_column() {
+        let now = chrono::Local::now();
+        let mut entry = LogEntry::divider("x");
+        entry.timestamp = Some("2024-01-15 10:30:01".to_string());
+        let label = |e: &LogEntry, w| time_label(e, TimeDisplay::Absolute, w, now);
+        assert_eq!(label(&entry, 21).as_deref(), Some("2024-01-15 10:30:01   "));
+        assert_eq!(label(&entry, 10).as_deref(), Some("2024-01-15 "));
+        entry.timestamp = None;
+        assert_eq!(label(&entry, 4).as_deref(), Some("     "));
+        assert_eq!(label(&entry, 0), None);
+    }
+
+    #[test]
+    fn ansi_spans_keep_colours_and_scroll() {
+        let spans = ansi_spans("[ERROR] ", Color::Red, "\x1b[32mok\x1b[0m done", 0);
+        let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "[ERROR] ok done");
+        assert!(spans
+            .iter()
+            .any(|s| s.content == "ok" && s.style.fg == Some(Color::Green)));
+
+        let scrolled = ansi_spans("", Color::Red, "\x1b[32mok\x1b[0m done", 1);
+        let text: String = scrolled.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "k done");
+    }
+
+    #[test]
+    fn raw_mode_renders_the_line_verbatim() {
+        let mut app = App::new("test".to_string());
+        let entry = LaravelParser.parse("[2024-01-15 10:30:01] production.ERROR: boom");
+        let re = Regex::new("production").unwrap();
+        let hl = [(&re, Style::default().bg(Color::Blue))];
+        let marks = || EntryMarks {
+            bookmarked: false,
+            flagged: false,
+            repeats: None,
+            similar: None,
+        };
+        let text =
+            |line: Line| -> String { line.spans.iter().map(|s| s.content.as_ref()).collect() };
+
+        assert_eq!(
+            text(colorize_entry(&entry, &app, &hl, marks(), None)),
+            "[ERROR] boom"
+        );
+        app.show_raw = true;
+        let line = colorize_entry(&entry, &app, &hl, marks(), None);
+        assert!(line
+            .spans
+            .iter()
+            .any(|s| s.content == "production" && s.style.bg == Some(Color::Blue)));
+        assert_eq!(line.spans[0].style.fg, Some(app.theme.error_color));
+        assert_eq!(text(line), entry.raw);
+        assert_eq!(scrollable_width(&entry, &app, [0; 4]), entry.raw.len());
+
+        app.horizontal_scroll = 22;
+        assert_eq!(
+            text(colorize_entry(&entry, &app, &[], marks(), None)),
+            "production.ERROR: boom"
+        );
+    }
+
+    #[test]
+    fn prompt_draws_the_cursor_and_scrolls_long_text() {
+        let mut app = App::new("test".to_string());
+        app.input_mode = InputMode::Search;
+        app.input_buffer = "abcdef".to_string();
+        app.input_cursor = 2;
+        let text = |line: Line| -> Vec<String> {
+            line.spans.iter().map(|s| s.content.to_string()).collect()
+        };
+        let area = Rect::new(0, 0, 40, 3);
+        let line = prompt_line(Span::raw(" > "), &app, Color::Yellow, area);
+        assert_eq!(text(line.clone()), [" > ", "ab", "c", "def"]);
+        assert!(line.spans[2]
+            .style
+            .add_modifier
+            .contains(Modifier::REVERSED));
+
+        // 12 columns: border, " > ", then "…", 5 characters and the cursor
+        app.input_cursor = usize::MAX;
+        let line = prompt_line(
+            Span::raw(" > "),
+            &app,
+            Color::Yellow,
+            Rect::new(0, 0, 12, 3),
+        );
+        assert_eq!(text(line.clone()), [" > ", "…", "bcdef", "_"]);
+        assert_eq!(line.width(), 10);
+    }
+
+    #[test]
+    fn format_span_units() {
+        assert_eq!(format_span(chrono::TimeDelta::seconds(42)), "42s");
+        assert_eq!(format_span(chrono::TimeDelta::seconds(1963)), "32m 43s");
+        assert_eq!(format_span(chrono::TimeDelta::seconds(7500)), "2h 05m");
+        assert_eq!(format_span(chrono::TimeDelta::seconds(-5)), "0s");
+    }
+
+    #[test]
+    fn format_age_units() {
+        assert_eq!(format_age(TimeDelta::seconds(3)), "-3s");
+        assert_eq!(format_age(TimeDelta::seconds(125)), "-2m");
+        assert_eq!(format_age(TimeDelta::seconds(3 * 3600 + 5)), "-3h");
+        assert_eq!(format_age(TimeDelta::days(2)), "-2d");
+        assert_eq!(format_age(TimeDelta::seconds(-4)), "+4s");
+    }
+}
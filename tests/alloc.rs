@@ -0,0 +1,72 @@
+//! Allocation counts for the hot paths a frame takes. The counting global
+//! allocator lives in its own test binary so the unit tests keep the system
+//! allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use logpulse::app::App;
+use logpulse::parser::{LogParser, PlainParser};
+
+/// Counts heap allocations made by the current thread, so a test can
+/// check a code path doesn't allocate (tests run on their own threads).
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn cached_lookups_on_a_full_buffer_do_not_allocate() {
+    let mut app = App::new("test".to_string());
+    for i in 0..10_000 {
+        let level = if i % 3 == 0 { "ERROR" } else { "INFO" };
+        let entry = PlainParser.parse(&format!("{} request {} done", level, i));
+        app.add_parsed_log(entry, &PlainParser);
+    }
+    app.filter_text = "request".to_string();
+    app.update_filter_regex();
+    app.error_only = true;
+    app.invalidate_visible();
+    app.selected_index = 100;
+    assert_eq!(app.visible_count(), 3334);
+
+    // What a frame plus a few key presses ask for, 20 times over
+    let cached = allocations_during(|| {
+        for _ in 0..20 {
+            assert_eq!(app.visible_count(), 3334);
+            assert_eq!(app.selected_entry().unwrap().0, 300);
+            assert_eq!(app.visible_indices().last(), Some(&9999));
+        }
+    });
+    assert_eq!(cached, 0);
+
+    // Rescanning every time, as before the cache, allocates on each pass
+    let rescanned = allocations_during(|| {
+        for _ in 0..20 {
+            app.invalidate_visible();
+            assert_eq!(app.visible_count(), 3334);
+        }
+    });
+    assert!(rescanned >= 20, "{} allocations", rescanned);
+}